@@ -8,8 +8,8 @@ fn main() {
     samples.iter_mut().for_each(|v| *v = rand::random());
 
     let conf = KMeansConfig::build()
-        .init_done(&|_| println!("Initialization completed."))
-        .iteration_done(&|s: &KMeansState<f64>, nr: usize, new_distsum: f64| {
+        .init_done(|_| println!("Initialization completed."))
+        .iteration_done(|s: &KMeansState<f64>, nr: usize, new_distsum: f64| {
             println!(
                 "Iteration {} - Error: {:.2} -> {:.2} | Improvement: {:.2}",
                 nr,
@@ -1,7 +1,15 @@
+use crate::api::DistanceFunction;
 use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Enum with possible abort strategies.
 /// These strategies specify when a running iteration (with the k-means calculation) is aborted.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AbortStrategy<T: Primitive> {
     /// This strategy aborts the calculation directly after an iteration produced no improvement where `improvement > threshold`
     /// for the first time.
@@ -16,9 +24,95 @@ pub enum AbortStrategy<T: Primitive> {
     /// - **abort_on_negative**: Specifies whether the strategy instantly aborts when a negative improvement occured (**true**), or if
     ///   negative improvements are handled as "no improvements" (**false**).
     NoImprovementForXIterations { x: usize, threshold: T, abort_on_negative: bool },
+    /// This strategy aborts the calculation once the next iteration would exceed the given compute **budget**, or
+    /// directly after an iteration produced no improvement where `improvement > threshold`.
+    /// Since the budget is expressed in (estimated) work rather than iterations, this gives comparable resource caps
+    /// to calculations with different amounts of samples, clusters and dimensions.
+    /// ## Fields:
+    /// - **budget**: The maximum amount of work the iterations are allowed to do, see [`ComputeBudget`]
+    /// - **threshold**: Threshold, used to detect an improvement (`improvement > threshold`)
+    ComputeBudget { budget: ComputeBudget, threshold: T },
+    /// This strategy aborts the calculation once the next iteration would (likely) exceed the given wall-clock time
+    /// budget, estimated from the duration of the previous iteration. The time is measured from the start of the
+    /// iterations (after the initialization). The state after the last finished iteration is returned, which makes
+    /// this suitable for latency-bounded callers. The distsum does not abort the calculation with this strategy, use
+    /// a convergence tolerance (see [`crate::KMeansConfigBuilder::tol`]) to additionally stop on convergence.
+    TimeBudget(Duration),
+}
+
+/// Compute budget, as used by [`AbortStrategy::ComputeBudget`].
+/// The work of one iteration is estimated from the amount of distance evaluations it does (`n * k` for a Lloyd
+/// iteration, `batch_size * k` for a Mini-Batch iteration).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ComputeBudget {
+    /// Maximum amount of distance evaluations
+    DistanceEvaluations(u64),
+    /// Maximum amount of floating point operations, where each distance evaluation is estimated to take
+    /// `3 * sample_dims` operations (subtraction, multiplication and addition per dimension)
+    Flops(u64),
+}
+
+/// Reason why a k-means calculation stopped iterating, as reported in [`crate::KMeansState::stop_reason`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StopReason {
+    /// The maximum amount of iterations was reached, before any other criterion stopped the calculation
+    #[default]
+    MaxIterations,
+    /// The configured [`AbortStrategy`] aborted the calculation
+    AbortStrategy,
+    /// The centroid movement or the relative improvement fell below the configured tolerance (see
+    /// [`crate::KMeansConfigBuilder::tol`])
+    Tolerance,
+    /// The smoothed batch inertia of a Mini-Batch calculation did not improve for the configured amount of batches
+    /// (see [`crate::KMeansConfigBuilder::minibatch_max_no_improvement`])
+    NoBatchImprovement,
+    /// The calculation was cancelled through its cancellation token (see
+    /// [`crate::KMeansConfigBuilder::cancellation_token`])
+    Cancelled,
+    /// NaN or infinite values appeared in the centroids or the distsum, see [`crate::KMeansState::divergence`]
+    Diverged,
+}
+impl StopReason {
+    /// Whether this reason stops the calculation unconditionally, even in iterations that restarted the convergence
+    /// (e.g. by a split-merge move, or re-seeded centroids).
+    pub(crate) fn is_forced(self) -> bool { matches!(self, StopReason::Cancelled | StopReason::Diverged) }
 }
+
+/// Detected divergence of a k-means calculation, as reported in [`crate::KMeansState::divergence`]: NaN or infinite
+/// values appeared in the centroids or the distsum (e.g. due to NaN samples, or overflowing values).
+///
+/// ## Fields
+/// - **iteration**: Number of the iteration, after which the non-finite values were detected
+/// - **cluster**: The first cluster with a non-finite centroid, or **None** if only the distsum was not finite
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Divergence {
+    pub iteration: usize,
+    pub cluster: Option<usize>,
+}
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.cluster {
+            Some(cluster) => write!(f, "centroid of cluster {cluster} is not finite after iteration {}", self.iteration),
+            None => write!(f, "distsum is not finite after iteration {}", self.iteration),
+        }
+    }
+}
+impl std::error::Error for Divergence {}
+
+/// Estimated cost of one iteration of a k-means calculation, used by budget-based abort strategies.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct IterationCost {
+    /// Amount of distance evaluations done per iteration
+    pub distance_evaluations: u64,
+    /// Amount of dimensions of each sample
+    pub sample_dims: usize,
+}
+
 impl<T: Primitive> AbortStrategy<T> {
-    pub(crate) fn create_logic(&self) -> Box<dyn AbortStrategyLogic<T>> {
+    pub(crate) fn create_logic(&self, cost: IterationCost) -> Box<dyn AbortStrategyLogic<T>> {
         match *self {
             AbortStrategy::NoImprovementForXIterations {
                 x,
@@ -35,6 +129,21 @@ impl<T: Primitive> AbortStrategy<T> {
                 threshold,
                 prev_error: T::infinity(),
             }),
+            AbortStrategy::ComputeBudget { budget, threshold } => {
+                let (budget, per_iteration) = match budget {
+                    ComputeBudget::DistanceEvaluations(evaluations) => (evaluations, cost.distance_evaluations),
+                    ComputeBudget::Flops(flops) => (flops, cost.distance_evaluations.saturating_mul(3 * cost.sample_dims as u64)),
+                };
+                Box::new(ComputeBudgetLogic {
+                    remaining: budget.saturating_sub(per_iteration),
+                    per_iteration,
+                    no_improvement: NoImprovementLogic {
+                        threshold,
+                        prev_error: T::infinity(),
+                    },
+                })
+            },
+            AbortStrategy::TimeBudget(budget) => Box::new(TimeBudgetLogic::new(budget, Instant::now())),
         }
     }
 }
@@ -87,6 +196,104 @@ impl<T: Primitive> AbortStrategyLogic<T> for NoImprovementForXIterationsLogic<T>
     }
 }
 
+pub(crate) struct ComputeBudgetLogic<T: Primitive> {
+    /// Budget that is left, after the already finished iterations
+    remaining: u64,
+    per_iteration: u64,
+    no_improvement: NoImprovementLogic<T>,
+}
+impl<T: Primitive> AbortStrategyLogic<T> for ComputeBudgetLogic<T> {
+    fn next(&mut self, error: T) -> bool {
+        if !self.no_improvement.next(error) || self.remaining < self.per_iteration {
+            return false;
+        }
+        self.remaining -= self.per_iteration;
+        true
+    }
+}
+
+pub(crate) struct TimeBudgetLogic {
+    deadline: Instant,
+    /// Start of the current iteration
+    iteration_start: Instant,
+}
+impl TimeBudgetLogic {
+    fn new(budget: Duration, start: Instant) -> Self {
+        Self {
+            deadline: start + budget,
+            iteration_start: start,
+        }
+    }
+
+    /// Whether another iteration fits into the budget, if the current one ended at **now**.
+    fn next_at(&mut self, now: Instant) -> bool {
+        let iteration_duration = now - self.iteration_start;
+        self.iteration_start = now;
+        now + iteration_duration <= self.deadline
+    }
+}
+impl<T: Primitive> AbortStrategyLogic<T> for TimeBudgetLogic {
+    fn next(&mut self, _error: T) -> bool { self.next_at(Instant::now()) }
+}
+
+/// All criteria that stop a running calculation: A detected divergence, the cancellation token, the configured
+/// [`AbortStrategy`], and the convergence tolerance.
+pub(crate) struct StopCriteria<T: Primitive> {
+    cancellation: Option<Arc<AtomicBool>>,
+    abort_strategy: Box<dyn AbortStrategyLogic<T>>,
+    tol: T,
+    prev_distsum: T,
+    /// Centroids after the previous iteration (only kept, if a tolerance is configured)
+    prev_centroids: Option<StrideBuffer<T>>,
+}
+impl<T: Primitive> StopCriteria<T> {
+    pub(crate) fn new(config: &KMeansConfig<'_, T>, state: &KMeansState<T>, cost: IterationCost) -> Self {
+        Self {
+            cancellation: config.cancellation.clone(),
+            abort_strategy: config.abort_strategy.create_logic(cost),
+            tol: config.tol,
+            prev_distsum: T::infinity(),
+            prev_centroids: (config.tol > T::zero()).then(|| state.centroids.clone()),
+        }
+    }
+
+    /// Function that has to be called once an iteration of the calculation ended (after the centroids were moved).
+    /// ## Arguments
+    /// - **state**: State after the iteration
+    /// - **distsum**: The new error (distsum), after the iteration
+    /// ## Returns
+    /// - The reason to stop the calculation, or **None** if the calculation should continue
+    pub(crate) fn next<const LANES: usize, D>(
+        &mut self, kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>, distsum: T,
+    ) -> Option<StopReason>
+    where
+        LaneCount<LANES>: SupportedLaneCount,
+        Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+        D: DistanceFunction<T, LANES>,
+    {
+        if state.divergence.is_some() {
+            return Some(StopReason::Diverged);
+        }
+        if self.cancellation.as_ref().is_some_and(|token| token.load(Ordering::Relaxed)) {
+            return Some(StopReason::Cancelled);
+        }
+        let proceed = self.abort_strategy.next(distsum);
+        let improvement = self.prev_distsum - distsum;
+        self.prev_distsum = distsum;
+        if !proceed {
+            return Some(StopReason::AbortStrategy);
+        }
+        let prev_centroids = self.prev_centroids.as_mut()?;
+        let max_shift = prev_centroids
+            .chunks_exact_stride()
+            .zip(state.centroids.chunks_exact_stride())
+            .map(|(prev, c)| kmean.distance_fn.distance(prev, c))
+            .fold(T::zero(), T::max);
+        prev_centroids.bfr.copy_from_slice(&state.centroids.bfr);
+        (max_shift < self.tol || improvement < self.tol * distsum).then_some(StopReason::Tolerance)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,35 +308,35 @@ mod tests {
             let mut abort_strategy = AbortStrategy::NoImprovement {
                 threshold: T::from(0.0005).unwrap(),
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(3000.0).unwrap()));
         }
         {
             let mut abort_strategy = AbortStrategy::NoImprovement {
                 threshold: T::from(0.0005).unwrap(),
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2999.99959).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(2999.99959).unwrap()));
         }
         {
             let mut abort_strategy = AbortStrategy::NoImprovement {
                 threshold: T::from(0.0005).unwrap(),
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2999.99935).unwrap()), true);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2999.99935).unwrap()));
         }
         {
             let mut abort_strategy = AbortStrategy::NoImprovement {
                 threshold: T::from(0.0005).unwrap(),
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.99).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.99999999).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(1999.99).unwrap()));
+            assert!(!abort_strategy.next(T::from(1999.99999999).unwrap()));
         }
     }
 
@@ -146,9 +353,9 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: false,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(3000.0).unwrap()));
         }
         {
             let mut abort_strategy = AbortStrategy::NoImprovementForXIterations {
@@ -156,9 +363,9 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: false,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2999.99959).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(2999.99959).unwrap()));
         }
         {
             let mut abort_strategy = AbortStrategy::NoImprovementForXIterations {
@@ -166,9 +373,9 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: false,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2999.99935).unwrap()), true);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2999.99935).unwrap()));
         }
         {
             let mut abort_strategy = AbortStrategy::NoImprovementForXIterations {
@@ -176,11 +383,11 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: false,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.99).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.99999999).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(1999.99).unwrap()));
+            assert!(!abort_strategy.next(T::from(1999.99999999).unwrap()));
         }
         // ABORT_ON_NEGATIVE (without negative improvements)
         {
@@ -189,9 +396,9 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: true,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(3000.0).unwrap()));
         }
         {
             let mut abort_strategy = AbortStrategy::NoImprovementForXIterations {
@@ -199,9 +406,9 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: true,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2999.99959).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(2999.99959).unwrap()));
         }
         {
             let mut abort_strategy = AbortStrategy::NoImprovementForXIterations {
@@ -209,9 +416,9 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: true,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2999.99935).unwrap()), true);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2999.99935).unwrap()));
         }
         {
             let mut abort_strategy = AbortStrategy::NoImprovementForXIterations {
@@ -219,11 +426,11 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: true,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.99).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.99999999).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(1999.99).unwrap()));
+            assert!(!abort_strategy.next(T::from(1999.99999999).unwrap()));
         }
         // ABORT_ON_NEGATIVE (with negative improvements)
         {
@@ -232,9 +439,9 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: true,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(3001.0).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(3001.0).unwrap()));
         }
         {
             // Should abort on negative improvement, even ifs absolute value < threshold
@@ -243,9 +450,9 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: true,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(3000.0004).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(3000.0004).unwrap()));
         }
         {
             let mut abort_strategy = AbortStrategy::NoImprovementForXIterations {
@@ -253,9 +460,9 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: true,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(3000.0007).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(3000.0007).unwrap()));
         }
 
         // X != 1
@@ -265,13 +472,13 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: false,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.0).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(1999.0).unwrap()));
+            assert!(abort_strategy.next(T::from(1999.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(1999.0).unwrap()));
         }
         {
             // Same as directly above, but with abort_on_negative = true
@@ -280,13 +487,13 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: true,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.0).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(1999.0).unwrap()));
+            assert!(abort_strategy.next(T::from(1999.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(1999.0).unwrap()));
         }
         {
             // Negative improvement before no_improvement_counter == 2
@@ -295,12 +502,140 @@ mod tests {
                 threshold: T::from(0.0005).unwrap(),
                 abort_on_negative: true,
             }
-            .create_logic();
-            assert_eq!(abort_strategy.next(T::from(3000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2000.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(1999.0).unwrap()), true);
-            assert_eq!(abort_strategy.next(T::from(2999.0).unwrap()), false);
+            .create_logic(IterationCost::default());
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(1999.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(2999.0).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_compute_budget_f32() { test_compute_budget::<f32>(); }
+    #[test]
+    fn test_compute_budget_f64() { test_compute_budget::<f64>(); }
+
+    fn test_compute_budget<T: Primitive>() {
+        let cost = IterationCost {
+            distance_evaluations: 100,
+            sample_dims: 2,
+        };
+        {
+            // Budget for 3 iterations
+            let mut abort_strategy = AbortStrategy::ComputeBudget {
+                budget: ComputeBudget::DistanceEvaluations(350),
+                threshold: T::from(0.0005).unwrap(),
+            }
+            .create_logic(cost);
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(abort_strategy.next(T::from(2000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(1000.0).unwrap()));
+        }
+        {
+            // 600 flops per iteration -> budget for 2 iterations
+            let mut abort_strategy = AbortStrategy::ComputeBudget {
+                budget: ComputeBudget::Flops(1200),
+                threshold: T::from(0.0005).unwrap(),
+            }
+            .create_logic(cost);
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(2000.0).unwrap()));
         }
+        {
+            // Aborts on convergence before the budget is used up
+            let mut abort_strategy = AbortStrategy::ComputeBudget {
+                budget: ComputeBudget::DistanceEvaluations(u64::MAX),
+                threshold: T::from(0.0005).unwrap(),
+            }
+            .create_logic(cost);
+            assert!(abort_strategy.next(T::from(3000.0).unwrap()));
+            assert!(!abort_strategy.next(T::from(3000.0).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut time_budget = TimeBudgetLogic::new(ms(100), start);
+        // Iterations that take a fraction of the budget continue
+        assert!(time_budget.next_at(start + ms(10)));
+        assert!(time_budget.next_at(start + ms(20)));
+        // The next iteration would take about as long as this one (60ms), and exceed the budget
+        assert!(!time_budget.next_at(start + ms(80)));
+        // An iteration that would exactly use up the budget still runs
+        let mut time_budget = TimeBudgetLogic::new(ms(100), start);
+        assert!(time_budget.next_at(start + ms(50)));
+        assert!(!time_budget.next_at(start + ms(100)));
+
+        let mut time_budget = TimeBudgetLogic::new(Duration::ZERO, start);
+        assert!(!time_budget.next_at(start + ms(1)));
+
+        // The budget is independent of the error
+        let mut abort_strategy = AbortStrategy::<f64>::TimeBudget(Duration::from_secs(3600)).create_logic(IterationCost::default());
+        assert!(abort_strategy.next(1000.0));
+        assert!(abort_strategy.next(1000.0));
+    }
+
+    #[test]
+    fn stop_reasons() {
+        let samples: Vec<f64> = (0..200).map(|i| (i % 4) as f64 * 10.0 + (i % 7) as f64 * 0.1).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, crate::EuclideanDistance);
+        let init = || KMeans::init_precomputed(vec![0.0, 1.0, 2.0, 3.0]);
+        let never_abort = AbortStrategy::NoImprovementForXIterations {
+            x: usize::MAX,
+            threshold: 0.0,
+            abort_on_negative: false,
+        };
+
+        let res = kmean.kmeans_lloyd(4, 50, init(), &KMeansConfig::build().abort_strategy(never_abort.clone()).build());
+        assert_eq!((res.n_iterations, res.stop_reason), (50, StopReason::MaxIterations));
+        assert!(!res.converged());
+
+        let conf = KMeansConfig::build().abort_strategy(never_abort).tol(1e-6).build();
+        let res = kmean.kmeans_lloyd(4, 50, init(), &conf);
+        assert_eq!(res.stop_reason, StopReason::Tolerance);
+        assert!(res.converged() && res.n_iterations < 50);
+        let elkan = kmean.kmeans_elkan(4, 50, init(), &conf);
+        assert_eq!((elkan.n_iterations, elkan.stop_reason), (res.n_iterations, StopReason::Tolerance));
+
+        let res = kmean.kmeans_lloyd(4, 50, init(), &KMeansConfig::default());
+        assert_eq!(res.stop_reason, StopReason::AbortStrategy);
+        assert!(res.n_iterations > 1 && res.n_iterations < 50);
+
+        // Cancelled from the callback, while the abort strategy would continue
+        let cancel = Arc::new(AtomicBool::new(false));
+        let conf = KMeansConfig::build()
+            .abort_strategy(AbortStrategy::NoImprovementForXIterations {
+                x: usize::MAX,
+                threshold: 0.0,
+                abort_on_negative: false,
+            })
+            .cancellation_token(cancel.clone())
+            .iteration_done(|_, iteration, _| cancel.store(iteration == 2, Ordering::Relaxed))
+            .build();
+        let res = kmean.kmeans_lloyd(4, 50, init(), &conf);
+        assert_eq!((res.n_iterations, res.stop_reason), (2, StopReason::Cancelled));
+        assert!(!res.converged());
+
+        // NaN samples poison the centroid of their cluster
+        let mut poisoned = samples.clone();
+        poisoned[6] = f64::NAN;
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&poisoned, poisoned.len(), 1, crate::EuclideanDistance);
+        let res = kmean.kmeans_lloyd(4, 50, init(), &KMeansConfig::default());
+        assert_eq!((res.n_iterations, res.stop_reason), (1, StopReason::Diverged));
+        assert_eq!(
+            res.divergence,
+            Some(Divergence {
+                iteration: 1,
+                cluster: Some(0)
+            })
+        );
+        assert!(!res.converged());
+        assert_eq!(
+            res.into_result().unwrap_err().to_string(),
+            "centroid of cluster 0 is not finite after iteration 1"
+        );
     }
 }
@@ -0,0 +1,98 @@
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::sync::Arc;
+
+/// Parse a cpu list of the kernel's sysfs format (e.g. `0-3,8,10-11`).
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter(|r| !r.is_empty())
+        .flat_map(|r| match r.split_once('-') {
+            Some((start, end)) => start.parse().unwrap_or(0)..=end.parse().unwrap_or(0),
+            None => {
+                let cpu = r.parse().unwrap_or(0);
+                cpu..=cpu
+            },
+        })
+        .collect()
+}
+
+/// Logical cpus this process may run on, with only one per physical core (the first of its SMT siblings).
+/// Without topology information, every logical cpu is treated as a physical core.
+#[cfg(target_os = "linux")]
+fn physical_cores() -> Vec<usize> {
+    // SAFETY: cpu_set_t is a plain bitset, for which all-zeros is a valid (empty) value
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    if unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) } != 0 {
+        return Vec::new();
+    }
+    let allowed: Vec<usize> = (0..libc::CPU_SETSIZE as usize)
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .collect();
+    allowed
+        .iter()
+        .cloned()
+        .filter(|&cpu| {
+            std::fs::read_to_string(format!("/sys/devices/system/cpu/cpu{}/topology/thread_siblings_list", cpu))
+                .ok()
+                .and_then(|siblings| parse_cpu_list(&siblings).into_iter().find(|s| allowed.contains(s)))
+                .is_none_or(|first| first == cpu)
+        })
+        .collect()
+}
+#[cfg(not(target_os = "linux"))]
+fn physical_cores() -> Vec<usize> { Vec::new() }
+
+/// Pin the calling thread to the given logical cpu. Failures (e.g. due to a restricted cpu set) are ignored, the
+/// thread then simply stays unpinned.
+#[cfg(target_os = "linux")]
+fn pin_current_thread(cpu: usize) {
+    // SAFETY: cpu is below CPU_SETSIZE, as it was taken from a cpu_set_t
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set);
+    }
+}
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread(_cpu: usize) {}
+
+/// Thread pool with one worker per physical core, each pinned to its core. If the physical cores can not be
+/// determined (e.g. on other platforms than linux), this is a pool with rayon's default, unpinned workers.
+pub(crate) fn pinned_thread_pool() -> Arc<ThreadPool> {
+    let cores = physical_cores();
+    let mut builder = ThreadPoolBuilder::new();
+    if !cores.is_empty() {
+        let worker_cores = cores.clone();
+        builder = builder
+            .num_threads(cores.len())
+            .thread_name(|idx| format!("kmeans-pinned-{}", idx))
+            .start_handler(move |idx| pin_current_thread(worker_cores[idx]));
+    }
+    Arc::new(builder.build().expect("Failed to create pinned thread pool"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn cpu_lists() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list("5"), vec![5]);
+        assert!(parse_cpu_list("\n").is_empty());
+    }
+
+    #[test]
+    fn core_pinning_equals_default_pool() {
+        let samples: Vec<f64> = (0..3000).map(|i| (i % 3) as f64 * 5.0 + (i % 7) as f64 * 0.1).collect();
+        let init = || KMeans::init_precomputed(vec![0.0, 1.0, 2.0]);
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let expected = kmean.kmeans_lloyd(3, 100, init(), &KMeansConfig::default());
+
+        let kmean = kmean.with_core_pinning();
+        let res = kmean.kmeans_lloyd(3, 100, init(), &KMeansConfig::default());
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.centroids.to_vec(), expected.centroids.to_vec());
+    }
+}
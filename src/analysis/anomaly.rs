@@ -0,0 +1,51 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>, samples: &[T]) -> Vec<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let radii = super::radius::mean_radii(state);
+    let (assignments, distances) = kmean.assign_samples(state, samples);
+    assignments
+        .into_iter()
+        .zip(distances)
+        .map(|(assignment, dist)| {
+            let radius = radii[assignment];
+            if radius > T::zero() {
+                dist / radius
+            } else if dist > T::zero() {
+                // Cluster without any spread (e.g. single sample) - everything that is not exactly on it is anomalous
+                T::infinity()
+            } else {
+                T::zero()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn anomaly_scores() {
+        let samples = vec![0.0f64, 1.0, 2.0, 100.0, 101.0, 102.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 100.0]), &KMeansConfig::default());
+
+        // Both clusters have a radius of (1 + 0 + 1) / 3 (squared euclidean distances)
+        let scores = kmean.anomaly_scores(&res, &[1.0, 2.0, 51.0, 110.0]);
+        assert_eq!(scores.len(), 4);
+        assert_eq!(scores[0], 0.0);
+        assert!((scores[1] - 1.5).abs() < 1e-10);
+        assert!((scores[2] - 2500.0 * 1.5).abs() < 1e-7);
+        assert!((scores[3] - 81.0 * 1.5).abs() < 1e-7);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{helpers, KMeans, KMeansState};
+
+/// Pair of matched centroids between two k-means results.
+///
+/// ## Fields
+/// - **a**: Id of the centroid in the first result
+/// - **b**: Id of the matched centroid in the second result
+/// - **displacement**: Distance between both centroids (as calculated by the distance function)
+/// - **overlap**: Membership overlap of both clusters (Jaccard index of their member sets, in `[0, 1]`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct CentroidMatch<T: Primitive> {
+    pub a: usize,
+    pub b: usize,
+    pub displacement: T,
+    pub overlap: T,
+}
+
+/// Comparison of two k-means results, calculated on the same samples.
+///
+/// ## Fields
+/// - **matches**: Matched centroid pairs, ordered by their id in the first result
+/// - **unmatched_a**: Centroids of the first result without partner (if it has more clusters than the second)
+/// - **unmatched_b**: Centroids of the second result without partner (if it has more clusters than the first)
+/// - **mean_displacement**: Mean displacement over all matched centroid pairs
+/// - **agreement**: Fraction of samples, that are assigned to matched clusters in both results
+#[derive(Clone, Debug)]
+pub struct ResultComparison<T: Primitive> {
+    pub matches: Vec<CentroidMatch<T>>,
+    pub unmatched_a: Vec<usize>,
+    pub unmatched_b: Vec<usize>,
+    pub mean_displacement: T,
+    pub agreement: T,
+}
+
+/// Contingency matrix [row-major] of two labelings, with `contingency[la * k_b + lb]` = amount of samples
+/// with label `la` in the first, and label `lb` in the second labeling.
+pub(crate) fn contingency_matrix(labels_a: &[usize], k_a: usize, labels_b: &[usize], k_b: usize) -> Vec<usize> {
+    assert_eq!(labels_a.len(), labels_b.len());
+    let mut contingency = vec![0usize; k_a * k_b];
+    labels_a
+        .iter()
+        .zip(labels_b.iter())
+        .for_each(|(&la, &lb)| contingency[la * k_b + lb] += 1);
+    contingency
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, a: &KMeansState<T>, b: &KMeansState<T>) -> ResultComparison<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    assert_eq!(a.assignments.len(), b.assignments.len());
+
+    let mut displacements = Vec::with_capacity(a.k * b.k);
+    for ca in a.centroids.chunks_exact_stride() {
+        for cb in b.centroids.chunks_exact_stride() {
+            displacements.push(kmean.distance_fn.distance(ca, cb));
+        }
+    }
+    let cost: Vec<f64> = displacements.iter().map(|d| d.to_f64().unwrap()).collect();
+    let assignment = helpers::hungarian(&cost, a.k, b.k);
+
+    let contingency = contingency_matrix(&a.assignments, a.k, &b.assignments, b.k);
+    let mut matches = Vec::new();
+    let mut unmatched_a = Vec::new();
+    let mut matched_b = vec![false; b.k];
+    let mut agreeing_samples = 0;
+    for (ca, cb) in assignment.into_iter().enumerate() {
+        let Some(cb) = cb else {
+            unmatched_a.push(ca);
+            continue;
+        };
+        matched_b[cb] = true;
+        let intersection = contingency[ca * b.k + cb];
+        let union = a.centroid_frequency[ca] + b.centroid_frequency[cb] - intersection;
+        agreeing_samples += intersection;
+        matches.push(CentroidMatch {
+            a: ca,
+            b: cb,
+            displacement: displacements[ca * b.k + cb],
+            overlap: if union > 0 {
+                T::from(intersection).unwrap() / T::from(union).unwrap()
+            } else {
+                T::zero()
+            },
+        });
+    }
+    let unmatched_b = (0..b.k).filter(|&cb| !matched_b[cb]).collect();
+    let mean_displacement = match matches.len() {
+        0 => T::zero(),
+        cnt => matches.iter().map(|m| m.displacement).sum::<T>() / T::from(cnt).unwrap(),
+    };
+
+    ResultComparison {
+        matches,
+        unmatched_a,
+        unmatched_b,
+        mean_displacement,
+        agreement: T::from(agreeing_samples).unwrap() / T::from(a.assignments.len()).unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn compare_results() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0, 20.0, 21.0, 22.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::default();
+        let a = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![0.0, 10.0, 20.0]), &conf);
+        let b = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![20.0, 0.0, 10.0]), &conf);
+
+        let cmp = kmean.compare_results(&a, &b);
+        assert_eq!(cmp.matches.iter().map(|m| (m.a, m.b)).collect::<Vec<_>>(), vec![
+            (0, 1),
+            (1, 2),
+            (2, 0)
+        ]);
+        assert!(cmp.matches.iter().all(|m| m.displacement == 0.0 && m.overlap == 1.0));
+        assert_eq!(cmp.agreement, 1.0);
+        assert_eq!(cmp.mean_displacement, 0.0);
+
+        // Retrained model with only two clusters
+        let c = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![1.0, 16.0]), &conf);
+        let cmp = kmean.compare_results(&a, &c);
+        assert_eq!(cmp.matches.len(), 2);
+        assert_eq!(cmp.unmatched_a.len(), 1);
+        assert!(cmp.unmatched_b.is_empty());
+        assert_eq!(cmp.matches[0].a, 0);
+        assert_eq!(cmp.matches[0].displacement, 0.0);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+
+/// Drift statistics of a new batch of samples, compared to the samples a k-means result was calculated on.
+///
+/// ## Fields
+/// - **inertia_ratio**: Mean distance of the new samples to their nearest centroid, divided by the mean distance of
+///   the original samples to their centroid (`1` = no change, `> 1` = new samples fit the model worse). If all
+///   original samples lie exactly on their centroid, this is `1` for new samples that do as well, and infinite otherwise.
+/// - **reference_occupancy**: Fraction of the original samples in each cluster
+/// - **occupancy**: Fraction of the new samples in each cluster
+/// - **occupancy_shift**: Per-cluster difference `occupancy - reference_occupancy`
+/// - **total_variation**: Total variation distance between both occupancies (`0.5 * sum(|occupancy_shift|)`, in `[0, 1]`)
+/// - **population_stability_index**: Population stability index (PSI) over the cluster assignments. As a rule of thumb,
+///   values below `0.1` indicate no significant shift, values above `0.25` a significant shift.
+#[derive(Clone, Debug)]
+pub struct DriftStatistics<T: Primitive> {
+    pub inertia_ratio: T,
+    pub reference_occupancy: Vec<T>,
+    pub occupancy: Vec<T>,
+    pub occupancy_shift: Vec<T>,
+    pub total_variation: T,
+    pub population_stability_index: T,
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>, samples: &[T]) -> DriftStatistics<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let (assignments, distances) = kmean.assign_samples(state, samples);
+    assert!(!assignments.is_empty());
+
+    let mut frequency = vec![0usize; state.k];
+    assignments.iter().for_each(|&a| frequency[a] += 1);
+
+    let reference_cnt = T::from(state.assignments.len()).unwrap();
+    let new_cnt = T::from(assignments.len()).unwrap();
+    let reference_occupancy: Vec<T> = state
+        .centroid_frequency
+        .iter()
+        .map(|&f| T::from(f).unwrap() / reference_cnt)
+        .collect();
+    let occupancy: Vec<T> = frequency.iter().map(|&f| T::from(f).unwrap() / new_cnt).collect();
+    let occupancy_shift: Vec<T> = occupancy.iter().zip(reference_occupancy.iter()).map(|(&q, &p)| q - p).collect();
+    let total_variation = occupancy_shift.iter().map(|v| v.abs()).sum::<T>() * T::from(0.5).unwrap();
+
+    // Empty bins would make the PSI infinite, so all occupancies are clamped to a small epsilon
+    let epsilon = T::from(1e-4).unwrap();
+    let population_stability_index = occupancy
+        .iter()
+        .zip(reference_occupancy.iter())
+        .map(|(&q, &p)| {
+            let (q, p) = (q.max(epsilon), p.max(epsilon));
+            (q - p) * (q / p).ln()
+        })
+        .sum();
+
+    let reference_inertia = state.centroid_distances.iter().cloned().sum::<T>() / reference_cnt;
+    let inertia = distances.iter().cloned().sum::<T>() / new_cnt;
+
+    // Without any reference inertia, only samples that lie on their centroids as well are unchanged
+    let inertia_ratio = match (reference_inertia > T::zero(), inertia > T::zero()) {
+        (true, _) => inertia / reference_inertia,
+        (false, false) => T::one(),
+        (false, true) => T::infinity(),
+    };
+
+    DriftStatistics {
+        inertia_ratio,
+        reference_occupancy,
+        occupancy,
+        occupancy_shift,
+        total_variation,
+        population_stability_index,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn drift_statistics() {
+        let samples = vec![0.0f64, 1.0, 2.0, 100.0, 101.0, 102.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 100.0]), &KMeansConfig::default());
+
+        // Same data -> no drift at all
+        let same = kmean.drift_statistics(&res, &samples);
+        assert!((same.inertia_ratio - 1.0).abs() < 1e-10);
+        assert_eq!(same.total_variation, 0.0);
+        assert_eq!(same.population_stability_index, 0.0);
+
+        // All new samples in the second cluster, with twice the spread
+        let drifted = kmean.drift_statistics(&res, &[99.0, 103.0, 101.0, 101.0, 101.0, 101.0]);
+        assert_eq!(drifted.occupancy, vec![0.0, 1.0]);
+        assert_eq!(drifted.occupancy_shift, vec![-0.5, 0.5]);
+        assert!((drifted.total_variation - 0.5).abs() < 1e-10);
+        assert!((drifted.inertia_ratio - 2.0).abs() < 1e-10);
+        assert!(drifted.population_stability_index > 0.25);
+    }
+
+    #[test]
+    fn zero_reference_inertia() {
+        // Every sample lies exactly on its centroid
+        let samples = vec![0.0f64, 0.0, 100.0, 100.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 100.0]), &KMeansConfig::default());
+        assert_eq!(res.distsum, 0.0);
+
+        assert_eq!(kmean.drift_statistics(&res, &samples).inertia_ratio, 1.0);
+        assert_eq!(kmean.drift_statistics(&res, &[0.0, 100.0, 100.0]).inertia_ratio, 1.0);
+        assert_eq!(kmean.drift_statistics(&res, &[1.0, 100.0]).inertia_ratio, f64::INFINITY);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::memory::*;
+use crate::KMeansState;
+
+/// Representative sample of a cluster, as returned by [`KMeansState::exemplars`].
+///
+/// ## Fields
+/// - **sample**: Index of the sample
+/// - **distance**: Distance of the sample to its centroid (as returned by the distance function, e.g. squared for
+///   [`crate::EuclideanDistance`])
+#[derive(Clone, Debug, PartialEq)]
+pub struct Exemplar<T: Primitive> {
+    pub sample: usize,
+    pub distance: T,
+}
+
+#[inline(always)]
+pub fn calculate<T: Primitive>(state: &KMeansState<T>, m: usize) -> Vec<Vec<Exemplar<T>>> {
+    let mut members = vec![Vec::new(); state.k];
+    state
+        .assignments
+        .iter()
+        .zip(state.centroid_distances.iter())
+        .enumerate()
+        .for_each(|(sample, (&c, &distance))| members[c].push(Exemplar { sample, distance }));
+
+    // Ties are resolved to the sample with the lower index
+    let cmp = |a: &Exemplar<T>, b: &Exemplar<T>| a.distance.partial_cmp(&b.distance).unwrap().then(a.sample.cmp(&b.sample));
+    members
+        .into_iter()
+        .map(|mut m_exemplars| {
+            if m_exemplars.len() > m && m > 0 {
+                m_exemplars.select_nth_unstable_by(m - 1, cmp);
+            }
+            m_exemplars.truncate(m);
+            m_exemplars.sort_unstable_by(cmp);
+            m_exemplars
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn nearest_members() {
+        let samples = vec![0.0f64, 4.0, 1.0, 2.0, 3.0, 100.0, 110.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 100.0]), &KMeansConfig::default());
+        assert_eq!(res.centroids.to_vec(), vec![2.0, 105.0]);
+
+        let exemplars = res.exemplars(3);
+        assert_eq!(exemplars[0], vec![
+            Exemplar { sample: 3, distance: 0.0 },
+            Exemplar { sample: 2, distance: 1.0 },
+            Exemplar { sample: 4, distance: 1.0 },
+        ]);
+        // Clusters with less than m members return all of them
+        assert_eq!(exemplars[1], vec![Exemplar { sample: 5, distance: 25.0 }, Exemplar {
+            sample: 6,
+            distance: 25.0
+        }]);
+        assert!(res.exemplars(0).iter().all(|e| e.is_empty()));
+    }
+}
@@ -0,0 +1,340 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use std::collections::HashSet;
+
+/// Spatial extent of one cluster of a k-means result, e.g. for spatial reporting on low-dimensional (geographic) data.
+///
+/// ## Fields
+/// - **count**: Amount of samples in the cluster
+/// - **min**: Per-dimension minimum of the cluster's samples (lower corner of the bounding box)
+/// - **max**: Per-dimension maximum of the cluster's samples (upper corner of the bounding box)
+/// - **volume**: Volume of the bounding box (the area for 2-D samples)
+/// - **hull**: For 2-D and 3-D samples only: Indices of the samples on the convex hull of the cluster (its corners,
+///   without samples on the edges or faces of the hull). For 2-D samples in counter-clockwise order, for 3-D samples
+///   in ascending order
+/// - **hull_area**: For 2-D samples only the area, for 3-D samples the surface area of the convex hull
+/// - **hull_volume**: For 3-D samples only: Volume of the convex hull
+///
+/// Hulls and bounding boxes enclose the samples of each cluster, not the (unbounded) Voronoi cells of the centroids.
+/// For empty clusters, **min** / **max** are `+inf` / `-inf`, while **volume**, **hull_area** and **hull_volume** are
+/// `0`. 3-D clusters, whose samples all lie in one plane, have the 2-D hull within that plane (with both of its sides
+/// as surface area) and a **hull_volume** of `0`.
+#[derive(Clone, Debug)]
+pub struct ClusterExtent<T: Primitive> {
+    pub count: usize,
+    pub min: Vec<T>,
+    pub max: Vec<T>,
+    pub volume: T,
+    pub hull: Option<Vec<usize>>,
+    pub hull_area: Option<T>,
+    pub hull_volume: Option<T>,
+}
+
+/// Cross product of the vectors `o -> a` and `o -> b` (positive for a counter-clockwise turn).
+fn cross<T: Primitive>(o: [T; 2], a: [T; 2], b: [T; 2]) -> T { (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0]) }
+
+/// Convex hull of the given samples (monotone chain algorithm).
+/// ## Returns
+/// Tuple of (sample indices on the hull in counter-clockwise order, area of the hull)
+fn convex_hull<T: Primitive>(mut points: Vec<(usize, [T; 2])>) -> (Vec<usize>, T) {
+    points.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+    points.dedup_by(|(_, a), (_, b)| a == b);
+    if points.len() < 3 {
+        return (points.into_iter().map(|(idx, _)| idx).collect(), T::zero());
+    }
+
+    let mut hull: Vec<(usize, [T; 2])> = Vec::with_capacity(2 * points.len());
+    // Lower hull from left to right
+    for &point in points.iter() {
+        while hull.len() >= 2 && cross(hull[hull.len() - 2].1, hull[hull.len() - 1].1, point.1) <= T::zero() {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    // Upper hull from right to left
+    let lower_len = hull.len() + 1;
+    for &point in points.iter().rev().skip(1) {
+        while hull.len() >= lower_len && cross(hull[hull.len() - 2].1, hull[hull.len() - 1].1, point.1) <= T::zero() {
+            hull.pop();
+        }
+        hull.push(point);
+    }
+    // The last point is the first point again
+    hull.pop();
+
+    let area = (0..hull.len())
+        .map(|i| {
+            let (a, b) = (hull[i].1, hull[(i + 1) % hull.len()].1);
+            a[0] * b[1] - b[0] * a[1]
+        })
+        .sum::<T>()
+        / T::from(2).unwrap();
+    (hull.into_iter().map(|(idx, _)| idx).collect(), area)
+}
+
+fn sub<T: Primitive>(a: [T; 3], b: [T; 3]) -> [T; 3] { [a[0] - b[0], a[1] - b[1], a[2] - b[2]] }
+
+fn dot<T: Primitive>(a: [T; 3], b: [T; 3]) -> T { a[0] * b[0] + a[1] * b[1] + a[2] * b[2] }
+
+fn cross3<T: Primitive>(a: [T; 3], b: [T; 3]) -> [T; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+/// Convex hull of the given 3-D samples (incremental algorithm, `O(n * faces)`).
+/// ## Returns
+/// Tuple of (sample indices of the hull's corners in ascending order, surface area of the hull, volume of the hull)
+fn convex_hull_3d<T: Primitive>(mut points: Vec<(usize, [T; 3])>) -> (Vec<usize>, T, T) {
+    points.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+    points.dedup_by(|(_, a), (_, b)| a == b);
+    let corners = |mut hull: Vec<usize>| {
+        hull.sort_unstable();
+        hull
+    };
+    if points.len() < 3 {
+        return (corners(points.into_iter().map(|(idx, _)| idx).collect()), T::zero(), T::zero());
+    }
+
+    // Tolerance of the orientation tests, relative to the extent of the samples
+    let scale = (0..3)
+        .map(|d| {
+            let (min, max) = points.iter().fold((T::infinity(), T::neg_infinity()), |(min, max), (_, p)| {
+                (min.min(p[d]), max.max(p[d]))
+            });
+            max - min
+        })
+        .fold(T::zero(), T::max);
+    let rel_tol = T::epsilon() * T::from(64).unwrap();
+    let tol = rel_tol * scale * scale * scale;
+    let p = |i: usize| points[i].1;
+
+    // Initial tetrahedron of four samples, that do not lie in one plane
+    let farthest = |score: &dyn Fn([T; 3]) -> T| {
+        (0..points.len())
+            .map(|i| (i, score(p(i))))
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .unwrap()
+    };
+    let (i1, _) = farthest(&|x| dot(sub(x, p(0)), sub(x, p(0))));
+    let (i2, _) = farthest(&|x| {
+        let normal = cross3(sub(p(i1), p(0)), sub(x, p(0)));
+        dot(normal, normal)
+    });
+    let normal = cross3(sub(p(i1), p(0)), sub(p(i2), p(0)));
+    let (i3, offset) = farthest(&|x| dot(normal, sub(x, p(0))).abs());
+    if dot(normal, normal) <= tol * tol / (scale * scale) {
+        // All samples lie on one line, whose ends are the first and last sample in lexicographic order
+        return (corners(vec![points[0].0, points[points.len() - 1].0]), T::zero(), T::zero());
+    }
+    if offset <= tol {
+        // All samples lie in one plane: 2-D hull within the plane, in orthonormal coordinates of the plane
+        let u = sub(p(i1), p(0));
+        let v = cross3(normal, u);
+        let (u_len, v_len) = (dot(u, u).sqrt(), dot(v, v).sqrt());
+        let projected = points
+            .iter()
+            .map(|&(idx, x)| (idx, [dot(sub(x, p(0)), u) / u_len, dot(sub(x, p(0)), v) / v_len]))
+            .collect();
+        let (hull, area) = convex_hull(projected);
+        return (corners(hull), T::from(2).unwrap() * area, T::zero());
+    }
+
+    // Faces are oriented counter-clockwise seen from outside, so that the normal cross(b - a, c - a) points outwards
+    let orientation = |[a, b, c]: [usize; 3], x: [T; 3]| dot(cross3(sub(p(b), p(a)), sub(p(c), p(a))), sub(x, p(a)));
+    let simplex = [0, i1, i2, i3];
+    let mut faces: Vec<[usize; 3]> = [[0, 1, 2, 3], [0, 1, 3, 2], [0, 2, 3, 1], [1, 2, 3, 0]]
+        .iter()
+        .map(|&[a, b, c, other]| {
+            let face = [simplex[a], simplex[b], simplex[c]];
+            match orientation(face, p(simplex[other])) > T::zero() {
+                true => [face[0], face[2], face[1]],
+                false => face,
+            }
+        })
+        .collect();
+    for i in (0..points.len()).filter(|i| !simplex.contains(i)) {
+        // Faces, in whose plane the sample lies, are replaced as well, so no degenerated faces are created
+        let (visible, hidden): (Vec<[usize; 3]>, Vec<[usize; 3]>) = faces.iter().partition(|&&f| orientation(f, p(i)) >= -tol);
+        if visible.is_empty() || visible.len() == faces.len() {
+            continue;
+        }
+        // The horizon consists of the edges of visible faces, whose opposite face is hidden
+        let edges = || visible.iter().flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)]);
+        let visible_edges: HashSet<(usize, usize)> = edges().collect();
+        faces = hidden;
+        faces.extend(edges().filter(|&(a, b)| !visible_edges.contains(&(b, a))).map(|(a, b)| [a, b, i]));
+    }
+
+    let half = T::from(0.5).unwrap();
+    let area = faces
+        .iter()
+        .map(|&[a, b, c]| {
+            let normal = cross3(sub(p(b), p(a)), sub(p(c), p(a)));
+            dot(normal, normal).sqrt() * half
+        })
+        .sum::<T>();
+    let volume = faces
+        .iter()
+        .map(|&[a, b, c]| dot(sub(p(a), p(0)), cross3(sub(p(b), p(0)), sub(p(c), p(0)))))
+        .sum::<T>()
+        / T::from(6).unwrap();
+
+    // Corners lie on at least three of the planes of the hull's faces, samples on edges on two, on faces on one
+    let planes: Vec<([T; 3], T)> = faces.iter().fold(Vec::new(), |mut planes, &[a, b, c]| {
+        let normal = cross3(sub(p(b), p(a)), sub(p(c), p(a)));
+        let length = dot(normal, normal).sqrt();
+        let normal = [normal[0] / length, normal[1] / length, normal[2] / length];
+        let offset = dot(normal, p(a));
+        let same = |(n, o): &([T; 3], T)| dot(sub(*n, normal), sub(*n, normal)) <= rel_tol && (*o - offset).abs() <= rel_tol * scale;
+        if !planes.iter().any(same) {
+            planes.push((normal, offset));
+        }
+        planes
+    });
+    let mut vertices: Vec<usize> = faces.iter().flatten().cloned().collect();
+    vertices.sort_unstable();
+    vertices.dedup();
+    let hull = vertices
+        .into_iter()
+        .filter(|&i| {
+            let on_plane = |(n, o): &&([T; 3], T)| (dot(*n, p(i)) - *o).abs() <= rel_tol * scale;
+            planes.iter().filter(on_plane).count() >= 3
+        })
+        .map(|i| points[i].0)
+        .collect();
+    (corners(hull), area, volume)
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>) -> Vec<ClusterExtent<T>>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let dims = kmean.sample_dims;
+    let mut extents: Vec<ClusterExtent<T>> = (0..state.k)
+        .map(|_| ClusterExtent {
+            count: 0,
+            min: vec![T::infinity(); dims],
+            max: vec![T::neg_infinity(); dims],
+            volume: T::zero(),
+            hull: None,
+            hull_area: None,
+            hull_volume: None,
+        })
+        .collect();
+    kmean.p_samples.iter().zip(state.assignments.iter().cloned()).for_each(|(s, c)| {
+        let extent = &mut extents[c];
+        extent.count += 1;
+        extent.min.iter_mut().zip(s.iter()).for_each(|(m, &v)| *m = m.min(v));
+        extent.max.iter_mut().zip(s.iter()).for_each(|(m, &v)| *m = m.max(v));
+    });
+    extents.iter_mut().filter(|e| e.count > 0).for_each(|e| {
+        e.volume = e
+            .min
+            .iter()
+            .zip(e.max.iter())
+            .map(|(&min, &max)| max - min)
+            .fold(T::one(), |v, extent| v * extent);
+    });
+
+    if dims == 2 {
+        let mut members = vec![Vec::new(); state.k];
+        kmean
+            .p_samples
+            .iter()
+            .zip(state.assignments.iter().cloned())
+            .enumerate()
+            .for_each(|(idx, (s, c))| members[c].push((idx, [s[0], s[1]])));
+        extents.iter_mut().zip(members).for_each(|(e, m)| {
+            let (hull, area) = convex_hull(m);
+            e.hull = Some(hull);
+            e.hull_area = Some(area);
+        });
+    } else if dims == 3 {
+        let mut members = vec![Vec::new(); state.k];
+        kmean
+            .p_samples
+            .iter()
+            .zip(state.assignments.iter().cloned())
+            .enumerate()
+            .for_each(|(idx, (s, c))| members[c].push((idx, [s[0], s[1], s[2]])));
+        extents.iter_mut().zip(members).for_each(|(e, m)| {
+            let (hull, area, volume) = convex_hull_3d(m);
+            e.hull = Some(hull);
+            e.hull_area = Some(area);
+            e.hull_volume = Some(volume);
+        });
+    }
+    extents
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn cluster_extents_2d() {
+        // A square with an inner and a collinear sample, and a far away pair of samples
+        let samples = vec![
+            0.0f64, 0.0, 2.0, 0.0, 2.0, 2.0, 0.0, 2.0, 1.0, 1.0, 1.0, 0.0, 20.0, 20.0, 21.0, 22.0,
+        ];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 8, 2, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(
+            2,
+            100,
+            KMeans::init_precomputed(vec![1.0, 1.0, 20.0, 20.0]),
+            &KMeansConfig::default(),
+        );
+        let extents = kmean.cluster_extents(&res);
+
+        assert_eq!(extents[0].count, 6);
+        assert_eq!((extents[0].min.clone(), extents[0].max.clone()), (vec![0.0, 0.0], vec![2.0, 2.0]));
+        assert_eq!(extents[0].volume, 4.0);
+        assert_eq!(extents[0].hull, Some(vec![0, 1, 2, 3]));
+        assert_eq!(extents[0].hull_area, Some(4.0));
+
+        // Two samples have no area, but a bounding box
+        assert_eq!(extents[1].hull, Some(vec![6, 7]));
+        assert_eq!(extents[1].hull_area, Some(0.0));
+        assert_eq!(extents[1].volume, 2.0);
+    }
+
+    #[test]
+    fn cluster_extents_3d() {
+        #[rustfmt::skip]
+        let samples = vec![
+            // A cube with an inner sample, and samples on an edge and on a face
+            0.0f64, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 2.0, 0.0, 2.0, 2.0, 0.0,
+            0.0, 0.0, 2.0, 2.0, 0.0, 2.0, 0.0, 2.0, 2.0, 2.0, 2.0, 2.0,
+            1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 2.0,
+            // Two samples on a line
+            10.0, 10.0, 10.0, 11.0, 12.0, 13.0,
+            // A rectangle in a tilted plane, with a sample in its center
+            20.0, 20.0, 20.0, 22.0, 20.0, 22.0, 22.0, 22.0, 22.0, 20.0, 22.0, 20.0, 21.0, 21.0, 21.0,
+        ];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 18, 3, EuclideanDistance);
+        let init = KMeans::init_precomputed(vec![1.0, 1.0, 1.0, 10.5, 11.0, 11.5, 21.0, 21.0, 21.0]);
+        let res = kmean.kmeans_lloyd(3, 100, init, &KMeansConfig::default());
+        assert_eq!(res.assignments, vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 2, 2, 2, 2, 2]);
+        let extents = kmean.cluster_extents(&res);
+
+        assert_eq!(extents[0].volume, 8.0);
+        assert_eq!(extents[0].hull, Some((0..8).collect()));
+        assert!((extents[0].hull_area.unwrap() - 24.0).abs() < 1e-12);
+        assert!((extents[0].hull_volume.unwrap() - 8.0).abs() < 1e-12);
+
+        // Two samples have neither a surface nor a volume, but a bounding box
+        assert_eq!(extents[1].volume, 6.0);
+        assert_eq!(extents[1].hull, Some(vec![11, 12]));
+        assert_eq!((extents[1].hull_area, extents[1].hull_volume), (Some(0.0), Some(0.0)));
+
+        // A flat cluster has both sides of the rectangle (with edge lengths 2 and sqrt(8)) as surface
+        assert_eq!(extents[2].hull, Some(vec![13, 14, 15, 16]));
+        assert!((extents[2].hull_area.unwrap() - 2.0 * 2.0 * 8.0f64.sqrt()).abs() < 1e-9);
+        assert_eq!(extents[2].hull_volume, Some(0.0));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+
+/// Per-dimension diagnostic, explaining which dimensions distinguish the clusters of a k-means result.
+///
+/// ## Fields
+/// - **variance_ratio**: Per dimension: ratio between the between-cluster variance and the within-cluster variance.
+///   High values mean that the dimension separates the clusters well, values near `0` that it does not contribute.
+///   Dimensions without any within-cluster variance get a ratio of infinity (or `0`, if they are constant overall).
+/// - **centroid_profiles**: Z-scored centroids [row-major] = [<centroid0>,<centroid1>,...], where each dimension is
+///   given in standard deviations from the global mean of all samples (`0` for constant dimensions)
+#[derive(Clone, Debug)]
+pub struct FeatureImportance<T: Primitive> {
+    pub variance_ratio: Vec<T>,
+    pub centroid_profiles: Vec<T>,
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>) -> FeatureImportance<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let (k, dims) = (state.k, kmean.sample_dims);
+    let sample_cnt = T::from(kmean.sample_cnt).unwrap();
+
+    // Global and per-cluster means (first pass)
+    let mut global_mean = vec![T::zero(); dims];
+    let mut cluster_mean = vec![T::zero(); k * dims];
+    let mut cluster_cnt = vec![0usize; k];
+    kmean.p_samples.iter().zip(state.assignments.iter().cloned()).for_each(|(s, c)| {
+        cluster_cnt[c] += 1;
+        for d in 0..dims {
+            global_mean[d] += s[d];
+            cluster_mean[c * dims + d] += s[d];
+        }
+    });
+    global_mean.iter_mut().for_each(|m| *m = *m / sample_cnt);
+    for c in 0..k {
+        if cluster_cnt[c] > 0 {
+            let cnt = T::from(cluster_cnt[c]).unwrap();
+            cluster_mean[c * dims..(c + 1) * dims].iter_mut().for_each(|m| *m = *m / cnt);
+        }
+    }
+
+    // Total and within-cluster sum of squares (second pass)
+    let mut total_ss = vec![T::zero(); dims];
+    let mut within_ss = vec![T::zero(); dims];
+    kmean.p_samples.iter().zip(state.assignments.iter().cloned()).for_each(|(s, c)| {
+        for d in 0..dims {
+            let (dg, dc) = (s[d] - global_mean[d], s[d] - cluster_mean[c * dims + d]);
+            total_ss[d] += dg * dg;
+            within_ss[d] += dc * dc;
+        }
+    });
+
+    let variance_ratio = (0..dims)
+        .map(|d| {
+            let between_ss = (0..k)
+                .map(|c| {
+                    let diff = cluster_mean[c * dims + d] - global_mean[d];
+                    T::from(cluster_cnt[c]).unwrap() * diff * diff
+                })
+                .sum::<T>();
+            if within_ss[d] > T::zero() {
+                between_ss / within_ss[d]
+            } else if between_ss > T::zero() {
+                T::infinity()
+            } else {
+                T::zero()
+            }
+        })
+        .collect();
+
+    let global_std: Vec<T> = total_ss.iter().map(|&ss| (ss / sample_cnt).sqrt()).collect();
+    let centroid_profiles = state
+        .centroids
+        .iter()
+        .flat_map(|c| {
+            c.iter().zip(global_mean.iter().zip(global_std.iter())).map(
+                |(&cv, (&mean, &std))| {
+                    if std > T::zero() {
+                        (cv - mean) / std
+                    } else {
+                        T::zero()
+                    }
+                },
+            )
+        })
+        .collect();
+
+    FeatureImportance {
+        variance_ratio,
+        centroid_profiles,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn feature_importance() {
+        // Dimension 0 separates both clusters, dimension 1 is noise, dimension 2 is constant
+        let samples = vec![0.0f64, 1.0, 5.0, 0.0, -1.0, 5.0, 10.0, 1.0, 5.0, 10.0, -1.0, 5.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 4, 3, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(
+            2,
+            100,
+            KMeans::init_precomputed(vec![0.0, 0.0, 5.0, 10.0, 0.0, 5.0]),
+            &KMeansConfig::default(),
+        );
+
+        let importance = kmean.feature_importance(&res);
+        assert_eq!(importance.variance_ratio, vec![f64::INFINITY, 0.0, 0.0]);
+        assert_eq!(importance.centroid_profiles, vec![-1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use rayon::prelude::*;
+
+/// Distribution of the relative assignment margins of a k-means result, as calculated by
+/// [`KMeans::margin_histogram`].
+///
+/// The relative margin of a sample is `(d2 - d1) / d1`, where `d1` is its distance to the nearest centroid and `d2`
+/// its distance to the runner-up centroid. Margins close to `0` mark samples on the border between two clusters,
+/// which an approximate assignment (e.g. [`KMeans::with_lsh_prefilter`]) may easily assign to the wrong cluster.
+///
+/// ## Fields
+/// - **bin_edges**: Edges of the equally wide bins, starting at `0` (one more than there are bins)
+/// - **counts**: Amount of samples with a margin within each bin (`bin_edges[i] <= margin < bin_edges[i + 1]`)
+/// - **overflow**: Amount of samples with a margin of at least the last edge (including infinite margins of samples
+///   located exactly on their centroid)
+/// - **median**: Median margin of all samples
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarginHistogram<T: Primitive> {
+    pub bin_edges: Vec<T>,
+    pub counts: Vec<usize>,
+    pub overflow: usize,
+    pub median: T,
+}
+impl<T: Primitive> MarginHistogram<T> {
+    /// Share of all samples, whose margin is below the edge at the end of the bin **bin** (e.g. to judge how many
+    /// samples are at risk of a wrong approximate assignment).
+    pub fn fraction_below_edge(&self, bin: usize) -> f64 {
+        let total = self.counts.iter().sum::<usize>() + self.overflow;
+        self.counts[..=bin].iter().sum::<usize>() as f64 / total as f64
+    }
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>, bins: usize, max_margin: T,
+) -> MarginHistogram<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    assert!(state.k >= 2, "margins require at least two centroids");
+    assert!(bins > 0 && max_margin > T::zero());
+    let squared = kmean.distance_fn.is_squared_euclidean();
+    let distance = |d: T| if squared { d.sqrt() } else { d };
+
+    let mut margins: Vec<T> = kmean
+        .p_samples
+        .bfr
+        .par_chunks_exact(kmean.p_samples.stride)
+        .map(|s| {
+            let (best, second) = state
+                .centroids
+                .chunks_exact_stride()
+                .fold((T::infinity(), T::infinity()), |(best, second), c| {
+                    let dist = kmean.distance_fn.distance(s, c);
+                    match dist < best {
+                        true => (dist, best),
+                        false => (best, second.min(dist)),
+                    }
+                });
+            let (best, second) = (distance(best), distance(second));
+            match best > T::zero() {
+                true => (second - best) / best,
+                false if second > T::zero() => T::infinity(),
+                // Two centroids at the same position as the sample
+                false => T::zero(),
+            }
+        })
+        .collect();
+
+    let bin_width = max_margin / T::from(bins).unwrap();
+    let bin_edges: Vec<T> = (0..=bins).map(|i| bin_width * T::from(i).unwrap()).collect();
+    let mut counts = vec![0; bins];
+    let mut overflow = 0;
+    margins.iter().for_each(|&margin| match margin < max_margin {
+        true => counts[((margin / bin_width).to_usize().unwrap()).min(bins - 1)] += 1,
+        false => overflow += 1,
+    });
+    let mid = margins.len() / 2;
+    let median = *margins.select_nth_unstable_by(mid, |a, b| a.partial_cmp(b).unwrap()).1;
+    MarginHistogram {
+        bin_edges,
+        counts,
+        overflow,
+        median,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn margin_histogram() {
+        let samples = vec![0.0f64, 1.0, 4.0, 6.0, 9.0, 10.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &KMeansConfig::default());
+        res.centroids
+            .to_vec()
+            .iter()
+            .zip([5.0 / 3.0, 25.0 / 3.0])
+            .for_each(|(c, expected)| assert!((c - expected).abs() < 1e-12, "{c} != {expected}"));
+
+        // Euclidean (not squared) margins: samples 4 and 6 are 7/3 from their centroid and 13/3 from the other one,
+        // all others have margins of 4 (samples 0 and 10) or 10 (samples 1 and 9)
+        let histogram = kmean.margin_histogram(&res, 4, 2.0);
+        assert_eq!(histogram.bin_edges, vec![0.0, 0.5, 1.0, 1.5, 2.0]);
+        assert_eq!(histogram.counts, vec![0, 2, 0, 0]);
+        assert_eq!(histogram.overflow, 4);
+        assert!((histogram.median - 4.0).abs() < 1e-12);
+        assert_eq!(histogram.fraction_below_edge(0), 0.0);
+        assert_eq!(histogram.fraction_below_edge(1), 2.0 / 6.0);
+    }
+}
@@ -0,0 +1,23 @@
+pub(crate) mod anomaly;
+pub(crate) mod comparison;
+pub(crate) mod drift;
+pub(crate) mod exemplars;
+pub(crate) mod extents;
+pub(crate) mod feature_importance;
+pub(crate) mod margins;
+pub(crate) mod profiles;
+pub(crate) mod radius;
+pub(crate) mod reassignment;
+pub(crate) mod relabel;
+pub(crate) mod stratified;
+
+pub use comparison::{CentroidMatch, ResultComparison};
+pub use drift::DriftStatistics;
+pub use exemplars::Exemplar;
+pub use extents::ClusterExtent;
+pub use feature_importance::FeatureImportance;
+pub use margins::MarginHistogram;
+pub use profiles::ClusterProfile;
+pub use radius::ClusterRadius;
+pub use reassignment::ReassignmentCost;
+pub use stratified::StratifiedSampling;
@@ -0,0 +1,153 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SimdFloat, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use rayon::prelude::*;
+
+/// Descriptive statistics of one cluster of a k-means result.
+///
+/// ## Fields
+/// - **count**: Amount of samples in the cluster
+/// - **mean**: Per-dimension mean of the cluster's samples
+/// - **std**: Per-dimension (population) standard deviation of the cluster's samples
+/// - **min**: Per-dimension minimum of the cluster's samples
+/// - **max**: Per-dimension maximum of the cluster's samples
+///
+/// For empty clusters, **mean** and **std** are `NaN`, while **min** / **max** are `+inf` / `-inf`.
+#[derive(Clone, Debug)]
+pub struct ClusterProfile<T: Primitive> {
+    pub count: usize,
+    pub mean: Vec<T>,
+    pub std: Vec<T>,
+    pub min: Vec<T>,
+    pub max: Vec<T>,
+}
+
+/// Per-thread accumulator, storing all statistics as SIMD vectors (`cluster * simd_per_sample + i`)
+struct ProfileAccumulator<T: Primitive, const LANES: usize>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    count: Vec<usize>,
+    sum: Vec<Simd<T, LANES>>,
+    sum_sq: Vec<Simd<T, LANES>>,
+    min: Vec<Simd<T, LANES>>,
+    max: Vec<Simd<T, LANES>>,
+}
+impl<T: Primitive, const LANES: usize> ProfileAccumulator<T, LANES>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    fn new(k: usize, simd_per_sample: usize) -> Self {
+        let size = k * simd_per_sample;
+        Self {
+            count: vec![0; k],
+            sum: vec![Simd::splat(T::zero()); size],
+            sum_sq: vec![Simd::splat(T::zero()); size],
+            min: vec![Simd::splat(T::infinity()); size],
+            max: vec![Simd::splat(T::neg_infinity()); size],
+        }
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        self.count.iter_mut().zip(other.count).for_each(|(a, b)| *a += b);
+        self.sum.iter_mut().zip(other.sum).for_each(|(a, b)| *a += b);
+        self.sum_sq.iter_mut().zip(other.sum_sq).for_each(|(a, b)| *a += b);
+        self.min.iter_mut().zip(other.min).for_each(|(a, b)| *a = a.simd_min(b));
+        self.max.iter_mut().zip(other.max).for_each(|(a, b)| *a = a.simd_max(b));
+        self
+    }
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>) -> Vec<ClusterProfile<T>>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let (k, stride, dims) = (state.k, kmean.p_samples.stride, kmean.sample_dims);
+    let simd_per_sample = stride / LANES;
+
+    let acc = kmean
+        .p_samples
+        .bfr
+        .par_chunks_exact(stride)
+        .zip(state.assignments.par_iter().cloned())
+        .fold(
+            || ProfileAccumulator::<T, LANES>::new(k, simd_per_sample),
+            |mut acc, (s, c)| {
+                acc.count[c] += 1;
+                let offset = c * simd_per_sample;
+                s.chunks_exact(LANES).map(|v| Simd::from_slice(v)).enumerate().for_each(|(i, v)| {
+                    acc.sum[offset + i] += v;
+                    acc.sum_sq[offset + i] += v * v;
+                    acc.min[offset + i] = acc.min[offset + i].simd_min(v);
+                    acc.max[offset + i] = acc.max[offset + i].simd_max(v);
+                });
+                acc
+            },
+        )
+        .reduce(|| ProfileAccumulator::new(k, simd_per_sample), ProfileAccumulator::merge);
+
+    // Flatten a cluster's SIMD vectors back into its (unpadded) dimensions
+    let unpack = |bfr: &[Simd<T, LANES>], c: usize| -> Vec<T> {
+        bfr[c * simd_per_sample..(c + 1) * simd_per_sample]
+            .iter()
+            .flat_map(|v| *v.as_array())
+            .take(dims)
+            .collect()
+    };
+    (0..k)
+        .map(|c| {
+            let count = acc.count[c];
+            let cnt = T::from(count).unwrap();
+            let mean: Vec<T> = unpack(&acc.sum, c).into_iter().map(|s| s / cnt).collect();
+            let std = unpack(&acc.sum_sq, c)
+                .into_iter()
+                .zip(mean.iter())
+                .map(|(sq, &m)| (sq / cnt - m * m).max(T::zero()).sqrt())
+                .collect();
+            ClusterProfile {
+                count,
+                mean,
+                std,
+                min: unpack(&acc.min, c),
+                max: unpack(&acc.max, c),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn cluster_profiles() {
+        let samples = vec![0.0f64, 1.0, 2.0, 3.0, 10.0, -1.0, 12.0, -3.0, 14.0, -5.0];
+        let kmean: KMeans<f64, 4, _> = KMeans::new(&samples, 5, 2, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(
+            2,
+            100,
+            KMeans::init_precomputed(vec![1.0, 2.0, 12.0, -3.0]),
+            &KMeansConfig::default(),
+        );
+
+        let profiles = kmean.cluster_profiles(&res);
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].count, 2);
+        assert_eq!(profiles[0].mean, vec![1.0, 2.0]);
+        assert_eq!(profiles[0].std, vec![1.0, 1.0]);
+        assert_eq!(profiles[0].min, vec![0.0, 1.0]);
+        assert_eq!(profiles[0].max, vec![2.0, 3.0]);
+        assert_eq!(profiles[1].count, 3);
+        assert_eq!(profiles[1].mean, vec![12.0, -3.0]);
+        assert!((profiles[1].std[0] - (8.0f64 / 3.0).sqrt()).abs() < 1e-10);
+        assert_eq!(profiles[1].min, vec![10.0, -5.0]);
+        assert_eq!(profiles[1].max, vec![14.0, -1.0]);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use rayon::prelude::*;
+
+/// Compactness of one cluster of a k-means result.
+///
+/// ## Fields
+/// - **count**: Amount of samples in the cluster
+/// - **mean_radius**: Mean distance of the cluster's samples to their centroid
+/// - **max_radius**: Maximum distance of the cluster's samples to their centroid
+/// - **diameter**: If requested: Approximate diameter of the cluster, as the distance between the sample farthest from
+///   the centroid, and the sample farthest from that one. This is a lower bound of the true diameter.
+///
+/// All distances are as returned by the distance function (e.g. squared for [`crate::EuclideanDistance`]).
+/// For empty clusters, all values are `0`.
+#[derive(Clone, Debug)]
+pub struct ClusterRadius<T: Primitive> {
+    pub count: usize,
+    pub mean_radius: T,
+    pub max_radius: T,
+    pub diameter: Option<T>,
+}
+
+/// Count, mean radius, max radius and the sample with the max radius of every cluster.
+fn radius_stats<T: Primitive>(state: &KMeansState<T>) -> Vec<(usize, T, T, Option<usize>)> {
+    let mut stats = vec![(0, T::zero(), T::zero(), None); state.k];
+    state
+        .assignments
+        .iter()
+        .cloned()
+        .zip(state.centroid_distances.iter().cloned())
+        .enumerate()
+        .for_each(|(idx, (assignment, dist))| {
+            let (cnt, sum, max, farthest) = &mut stats[assignment];
+            *cnt += 1;
+            *sum += dist;
+            if farthest.is_none() || dist > *max {
+                *max = dist;
+                *farthest = Some(idx);
+            }
+        });
+    stats.iter_mut().filter(|(cnt, ..)| *cnt > 0).for_each(|(cnt, sum, ..)| {
+        *sum = *sum / T::from(*cnt).unwrap();
+    });
+    stats
+}
+
+/// Radius of each cluster, as the mean distance of the cluster's members to their centroid.
+pub(crate) fn mean_radii<T: Primitive>(state: &KMeansState<T>) -> Vec<T> {
+    radius_stats(state).into_iter().map(|(_, mean, ..)| mean).collect()
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>, with_diameter: bool,
+) -> Vec<ClusterRadius<T>>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let stats = radius_stats(state);
+    let diameters = with_diameter.then(|| {
+        // Distance of every sample to the sample farthest from its cluster's centroid
+        let distances: Vec<T> = kmean
+            .p_samples
+            .bfr
+            .par_chunks_exact(kmean.p_samples.stride)
+            .zip(state.assignments.par_iter().cloned())
+            .map(|(s, assignment)| {
+                let farthest = stats[assignment].3.unwrap();
+                kmean.distance_fn.distance(s, kmean.p_samples.nth_stride(farthest))
+            })
+            .collect();
+        let mut diameters = vec![T::zero(); state.k];
+        state.assignments.iter().zip(distances).for_each(|(&assignment, dist)| {
+            diameters[assignment] = diameters[assignment].max(dist);
+        });
+        diameters
+    });
+    stats
+        .into_iter()
+        .enumerate()
+        .map(|(c, (count, mean_radius, max_radius, _))| ClusterRadius {
+            count,
+            mean_radius,
+            max_radius,
+            diameter: diameters.as_ref().map(|d| d[c]),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn cluster_radii() {
+        let samples = vec![0.0f64, 1.0, 3.0, 100.0, 101.0, 102.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 100.0]), &KMeansConfig::default());
+        assert_eq!(res.centroids.to_vec(), vec![4.0 / 3.0, 101.0]);
+
+        let radii = kmean.cluster_radii(&res, true);
+        assert_eq!(radii.len(), 2);
+        assert_eq!(radii[0].count, 3);
+        assert!((radii[0].mean_radius - (16.0 + 1.0 + 25.0) / 27.0).abs() < 1e-10);
+        assert!((radii[0].max_radius - 25.0 / 9.0).abs() < 1e-10);
+        assert_eq!(radii[0].diameter, Some(9.0));
+        assert_eq!(
+            (radii[1].mean_radius, radii[1].max_radius, radii[1].diameter),
+            (2.0 / 3.0, 1.0, Some(4.0))
+        );
+        assert!(kmean.cluster_radii(&res, false).iter().all(|r| r.diameter.is_none()));
+
+        // Empty cluster
+        let mut state = res.clone();
+        state.assignments = vec![0; samples.len()];
+        let radii = kmean.cluster_radii(&state, true);
+        assert_eq!(
+            (radii[1].count, radii[1].mean_radius, radii[1].max_radius, radii[1].diameter),
+            (0, 0.0, 0.0, Some(0.0))
+        );
+    }
+}
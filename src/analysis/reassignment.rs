@@ -0,0 +1,89 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use rayon::prelude::*;
+
+/// Cost of moving one sample from its cluster into its second-best cluster.
+///
+/// ## Fields
+/// - **cluster**: The second-best cluster of the sample (nearest centroid, besides the one it is assigned to)
+/// - **sse_delta**: Change of the total sum of squared distances, if the sample was moved into **cluster** and both
+///   centroids were updated accordingly. Values close to `0` indicate samples at the boundary between two clusters,
+///   while negative values indicate samples that are not at a local optimum yet.
+#[derive(Clone, Debug)]
+pub struct ReassignmentCost<T: Primitive> {
+    pub cluster: usize,
+    pub sse_delta: T,
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>) -> Vec<ReassignmentCost<T>>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    assert!(state.k > 1);
+    let centroids = &state.centroids;
+    let frequency = &state.centroid_frequency;
+    kmean
+        .p_samples
+        .bfr
+        .par_chunks_exact(kmean.p_samples.stride)
+        .zip(state.assignments.par_iter().cloned())
+        .zip(state.centroid_distances.par_iter().cloned())
+        .map(|((s, assignment), dist)| {
+            let (cluster, second_dist) = centroids
+                .chunks_exact_stride()
+                .enumerate()
+                .filter(|&(c, _)| c != assignment)
+                .map(|(c, centroid)| (c, kmean.distance_fn.distance(s, centroid)))
+                .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+                .unwrap();
+
+            // Moving the sample changes the mean of both clusters (Hartigan's update):
+            // sse_delta = n_b / (n_b + 1) * d(s, c_b) - n_a / (n_a - 1) * d(s, c_a)
+            let (n_a, n_b) = (T::from(frequency[assignment]).unwrap(), T::from(frequency[cluster]).unwrap());
+            let gain = n_b / (n_b + T::one()) * second_dist;
+            let loss = if frequency[assignment] > 1 {
+                n_a / (n_a - T::one()) * dist
+            } else {
+                T::zero()
+            };
+            ReassignmentCost {
+                cluster,
+                sse_delta: gain - loss,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn reassignment_costs() {
+        let samples = vec![0.0f64, 1.0, 2.0, 5.0, 10.0, 11.0, 12.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &KMeansConfig::default());
+        assert_eq!(res.centroids.to_vec(), vec![2.0, 11.0]);
+
+        let costs = kmean.reassignment_costs(&res);
+        assert_eq!(costs.iter().map(|c| c.cluster).collect::<Vec<_>>(), vec![1, 1, 1, 1, 0, 0, 0]);
+        // Moving 5.0 into the other cluster: 3/4 * 36 - 4/3 * 9
+        assert!((costs[3].sse_delta - 15.0).abs() < 1e-10);
+
+        // Compare against the brute-force recalculation of the sum of squared distances
+        let sse = |a: &[f64]| {
+            let mean = a.iter().sum::<f64>() / a.len() as f64;
+            a.iter().map(|v| (v - mean).powi(2)).sum::<f64>()
+        };
+        let moved = sse(&[0.0, 1.0, 2.0]) + sse(&[5.0, 10.0, 11.0, 12.0]);
+        assert!((costs[3].sse_delta - (moved - res.distsum)).abs() < 1e-10);
+        // The sample at the boundary is the cheapest to move
+        assert!(costs.iter().all(|c| c.sse_delta >= costs[3].sse_delta));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::analysis::comparison::contingency_matrix;
+use crate::memory::*;
+use crate::{helpers, KMeansState};
+
+#[inline(always)]
+pub fn calculate<T: Primitive>(state: &mut KMeansState<T>, reference_labels: &[usize]) -> Vec<usize> {
+    assert_eq!(state.assignments.len(), reference_labels.len());
+    let k = state.k;
+    let k_ref = reference_labels.iter().cloned().max().map_or(0, |m| m + 1);
+
+    // Maximize agreement = minimize the negated contingency counts
+    let contingency = contingency_matrix(&state.assignments, k, reference_labels, k_ref);
+    let cost: Vec<f64> = contingency.iter().map(|&cnt| -(cnt as f64)).collect();
+    let matching = helpers::hungarian(&cost, k, k_ref);
+
+    // Clusters take over the id of their matched reference label. Clusters without a (usable) match get the
+    // remaining free ids, in ascending order.
+    let mut permutation = vec![usize::MAX; k];
+    let mut id_used = vec![false; k];
+    for (c, label) in matching.iter().enumerate() {
+        if let Some(label) = *label {
+            if label < k {
+                permutation[c] = label;
+                id_used[label] = true;
+            }
+        }
+    }
+    let mut free_ids = (0..k).filter(|&id| !id_used[id]);
+    permutation
+        .iter_mut()
+        .filter(|p| **p == usize::MAX)
+        .for_each(|p| *p = free_ids.next().unwrap());
+
+    apply_permutation(state, &permutation);
+    permutation
+}
+
+/// Rename all clusters of the given state, with cluster `c` getting the new id `permutation[c]`.
+pub(crate) fn apply_permutation<T: Primitive>(state: &mut KMeansState<T>, permutation: &[usize]) {
+    let old_centroids = state.centroids.clone();
+    let old_frequency = state.centroid_frequency.clone();
+    let old_distsum = state.per_cluster_distsum.clone();
+    for (c, &new_c) in permutation.iter().enumerate() {
+        state.centroids.nth_stride_mut(new_c).copy_from_slice(old_centroids.nth_stride(c));
+        state.centroid_frequency[new_c] = old_frequency[c];
+        // Results deserialized from older versions have no per-cluster distsums
+        if let Some(&distsum) = old_distsum.get(c) {
+            state.per_cluster_distsum[new_c] = distsum;
+        }
+    }
+    state.assignments.iter_mut().for_each(|a| *a = permutation[*a]);
+    if let Some(repair) = state.cluster_size_repair.as_mut() {
+        repair.id_map.iter_mut().flatten().for_each(|id| *id = permutation[*id]);
+    }
+    if let Some(snapping) = state.centroid_snapping.as_mut() {
+        let old_exemplars = snapping.exemplars.clone();
+        permutation
+            .iter()
+            .zip(old_exemplars)
+            .for_each(|(&new_c, e)| snapping.exemplars[new_c] = e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn relabel_to_match() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0, 20.0, 21.0, 22.0];
+        let weights = vec![1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 3.0, 3.0, 3.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance).with_sample_weights(&weights);
+        let mut res = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![20.0, 0.0, 10.0]), &KMeansConfig::default());
+        assert_eq!(res.assignments, vec![1, 1, 1, 2, 2, 2, 0, 0, 0]);
+        assert_eq!(res.cluster_inertias(), &[6.0, 2.0, 4.0]);
+
+        // Reference labeling with one "mislabeled" sample
+        let reference = vec![0, 0, 0, 1, 1, 2, 2, 2, 2];
+        let permutation = res.relabel_to_match(&reference);
+        assert_eq!(permutation, vec![2, 0, 1]);
+        assert_eq!(res.assignments, vec![0, 0, 0, 1, 1, 1, 2, 2, 2]);
+        assert_eq!(res.centroids.to_vec(), vec![1.0, 11.0, 21.0]);
+        assert_eq!(res.centroid_frequency, vec![3, 3, 3]);
+        assert_eq!(res.cluster_inertias(), &[2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn relabel_to_match_fewer_reference_labels() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0, 20.0, 21.0, 22.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let mut res = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![20.0, 0.0, 10.0]), &KMeansConfig::default());
+
+        let reference = vec![1, 1, 1, 0, 0, 0, 0, 0, 1];
+        res.relabel_to_match(&reference);
+        assert_eq!(res.assignments[0], 1);
+        assert_eq!(res.assignments[3], 0);
+        assert_eq!(res.assignments[6], 2);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::memory::*;
+use crate::KMeansState;
+use rand::prelude::*;
+
+/// Strategy for stratified subsampling of a k-means result (see [`KMeansState::stratified_sample`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StratifiedSampling {
+    /// Draw (up to) **n** samples from every cluster, resulting in a balanced subsample.
+    /// Clusters with less than **n** members are taken completely.
+    PerCluster(usize),
+    /// Draw **n** samples in total, distributed over the clusters proportionally to their sizes
+    /// (using largest-remainder rounding).
+    Proportional(usize),
+}
+
+#[inline(always)]
+pub fn calculate<T: Primitive, R: Rng + ?Sized>(state: &KMeansState<T>, strategy: StratifiedSampling, rnd: &mut R) -> Vec<Vec<usize>> {
+    let mut members = vec![Vec::new(); state.k];
+    state.assignments.iter().enumerate().for_each(|(idx, &c)| members[c].push(idx));
+
+    let quotas: Vec<usize> = match strategy {
+        StratifiedSampling::PerCluster(n) => members.iter().map(|m| m.len().min(n)).collect(),
+        StratifiedSampling::Proportional(n) => {
+            let sample_cnt = state.assignments.len();
+            let n = n.min(sample_cnt);
+            let exact: Vec<f64> = members.iter().map(|m| (n * m.len()) as f64 / sample_cnt as f64).collect();
+            let mut quotas: Vec<usize> = exact.iter().map(|e| e.floor() as usize).collect();
+            // Hand out the remaining samples to the clusters with the largest remainders
+            let mut by_remainder: Vec<usize> = (0..state.k).collect();
+            by_remainder.sort_by(|&a, &b| (exact[b] - quotas[b] as f64).partial_cmp(&(exact[a] - quotas[a] as f64)).unwrap());
+            let missing = n - quotas.iter().sum::<usize>();
+            by_remainder.into_iter().take(missing).for_each(|c| quotas[c] += 1);
+            quotas
+        },
+    };
+
+    members
+        .into_iter()
+        .zip(quotas)
+        .map(|(m, quota)| {
+            let mut drawn = m.choose_multiple(rnd, quota).cloned().collect::<Vec<_>>();
+            drawn.sort_unstable();
+            drawn
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn stratified_sample() {
+        let samples = vec![0.0f64, 1.0, 2.0, 3.0, 4.0, 5.0, 100.0, 101.0, 102.0, 103.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 100.0]), &KMeansConfig::default());
+        let mut rnd = rand::rngs::StdRng::seed_from_u64(1337);
+
+        let per_cluster = res.stratified_sample(StratifiedSampling::PerCluster(5), &mut rnd);
+        assert_eq!(per_cluster[0].len(), 5);
+        assert_eq!(per_cluster[1], vec![6, 7, 8, 9]);
+        assert!(per_cluster[0].iter().all(|&idx| res.assignments[idx] == 0));
+
+        let proportional = res.stratified_sample(StratifiedSampling::Proportional(5), &mut rnd);
+        assert_eq!(proportional.iter().map(|p| p.len()).collect::<Vec<_>>(), vec![3, 2]);
+        assert!(proportional[1].iter().all(|&idx| res.assignments[idx] == 1));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::variants::Lloyd;
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rand::prelude::*;
+
+/// (Weighted) standard deviation of the samples in every dimension. The perturbations are scaled with this, so they
+/// adapt to the spread of the samples in every dimension.
+fn deviation_scale<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>) -> Vec<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let (mut mean, mut sums) = (vec![T::zero(); kmean.sample_dims], vec![T::zero(); kmean.sample_dims]);
+    let mut total_weight = T::zero();
+    kmean.p_samples.iter().enumerate().for_each(|(sample_id, s)| {
+        let w = kmean.sample_weight(sample_id);
+        mean.iter_mut().zip(s.iter()).for_each(|(m, &v)| *m += v * w);
+        total_weight += w;
+    });
+    mean.iter_mut().for_each(|m| *m = *m / total_weight);
+    kmean.p_samples.iter().enumerate().for_each(|(sample_id, s)| {
+        let w = kmean.sample_weight(sample_id);
+        sums.iter_mut()
+            .zip(s.iter().zip(mean.iter()))
+            .for_each(|(sum, (&v, &m))| *sum += (v - m) * (v - m) * w);
+    });
+    sums.into_iter().map(|sum| (sum / total_weight).sqrt()).collect()
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D, F>(
+    kmean: &KMeans<T, LANES, D>, k: usize, rounds: usize, initial_noise: T, max_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+) -> KMeansState<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+    for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+{
+    assert!(initial_noise >= T::zero());
+    let mut best = Lloyd::calculate_unprocessed(kmean, k, max_iter, init, config);
+    let scale = deviation_scale(kmean);
+    // Uniform noise in [-w, w] has a standard deviation of w / sqrt(3)
+    let sqrt3 = T::from(3.0).unwrap().sqrt();
+
+    for round in 0..rounds {
+        // Linearly decaying noise, from initial_noise in the first round towards 0
+        let noise = initial_noise * T::from(rounds - round).unwrap() / T::from(rounds).unwrap();
+        let mut perturbed = best.centroids.clone();
+        {
+            let mut rnd = config.rnd.borrow_mut();
+            perturbed.iter_mut().for_each(|c| {
+                c.iter_mut().zip(scale.iter()).for_each(|(v, &s)| {
+                    let width = noise * s * sqrt3;
+                    if width > T::zero() {
+                        *v += rnd.gen_range(-width..width);
+                    }
+                })
+            });
+        }
+        let candidate = Lloyd::calculate_unprocessed(kmean, k, max_iter, move |_, state, _| state.centroids = perturbed, config);
+        if candidate.distsum < best.distsum {
+            best = candidate;
+        }
+    }
+
+    // The best result so far, and the candidate of the current round
+    let state_size = best.memory_usage.state;
+    best.memory_usage.record_temporaries(state_size);
+    crate::postprocessing::apply(kmean, &mut best, config);
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn annealing_improves_local_minimum() {
+        // Grid of 16 separated clusters, where single Lloyd runs from random samples get stuck in local minima
+        let mut rnd = StdRng::seed_from_u64(3);
+        let (per_cluster, k) = (50, 16);
+        let samples: Vec<f64> = (0..k * per_cluster)
+            .flat_map(|i| {
+                let cluster = i % k;
+                [(cluster % 4) as f64 * 10.0, (cluster / 4) as f64 * 10.0]
+            })
+            .map(|v| v + rnd.gen_range(-1.0..1.0))
+            .collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, k * per_cluster, 2, EuclideanDistance);
+        let conf = || KMeansConfig::build().random_generator(StdRng::seed_from_u64(7)).build();
+
+        // Without any rounds, this is a plain Lloyd calculation
+        let plain = kmean.kmeans_lloyd(k, 100, KMeans::init_random_sample, &conf());
+        let unperturbed = kmean.kmeans_annealing(k, 0, 1.0, 100, KMeans::init_random_sample, &conf());
+        assert_eq!(unperturbed.distsum, plain.distsum);
+
+        let res = kmean.kmeans_annealing(k, 30, 1.0, 100, KMeans::init_random_sample, &conf());
+        assert!(res.distsum < plain.distsum);
+    }
+}
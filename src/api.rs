@@ -1,17 +1,39 @@
+use crate::lsh::LshPrefilter;
 use crate::memory::*;
-use crate::AbortStrategy;
-use core::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::simd::{self, LaneCount, Simd, SupportedLaneCount};
+use crate::{
+    AbortStrategy, CentroidSnapping, CentroidTrajectory, ClusterExtent, ClusterProfile, ClusterRadius, ClusterSizeRepair,
+    DistanceStatistics, Divergence, DriftStatistics, Exemplar, FeatureImportance, FuzzyKMeansState, KMeansRun, KMedoidsState, KSweep,
+    KernelConfig, LearningSchedule, LshFamily, MarginHistogram, MemoryUsage, OverlappingKMeansState, Projection2D, ProjectionMethod,
+    ReassignmentCost, ResultComparison, RunManifest, SparseCentroids, StopReason, StratifiedSampling, LANES_F32, LANES_F64,
+};
 use rand::prelude::*;
+use rand::rngs::StdRng;
 use rayon::prelude::*;
-use std::cell::RefCell;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, MutexGuard};
 
-pub type InitDoneCallbackFn<'a, T> = &'a dyn Fn(&KMeansState<T>);
-pub type IterationDoneCallbackFn<'a, T> = &'a dyn Fn(&KMeansState<T>, usize, T);
+pub type InitDoneCallbackFn<'a, T> = Arc<dyn Fn(&KMeansState<T>) + Send + Sync + 'a>;
+pub type IterationDoneCallbackFn<'a, T> = Arc<dyn Fn(&KMeansState<T>, usize, T) + Send + Sync + 'a>;
+
+/// Random number generator of a [`KMeansConfig`], usable from multiple threads.
+pub(crate) struct ConfigRng(Mutex<Box<dyn RngCore + Send>>);
+impl ConfigRng {
+    fn new<R: RngCore + Send + 'static>(rnd: R) -> Self { Self(Mutex::new(Box::new(rnd))) }
+
+    #[inline(always)]
+    pub(crate) fn borrow_mut(&self) -> MutexGuard<'_, Box<dyn RngCore + Send>> { self.0.lock().unwrap() }
+}
 
 /// This is a structure holding various configuration options for the a k-means calculations, such as
 /// the random number generator to use, or a couple of callbacks, that can be set to get status information from
 /// a running k-means calculation.
 ///
+/// A [`KMeansConfig`] is [`Send`] + [`Sync`] and can be cloned, so one configuration can be used to spawn multiple
+/// calculations in parallel. Callbacks are shared between all clones, while every clone gets its own random number
+/// generator, seeded from the generator of the configuration it was cloned from. Cloning a configuration with a seeded
+/// generator thus still yields deterministically repeatable results.
+///
 /// For a more detailed information about all possible options, have a look at [`KMeansConfigBuilder`].
 pub struct KMeansConfig<'a, T: Primitive> {
     /// Callback that is called, when the initialization phase finished
@@ -25,19 +47,98 @@ pub struct KMeansConfig<'a, T: Primitive> {
     /// - **distsum**: New distance sum (**state** contains the distsum from the previous iteration)
     pub(crate) iteration_done: IterationDoneCallbackFn<'a, T>,
     /// Random number generator to use
-    pub(crate) rnd: Box<RefCell<dyn RngCore>>,
+    pub(crate) rnd: ConfigRng,
+    /// Seed of the random number generator, if it was seeded through [`KMeansConfigBuilder::seed`]
+    pub(crate) seed: Option<u64>,
     /// The abort-strategy to use for the running calculation
     pub(crate) abort_strategy: AbortStrategy<T>,
+    /// Token, that stops the running calculation after the current iteration once it is set
+    pub(crate) cancellation: Option<Arc<AtomicBool>>,
+    /// Minimum amount of samples per cluster, enforced after convergence (0 = disabled)
+    pub(crate) min_cluster_size: usize,
+    /// Replace every centroid with its nearest sample, after convergence
+    pub(crate) snap_to_samples: bool,
+    /// Learning-rate schedule of the online centroid updates
+    pub(crate) learning_schedule: LearningSchedule<T>,
+    /// Custom centroid update rule (**None** = mean, using the optimized built-in implementation)
+    pub(crate) centroid_updater: Option<Arc<dyn CentroidUpdater<T> + 'a>>,
+    /// Amount of Lloyd iterations between two split-merge refinement moves (0 = disabled)
+    pub(crate) split_merge_interval: usize,
+    /// Amount of iterations between two recorded snapshots of the centroids (0 = disabled)
+    pub(crate) trajectory_interval: usize,
+    /// Accumulate distance sums and centroid sums in f64 precision
+    pub(crate) high_precision: bool,
+    /// Accumulate distance sums and centroid sums using compensated (Kahan) summation
+    pub(crate) compensated_summation: bool,
+    /// Defer the mini-batch centroid updates, so every touched centroid is only moved once per batch
+    pub(crate) sparse_centroid_updates: bool,
+    /// Relative frequency below which mini-batch centroids are re-seeded (0 = disabled)
+    pub(crate) reassignment_ratio: T,
+    /// Amount of mini-batches without improvement of the smoothed batch inertia, after which to stop (0 = disabled)
+    pub(crate) max_no_improvement: usize,
+    /// Minimum amount of samples drawn from every current cluster into each mini-batch (0 = uniform batches)
+    pub(crate) stratified_min_per_cluster: usize,
+    /// Amount of samples the distance sum of intermediate iterations is estimated from (0 = exact)
+    pub(crate) inertia_sample_size: usize,
+    /// Amount of candidates per centroid of the greedy k-means++ (**None** = plain k-means++, 0 = `2 + ln(k)`)
+    pub(crate) greedy_kmeanplusplus: Option<usize>,
+    /// Convergence tolerance of the centroid movement and the relative distsum improvement (0 = disabled)
+    pub(crate) tol: T,
 }
 impl<T: Primitive> Default for KMeansConfig<'_, T> {
     fn default() -> Self {
         Self {
-            init_done: &|_| {},
-            iteration_done: &|_, _, _| {},
-            rnd: Box::new(RefCell::new(rand::thread_rng())),
+            init_done: Arc::new(|_| {}),
+            iteration_done: Arc::new(|_, _, _| {}),
+            rnd: ConfigRng::new(StdRng::from_entropy()),
+            seed: None,
             abort_strategy: AbortStrategy::<T>::NoImprovement {
                 threshold: T::from(0.0005).unwrap(),
             },
+            cancellation: None,
+            min_cluster_size: 0,
+            snap_to_samples: false,
+            learning_schedule: LearningSchedule::InverseCount,
+            centroid_updater: None,
+            split_merge_interval: 0,
+            trajectory_interval: 0,
+            high_precision: false,
+            compensated_summation: false,
+            sparse_centroid_updates: false,
+            reassignment_ratio: T::zero(),
+            max_no_improvement: 0,
+            stratified_min_per_cluster: 0,
+            inertia_sample_size: 0,
+            greedy_kmeanplusplus: None,
+            tol: T::zero(),
+        }
+    }
+}
+impl<T: Primitive> Clone for KMeansConfig<'_, T> {
+    fn clone(&self) -> Self {
+        let seed = self.rnd.borrow_mut().next_u64();
+        Self {
+            init_done: self.init_done.clone(),
+            iteration_done: self.iteration_done.clone(),
+            rnd: ConfigRng::new(StdRng::seed_from_u64(seed)),
+            seed: Some(seed),
+            abort_strategy: self.abort_strategy.clone(),
+            cancellation: self.cancellation.clone(),
+            min_cluster_size: self.min_cluster_size,
+            snap_to_samples: self.snap_to_samples,
+            learning_schedule: self.learning_schedule.clone(),
+            centroid_updater: self.centroid_updater.clone(),
+            split_merge_interval: self.split_merge_interval,
+            trajectory_interval: self.trajectory_interval,
+            high_precision: self.high_precision,
+            compensated_summation: self.compensated_summation,
+            sparse_centroid_updates: self.sparse_centroid_updates,
+            reassignment_ratio: self.reassignment_ratio,
+            max_no_improvement: self.max_no_improvement,
+            stratified_min_per_cluster: self.stratified_min_per_cluster,
+            inertia_sample_size: self.inertia_sample_size,
+            greedy_kmeanplusplus: self.greedy_kmeanplusplus,
+            tol: self.tol,
         }
     }
 }
@@ -48,6 +149,77 @@ impl<'a, T: Primitive> KMeansConfig<'a, T> {
             config: KMeansConfig::default(),
         }
     }
+
+    /// Sum of the given distances (each multiplied with the weight of its sample, if **weights** are given), accumulated
+    /// in f64 precision or using compensated summation if configured.
+    pub(crate) fn distsum(&self, distances: &[T], weights: Option<&[T]>) -> T {
+        match weights {
+            Some(weights) => self.sum(distances.iter().zip(weights.iter()).map(|(&d, &w)| d * w)),
+            None => self.sum(distances.iter().cloned()),
+        }
+    }
+
+    /// Distance sum of an intermediate iteration, like [`KMeansConfig::distsum`], but estimated from every
+    /// `distances.len() / inertia_sample_size`-th sample if configured. The estimate is scaled up to all samples.
+    pub(crate) fn iteration_distsum(&self, distances: &[T], weights: Option<&[T]>) -> T {
+        let sample_cnt = distances.len();
+        if self.inertia_sample_size == 0 || self.inertia_sample_size >= sample_cnt {
+            return self.distsum(distances, weights);
+        }
+        let step = sample_cnt / self.inertia_sample_size;
+        let sampled_cnt = sample_cnt.div_ceil(step);
+        let sampled = match weights {
+            Some(weights) => self.sum(distances.iter().zip(weights.iter()).step_by(step).map(|(&d, &w)| d * w)),
+            None => self.sum(distances.iter().cloned().step_by(step)),
+        };
+        sampled * T::from(sample_cnt).unwrap() / T::from(sampled_cnt).unwrap()
+    }
+
+    fn sum(&self, values: impl Iterator<Item = T>) -> T {
+        if self.high_precision {
+            T::from(values.map(|v| v.to_f64().unwrap()).sum::<f64>()).unwrap()
+        } else if self.compensated_summation {
+            crate::helpers::kahan_sum(values)
+        } else {
+            values.sum()
+        }
+    }
+
+    /// Bookkeeping after the initialization of a calculation: pad the centroids (see
+    /// [`DistanceFunction::padding_value`]), start the distance statistics (if the distance function counts its
+    /// evaluations), and notify the subscriber.
+    pub(crate) fn notify_init<const LANES: usize, D>(&self, kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>)
+    where
+        LaneCount<LANES>: SupportedLaneCount,
+        Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+        D: DistanceFunction<T, LANES>,
+    {
+        state.centroids.fill_padding(kmean.distance_fn.padding_value());
+        crate::distance_statistics::start(kmean, state);
+        (self.init_done)(state);
+    }
+
+    /// Bookkeeping after each iteration of a calculation: restore the padding of the centroids, detect a divergence, record the centroid trajectory (if
+    /// enabled) and the distance statistics (if counted), and notify the subscriber.
+    pub(crate) fn notify_iteration<const LANES: usize, D>(
+        &self, kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, iteration: usize, distsum: T,
+    ) where
+        LaneCount<LANES>: SupportedLaneCount,
+        Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+        D: DistanceFunction<T, LANES>,
+    {
+        // The padding may be perturbed by rounding errors of the centroid updates
+        state.centroids.fill_padding(kmean.distance_fn.padding_value());
+        if state.divergence.is_none() {
+            let cluster = state.centroids.chunks_exact_stride().position(|c| c.iter().any(|v| !v.is_finite()));
+            if cluster.is_some() || !distsum.is_finite() {
+                state.divergence = Some(Divergence { iteration, cluster });
+            }
+        }
+        crate::trajectory::record(state, iteration, self.trajectory_interval);
+        crate::distance_statistics::record_iteration(kmean, state);
+        (self.iteration_done)(state, iteration, distsum);
+    }
 }
 impl<T: Primitive> std::fmt::Debug for KMeansConfig<'_, T> {
     fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { Ok(()) }
@@ -58,19 +230,29 @@ pub struct KMeansConfigBuilder<'a, T: Primitive> {
 }
 impl<'a, T: Primitive> KMeansConfigBuilder<'a, T> {
     /// Set the callback that should be called after the centroid initialization, before the iteration starts.
-    pub fn init_done(mut self, init_done: InitDoneCallbackFn<'a, T>) -> Self {
-        self.config.init_done = init_done;
+    pub fn init_done<F: Fn(&KMeansState<T>) + Send + Sync + 'a>(mut self, init_done: F) -> Self {
+        self.config.init_done = Arc::new(init_done);
         self
     }
     /// Set the callback that should be called after each iteration during a running k-means calculation.
-    pub fn iteration_done(mut self, iteration_done: IterationDoneCallbackFn<'a, T>) -> Self {
-        self.config.iteration_done = iteration_done;
+    pub fn iteration_done<F: Fn(&KMeansState<T>, usize, T) + Send + Sync + 'a>(mut self, iteration_done: F) -> Self {
+        self.config.iteration_done = Arc::new(iteration_done);
         self
     }
     /// Set the random number generator that should be used in the k-means calculation.
     /// Use a seeded generator for deterministically repeatable results.
-    pub fn random_generator<R: RngCore + 'static>(mut self, rnd: R) -> Self {
-        self.config.rnd = Box::new(RefCell::new(rnd));
+    pub fn random_generator<R: RngCore + Send + 'static>(mut self, rnd: R) -> Self {
+        self.config.rnd = ConfigRng::new(rnd);
+        self.config.seed = None;
+        self
+    }
+    /// Use a [`StdRng`] seeded with **seed** as random number generator. Other than with
+    /// [`KMeansConfigBuilder::random_generator`], the seed is recorded in a [`crate::RunManifest`] (see
+    /// [`KMeans::run_manifest`]), so the calculation can be reproduced from its manifest. Clones of the configuration
+    /// record the seed of their own generator.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.rnd = ConfigRng::new(StdRng::seed_from_u64(seed));
+        self.config.seed = Some(seed);
         self
     }
     /// Set the abort-strategy to use during a running k-means calculation. For more information,
@@ -81,6 +263,199 @@ impl<'a, T: Primitive> KMeansConfigBuilder<'a, T> {
         self.config.abort_strategy = abort_strategy;
         self
     }
+    /// Set a token to cancel the running k-means calculation cooperatively, e.g. from another thread or from within
+    /// the [`KMeansConfigBuilder::iteration_done`] callback. The token is checked after every iteration (after the
+    /// callback was called): Once it is set to `true`, the calculation stops, finishes the result as if it had
+    /// converged at that point (including all post-processing), and reports [`StopReason::Cancelled`]. A cancelled
+    /// calculation thus still returns the best state it found so far. Clones of the configuration share the token.
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let (sample_cnt, sample_dims, k) = (2000, 4, 8);
+    /// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    ///
+    /// let cancel = Arc::new(AtomicBool::new(false));
+    /// let conf = KMeansConfig::build()
+    ///     .cancellation_token(cancel.clone())
+    ///     .iteration_done(|_, iteration, _| cancel.store(iteration >= 3, Ordering::Relaxed))
+    ///     .build();
+    /// let result = kmean.kmeans_lloyd(k, 100, KMeans::init_kmeanplusplus, &conf);
+    /// assert!(result.n_iterations <= 3);
+    /// ```
+    /// ## Default
+    /// No token (the calculation can not be cancelled)
+    pub fn cancellation_token(mut self, token: Arc<AtomicBool>) -> Self {
+        self.config.cancellation = Some(token);
+        self
+    }
+    /// Set the convergence tolerance: The calculation additionally stops after the first iteration, in which the
+    /// largest movement of a centroid (measured with the distance function of the calculation, e.g. the squared
+    /// euclidean distance) or the relative improvement of the distance sum (`improvement / distsum`) falls below
+    /// **tol**. This is checked alongside the [`AbortStrategy`], while iterations that did a split-merge move or
+    /// re-seeded centroids never stop the calculation. Why a calculation stopped is reported in
+    /// [`KMeansState::stop_reason`].
+    /// ## Default
+    /// `0` (disabled)
+    pub fn tol(mut self, tol: T) -> Self {
+        assert!(tol >= T::zero());
+        self.config.tol = tol;
+        self
+    }
+    /// Set the minimum amount of samples each cluster of the result has to contain.
+    /// After convergence, a repair pass dissolves undersized clusters (smallest first), by moving their samples
+    /// into the nearest remaining cluster. The result then contains less than the requested **k** clusters, and a
+    /// report of what was done in [`KMeansState::cluster_size_repair`].
+    /// ## Default
+    /// `0` (disabled)
+    pub fn min_cluster_size(mut self, min_cluster_size: usize) -> Self {
+        self.config.min_cluster_size = min_cluster_size;
+        self
+    }
+    /// Replace every centroid of the result with its nearest sample, for applications that have to present real
+    /// exemplars instead of synthetic means. After convergence (and after the minimum cluster size repair pass),
+    /// all samples are re-assigned to the nearest of the replaced centroids. The chosen samples and the change of the
+    /// sum of distances are reported in [`KMeansState::centroid_snapping`].
+    /// ## Default
+    /// `false`
+    pub fn snap_centroids_to_samples(mut self, enabled: bool) -> Self {
+        self.config.snap_to_samples = enabled;
+        self
+    }
+    /// Set the learning-rate schedule of the online centroid updates, as done by [`KMeans::kmeans_minibatch`]. For more
+    /// information, see documentation of [`LearningSchedule`].
+    /// ## Default
+    /// [`LearningSchedule::InverseCount`]
+    pub fn learning_schedule(mut self, learning_schedule: LearningSchedule<T>) -> Self {
+        assert!(learning_schedule.is_valid(), "invalid learning schedule: {learning_schedule:?}");
+        self.config.learning_schedule = learning_schedule;
+        self
+    }
+    /// Set the rule that is used to update the centroids from their members in each iteration of
+    /// [`KMeans::kmeans_lloyd`] (and [`KMeans::start_lloyd`], [`KMeans::kmeans_elkan`], [`KMeans::kmeans_hamerly`]).
+    /// For more information, see documentation of [`CentroidUpdater`]. Other variants always use the mean.
+    /// ## Default
+    /// Mean of all members (like [`crate::MeanUpdater`], but using an optimized built-in implementation)
+    pub fn centroid_updater<U: CentroidUpdater<T> + 'a>(mut self, centroid_updater: U) -> Self {
+        self.config.centroid_updater = Some(Arc::new(centroid_updater));
+        self
+    }
+    /// Defer the centroid updates of [`KMeans::kmeans_minibatch`] (and [`KMeans::kmeans_minibatch_polished`]) to the
+    /// end of each batch: The samples of the batch are accumulated per touched centroid, and every touched centroid
+    /// is then moved only once. This keeps the work and memory of the updates proportional to the amount of touched
+    /// centroids, instead of the amount of samples or k, which matters for huge codebooks (e.g. k = 100000). For the
+    /// default [`LearningSchedule::InverseCount`], the result is the same as with the sequential updates; other
+    /// schedules weigh all samples of a batch evenly.
+    /// ## Default
+    /// `false`
+    pub fn sparse_centroid_updates(mut self, enabled: bool) -> Self {
+        self.config.sparse_centroid_updates = enabled;
+        self
+    }
+    /// Re-seed stagnating centroids during [`KMeans::kmeans_minibatch`] (and [`KMeans::kmeans_minibatch_polished`]),
+    /// similar to the `reassignment_ratio` of scikit-learn: Every `10 + min(frequency)` iterations, all centroids
+    /// whose frequency (amount of samples they were updated with) is below **ratio** times the highest frequency are
+    /// moved onto samples of the current batch, drawn with a probability proportional to their distance to their
+    /// centroid (at most half the batch size). Their frequency is then reset to the lowest frequency of the remaining
+    /// centroids, so they can still move considerably. Iterations that re-seeded a centroid never abort the calculation.
+    /// ## Default
+    /// `0` (disabled)
+    pub fn minibatch_reassignment_ratio(mut self, ratio: T) -> Self {
+        assert!(ratio >= T::zero() && ratio <= T::one());
+        self.config.reassignment_ratio = ratio;
+        self
+    }
+    /// Stop [`KMeans::kmeans_minibatch`] (and the training of [`KMeans::kmeans_minibatch_polished`]) early, once the
+    /// smoothed batch inertia did not improve for **batches** consecutive batches, similar to the `max_no_improvement`
+    /// of scikit-learn. The batch inertia (mean distance of the batch's samples to their centroids) is smoothed with
+    /// an exponentially weighted moving average, with a weight of `min(1, 2 * batch_size / (sample_cnt + 1))` for the
+    /// newest batch. Early stops are reported as [`StopReason::NoBatchImprovement`]. This is checked in addition to the
+    /// configured [`AbortStrategy`], and iterations that re-seeded a centroid never stop the calculation.
+    /// ## Default
+    /// `0` (disabled)
+    pub fn minibatch_max_no_improvement(mut self, batches: usize) -> Self {
+        self.config.max_no_improvement = batches;
+        self
+    }
+    /// Draw the batches of [`KMeans::kmeans_minibatch`] (and [`KMeans::kmeans_minibatch_polished`]) stratified by the
+    /// current clusters, instead of as random blocks of samples: Every cluster contributes its proportional share of
+    /// the batch size, but at least **min_per_cluster** samples (or all of its members, if it has less). Small clusters
+    /// of imbalanced data are thus updated in every batch, instead of being starved of updates (and collapsing) over
+    /// long trainings. The batches are correspondingly larger than the configured batch size.
+    /// ## Default
+    /// `0` (disabled, random blocks of samples)
+    pub fn minibatch_stratified_sampling(mut self, min_per_cluster: usize) -> Self {
+        self.config.stratified_min_per_cluster = min_per_cluster;
+        self
+    }
+    /// Estimate the distance sum of the intermediate iterations of [`KMeans::kmeans_lloyd`] (and
+    /// [`KMeans::start_lloyd`], [`KMeans::kmeans_elkan`], [`KMeans::kmeans_hamerly`]) from a subsample of
+    /// **sample_size** samples, instead of summing the distances of all samples. For large datasets, whose distance
+    /// sum is only watched for the progress, this saves one pass over all distances per iteration. The subsample is
+    /// systematic (every `sample_cnt / sample_size`-th sample), so every iteration estimates from the same samples and
+    /// the improvements seen by the [`AbortStrategy`] stay comparable. The distance sum of the result is always exact.
+    /// ## Default
+    /// `0` (disabled, exact distance sums)
+    pub fn estimated_inertia(mut self, sample_size: usize) -> Self {
+        self.config.inertia_sample_size = sample_size;
+        self
+    }
+    /// Use the greedy variant of [`KMeans::init_kmeanplusplus`] (as scikit-learn does): For every centroid after the
+    /// first, **local_trials** candidates are drawn (instead of one), and the candidate that minimizes the (weighted)
+    /// sum of distances of all samples to their nearest centroid is chosen. This noticeably improves the initial (and
+    /// often the final) distance sum of clustered data, at the cost of one pass over all samples per candidate. Pass
+    /// `0` for scikit-learn's default of `2 + ln(k)` candidates. This also applies to the k-means++ phases of
+    /// [`KMeans::init_kmeans_parallel`] and [`KMeans::init_labels`].
+    /// ## Default
+    /// Disabled (plain k-means++, one candidate per centroid)
+    pub fn greedy_kmeanplusplus(mut self, local_trials: usize) -> Self {
+        self.config.greedy_kmeanplusplus = Some(local_trials);
+        self
+    }
+    /// Enable split-merge refinement moves every **interval** iterations of [`KMeans::kmeans_lloyd`] (and
+    /// [`KMeans::start_lloyd`], [`KMeans::kmeans_elkan`], [`KMeans::kmeans_hamerly`]), to escape the local minima the
+    /// plain Lloyd iterations get stuck in.
+    /// Each move tries to split the worst cluster (highest sum of distances) into two, while merging the closest pair
+    /// of the remaining clusters. It is only accepted, if this decreases the total sum of distances. Other variants
+    /// ignore this setting.
+    /// ## Default
+    /// `0` (disabled)
+    pub fn split_merge_interval(mut self, interval: usize) -> Self {
+        self.config.split_merge_interval = interval;
+        self
+    }
+    /// Record the positions of all centroids every **interval** iterations into [`KMeansState::trajectory`], e.g. for
+    /// the visualization of the convergence. For more information, see documentation of [`CentroidTrajectory`].
+    /// ## Default
+    /// `0` (disabled)
+    pub fn record_trajectory(mut self, interval: usize) -> Self {
+        self.config.trajectory_interval = interval;
+        self
+    }
+    /// Accumulate all sums over many samples (the distance sums of all variants, and the centroid sums of
+    /// [`KMeans::kmeans_lloyd`] / [`KMeans::start_lloyd`] / [`KMeans::kmeans_elkan`] / [`KMeans::kmeans_hamerly`])
+    /// in f64 precision. For large f32 datasets, this avoids visibly wrong distance sums and drifting centroids due
+    /// to accumulated rounding errors, at the cost of some speed and memory. This has no effect on f64 calculations.
+    /// ## Default
+    /// `false`
+    pub fn high_precision_accumulation(mut self, enabled: bool) -> Self {
+        self.config.high_precision = enabled && std::mem::size_of::<T>() < std::mem::size_of::<f64>();
+        self
+    }
+    /// Accumulate the same sums as [`KMeansConfigBuilder::high_precision_accumulation`] using compensated (Kahan)
+    /// summation in the calculation's own precision. This bounds the floating-point error of very large clusters
+    /// independently of their size, and also works for f64 calculations. The overhead is modest, since the
+    /// centroid sums are only a small part of each iteration (see the `complete_benchmark_lloyd_compensated_*`
+    /// benchmarks). If both options are enabled, the f64 accumulation is used.
+    /// ## Default
+    /// `false`
+    pub fn compensated_summation(mut self, enabled: bool) -> Self {
+        self.config.compensated_summation = enabled;
+        self
+    }
     /// Return the internally built configuration structure.
     pub fn build(self) -> KMeansConfig<'a, T> { self.config }
 }
@@ -90,6 +465,10 @@ impl<'a, T: Primitive> KMeansConfigBuilder<'a, T> {
 /// All mutations are done in this structure, making [`KMeans`] immutable, and therefore allowing
 /// it to be used in parallel, without having to duplicate the input-data.
 ///
+/// With the `serde` feature, results can be serialized (e.g. to train a model offline, and serve predictions with a
+/// [`crate::ModelRegistry`] elsewhere). The centroids are stored without their SIMD padding, but with their stride:
+/// A deserialized result has to be used with the same **LANES** it was calculated with.
+///
 /// ## Generics
 /// - **T**: Underlying primitive type that was used for the calculation
 ///
@@ -97,17 +476,42 @@ impl<'a, T: Primitive> KMeansConfigBuilder<'a, T> {
 /// - **k**: The amount of clusters that were requested when calculating this k-means result
 /// - **distsum**: The total sum of (squared) distances from all samples to their respective centroids
 /// - **centroids**: Calculated cluster centers [row-major] = [<centroid0>,<centroid1>,<centroid2>,...]
-/// - **centroid_frequency**: Amount of samples in each centroid
+/// - **centroid_frequency**: Amount of samples in each centroid (see [`KMeansState::cluster_sizes`])
+/// - **per_cluster_distsum**: Sum of the (squared) distances of the samples of each cluster to its centroid, e.g. to
+///   decide which clusters to split (see [`KMeansState::cluster_inertias`])
 /// - **assignments**: Vector mapping each sample to its respective nearest cluster
 /// - **centroid_distances**: Vector containing each sample's (squared) distance to its centroid
+/// - **cluster_size_repair**: Report of the minimum cluster size repair pass, if it changed the result
+///   (see [`KMeansConfigBuilder::min_cluster_size`])
+/// - **centroid_snapping**: Report of the replacement of the centroids with their nearest samples, if enabled
+///   (see [`KMeansConfigBuilder::snap_centroids_to_samples`])
+/// - **memory_usage**: Memory used by the calculation that produced this result, see [`MemoryUsage`]
+/// - **trajectory**: History of the centroid positions, if enabled (see [`KMeansConfigBuilder::record_trajectory`])
+/// - **n_iterations**: Amount of iterations the calculation did
+/// - **stop_reason**: Why the calculation stopped iterating, see [`StopReason`]
+/// - **distance_statistics**: Amount of distance evaluations, if counted by the distance function (see
+///   [`crate::CountingDistance`])
+/// - **divergence**: Where NaN or infinite values appeared in the centroids or the distsum, if they did (see
+///   [`KMeansState::into_result`])
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KMeansState<T: Primitive> {
     pub k: usize,
     pub distsum: T,
     pub centroids: StrideBuffer<T>,
     pub centroid_frequency: Vec<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub per_cluster_distsum: Vec<T>,
     pub assignments: Vec<usize>,
     pub centroid_distances: Vec<T>,
+    pub cluster_size_repair: Option<ClusterSizeRepair>,
+    pub centroid_snapping: Option<CentroidSnapping<T>>,
+    pub memory_usage: MemoryUsage,
+    pub trajectory: Option<CentroidTrajectory<T>>,
+    pub n_iterations: usize,
+    pub stop_reason: StopReason,
+    pub distance_statistics: Option<DistanceStatistics>,
+    pub divergence: Option<Divergence>,
 }
 impl<T: Primitive> KMeansState<T> {
     pub(crate) fn new<const LANES: usize>(sample_cnt: usize, sample_dims: usize, k: usize) -> Self {
@@ -116,10 +520,153 @@ impl<T: Primitive> KMeansState<T> {
             distsum: T::zero(),
             centroids: StrideBuffer::new::<LANES>(k, sample_dims),
             centroid_frequency: vec![0usize; k],
+            per_cluster_distsum: vec![T::zero(); k],
             assignments: vec![0usize; sample_cnt],
             centroid_distances: vec![T::infinity(); sample_cnt],
+            cluster_size_repair: None,
+            centroid_snapping: None,
+            memory_usage: MemoryUsage::new::<T, LANES>(sample_cnt, sample_dims, k),
+            trajectory: None,
+            n_iterations: 0,
+            stop_reason: StopReason::MaxIterations,
+            distance_statistics: None,
+            divergence: None,
+        }
+    }
+
+    /// Amount of samples in each cluster.
+    pub fn cluster_sizes(&self) -> &[usize] { &self.centroid_frequency }
+
+    /// Sum of the (squared) distances of the samples of each cluster to its centroid (weighted, if the samples are
+    /// weighted). The sum of all entries is the **distsum** of the result.
+    pub fn cluster_inertias(&self) -> &[T] { &self.per_cluster_distsum }
+
+    /// Whether the calculation converged, i.e. stopped before its maximum amount of iterations was reached, without
+    /// being cancelled.
+    pub fn converged(&self) -> bool {
+        !matches!(
+            self.stop_reason,
+            StopReason::MaxIterations | StopReason::Cancelled | StopReason::Diverged
+        )
+    }
+
+    /// Turn this result into an error, if the calculation diverged (see [`KMeansState::divergence`]).
+    /// A diverged calculation stops directly after the iteration, in which NaN or infinite values appeared.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let samples = vec![0.0f64, 1.0, f64::NAN, 10.0, 11.0, 12.0];
+    /// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+    /// let result = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![1.0, 11.0]), &KMeansConfig::default());
+    /// let err = result.into_result().unwrap_err();
+    /// assert_eq!(err.iteration, 1);
+    /// ```
+    pub fn into_result(self) -> Result<Self, Divergence> {
+        match self.divergence {
+            Some(divergence) => Err(divergence),
+            None => Ok(self),
         }
     }
+
+    /// Export the assignments of this result as Arrow record batch, with one row per sample.
+    ///
+    /// ## Description
+    /// The record batch contains the columns `sample` (index of the sample), `cluster` (assigned cluster) and
+    /// `distance` (distance to the assigned centroid, in the precision of **T**).
+    #[cfg(feature = "arrow")]
+    pub fn assignments_record_batch(&self) -> Result<arrow_array::RecordBatch, arrow_schema::ArrowError> {
+        crate::export::assignments(self)
+    }
+
+    /// Export the centroids of this result as Arrow record batch, with one row per cluster.
+    ///
+    /// ## Description
+    /// The record batch contains the columns `cluster` (id of the cluster), `frequency` (amount of samples in the
+    /// cluster), as well as one column `dim_<d>` per dimension of the centroids (in the precision of **T**).
+    #[cfg(feature = "arrow")]
+    pub fn centroids_record_batch(&self) -> Result<arrow_array::RecordBatch, arrow_schema::ArrowError> { crate::export::centroids(self) }
+
+    /// Write the assignments of this result (see [`KMeansState::assignments_record_batch`]) as Arrow IPC file.
+    #[cfg(feature = "arrow")]
+    pub fn write_assignments_ipc<W: std::io::Write>(&self, writer: W) -> Result<(), arrow_schema::ArrowError> {
+        crate::export::write_assignments_ipc(self, writer)
+    }
+
+    /// Write the centroids of this result (see [`KMeansState::centroids_record_batch`]) as Arrow IPC file.
+    #[cfg(feature = "arrow")]
+    pub fn write_centroids_ipc<W: std::io::Write>(&self, writer: W) -> Result<(), arrow_schema::ArrowError> {
+        crate::export::write_centroids_ipc(self, writer)
+    }
+
+    /// Rename the clusters of this result, such that the cluster assignments maximally agree with the given
+    /// reference labeling (e.g. a ground truth, or the assignments of a previous run).
+    ///
+    /// ## Description
+    /// Cluster ids are permuted using an optimal matching on the contingency matrix of both labelings. Clusters
+    /// that could not be matched to a reference label (smaller than **k**) get the remaining, unused ids.
+    /// Assignments, centroids and frequencies are updated accordingly.
+    ///
+    /// ## Arguments
+    /// - **reference_labels**: Reference label of each sample
+    ///
+    /// ## Returns
+    /// The applied permutation, where cluster `c` was renamed to `permutation[c]`.
+    pub fn relabel_to_match(&mut self, reference_labels: &[usize]) -> Vec<usize> {
+        crate::analysis::relabel::calculate(self, reference_labels)
+    }
+
+    /// Draw a stratified subsample of sample indices from this result, e.g. for labeling, inspection or building
+    /// balanced training sets.
+    ///
+    /// ## Arguments
+    /// - **strategy**: How many samples to draw from each cluster, see [`StratifiedSampling`]
+    /// - **rnd**: Random number generator to draw the samples with
+    ///
+    /// ## Returns
+    /// For every cluster, the (ascending) indices of the samples drawn from it.
+    pub fn stratified_sample<R: Rng + ?Sized>(&self, strategy: StratifiedSampling, rnd: &mut R) -> Vec<Vec<usize>> {
+        crate::analysis::stratified::calculate(self, strategy, rnd)
+    }
+
+    /// The **m** samples closest to the centroid of each cluster, e.g. for labeling clusters by representative
+    /// examples.
+    ///
+    /// ## Arguments
+    /// - **m**: Maximum amount of exemplars per cluster. Clusters with less members return all of them.
+    ///
+    /// ## Returns
+    /// For every cluster, its exemplars with ascending distance to the centroid (ties in ascending order of the
+    /// sample indices), see [`Exemplar`].
+    pub fn exemplars(&self, m: usize) -> Vec<Vec<Exemplar<T>>> { crate::analysis::exemplars::calculate(self, m) }
+
+    /// Sparse representation of the centroids of this result, dropping all components with an absolute value below
+    /// **threshold**. For high-dimensional sparse data (e.g. text features), this keeps the model small enough to be
+    /// stored and shipped, see [`SparseCentroids`].
+    ///
+    /// ## Arguments
+    /// - **threshold**: Minimum absolute value of a component to be kept
+    ///
+    /// ## Returns
+    /// The thresholded centroids, in compressed sparse row format.
+    pub fn sparse_centroids(&self, threshold: T) -> SparseCentroids<T> { crate::sparse_centroids::calculate(self, threshold) }
+}
+
+/// Assignment of (new) samples to the centroids of a previously calculated k-means result,
+/// as returned by [`KMeans::assign_with_distances`].
+///
+/// ## Fields
+/// - **assignments**: Vector mapping each sample to its respective nearest cluster
+/// - **centroid_distances**: Vector containing each sample's (squared) distance to its nearest centroid
+/// - **margins**: If requested: Vector containing, for each sample, the difference between its distance to the
+///   runner-up centroid and its distance to the nearest centroid. Small margins indicate samples that lie close to
+///   the border between two clusters. Infinity if the result only has one cluster.
+#[derive(Clone, Debug)]
+pub struct SampleAssignments<T: Primitive> {
+    pub assignments: Vec<usize>,
+    pub centroid_distances: Vec<T>,
+    pub margins: Option<Vec<T>>,
 }
 
 /// A trait representing a customizable distance function for k-means clustering.
@@ -131,10 +678,166 @@ impl<T: Primitive> KMeansState<T> {
 /// - `T`: The data type of the elements in the slices. This type must support
 ///        arithmetic operations
 /// - `LANES`: SIMD lane size as requested by the API user
+///
+/// ## Runtime selection
+/// This trait is object-safe, so the distance function can also be chosen at runtime (e.g. from a
+/// configuration file) by using a [`DynDistanceFunction`] as distance function for [`KMeans`].
+/// Note that this comes at a (modest) performance cost, since every distance calculation then
+/// goes through a virtual call that can not be inlined into the hot loops.
 pub trait DistanceFunction<T, const LANES: usize>: Send + Sync {
     fn distance(&self, a: &[T], b: &[T]) -> T;
+
+    /// Whether the distance of two samples equals the sum of the distances of their parts, when both samples are split
+    /// at the same (LANES-aligned) positions. This is required for the dimension-chunked processing of very wide
+    /// samples (see [`KMeans::with_dimension_chunking`]), which is not used for distance functions returning `false`.
+    fn is_separable(&self) -> bool { false }
+
+    /// Distance calculation, that may be abandoned as soon as the distance is known to exceed **bound** (e.g. the
+    /// distance of a sample to the nearest centroid found so far).
+    /// ## Returns
+    /// The exact distance if it is smaller than or equal to **bound**, otherwise any value larger than **bound**.
+    /// The default implementation always calculates the full distance.
+    #[inline(always)]
+    fn distance_bounded(&self, a: &[T], b: &[T], _bound: T) -> T { self.distance(a, b) }
+
+    /// Whether this distance function calculates the squared euclidean distance. This allows the assignment step to
+    /// use the decomposition `|a - b|² = |a|² - 2 a·b + |b|²` with cached norms (see [`KMeans::with_norm_cache`]).
+    fn is_squared_euclidean(&self) -> bool { false }
+
+    /// Amount of evaluations of this distance function so far, if it counts them (see [`crate::CountingDistance`]).
+    /// The counted evaluations of a calculation are reported in [`KMeansState::distance_statistics`].
+    fn distance_evaluations(&self) -> Option<u64> { None }
+
+    /// Neutral value, that samples and centroids are padded with up to a multiple of `LANES` dimensions. The padding
+    /// of both vectors is always equal, and has to leave the distance unchanged: `0` suits all distances based on
+    /// differences of the values (e.g. [`crate::EuclideanDistance`]), while divergences based on ratios or logarithms
+    /// of the values (such as the Kullback-Leibler divergence, or the χ² distance) need a padding of `1`, as their
+    /// terms of two zeros are undefined (`0 / 0`).
+    fn padding_value(&self) -> T
+    where
+        T: Primitive,
+    {
+        T::zero()
+    }
+}
+impl<T, const LANES: usize, D: DistanceFunction<T, LANES> + ?Sized> DistanceFunction<T, LANES> for Box<D> {
+    #[inline(always)]
+    fn distance(&self, a: &[T], b: &[T]) -> T { (**self).distance(a, b) }
+
+    fn is_separable(&self) -> bool { (**self).is_separable() }
+
+    #[inline(always)]
+    fn distance_bounded(&self, a: &[T], b: &[T], bound: T) -> T { (**self).distance_bounded(a, b, bound) }
+
+    fn is_squared_euclidean(&self) -> bool { (**self).is_squared_euclidean() }
+
+    fn distance_evaluations(&self) -> Option<u64> { (**self).distance_evaluations() }
+
+    fn padding_value(&self) -> T
+    where
+        T: Primitive,
+    {
+        (**self).padding_value()
+    }
+}
+impl<T, const LANES: usize, D: DistanceFunction<T, LANES> + ?Sized> DistanceFunction<T, LANES> for &D {
+    #[inline(always)]
+    fn distance(&self, a: &[T], b: &[T]) -> T { (**self).distance(a, b) }
+
+    fn is_separable(&self) -> bool { (**self).is_separable() }
+
+    #[inline(always)]
+    fn distance_bounded(&self, a: &[T], b: &[T], bound: T) -> T { (**self).distance_bounded(a, b, bound) }
+
+    fn is_squared_euclidean(&self) -> bool { (**self).is_squared_euclidean() }
+
+    fn distance_evaluations(&self) -> Option<u64> { (**self).distance_evaluations() }
+
+    fn padding_value(&self) -> T
+    where
+        T: Primitive,
+    {
+        (**self).padding_value()
+    }
+}
+impl<T, const LANES: usize, D: DistanceFunction<T, LANES> + ?Sized> DistanceFunction<T, LANES> for std::sync::Arc<D> {
+    #[inline(always)]
+    fn distance(&self, a: &[T], b: &[T]) -> T { (**self).distance(a, b) }
+
+    fn is_separable(&self) -> bool { (**self).is_separable() }
+
+    #[inline(always)]
+    fn distance_bounded(&self, a: &[T], b: &[T], bound: T) -> T { (**self).distance_bounded(a, b, bound) }
+
+    fn is_squared_euclidean(&self) -> bool { (**self).is_squared_euclidean() }
+
+    fn distance_evaluations(&self) -> Option<u64> { (**self).distance_evaluations() }
+
+    fn padding_value(&self) -> T
+    where
+        T: Primitive,
+    {
+        (**self).padding_value()
+    }
+}
+
+/// A trait representing a customizable rule, how a centroid is calculated from the samples that were assigned to it.
+///
+/// Implementations (such as [`crate::MeanUpdater`], [`crate::MedianUpdater`], [`crate::GeometricMedianUpdater`] or
+/// [`crate::NormalizedMeanUpdater`]) can be injected into the Lloyd iterations using
+/// [`KMeansConfigBuilder::centroid_updater`], which allows for robust or constrained k-means variants, without having
+/// to duplicate the assignment machinery.
+///
+/// # Generics
+/// - `T`: The data type of the samples and centroids
+pub trait CentroidUpdater<T>: Send + Sync {
+    /// Calculate the new centroid of a cluster.
+    /// ## Arguments
+    /// - **members**: All samples (without padding) that were assigned to the cluster. Never empty, since empty
+    ///   clusters are refilled before the update.
+    /// - **centroid**: The current centroid of the cluster (without padding), to be overwritten with the new one
+    fn update(&self, members: &[&[T]], centroid: &mut [T]);
 }
 
+/// Boxed, dynamically dispatched [`DistanceFunction`], for distance functions that are selected at runtime.
+///
+/// ## Example
+/// ```rust
+/// use kmeans::*;
+///
+/// let metric = "histogram"; // e.g. read from a configuration file
+/// let distance_fn: DynDistanceFunction<f64, 8> = match metric {
+///     "histogram" => Box::new(HistogramDistance),
+///     _ => Box::new(EuclideanDistance),
+/// };
+///
+/// let samples = vec![0.0f64, 1.0, 10.0, 11.0, 20.0, 21.0];
+/// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 6, 1, distance_fn);
+/// let result = kmean.kmeans_lloyd(3, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+/// println!("Centroids: {:?}", result.centroids);
+/// ```
+pub type DynDistanceFunction<T, const LANES: usize> = Box<dyn DistanceFunction<T, LANES>>;
+
+/// [`KMeans`] for `f32` samples, with the amount of SIMD lanes that fills one vector register of the target features
+/// this crate was compiled for (see [`crate::LANES_F32`]). Use [`KMeans`] directly, to choose the amount of lanes
+/// explicitly.
+///
+/// ## Example
+/// ```rust
+/// use kmeans::*;
+///
+/// let samples = vec![0.0f32, 1.0, 10.0, 11.0, 20.0, 21.0];
+/// let kmean: KMeansF32<_> = KMeans::new(&samples, 6, 1, EuclideanDistance);
+/// let result = kmean.kmeans_lloyd(3, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+/// println!("Centroids: {:?}", result.centroids);
+/// ```
+pub type KMeansF32<D> = KMeans<f32, LANES_F32, D>;
+
+/// [`KMeans`] for `f64` samples, with the amount of SIMD lanes that fills one vector register of the target features
+/// this crate was compiled for (see [`crate::LANES_F64`]). Use [`KMeans`] directly, to choose the amount of lanes
+/// explicitly.
+pub type KMeansF64<D> = KMeans<f64, LANES_F64, D>;
+
 /// Entrypoint of this crate's API-Surface.
 ///
 /// Create an instance of this struct, giving the samples you want to operate on. The primitive type
@@ -143,12 +846,60 @@ pub trait DistanceFunction<T, const LANES: usize>: Send + Sync {
 ///
 /// ## Supported variants
 /// - k-Means clustering (Lloyd) [`KMeans::kmeans_lloyd`]
-/// - Mini-Batch k-Means clustering [`KMeans::kmeans_minibatch`]
+/// - k-Means clustering (Lloyd), accelerated using the triangle inequality (Elkan) [`KMeans::kmeans_elkan`]
+/// - k-Means clustering (Lloyd), accelerated using the triangle inequality (Hamerly) [`KMeans::kmeans_hamerly`]
+/// - k-Means clustering (Lloyd), accelerated using a kd-tree over the samples (filtering) [`KMeans::kmeans_kdtree`]
+/// - Spherical k-Means clustering, with unit-length centroids [`KMeans::kmeans_spherical`]
+/// - K-Harmonic-Means clustering, less sensitive to the initialization [`KMeans::kmeans_harmonic`]
+/// - Mini-Batch k-Means clustering [`KMeans::kmeans_minibatch`], with automatic batch size selection [`KMeans::auto_batch_size`]
+/// - Mini-Batch k-Means clustering, polished with full Lloyd iterations [`KMeans::kmeans_minibatch_polished`]
+/// - Importance-sampled Mini-Batch k-Means clustering [`KMeans::kmeans_minibatch_importance`]
+/// - Step-wise k-Means clustering (Lloyd) [`KMeans::start_lloyd`]
+/// - Warm-started k-Means clustering (Lloyd), resumed from a previous result [`KMeans::kmeans_lloyd_resume`]
+/// - Overlapping k-Means clustering [`KMeans::kmeans_overlapping`]
+/// - Fuzzy c-Means clustering, with soft memberships of every sample in every cluster [`KMeans::kmeans_fuzzy`]
+/// - Radius-constrained k-Means clustering, spawning clusters for uncovered samples [`KMeans::kmeans_max_radius`]
+/// - k-Medoids clustering (FasterPAM), with samples as centers for any distance function [`KMeans::kmedoids`]
+/// - Evolutionary k-Means clustering, recombining a population of Lloyd results [`KMeans::kmeans_evolutionary`]
+/// - k-Means clustering (Lloyd), refined by decaying random perturbations [`KMeans::kmeans_annealing`]
+///
+/// ## Many independent datasets
+/// - Batch-parallel clustering of many small datasets [`KMeans::batch`]
+///
+/// ## Selection and adjustment of k
+/// - Sweep over k, with elbow detection [`KMeans::sweep_k`]
+/// - Parallel sweep over k using Lloyd, with elbow detection [`KMeans::sweep_k_lloyd`]
+/// - X-Means, splitting clusters while the BIC improves [`KMeans::kmeans_xmeans`]
+/// - Incrementally growing k of a calculated result [`KMeans::add_cluster`]
+/// - Removing clusters from a calculated result [`KMeans::remove_clusters`]
 ///
 /// ## Supported initialization methods
 /// - K-Mean++ [`KMeans::init_kmeanplusplus`]
+/// - Scalable K-Means++ (k-means||) [`KMeans::init_kmeans_parallel`]
 /// - Random-Sample [`KMeans::init_random_sample`]
+/// - Maximin (farthest-first) [`KMeans::init_maximin`]
 /// - Random-Partition [`KMeans::init_random_partition`]
+/// - Grid-based seeding in the densest cells [`KMeans::init_grid`]
+/// - Class means of (partially) labeled samples [`KMeans::init_labels`]
+///
+/// ## Assignment of new samples
+/// - Nearest centroid, with distances and margins [`KMeans::assign_with_distances`]
+/// - Nearest centroid labels only [`KMeans::predict`]
+/// - Distances to all centroids [`KMeans::transform`]
+///
+/// ## Analysis of calculated results
+/// - Anomaly scores [`KMeans::anomaly_scores`]
+/// - Data drift monitoring [`KMeans::drift_statistics`]
+/// - Model-to-model comparison [`KMeans::compare_results`]
+/// - Per-dimension feature importance [`KMeans::feature_importance`]
+/// - Per-cluster descriptive statistics [`KMeans::cluster_profiles`]
+/// - Per-cluster radius and diameter [`KMeans::cluster_radii`]
+/// - Per-cluster bounding boxes and (2-D / 3-D) convex hulls [`KMeans::cluster_extents`]
+/// - Histogram of the assignment margins [`KMeans::margin_histogram`]
+/// - Two-dimensional projection for visualization [`KMeans::project_2d`]
+/// - Per-sample reassignment costs [`KMeans::reassignment_costs`]
+/// - Silhouette coefficients [`crate::metrics::silhouette_score`]
+/// - Davies-Bouldin and Calinski-Harabasz indices [`crate::metrics::davies_bouldin_index`], [`crate::metrics::calinski_harabasz_index`]
 ///
 /// # Generics
 /// - `T`: The type of primitive to work with (e.g. f32 of f64)
@@ -165,6 +916,14 @@ where
     pub(crate) sample_dims: usize,
     pub(crate) p_samples: StrideBuffer<T>,
     pub(crate) distance_fn: D,
+    pub(crate) dimension_chunk: Option<usize>,
+    pub(crate) sample_norms: Option<Vec<T>>,
+    pub(crate) lsh: Option<LshPrefilter<T>>,
+    pub(crate) parallel_chunk_size: Option<usize>,
+    pub(crate) sample_weights: Option<Vec<T>>,
+    pub(crate) blocked_scan: bool,
+    #[cfg(feature = "core-pinning")]
+    pub(crate) pinned_pool: Option<std::sync::Arc<rayon::ThreadPool>>,
 }
 impl<T, const LANES: usize, D: DistanceFunction<T, LANES>> KMeans<T, LANES, D>
 where
@@ -180,71 +939,586 @@ where
     /// - **sample_dims**: Amount of dimensions each sample from the **sample** vector has
     /// - **distance_fn**: Distance function to use for the calculation
     pub fn new(samples: &Vec<T>, sample_cnt: usize, sample_dims: usize, distance_fn: D) -> Self {
+        Self::from_slice(samples, sample_cnt, sample_dims, distance_fn)
+    }
+
+    pub(crate) fn from_slice(samples: &[T], sample_cnt: usize, sample_dims: usize, distance_fn: D) -> Self {
         assert!(samples.len() == sample_cnt * sample_dims);
 
         Self {
             sample_cnt,
             sample_dims,
-            p_samples: StrideBuffer::from_slice::<LANES>(sample_dims, samples),
+            p_samples: StrideBuffer::from_slice::<LANES>(sample_dims, samples).padded(distance_fn.padding_value()),
             distance_fn,
+            dimension_chunk: None,
+            sample_norms: None,
+            lsh: None,
+            parallel_chunk_size: None,
+            sample_weights: None,
+            blocked_scan: false,
+            #[cfg(feature = "core-pinning")]
+            pinned_pool: None,
         }
     }
 
-    pub(crate) fn update_centroid_distances(&self, state: &mut KMeansState<T>) {
-        let centroids = &state.centroids;
-
-        // manually calculate work-packet size, because rayon does not do static scheduling (which is more apropriate here)
-        let work_packet_size = self.p_samples.bfr.len() / self.p_samples.stride / rayon::current_num_threads();
-        self.p_samples
-            .bfr
-            .par_chunks_exact(self.p_samples.stride)
-            .with_min_len(work_packet_size)
-            .zip(state.assignments.par_iter().cloned())
-            .zip(state.centroid_distances.par_iter_mut())
-            .for_each(|((s, assignment), centroid_dist)| {
-                *centroid_dist = self.distance_fn.distance(s, centroids.nth_stride(assignment));
-            });
+    /// Enable the norm cache for the euclidean assignment step.
+    ///
+    /// ## Description
+    /// The squared norms of all samples are calculated once, and the squared norms of the centroids are recalculated
+    /// once per assignment step (after they were updated). Distances are then calculated using the decomposition
+    /// `|s - c|² = |s|² - 2 s·c + |c|²`, which only needs a dot product per sample and centroid. This noticeably reduces
+    /// the amount of FLOPs per iteration for wide data, at the cost of one additional value per sample.
+    ///
+    /// The cache is only used with distance functions calculating the squared euclidean distance (see
+    /// [`DistanceFunction::is_squared_euclidean`]), such as [`crate::EuclideanDistance`]. For all other distance
+    /// functions, this setting has no effect. Note that the decomposition is numerically less accurate for samples
+    /// with large norms, that are close to a centroid (distances are clamped to `0`). Samples whose nearest centroids
+    /// are within that error of each other are assigned using their exact distances instead.
+    pub fn with_norm_cache(mut self) -> Self {
+        if self.distance_fn.is_squared_euclidean() {
+            self.sample_norms = Some(crate::norm_cache::squared_norms::<T, LANES>(&self.p_samples));
+        }
+        self
     }
 
-    pub(crate) fn update_cluster_assignments(&self, state: &mut KMeansState<T>, limit_k: Option<usize>) {
-        let centroids = &state.centroids;
-        let k = limit_k.unwrap_or(state.k);
-
-        // manually calculate work-packet size, because rayon does not do static scheduling (which is more apropriate here)
-        let work_packet_size = self.p_samples.bfr.len() / self.p_samples.stride / rayon::current_num_threads();
-        self.p_samples
-            .bfr
-            .par_chunks_exact(self.p_samples.stride)
-            .with_min_len(work_packet_size)
-            .zip(state.assignments.par_iter_mut())
-            .zip(state.centroid_distances.par_iter_mut())
-            .for_each(|((s, assignment), centroid_dist)| {
-                let (best_idx, best_dist) = centroids
-                    .chunks_exact_stride()
-                    .take(k)
-                    .map(|c| self.distance_fn.distance(s, c))
-                    .enumerate()
-                    .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
-                    .unwrap();
-                *assignment = best_idx;
-                *centroid_dist = best_dist;
-            });
+    /// Enable the approximate, locality-sensitive hashing (LSH) based candidate pre-filter for the assignment step.
+    ///
+    /// ## Description
+    /// All samples are hashed once with **hashes** random hash functions of the given [`LshFamily`], and all centroids
+    /// are hashed again in every assignment step. Each sample is then only compared exactly against the
+    /// **candidates** centroids with the most similar hashes (and its current centroid), instead of all centroids.
+    /// This turns the assignment step from `O(n·k·d)` into roughly `O(n·(k·hashes + candidates·d))`, which is an
+    /// approximation for extreme workloads with high values of k: Samples may end up assigned to a centroid that is
+    /// not their nearest one. More hashes and candidates increase the accuracy, at the cost of speed.
+    ///
+    /// ## Arguments
+    /// - **family**: Family of hash functions, matching the distance function, see [`LshFamily`]
+    /// - **hashes**: Amount of hash functions per sample / centroid
+    /// - **candidates**: Amount of candidate centroids per sample, whose exact distance is calculated
+    /// - **seed**: Seed of the random projections of the hash functions
+    pub fn with_lsh_prefilter(mut self, family: LshFamily<T>, hashes: usize, candidates: usize, seed: u64) -> Self {
+        self.lsh = Some(LshPrefilter::new::<LANES>(&self.p_samples, family, hashes, candidates, seed));
+        self
     }
 
-    pub(crate) fn update_cluster_frequencies(&self, assignments: &[usize], centroid_frequency: &mut [usize]) -> usize {
-        centroid_frequency.iter_mut().for_each(|v| *v = 0);
-        let mut used_centroids_cnt = 0;
-        assignments.iter().cloned().for_each(|centroid_id| {
-            if centroid_frequency[centroid_id] == 0 {
-                used_centroids_cnt += 1; // Count the amount of centroids with more than 0 samples
-            }
-            centroid_frequency[centroid_id] += 1;
-        });
-        used_centroids_cnt
+    /// Enable the blocked (tiled) centroid scan of the assignment step, for large amounts of centroids.
+    ///
+    /// ## Description
+    /// By default, every sample is compared against all centroids, one sample after the other. Once the centroids no
+    /// longer fit into the L2 cache (e.g. thousands of centroids), every sample thus streams all centroids from main
+    /// memory. With this setting, blocks of samples are instead compared against one cache-sized tile of centroids
+    /// after the other, while the next tile is software-prefetched. Each centroid is then loaded from memory once per
+    /// block of samples, which improves the effective memory bandwidth. The results are identical, but whether this
+    /// is faster depends on the CPU and the data shape, so it should be measured.
+    pub fn with_blocked_centroid_scan(mut self) -> Self {
+        self.blocked_scan = true;
+        self
     }
 
-    /// Normal K-Means algorithm implementation. This is the same algorithm as implemented in Matlab (one-phase).
-    /// (see: https://uk.mathworks.com/help/stats/kmeans.html#bueq7aj-5    Section: More About)
+    /// Choose the fastest configuration of the assignment step for this data shape, by timing the candidates.
+    ///
+    /// ## Description
+    /// A quick calibration run times the assignment step of a few samples (with **k** centroids taken from the
+    /// samples) for every candidate configuration, and applies the fastest one:
+    /// - the default kernel, the blocked centroid scan (see [`KMeans::with_blocked_centroid_scan`]), the norm cache
+    ///   (see [`KMeans::with_norm_cache`], only for the squared euclidean distance) and the dimension-chunked
+    ///   processing with a few chunk sizes (see [`KMeans::with_dimension_chunking`], only for separable distances)
+    /// - each with the default static partitioning, and a few parallel chunk sizes (see
+    ///   [`KMeans::with_parallel_chunk_size`])
+    ///
+    /// These settings of **self** are replaced with the fastest candidate (see [`KMeans::kernel_config`]), all other
+    /// settings are kept. The choice is cached for the rest of the process, per sample type, `LANES`, distance
+    /// function, amount of dimensions, **k** and amount of threads, so calibrating many datasets of the same shape
+    /// only costs one run. Since `LANES` is a compile-time parameter, it can not be tuned here: Compare a few values
+    /// of `LANES` with the same data instead. As all candidates are timed on the actual machine, the choice may differ
+    /// between processes, while the results of the calculations stay the same (apart from the last bits of the
+    /// distances, due to the differing order of summation).
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters the calculations will search for
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k) = (5000, 200, 10);
+    /// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance).with_auto_tuning(k);
+    /// println!("Tuned kernel: {:?}", kmean.kernel_config());
+    /// let result = kmean.kmeans_lloyd(k, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn with_auto_tuning(self, k: usize) -> Self { crate::auto_tune::with_auto_tuning(self, k) }
+
+    /// Current configuration of the assignment step's kernel, e.g. as chosen by [`KMeans::with_auto_tuning`].
+    pub fn kernel_config(&self) -> KernelConfig { crate::auto_tune::kernel_config(self) }
+
+    /// Set the amount of samples that are processed by a thread at once, when the samples are split across threads.
+    ///
+    /// ## Description
+    /// By default, the samples are statically partitioned into one block per thread, which avoids scheduling overhead
+    /// as long as every sample costs about the same. If the cost per sample varies (e.g. due to the early abandoning of
+    /// distance calculations, or the candidate pre-filter), this leads to load imbalance, where some threads finish
+    /// long before the others. Smaller chunks allow idle threads to steal work from the busy ones, at the cost of some
+    /// scheduling overhead (a few hundred to a few thousand samples per chunk typically work well).
+    ///
+    /// ## Arguments
+    /// - **chunk_size**: Minimum amount of samples per work packet (`>= 1`)
+    pub fn with_parallel_chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0);
+        self.parallel_chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Set a weight for every sample, e.g. for pre-aggregated data, where each sample represents many original records.
+    ///
+    /// ## Description
+    /// A sample with weight **w** counts as if it was contained **w** times in the data: Centroids are moved into the
+    /// weighted mean of their samples, the distsum is the weighted sum of distances, and the random initialization
+    /// methods ([`KMeans::init_kmeanplusplus`], [`KMeans::init_random_sample`], [`KMeans::init_random_partition`])
+    /// select samples with a probability proportional to their weight. [`KMeans::init_grid`] weights the density of
+    /// its cells. All variants honor the weights.
+    ///
+    /// The **centroid_frequency** of results still counts the samples of each cluster (not their weights), and custom
+    /// [`CentroidUpdater`]s only get the unweighted members of each cluster.
+    ///
+    /// ## Arguments
+    /// - **weights**: Weight of every sample (positive and finite)
+    pub fn with_sample_weights(mut self, weights: &[T]) -> Self {
+        assert_eq!(weights.len(), self.sample_cnt);
+        assert!(weights.iter().all(|w| *w > T::zero() && w.is_finite()));
+        self.sample_weights = Some(weights.to_vec());
+        self
+    }
+
+    /// Add a tiny, seeded noise to all samples, to break ties between exactly equidistant centroids deterministically.
+    ///
+    /// ## Description
+    /// On quantized or integer-like data, samples are often exactly equidistant to multiple centroids. Which one they
+    /// are assigned to then depends on the last bits of the distances, which differ with the order of summation, and
+    /// thus between values of `LANES`, kernels of the assignment step (see [`KMeans::kernel_config`]) and platforms.
+    /// This adds a uniformly distributed noise within `[-magnitude, magnitude)` to every value of every sample, drawn
+    /// from a [`StdRng`] seeded with **seed**, which separates the tied distances far beyond the rounding errors of the
+    /// distance function. The noise is the same on all platforms and for all values of `LANES`, so the assignments of
+    /// such samples are as well. The norm cache (see [`KMeans::with_norm_cache`]) resolves near-ties from the exact
+    /// distances, so its assignments are the same, too.
+    ///
+    /// The samples stay perturbed for all calculations of this instance (the centroids are means of the perturbed
+    /// samples), so **magnitude** should be far below the resolution of the data (e.g. `1e-6` times the quantization
+    /// step), while still far above the rounding errors of the distances. Samples passed to the assignment of new
+    /// samples (e.g. [`KMeans::predict`]) are not perturbed. Call this before [`KMeans::with_lsh_prefilter`], whose
+    /// hashes are calculated from the samples.
+    ///
+    /// ## Arguments
+    /// - **magnitude**: Maximum absolute noise per value (`> 0`)
+    /// - **seed**: Seed of the noise
+    pub fn with_tie_breaking_noise(mut self, magnitude: T, seed: u64) -> Self {
+        assert!(magnitude > T::zero());
+        assert!(self.lsh.is_none());
+        let mut rnd = StdRng::seed_from_u64(seed);
+        let dims = self.sample_dims;
+        self.p_samples.chunks_exact_stride_mut().for_each(|s| {
+            s[..dims]
+                .iter_mut()
+                .for_each(|v| *v += magnitude * rnd.gen_range(-T::one()..T::one()))
+        });
+        if self.sample_norms.is_some() {
+            self.sample_norms = Some(crate::norm_cache::squared_norms::<T, LANES>(&self.p_samples));
+        }
+        self
+    }
+
+    /// Distance function of this instance, e.g. to read or reset the evaluations of a [`crate::CountingDistance`].
+    pub fn distance_fn(&self) -> &D { &self.distance_fn }
+
+    /// Weight of the given sample (`1` without sample weights).
+    #[inline(always)]
+    pub(crate) fn sample_weight(&self, sample_id: usize) -> T { self.sample_weights.as_ref().map_or(T::one(), |w| w[sample_id]) }
+
+    /// Run the assignment step on a dedicated thread pool, with one worker pinned to each physical core.
+    ///
+    /// ## Description
+    /// Workers are only placed on the first logical cpu of every physical core (avoiding its SMT siblings), and
+    /// stay there for the whole calculation. For the memory-bound SIMD code of the assignment step, this avoids two
+    /// workers competing for the execution units and caches of one core, as well as migrations between cores, which
+    /// measurably improves throughput on many CPUs. If the physical cores can not be determined (currently only
+    /// linux is supported), the workers stay unpinned.
+    ///
+    /// Requires the `core-pinning` feature.
+    #[cfg(feature = "core-pinning")]
+    pub fn with_core_pinning(mut self) -> Self {
+        self.pinned_pool = Some(crate::affinity::pinned_thread_pool());
+        self
+    }
+
+    /// Minimum amount of samples per work packet, when processing **cnt** samples in parallel.
+    pub(crate) fn work_packet_size(&self, cnt: usize) -> usize {
+        // manually calculate work-packet size, because rayon does not do static scheduling (which is more apropriate here)
+        self.parallel_chunk_size.unwrap_or(cnt / rayon::current_num_threads())
+    }
+
+    /// Enable the dimension-chunked processing of very wide samples (e.g. with thousands of dimensions).
+    ///
+    /// Instead of calculating the distance of one sample to all centroids at a time, the assignment step then
+    /// processes the dimensions in chunks of **chunk_dims** across a block of samples and all centroids,
+    /// accumulating partial distances. This keeps the currently processed parts of the samples and centroids in
+    /// the cache, which can significantly speed up the calculation for very wide datasets.
+    ///
+    /// ## Arguments
+    /// - **chunk_dims**: Amount of dimensions per chunk (rounded up to a multiple of `LANES`)
+    ///
+    /// ## Description
+    /// The chunked processing is only used with distance functions that are separable over the dimensions (see
+    /// [`DistanceFunction::is_separable`]), such as [`crate::EuclideanDistance`]. For all other distance functions,
+    /// this setting has no effect. Due to the differing order of summation, distances may differ in the last bits.
+    pub fn with_dimension_chunking(mut self, chunk_dims: usize) -> Self {
+        assert!(chunk_dims > 0);
+        self.dimension_chunk = Some(chunk_dims.div_ceil(LANES) * LANES);
+        self
+    }
+
+    pub(crate) fn update_centroid_distances(&self, state: &mut KMeansState<T>) {
+        let centroids = &state.centroids;
+
+        let work_packet_size = self.work_packet_size(self.sample_cnt);
+        let samples = self
+            .p_samples
+            .bfr
+            .par_chunks_exact(self.p_samples.stride)
+            .zip(state.assignments.par_iter().cloned())
+            .zip(state.centroid_distances.par_iter_mut());
+        simd::for_each_packet(samples, work_packet_size, |((s, assignment), centroid_dist)| {
+            *centroid_dist = self.distance_fn.distance(s, centroids.nth_stride(assignment));
+        });
+    }
+
+    pub(crate) fn update_cluster_assignments(&self, state: &mut KMeansState<T>, limit_k: Option<usize>) {
+        #[cfg(feature = "core-pinning")]
+        if let Some(pool) = self.pinned_pool.as_ref().filter(|pool| pool.current_thread_index().is_none()) {
+            return pool.install(|| self.update_cluster_assignments(state, limit_k));
+        }
+        let k = limit_k.unwrap_or(state.k);
+        if let Some(chunk_dims) = self
+            .dimension_chunk
+            .filter(|&c| c < self.p_samples.stride && self.distance_fn.is_separable())
+        {
+            return crate::dimension_chunking::update_cluster_assignments(self, state, k, chunk_dims);
+        }
+        if let Some(sample_norms) = &self.sample_norms {
+            return crate::norm_cache::update_cluster_assignments(self, state, k, sample_norms);
+        }
+        if let Some(lsh) = &self.lsh {
+            return crate::lsh::update_cluster_assignments(self, state, k, lsh);
+        }
+        if self.blocked_scan {
+            return crate::blocked_scan::update_cluster_assignments(self, state, k);
+        }
+        let centroids = &state.centroids;
+
+        let work_packet_size = self.work_packet_size(self.sample_cnt);
+        let samples = self
+            .p_samples
+            .bfr
+            .par_chunks_exact(self.p_samples.stride)
+            .zip(state.assignments.par_iter_mut())
+            .zip(state.centroid_distances.par_iter_mut());
+        simd::for_each_packet(samples, work_packet_size, |((s, assignment), centroid_dist)| {
+            // The distance to the best centroid so far bounds all following distance calculations
+            let (best_idx, best_dist) =
+                centroids
+                    .chunks_exact_stride()
+                    .take(k)
+                    .enumerate()
+                    .fold((0, T::infinity()), |(best_idx, best_dist), (idx, c)| {
+                        let dist = self.distance_fn.distance_bounded(s, c, best_dist);
+                        if dist < best_dist {
+                            (idx, dist)
+                        } else {
+                            (best_idx, best_dist)
+                        }
+                    });
+            *assignment = best_idx;
+            *centroid_dist = best_dist;
+        });
+    }
+
+    /// Recalculate the per-cluster sums of the (weighted) distances of **state**, from its assignments and distances.
+    pub(crate) fn update_per_cluster_distsum(&self, state: &mut KMeansState<T>) {
+        let mut per_cluster_distsum = vec![T::zero(); state.k];
+        state
+            .assignments
+            .iter()
+            .zip(state.centroid_distances.iter())
+            .enumerate()
+            .for_each(|(sample_id, (&centroid_id, &dist))| per_cluster_distsum[centroid_id] += dist * self.sample_weight(sample_id));
+        state.per_cluster_distsum = per_cluster_distsum;
+    }
+
+    pub(crate) fn update_cluster_frequencies(&self, assignments: &[usize], centroid_frequency: &mut [usize]) -> usize {
+        centroid_frequency.iter_mut().for_each(|v| *v = 0);
+        let mut used_centroids_cnt = 0;
+        assignments.iter().cloned().for_each(|centroid_id| {
+            if centroid_frequency[centroid_id] == 0 {
+                used_centroids_cnt += 1; // Count the amount of centroids with more than 0 samples
+            }
+            centroid_frequency[centroid_id] += 1;
+        });
+        used_centroids_cnt
+    }
+
+    /// Assign the given (not yet padded) samples to their nearest centroid in the given state.
+    /// ## Returns
+    /// Tuple of (assignments, centroid_distances) for the given samples
+    pub(crate) fn assign_samples(&self, state: &KMeansState<T>, samples: &[T]) -> (Vec<usize>, Vec<T>) {
+        let res = self.assign_samples_with(state, samples, false);
+        (res.assignments, res.centroid_distances)
+    }
+
+    /// Assign the given (not yet padded) samples to their nearest centroid in the given state, optionally also
+    /// tracking the distance to the runner-up centroid, to calculate each sample's margin.
+    pub(crate) fn assign_samples_with(&self, state: &KMeansState<T>, samples: &[T], margins: bool) -> SampleAssignments<T> {
+        assert_eq!(samples.len() % self.sample_dims, 0);
+        let padding = self.distance_fn.padding_value();
+        let p_samples = StrideBuffer::from_slice::<LANES>(self.sample_dims, samples).padded(padding);
+        // The state may come from elsewhere (e.g. deserialized), without the padding of this distance function
+        let centroids = &state.centroids.clone().padded(padding);
+
+        let samples = p_samples.bfr.par_chunks_exact(p_samples.stride);
+        let nearest: Vec<(usize, T, T)> = simd::map_packets(samples, simd::PACKET_SIZE, |s| {
+            let (mut best_idx, mut best_dist, mut second_dist) = (0, T::infinity(), T::infinity());
+            centroids.chunks_exact_stride().enumerate().for_each(|(idx, c)| {
+                let dist = self.distance_fn.distance(s, c);
+                if dist < best_dist {
+                    (best_idx, best_dist, second_dist) = (idx, dist, best_dist);
+                } else if dist < second_dist {
+                    second_dist = dist;
+                }
+            });
+            (best_idx, best_dist, second_dist - best_dist)
+        });
+        SampleAssignments {
+            assignments: nearest.iter().map(|n| n.0).collect(),
+            centroid_distances: nearest.iter().map(|n| n.1).collect(),
+            margins: margins.then(|| nearest.iter().map(|n| n.2).collect()),
+        }
+    }
+
+    /// Capture the manifest of a calculation on the samples of this instance, to audit its result and exactly
+    /// reproduce it later. See [`RunManifest`] for details.
+    ///
+    /// ## Description
+    /// The manifest records all values of **config**, the seed of its random number generator (if it was seeded
+    /// through [`KMeansConfigBuilder::seed`]), a fingerprint of the samples, the crate version and the chosen variant.
+    /// Capture it before the calculation: The random number generator advances with every calculation, so only a
+    /// freshly built (or cloned) configuration reproduces the result of its recorded seed.
+    ///
+    /// ## Arguments
+    /// - **variant**: Name of the variant (and initialization method) that is used, e.g. `"lloyd/kmeans++"`
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Maximum amount of iterations
+    /// - **config**: [`KMeansConfig`] instance the calculation is done with
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k) = (2000, 4, 8);
+    /// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    ///
+    /// let conf = KMeansConfig::build().seed(42).build();
+    /// let manifest = kmean.run_manifest("lloyd/kmeans++", k, 100, &conf);
+    /// let result = kmean.kmeans_lloyd(k, 100, KMeans::init_kmeanplusplus, &conf);
+    ///
+    /// // Later: reproduce the result from the manifest
+    /// assert!(manifest.matches_data(&kmean));
+    /// let reproduced = kmean.kmeans_lloyd(manifest.k, manifest.max_iter, KMeans::init_kmeanplusplus, &manifest.config());
+    /// assert_eq!(reproduced.assignments, result.assignments);
+    /// ```
+    pub fn run_manifest(&self, variant: &str, k: usize, max_iter: usize, config: &KMeansConfig<'_, T>) -> RunManifest<T> {
+        crate::manifest::calculate(self, variant, k, max_iter, config)
+    }
+
+    /// Normal K-Means algorithm implementation. This is the same algorithm as implemented in Matlab (one-phase).
+    /// (see: https://uk.mathworks.com/help/stats/kmeans.html#bueq7aj-5    Section: More About)
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k, max_iter) = (20000, 200, 4, 100);
+    ///
+    /// // Generate some random data
+    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
+    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    ///
+    /// // Calculate kmeans, using kmean++ as initialization-method
+    /// // KMeans<_, 8> specifies to use f64 SIMD vectors with 8 lanes (e.g. AVX512)
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_lloyd(k, max_iter, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    ///
+    /// println!("Centroids: {:?}", result.centroids);
+    /// println!("Cluster-Assignments: {:?}", result.assignments);
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn kmeans_lloyd<F>(&self, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::Lloyd::calculate(self, k, max_iter, init, config)
+    }
+
+    /// Continue the Lloyd iterations (see [`KMeans::kmeans_lloyd`]) from the centroids of a previous result, instead
+    /// of initializing them again (warm start).
+    ///
+    /// ## Description
+    /// Only the centroids of **state** are used, so it may also have been calculated on different samples with the
+    /// same amount of dimensions (e.g. to refresh a model on slightly changed data, which then converges in a handful
+    /// of iterations). The returned result counts the resumed iterations only, in [`KMeansState::n_iterations`].
+    ///
+    /// ## Arguments
+    /// - **state**: Previous k-means result, calculated with the same **LANES**
+    /// - **extra_iters**: Limit the maximum amount of additional iterations
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k) = (2000, 4, 8);
+    /// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_lloyd(k, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    ///
+    /// // Refresh the model on changed data
+    /// let changed: Vec<f64> = samples.iter().map(|v| v + 0.01 * rand::random::<f64>()).collect();
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&changed, sample_cnt, sample_dims, EuclideanDistance);
+    /// let refreshed = kmean.kmeans_lloyd_resume(result, 100, &KMeansConfig::default());
+    /// println!("Refreshed in {} iterations", refreshed.n_iterations);
+    /// ```
+    pub fn kmeans_lloyd_resume(&self, state: KMeansState<T>, extra_iters: usize, config: &KMeansConfig<'_, T>) -> KMeansState<T> {
+        assert_eq!(state.centroids.centroid_dim, self.sample_dims);
+        assert_eq!(
+            state.centroids.stride, self.p_samples.stride,
+            "state was calculated with different LANES"
+        );
+        let k = state.centroids.centroid_cnt;
+        let centroids = state.centroids;
+        crate::variants::Lloyd::calculate(self, k, extra_iters, move |_, state, _| state.centroids = centroids, config)
+    }
+
+    /// Elkan's accelerated k-Means algorithm, producing the same results as [`KMeans::kmeans_lloyd`].
+    ///
+    /// ## Description
+    /// For every sample, a lower bound of its distance to every centroid is maintained, and the distances between all
+    /// centroids are calculated once per iteration. Using the triangle inequality, all centroids that can not be
+    /// nearer to a sample than its current centroid are skipped, which avoids the vast majority of distance
+    /// calculations for high values of k and high-dimensional data. The bounds are based on the square root of the
+    /// distance function, so it has to be a metric, or the square of a metric (e.g. [`crate::EuclideanDistance`]).
+    /// The lower bounds require additional memory of `sample_cnt * k` values.
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k, max_iter) = (5000, 50, 32, 100);
+    ///
+    /// // Generate some random data
+    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
+    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_elkan(k, max_iter, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    ///
+    /// println!("Centroids: {:?}", result.centroids);
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn kmeans_elkan<F>(&self, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::Elkan::calculate(self, k, max_iter, init, config)
+    }
+
+    /// Hamerly's accelerated k-Means algorithm, producing the same results as [`KMeans::kmeans_lloyd`].
+    ///
+    /// ## Description
+    /// Like [`KMeans::kmeans_elkan`], this uses the triangle inequality to skip distance calculations. Instead of one
+    /// lower bound per sample and centroid, only a single lower bound of the distance to the second-nearest centroid
+    /// is maintained per sample. Samples whose current centroid is guaranteed to stay the nearest are skipped, all
+    /// others are compared against every centroid. This skips fewer distance calculations than Elkan's algorithm, but
+    /// only requires additional memory in the order of `sample_cnt` (instead of `sample_cnt * k`), which makes it the
+    /// better choice for very large datasets or very high values of k.
+    /// The distance function has to be a metric, or the square of a metric (e.g. [`crate::EuclideanDistance`]).
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k, max_iter) = (5000, 50, 32, 100);
+    ///
+    /// // Generate some random data
+    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
+    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_hamerly(k, max_iter, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    ///
+    /// println!("Centroids: {:?}", result.centroids);
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn kmeans_hamerly<F>(&self, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::Hamerly::calculate(self, k, max_iter, init, config)
+    }
+
+    /// kd-tree accelerated k-Means algorithm (filtering algorithm of Kanungo et al. / Pelleg and Moore), producing the
+    /// same results as [`KMeans::kmeans_lloyd`].
+    ///
+    /// ## Description
+    /// A kd-tree over all samples is built once, splitting every node at the median of its widest dimension. In every
+    /// assignment step, the tree is traversed with the set of candidate centroids of each node: Candidates that are
+    /// farther than the centroid nearest to the node's midpoint from the whole bounding box of the node are pruned, and
+    /// once a single candidate is left, all samples of the node are assigned to it without comparing them against any
+    /// other centroid. For low-dimensional data (up to about 16 dimensions) with spatial structure, this skips most
+    /// distance calculations. For high-dimensional data, the bounding boxes hardly prune any centroids, so
+    /// [`KMeans::kmeans_elkan`] or [`KMeans::kmeans_hamerly`] are the better choice.
+    /// The pruning relies on the geometry of the euclidean distance, so the distance function has to calculate the
+    /// squared euclidean distance (see [`DistanceFunction::is_squared_euclidean`]), e.g. [`crate::EuclideanDistance`].
+    /// The tree requires additional memory in the order of `sample_cnt` values.
     ///
     /// ## Arguments
     /// - **k**: Amount of clusters to search for
@@ -253,127 +1527,1205 @@ where
     /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
     ///
     /// ## Returns
-    /// Instance of [`KMeansState`], containing the final state (result).
+    /// Instance of [`KMeansState`], containing the final state (result).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k, max_iter) = (20000, 2, 32, 100);
+    ///
+    /// // Generate some random (geospatial) data
+    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
+    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    ///
+    /// let kmean: KMeans<_, 2, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_kdtree(k, max_iter, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    ///
+    /// println!("Centroids: {:?}", result.centroids);
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn kmeans_kdtree<F>(&self, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::KdTreeFiltering::calculate(self, k, max_iter, init, config)
+    }
+
+    /// Spherical k-Means implementation, for clustering samples by their direction (e.g. tf-idf vectors or embeddings).
+    ///
+    /// ## Description
+    /// Like [`KMeans::kmeans_lloyd`], but every centroid is re-normalized to unit length after each update (and after
+    /// the initialization). Meant to be used with [`crate::CosineDistance`], for which the normalized mean of the
+    /// members is the centroid that maximizes their summed cosine similarity. For this to hold, the samples should be
+    /// normalized to unit length as well, otherwise longer samples pull the centroids more strongly. A centroid of
+    /// length zero is kept as it is. Other than [`KMeansConfigBuilder::centroid_updater`] with
+    /// [`crate::NormalizedMeanUpdater`], this uses the optimized built-in mean calculation.
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k, max_iter) = (2000, 64, 8, 100);
+    ///
+    /// // Generate some random data, normalized to unit length
+    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
+    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    /// samples.chunks_exact_mut(sample_dims).for_each(|s| {
+    ///     let norm = s.iter().map(|v| v * v).sum::<f64>().sqrt();
+    ///     s.iter_mut().for_each(|v| *v /= norm);
+    /// });
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, CosineDistance);
+    /// let result = kmean.kmeans_spherical(k, max_iter, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    ///
+    /// println!("Centroids: {:?}", result.centroids);
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn kmeans_spherical<F>(&self, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::Spherical::calculate(self, k, max_iter, init, config)
+    }
+
+    /// K-Harmonic-Means implementation (KHM), which is markedly less sensitive to the initialization than
+    /// [`KMeans::kmeans_lloyd`].
+    ///
+    /// ## Description
+    /// Instead of the distance to the nearest centroid, KHM minimizes the harmonic mean of the distances to all
+    /// centroids: `sum_i k / sum_j d_ij^(-p)`. Every sample thus pulls on every centroid, weighted by
+    /// `d_ij^(-p-2) / (sum_l d_il^(-p))^2`, which lets centroids escape from clusters they share with other centroids.
+    /// For [`crate::EuclideanDistance`], the (unsquared) euclidean distance is used as `d`, for all other distance
+    /// functions the distance as returned. The harmonic objective is passed to the abort strategy and the iteration
+    /// callbacks, while the returned result is the hard partitioning of the samples to their nearest centroid, with
+    /// the usual **distsum**.
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
+    /// - **p**: Exponent of the distances (`>= 2`). Larger values give samples far from all centroids more weight,
+    ///   but can make the iterations oscillate (`2` is a robust choice, `3.5` is common for high-dimensional data)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k, max_iter) = (2000, 16, 8, 100);
+    ///
+    /// // Generate some random data
+    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
+    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_harmonic(k, max_iter, 2.0, KMeans::init_random_sample, &KMeansConfig::default());
+    ///
+    /// println!("Centroids: {:?}", result.centroids);
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn kmeans_harmonic<F>(&self, k: usize, max_iter: usize, p: T, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::Harmonic::calculate(self, k, max_iter, p, init, config)
+    }
+
+    /// Mini-Batch k-Means implementation with importance sampling.
+    ///
+    /// ## Description
+    /// Instead of uniformly drawn batches, each batch preferentially contains samples with a high loss (distance to
+    /// their centroid): Samples are drawn (with replacement) with a probability that is a mixture of the uniform
+    /// distribution and a distribution proportional to their last known loss. The centroid update is corrected using
+    /// inverse-probability weights, so the centroids remain unbiased estimates of their cluster's mean. This
+    /// typically converges in fewer batches on datasets with rare but important regions.
+    ///
+    /// ## Arguments
+    /// - **batch_size**: Amount of samples to use per iteration (higher -> better approximation but slower)
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k, max_iter) = (20000, 20, 4, 100);
+    ///
+    /// // Generate some random data
+    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
+    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_minibatch_importance(500, k, max_iter, KMeans::init_random_sample, &KMeansConfig::default());
+    ///
+    /// println!("Centroids: {:?}", result.centroids);
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn kmeans_minibatch_importance<F>(
+        &self, batch_size: usize, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+    ) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::ImportanceMinibatch::calculate(self, batch_size, k, max_iter, init, config)
+    }
+
+    /// Estimate the memory a calculation with **k** clusters on the samples of this [`KMeans`] instance requires, e.g.
+    /// to check whether a job fits into a container's memory limit before launching it.
+    ///
+    /// ## Description
+    /// The estimate is done for [`KMeans::kmeans_lloyd`], including the worst case of its temporary buffers (which
+    /// occurs when empty clusters have to be refilled). Mini-Batch calculations additionally keep a shuffled copy of
+    /// the samples, which is included as well for an upper bound. The memory that was actually used by a finished
+    /// calculation is reported in [`KMeansState::memory_usage`].
+    ///
+    /// ## Returns
+    /// The estimated [`MemoryUsage`], in bytes.
+    pub fn estimated_memory(&self, k: usize) -> MemoryUsage {
+        let mut usage = MemoryUsage::new::<T, LANES>(self.sample_cnt, self.sample_dims, k);
+        usage.samples += self.sample_norms.as_ref().map_or(0, |n| std::mem::size_of_val(n.as_slice()));
+        usage.samples += self.lsh.as_ref().map_or(0, |l| std::mem::size_of_val(l.sample_hashes.as_slice()));
+        usage.samples += self.sample_weights.as_ref().map_or(0, |w| std::mem::size_of_val(w.as_slice()));
+        let centroids = k * self.p_samples.stride * std::mem::size_of::<T>();
+        let lloyd = centroids + self.sample_cnt * std::mem::size_of::<usize>();
+        let minibatch = usage.samples + self.sample_cnt * std::mem::size_of::<usize>();
+        usage.record_temporaries(lloyd.max(minibatch));
+        usage
+    }
+
+    /// Step-wise variant of [`KMeans::kmeans_lloyd`], where the caller drives the iterations.
+    ///
+    /// ## Description
+    /// This initializes the calculation, and returns a [`KMeansRun`], whose [`KMeansRun::step`] does one iteration at
+    /// a time. Between the iterations, the current state can be inspected and modified. Once done,
+    /// [`KMeansRun::finish`] returns the final result.
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k) = (2000, 20, 4);
+    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
+    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let conf = KMeansConfig::default();
+    /// let mut run = kmean.start_lloyd(k, KMeans::init_kmeanplusplus, &conf);
+    /// loop {
+    ///     let stats = run.step();
+    ///     println!("Iteration {}: {}", stats.iteration, stats.distsum);
+    ///     // Custom stopping criterion
+    ///     if stats.abort_requested || stats.improvement < 0.01 * stats.distsum {
+    ///         break;
+    ///     }
+    /// }
+    /// let result = run.finish();
+    /// println!("Centroids: {:?}", result.centroids);
+    /// ```
+    pub fn start_lloyd<'r, F>(&'r self, k: usize, init: F, config: &'r KMeansConfig<'r, T>) -> KMeansRun<'r, T, LANES, D>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        KMeansRun::new(self, k, init, config)
+    }
+
+    /// Mini-Batch k-Means implementation.
+    /// (see: https://dl.acm.org/citation.cfm?id=1772862)
+    ///
+    /// ## Arguments
+    /// - **batch_size**: Amount of samples to use per iteration (higher -> better approximation but slower)
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k, max_iter) = (20000, 200, 4, 100);
+    ///
+    /// // Generate some random data
+    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
+    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    ///
+    /// // Calculate kmeans, using kmean++ as initialization-method
+    /// // KMeans<_, 8> specifies to use f64 SIMD vectors with 8 lanes (e.g. AVX512)
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_minibatch(4, k, max_iter, KMeans::init_random_sample, &KMeansConfig::default());
+    ///
+    /// println!("Centroids: {:?}", result.centroids);
+    /// println!("Cluster-Assignments: {:?}", result.assignments);
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn kmeans_minibatch<F>(&self, batch_size: usize, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+        T: Primitive,
+        LaneCount<LANES>: SupportedLaneCount,
+        Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    {
+        crate::variants::Minibatch::calculate(self, batch_size, k, max_iter, init, config)
+    }
+
+    /// Choose a batch size for [`KMeans::kmeans_minibatch`] (and the other mini-batch variants), instead of guessing
+    /// one by hand.
+    ///
+    /// ## Description
+    /// A quick calibration run measures the time of the distance calculations on a few samples, from which the batch
+    /// size follows, for which the assignment step of one iteration (`batch_size * k` distance calculations, on all
+    /// threads of the rayon thread pool) takes about **target_batch_time**. This is then bounded:
+    /// - below by `64` samples per thread (and at least **k**), since smaller batches are dominated by the overhead of
+    ///   the parallelization
+    /// - above by an assumed last-level cache size of 8 MiB for the batch and the centroids, so the centroid update
+    ///   can read the batch from the cache again
+    /// - above by the amount of samples (minus one)
+    ///
+    /// Since the calibration measures the actual machine, the result may differ between calls.
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters the calculation will search for
+    /// - **target_batch_time**: Desired time per iteration (e.g. a few milliseconds)
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    /// use std::time::Duration;
+    ///
+    /// let (sample_cnt, sample_dims, k) = (20000, 50, 10);
+    /// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let batch_size = kmean.auto_batch_size(k, Duration::from_millis(2));
+    /// let result = kmean.kmeans_minibatch(batch_size, k, 100, KMeans::init_random_sample, &KMeansConfig::default());
+    /// println!("Batch size: {}, Error: {}", batch_size, result.distsum);
+    /// ```
+    pub fn auto_batch_size(&self, k: usize, target_batch_time: std::time::Duration) -> usize {
+        crate::auto_batch_size::calculate(self, k, target_batch_time)
+    }
+
+    /// Mini-Batch k-Means implementation (see [`KMeans::kmeans_minibatch`]), followed by a polish pass of full Lloyd
+    /// iterations (see [`KMeans::kmeans_lloyd`]).
+    ///
+    /// ## Description
+    /// The mini-batch training quickly finds a good approximation, which is then refined by up to **polish_iter**
+    /// iterations on all samples, starting from the mini-batch centroids. Both phases use the abort-strategy and the
+    /// callbacks of **config** (the iteration count restarts at `1` for the polish pass), while the post-processing
+    /// steps are only applied to the polished result.
+    ///
+    /// ## Arguments
+    /// - **batch_size**: Amount of samples to use per mini-batch iteration
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of mini-batch iterations
+    /// - **polish_iter**: Limit the maximum amount of full Lloyd iterations afterwards (`0` = no polishing)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k) = (20000, 200, 4);
+    /// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_minibatch_polished(100, k, 100, 5, KMeans::init_random_sample, &KMeansConfig::default());
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn kmeans_minibatch_polished<F>(
+        &self, batch_size: usize, k: usize, max_iter: usize, polish_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+    ) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::Minibatch::calculate_polished(self, batch_size, k, max_iter, polish_iter, init, config)
+    }
+
+    /// Overlapping k-Means implementation, where a sample may belong to multiple clusters.
+    ///
+    /// ## Description
+    /// In every iteration, each sample is assigned to its nearest cluster, as well as to all other clusters whose
+    /// centroid is within **overlap** times the distance to the nearest centroid (as returned by the distance function,
+    /// so e.g. squared distances for [`crate::EuclideanDistance`]). Every centroid is then moved into the mean of all
+    /// its members, including the overlapping ones. This yields non-exclusive clusters, as needed e.g. for tagging.
+    /// Post-processing steps of the configuration (such as the minimum cluster size) are not applied.
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
+    /// - **overlap**: Factor (`>= 1`) on the nearest distance, up to which a sample belongs to further clusters
+    ///   (`1` results in normal, non-overlapping clusters, apart from ties)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`OverlappingKMeansState`], containing the final state (result) and the memberships of all samples.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let samples = vec![0.0f64, 1.0, 2.0, 5.0, 8.0, 9.0, 10.0];
+    /// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 7, 1, EuclideanDistance);
+    /// let result = kmean.kmeans_overlapping(2, 100, 1.5, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    /// println!("Memberships: {:?}", result.memberships);
+    /// ```
+    pub fn kmeans_overlapping<F>(
+        &self, k: usize, max_iter: usize, overlap: T, init: F, config: &KMeansConfig<'_, T>,
+    ) -> OverlappingKMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::Overlapping::calculate(self, k, max_iter, overlap, init, config)
+    }
+
+    /// Fuzzy c-Means implementation, where every sample belongs to every cluster with a membership degree.
+    ///
+    /// ## Description
+    /// In every iteration, the membership degree of every sample in every cluster is calculated from its distances
+    /// `u_j = 1 / sum_l (d_j / d_l)^(1 / (m - 1))` (as returned by the distance function, so e.g. squared distances for
+    /// [`crate::EuclideanDistance`], which is the usual formulation). Every centroid is then moved into the mean of all
+    /// samples, weighted by `u^m`. The memberships of each sample sum up to `1`, and the fuzzifier **m** controls how
+    /// soft they are: values close to `1` approach the hard partitioning of k-means, while higher values share samples
+    /// more evenly between the clusters. The abort strategy observes the fuzzy objective `sum_i sum_j u_ij^m * d_ij`,
+    /// while the reported **distsum** (as well as **assignments** and **centroid_frequency**) refer to the hard
+    /// partitioning derived from the final memberships. Post-processing steps of the configuration (such as the minimum
+    /// cluster size) are not applied.
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
+    /// - **m**: Fuzzifier (`> 1`), typically `2`
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`FuzzyKMeansState`], containing the final state (result) and the n×k membership matrix.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let samples = vec![0.0f64, 1.0, 2.0, 6.0, 10.0, 11.0, 12.0];
+    /// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 7, 1, EuclideanDistance);
+    /// let result = kmean.kmeans_fuzzy(2, 100, 2.0, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    /// println!("Memberships of the middle sample: {:?}", result.memberships_of(3));
+    /// ```
+    pub fn kmeans_fuzzy<F>(&self, k: usize, max_iter: usize, m: T, init: F, config: &KMeansConfig<'_, T>) -> FuzzyKMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::Fuzzy::calculate(self, k, max_iter, m, init, config)
+    }
+
+    /// Radius-constrained k-Means implementation, that adds clusters until every sample lies within a maximum radius of
+    /// its centroid.
+    ///
+    /// ## Description
+    /// For applications such as geospatial coverage, the real constraint is the maximum distance of every sample to its
+    /// centroid, rather than the amount of clusters. This variant starts with **k** centroids and runs Lloyd
+    /// iterations (see [`KMeans::kmeans_lloyd`]). Whenever samples lie further than **max_radius** from their nearest
+    /// centroid, new centroids are spawned on them in the manner of leader clustering: The uncovered samples are
+    /// visited in order, and each one that is not within **max_radius** of a centroid spawned before becomes a new
+    /// centroid. The radius is given in the units of the distance function, so e.g. as squared distance for
+    /// [`crate::EuclideanDistance`]. Spawning centroids restarts the abort strategy, so the calculation only converges
+    /// once no more centroids are spawned (or it is aborted by **max_iter** or a forced abort).
+    ///
+    /// After the last iteration, samples that were left uncovered by moving the centroids into their means get
+    /// centroids of their own, so every sample of the result lies within **max_radius** of its centroid, and the
+    /// result's **k** is the final amount of clusters. Post-processing steps of the configuration (such as the minimum
+    /// cluster size) are not applied, as they would break this guarantee.
+    ///
+    /// ## Arguments
+    /// - **k**: Initial amount of clusters
+    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
+    /// - **max_radius**: Maximum distance (`>= 0`) of every sample to its centroid
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0, 20.0, 21.0, 22.0];
+    /// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 9, 1, EuclideanDistance);
+    /// // Squared euclidean distance, so every sample is at most 2 away from its centroid
+    /// let result = kmean.kmeans_max_radius(1, 100, 4.0, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    /// println!("Clusters: {}", result.k);
+    /// ```
+    pub fn kmeans_max_radius<F>(&self, k: usize, max_iter: usize, max_radius: T, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::MaxRadius::calculate(self, k, max_iter, max_radius, init, config)
+    }
+
+    /// k-Medoids implementation (FasterPAM), whose cluster centers are samples instead of means.
+    ///
+    /// ## Description
+    /// Means are only meaningful for distance functions such as the (squared) euclidean distance. k-Medoids instead
+    /// restricts every center (medoid) to one of the samples, and minimizes the total deviation (the sum of the
+    /// distances of all samples to their medoid) for any distance function. The initial centroids (of the given
+    /// initialization method) are replaced by their nearest samples. Afterwards, every pass tries each sample as
+    /// replacement of a medoid, using the FasterPAM algorithm of Schubert and Rousseeuw: The change of the total
+    /// deviation is evaluated for swaps with all medoids at once, in a single (parallel) pass over all samples, using
+    /// the cached distances of every sample to its nearest and second-nearest medoid. Every improving swap is
+    /// performed immediately. The calculation stops (with [`StopReason::Tolerance`]) after a pass without any swap.
+    ///
+    /// Every pass costs `sample_cnt²` distance calculations, which limits this to moderately sized datasets.
+    /// Post-processing steps of the configuration (such as the minimum cluster size) are not applied, as they would
+    /// move the centers away from the samples.
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of passes over all samples (just pass a high number for infinite)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMedoidsState`], containing the final state (result) and the indices of the medoids.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let samples = vec![0.0f64, 1.0, 2.0, 3.0, 4.0, 20.0, 21.0, 22.0, 23.0, 40.0];
+    /// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 10, 1, ManhattanDistance);
+    /// let result = kmean.kmedoids(2, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    /// println!("Medoids: {:?}", result.medoids);
+    /// ```
+    pub fn kmedoids<F>(&self, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMedoidsState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::variants::KMedoids::calculate(self, k, max_iter, init, config)
+    }
+
+    /// Evolutionary k-Means implementation, a genetic-style hybrid of multiple Lloyd calculations (see
+    /// [`KMeans::kmeans_lloyd`]).
+    ///
+    /// ## Description
+    /// A population of **population_size** Lloyd results is calculated from independent initializations. In every
+    /// generation, the same amount of children is created: Two parents are drawn (binary tournament of the sum of
+    /// distances), every centroid of the first parent is matched with the nearest centroid of the second one, and the
+    /// child randomly inherits one centroid of each matched pair. Half of the children additionally re-seed one
+    /// centroid onto a sample far away from its centroid. Every child is then refined by Lloyd iterations, and the
+    /// best individuals of parents and children form the next generation.
+    ///
+    /// Recombining centroid sets finds better optima than the same amount of independent restarts on hard datasets
+    /// (e.g. many clusters of different density), since children of good results inherit their well-placed
+    /// centroids. The result is never worse than the best of the initial population. The callbacks of **config** are
+    /// called for every refinement (the iteration count restarts at `1`), while the post-processing steps are only
+    /// applied to the final result.
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **population_size**: Amount of individuals per generation (`>= 2`)
+    /// - **generations**: Amount of generations of children (`0` = best of **population_size** independent restarts)
+    /// - **max_iter**: Limit the maximum amount of Lloyd iterations of each refinement (short refinements suffice)
+    /// - **init**: Initialization-Method to use for the initialization of each individual of the initial population
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the best individual of the last generation.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k) = (2000, 2, 10);
+    /// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_evolutionary(k, 8, 10, 20, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn kmeans_evolutionary<F>(
+        &self, k: usize, population_size: usize, generations: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+    ) -> KMeansState<T>
+    where
+        for<'c> F: Fn(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::evolutionary::calculate(self, k, population_size, generations, max_iter, init, config)
+    }
+
+    /// Lloyd k-Means implementation (see [`KMeans::kmeans_lloyd`]), refined by simulated-annealing-like perturbations.
+    ///
+    /// ## Description
+    /// After the first calculation converged, every one of the **rounds** perturbs the centroids of the best result so
+    /// far with random noise, and re-runs Lloyd iterations from there. A result is accepted, if it improves the sum of
+    /// distances. The noise decays linearly over the rounds: Early rounds can move centroids over to other clusters,
+    /// while later rounds only search the close neighborhood of the best result. This is a cheap local search, that
+    /// escapes local minima more reliably than the same amount of independent restarts. The callbacks of **config**
+    /// are called for every calculation (the iteration count restarts at `1`), while the post-processing steps are
+    /// only applied to the final result.
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **rounds**: Amount of perturbations (`0` = plain Lloyd calculation)
+    /// - **initial_noise**: Standard deviation of the noise of the first round, relative to the standard deviation of
+    ///   the samples in each dimension (e.g. `0.5`)
+    /// - **max_iter**: Limit the maximum amount of Lloyd iterations of each calculation
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids of the first calculation
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the best result of all rounds.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k) = (2000, 2, 10);
+    /// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_annealing(k, 20, 0.5, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    /// println!("Error: {}", result.distsum);
+    /// ```
+    pub fn kmeans_annealing<F>(
+        &self, k: usize, rounds: usize, initial_noise: T, max_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+    ) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::annealing::calculate(self, k, rounds, initial_noise, max_iter, init, config)
+    }
+
+    /// Sweep over multiple values of **k**, calculating one k-means result per value.
+    ///
+    /// ## Description
+    /// This calls **run** once for every given **k**, and collects the inertia, explained variance and runtime of every
+    /// calculation into a ready-to-plot structure. Additionally, the elbow of the inertia-vs-k curve is detected using
+    /// the kneedle algorithm (see [`crate::metrics::elbow_k`]), to programmatically suggest a value for **k**. See
+    /// [`KSweep`] for details.
+    ///
+    /// ## Arguments
+    /// - **ks**: Values of **k** to calculate (e.g. `2..=10`)
+    /// - **run**: Closure calculating one k-means result for the given **k**, using any variant / initialization method
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims) = (2000, 2);
+    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
+    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    ///
+    /// let kmean: KMeans<_, 4, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let conf = KMeansConfig::default();
+    /// let sweep = kmean.sweep_k(1..=8, |kmean, k| kmean.kmeans_lloyd(k, 100, KMeans::init_kmeanplusplus, &conf));
+    ///
+    /// println!("Curve: {:?}", sweep.curve());
+    /// println!("Suggested k: {:?}", sweep.elbow);
+    /// ```
+    pub fn sweep_k<F>(&self, ks: impl IntoIterator<Item = usize>, run: F) -> KSweep<T>
+    where
+        F: Fn(&KMeans<T, LANES, D>, usize) -> KMeansState<T>,
+    {
+        crate::sweep::calculate(self, ks, run)
+    }
+
+    /// Sweep over multiple values of **k** like [`KMeans::sweep_k`], calculating the results with
+    /// [`KMeans::kmeans_lloyd`] in parallel.
+    ///
+    /// ## Description
+    /// All calculations share the samples of this [`KMeans`] instance, as well as one pass for the total sum of squares
+    /// (of the explained variances). They run in parallel on the rayon thread pool, where each one uses its own clone
+    /// of **config**. With a seeded random number generator, the sweep thus stays repeatable. Since all calculations
+    /// share the cores, the reported runtimes are wall-clock times of concurrent calculations.
+    ///
+    /// ## Arguments
+    /// - **ks**: Values of **k** to calculate (e.g. `2..=10`)
+    /// - **max_iter**: Limit the maximum amount of iterations of each calculation
+    /// - **init**: Initialization-Method to use for the initialization of the centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculations.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims) = (2000, 2);
+    /// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+    ///
+    /// let kmean: KMeans<_, 4, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let sweep = kmean.sweep_k_lloyd(1..=8, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    ///
+    /// println!("Curve: {:?}", sweep.curve());
+    /// println!("Suggested k: {:?}", sweep.elbow);
+    /// ```
+    pub fn sweep_k_lloyd<F>(&self, ks: impl IntoIterator<Item = usize>, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KSweep<T>
+    where
+        for<'c> F: Fn(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>) + Sync,
+    {
+        crate::sweep::calculate_lloyd(self, ks, max_iter, init, config)
+    }
+
+    /// X-Means implementation, which chooses the amount of clusters by recursively splitting clusters.
+    ///
+    /// ## Description
+    /// Starts with a k-means calculation (Lloyd) with **k_min** clusters. Then, every cluster is split into two halves
+    /// with a local 2-means on its samples, and the split is kept if it improves the Bayesian information criterion
+    /// (BIC) of the cluster's samples, modelled as spherical gaussians. After each round of splits, all centroids are
+    /// refined with a Lloyd calculation on all samples. This repeats until no split improves the BIC, or **k_max**
+    /// clusters are reached (in which case the splits with the highest improvements are preferred). The BIC is based
+    /// on squared euclidean distances, so this requires the [`crate::EuclideanDistance`].
+    ///
+    /// ## Arguments
+    /// - **k_min**: Amount of clusters to start with
+    /// - **k_max**: Maximum amount of clusters
+    /// - **max_iter**: Limit the maximum amount of iterations of each Lloyd calculation
+    /// - **init**: Initialization-Method to use for the initialization of the **k_min** centroids
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculations.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result), whose **k** is the chosen amount of clusters.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let samples = vec![0.0f64, 0.5, 1.0, 10.0, 10.5, 11.0, 20.0, 20.5, 21.0];
+    /// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 9, 1, EuclideanDistance);
+    /// let result = kmean.kmeans_xmeans(1, 5, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    /// println!("Chosen k: {}", result.k);
+    /// ```
+    pub fn kmeans_xmeans<F>(&self, k_min: usize, k_max: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        crate::xmeans::calculate(self, k_min, k_max, max_iter, init, config)
+    }
+
+    /// Cluster many small, independent datasets (e.g. one per customer) in one call.
+    ///
+    /// ## Description
+    /// Looping over thousands of tiny k-means problems underutilizes the thread pool, since every single calculation
+    /// is too small to be split between the threads efficiently. This instead distributes the datasets over the
+    /// thread pool, calling **run** once per dataset (each calculation still uses SIMD internally).
+    ///
+    /// Note that all calculations sharing one [`KMeansConfig`] also share its random number generator, which makes
+    /// the results non-deterministic. For repeatable results, build one seeded configuration per dataset within **run**.
+    ///
+    /// ## Arguments
+    /// - **datasets**: The datasets to cluster, each in the format of the **samples** of [`KMeans::new`]
+    /// - **sample_dims**: Amount of dimensions of the samples (shared by all datasets)
+    /// - **distance_fn**: Distance function to use for all datasets
+    /// - **run**: Closure calculating the k-means result for the dataset with the given index, using any variant /
+    ///   initialization method
+    ///
+    /// ## Returns
+    /// One [`KMeansState`] per dataset, in the order of **datasets**.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    /// use rand::prelude::*;
+    ///
+    /// // 1000 datasets, with 50 samples of 2 dimensions each
+    /// let datasets: Vec<Vec<f64>> = (0..1000).map(|_| (0..100).map(|_| rand::random()).collect()).collect();
+    /// let datasets: Vec<&[f64]> = datasets.iter().map(|d| d.as_slice()).collect();
+    ///
+    /// let results = KMeans::<_, 4, _>::batch(&datasets, 2, EuclideanDistance, |idx, kmean| {
+    ///     let conf = KMeansConfig::build().random_generator(StdRng::seed_from_u64(idx as u64)).build();
+    ///     kmean.kmeans_lloyd(3, 100, KMeans::init_kmeanplusplus, &conf)
+    /// });
+    /// assert_eq!(results.len(), 1000);
+    /// ```
+    pub fn batch<F>(datasets: &[&[T]], sample_dims: usize, distance_fn: D, run: F) -> Vec<KMeansState<T>>
+    where
+        D: Clone,
+        F: Fn(usize, &KMeans<T, LANES, D>) -> KMeansState<T> + Sync,
+    {
+        crate::batch::calculate(datasets, sample_dims, distance_fn, run)
+    }
+
+    /// K-Means++ initialization method, as implemented in Matlab
+    ///
+    /// ## Description
+    /// This initialization method starts by selecting one sample as first centroid.
+    /// Proceeding from there, the method iteratively selects one new centroid (per iteration) by calculating
+    /// each sample's probability of "being a centroid". This probability is bigger, the farther away a sample
+    /// is from its centroid. Then, one sample is randomly selected, while taking their probability of being
+    /// the next centroid into account. This leads to a tendency of selecting centroids, that are far away from
+    /// their currently assigned cluster's centroid.
+    /// (see: https://uk.mathworks.com/help/stats/kmeans.html#bueq7aj-5    Section: More About)
+    /// With sample weights (see [`KMeans::with_sample_weights`]), all probabilities are proportional to the weights.
+    /// The greedy variant, which picks the best of multiple candidates per centroid, is enabled with
+    /// [`KMeansConfigBuilder::greedy_kmeanplusplus`].
+    ///
+    /// ## Note
+    /// This method is not meant for direct invocation. Pass a reference to it, to an instance-method of [`KMeans`].
+    pub fn init_kmeanplusplus(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>) {
+        crate::inits::kmeanplusplus::calculate(kmean, state, config);
+    }
+
+    /// Scalable K-Means++ (k-means||) initialization method, by Bahmani et al.
+    ///
+    /// ## Description
+    /// [`KMeans::init_kmeanplusplus`] needs k sequential passes over all samples, which dominates the runtime of large
+    /// datasets with hundreds of clusters. This initialization method instead oversamples candidates in a few parallel
+    /// passes: Starting from one random sample, each round independently selects every sample as candidate with a
+    /// probability proportional to its distance to the nearest candidate (**oversampling** * k candidates per round, in
+    /// expectation). Afterwards, every candidate is weighted with the amount of samples nearest to it, and the weighted
+    /// candidates are reclustered into k centroids using K-Means++. If the rounds found less than k candidates, the
+    /// remaining centroids are selected using K-Means++ on all samples.
+    ///
+    /// ## Arguments
+    /// - **rounds**: Amount of oversampling rounds (passes over all samples), `5` is usually sufficient
+    /// - **oversampling**: Expected amount of candidates per round, as multiple of k (e.g. `2.0`)
+    ///
+    /// ## Note
+    /// This method must be invoked with the amount of rounds and the oversampling factor. It then
+    /// returns a closure that can be passed to the [`KMeans`] object.
+    pub fn init_kmeans_parallel(
+        rounds: usize, oversampling: T,
+    ) -> impl Fn(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'_, T>) {
+        assert!(oversampling > T::zero());
+        move |kmean, state, config| {
+            crate::inits::kmeansparallel::calculate(kmean, state, config, rounds, oversampling);
+        }
+    }
+
+    /// Random-Parition initialization method
+    ///
+    /// ## Description
+    /// This initialization method randomly partitions the samples into k partitions, and then calculates these partion's means.
+    /// These means are then used as initial clusters.
+    ///
+    pub fn init_random_partition(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>) {
+        crate::inits::randompartition::calculate(kmean, state, config);
+    }
+
+    /// Random sample initialization method (a.k.a. Forgy)
+    ///
+    /// ## Description
+    /// This initialization method randomly selects k centroids from the samples as initial centroids.
+    ///
+    /// ## Note
+    /// This method is not meant for direct invocation. Pass a reference to it, to an instance-method of [`KMeans`].
+    pub fn init_random_sample(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>) {
+        crate::inits::randomsample::calculate(kmean, state, config);
+    }
+
+    /// Maximin (farthest-first traversal) initialization method
+    ///
+    /// ## Description
+    /// This initialization method randomly selects one sample as first centroid (with a probability proportional to
+    /// the sample weights, if given). Every following centroid is then the sample that is farthest away from all
+    /// centroids chosen so far (the first one, on ties). Given the first centroid, the selection is thus deterministic.
+    /// It reliably covers well-separated clusters, and is cheaper than [`KMeans::init_kmeanplusplus`], since every
+    /// centroid only needs one pass over the samples and no weighted sampling. As it prefers the most remote samples,
+    /// it is sensitive to outliers, which tend to become centroids of their own.
+    ///
+    /// ## Note
+    /// This method is not meant for direct invocation. Pass a reference to it, to an instance-method of [`KMeans`].
+    pub fn init_maximin(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>) {
+        crate::inits::maximin::calculate(kmean, state, config);
+    }
+
+    /// Grid-based initialization method, for low-dimensional (e.g. 2-D / 3-D spatial) data.
+    ///
+    /// ## Description
+    /// This initialization method overlays a coarse grid with **cells_per_dim** cells per dimension over the bounding
+    /// box of all samples, and counts the samples in each cell (weighted by the sample weights, if given). The k initial
+    /// centroids are then seeded in the (weighted) means of the samples within the k densest cells. This only needs a
+    /// single pass over the samples, which makes it extremely fast compared to [`KMeans::init_kmeanplusplus`]. If less
+    /// than k cells contain samples, the remaining centroids are randomly selected from the samples.
+    ///
+    /// Note that the amount of cells grows exponentially with the amount of dimensions, so this method is not
+    /// suitable for high-dimensional data.
+    ///
+    /// ## Note
+    /// This method must be invoked with the grid resolution. It then
+    /// returns a closure that can be passed to the [`KMeans`] object.
+    pub fn init_grid(cells_per_dim: usize) -> impl Fn(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'_, T>) {
+        move |kmean, state, config| {
+            crate::inits::grid::calculate(kmean, state, config, cells_per_dim);
+        }
+    }
+
+    /// Class-label initialization method (supervised warm start), e.g. to refine an existing taxonomy.
+    ///
+    /// ## Description
+    /// This initialization method initializes the centroid of every cluster **c** as the mean of all samples labeled
+    /// with class **c**. Centroids of clusters without any labeled samples are selected using K-Means++ (see
+    /// [`KMeans::init_kmeanplusplus`]), taking the class means into account.
+    ///
+    /// ## Note
+    /// This method must be invoked with the (partial or full) class label of every sample (**None** = unlabeled,
+    /// labels have to be smaller than **k**). It then returns a closure that can be passed to the [`KMeans`] object.
+    pub fn init_labels(labels: Vec<Option<usize>>) -> impl Fn(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'_, T>) {
+        move |kmean, state, config| {
+            crate::inits::labels::calculate(kmean, state, config, &labels);
+        }
+    }
+
+    /// Precomputed centroids initialization method
+    ///
+    /// ## Description
+    /// This initialization method requires a precomputed list of k centroids to use as initial
+    /// centroids.
+    ///
+    /// ## Note
+    /// This method must be invoked with a precomputed list of centroids. It then
+    /// returns a closure that can be passed to the [`KMeans`] object.
+    pub fn init_precomputed(centroids: Vec<T>) -> impl Fn(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'_, T>) {
+        move |kmean, state, config| {
+            crate::inits::precomputed::calculate(kmean, state, config, &centroids);
+        }
+    }
+
+    /// Add one cluster to a previously calculated k-means result, without restarting the calculation from scratch.
+    ///
+    /// ## Description
+    /// The worst cluster (the one with the highest sum of distances to its centroid) is split into two, using a local
+    /// 2-means on its members. The resulting **k + 1** centroids are then refined using up to **max_iter** iterations
+    /// of the Lloyd algorithm (see [`KMeans::kmeans_lloyd`]) on all samples.
+    ///
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result, on the samples of this [`KMeans`] instance
+    /// - **max_iter**: Maximum amount of refinement iterations (at least one iteration is always done)
+    /// - **config**: Configuration of the refinement calculation
+    ///
+    /// ## Returns
+    /// New k-means result with **k + 1** clusters. The first **k** cluster ids still roughly correspond to the clusters
+    /// of **state**, the split cluster keeps one half under its id and the other half gets the new id **k**.
     ///
     /// ## Example
     /// ```rust
     /// use kmeans::*;
     ///
-    /// let (sample_cnt, sample_dims, k, max_iter) = (20000, 200, 4, 100);
+    /// let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0, 20.0, 21.0, 22.0];
+    /// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 9, 1, EuclideanDistance);
+    /// let conf = KMeansConfig::default();
+    /// let result = kmean.kmeans_lloyd(2, 100, KMeans::init_kmeanplusplus, &conf);
+    /// let finer = kmean.add_cluster(&result, 10, &conf);
+    /// assert_eq!(finer.k, 3);
+    /// ```
+    pub fn add_cluster(&self, state: &KMeansState<T>, max_iter: usize, config: &KMeansConfig<'_, T>) -> KMeansState<T> {
+        crate::incremental::calculate(self, state, max_iter, config)
+    }
+
+    /// Remove the given clusters from a previously calculated k-means result (e.g. empty or tiny clusters).
     ///
-    /// // Generate some random data
-    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
-    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    /// ## Description
+    /// The samples of all removed clusters are reassigned to their nearest remaining cluster. The remaining clusters
+    /// are re-numbered consecutively (keeping their order), and the frequencies, distances and distsum of **state**
+    /// are updated accordingly. The remaining centroids themselves are kept unchanged.
     ///
-    /// // Calculate kmeans, using kmean++ as initialization-method
-    /// // KMeans<_, 8> specifies to use f64 SIMD vectors with 8 lanes (e.g. AVX512)
-    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
-    /// let result = kmean.kmeans_lloyd(k, max_iter, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result, on the samples of this [`KMeans`] instance
+    /// - **ids**: Ids of the clusters to remove (at least one cluster has to remain)
     ///
-    /// println!("Centroids: {:?}", result.centroids);
-    /// println!("Cluster-Assignments: {:?}", result.assignments);
-    /// println!("Error: {}", result.distsum);
+    /// ## Returns
+    /// Mapping from the old cluster ids to the new cluster ids (**None** for removed clusters).
+    pub fn remove_clusters(&self, state: &mut KMeansState<T>, ids: &[usize]) -> Vec<Option<usize>> {
+        crate::postprocessing::remove_clusters::calculate(self, state, ids)
+    }
+
+    /// Assign the given samples to the centroids of a previously calculated k-means result.
+    ///
+    /// ## Description
+    /// Each sample is assigned to its nearest centroid. Its distance to that centroid (and optionally the margin to the
+    /// runner-up centroid) is tracked within the same pass, so confidence values do not require a second distance
+    /// calculation.
+    ///
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result, on the samples of this [`KMeans`] instance
+    /// - **samples**: Samples to assign [row-major] = [<sample0>,<sample1>,<sample2>,...], with the same dimensions as
+    ///   the samples of this [`KMeans`] instance
+    /// - **with_margins**: Whether to also return the margin of each sample to the runner-up centroid
+    ///
+    /// ## Returns
+    /// The assignments of the given samples, see [`SampleAssignments`].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0];
+    /// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 6, 1, EuclideanDistance);
+    /// let result = kmean.kmeans_lloyd(2, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    ///
+    /// let assigned = kmean.assign_with_distances(&result, &[0.5, 6.5], true);
+    /// assert_eq!(assigned.assignments[0], result.assignments[0]);
+    /// println!("Margins: {:?}", assigned.margins.unwrap());
     /// ```
-    pub fn kmeans_lloyd<F>(&self, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
-    where
-        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
-    {
-        crate::variants::Lloyd::calculate(self, k, max_iter, init, config)
+    pub fn assign_with_distances(&self, state: &KMeansState<T>, samples: &[T], with_margins: bool) -> SampleAssignments<T> {
+        self.assign_samples_with(state, samples, with_margins)
     }
 
-    /// Mini-Batch k-Means implementation.
-    /// (see: https://dl.acm.org/citation.cfm?id=1772862)
+    /// Assign the given (unseen) samples to the centroids of a previously calculated k-means result, without
+    /// re-running the calculation. This is the same as [`KMeans::assign_with_distances`], only returning the labels.
     ///
     /// ## Arguments
-    /// - **batch_size**: Amount of samples to use per iteration (higher -> better approximation but slower)
-    /// - **k**: Amount of clusters to search for
-    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
-    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids
-    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    /// - **state**: Previously calculated k-means result (the model), on the samples of this [`KMeans`] instance
+    /// - **samples**: Samples to assign [row-major] = [<sample0>,<sample1>,<sample2>,...], with the same dimensions as
+    ///   the samples of this [`KMeans`] instance
     ///
     /// ## Returns
-    /// Instance of [`KMeansState`], containing the final state (result).
+    /// Vector mapping each given sample to its nearest cluster.
     ///
     /// ## Example
     /// ```rust
     /// use kmeans::*;
     ///
-    /// let (sample_cnt, sample_dims, k, max_iter) = (20000, 200, 4, 100);
+    /// let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0];
+    /// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 6, 1, EuclideanDistance);
+    /// let result = kmean.kmeans_lloyd(2, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
     ///
-    /// // Generate some random data
-    /// let mut samples = vec![0.0f64;sample_cnt * sample_dims];
-    /// samples.iter_mut().for_each(|v| *v = rand::random());
+    /// let labels = kmean.predict(&result, &[0.5, 11.5]);
+    /// assert_eq!(labels, vec![result.assignments[0], result.assignments[5]]);
+    /// ```
+    pub fn predict(&self, state: &KMeansState<T>, samples: &[T]) -> Vec<usize> { self.assign_samples(state, samples).0 }
+
+    /// Distances of the given samples to all centroids of a previously calculated k-means result, e.g. to use them
+    /// as feature embedding in a downstream pipeline.
     ///
-    /// // Calculate kmeans, using kmean++ as initialization-method
-    /// // KMeans<_, 8> specifies to use f64 SIMD vectors with 8 lanes (e.g. AVX512)
-    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
-    /// let result = kmean.kmeans_minibatch(4, k, max_iter, KMeans::init_random_sample, &KMeansConfig::default());
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result, on the samples of this [`KMeans`] instance
+    /// - **samples**: Samples to transform [row-major] = [<sample0>,<sample1>,<sample2>,...], with the same dimensions
+    ///   as the samples of this [`KMeans`] instance
     ///
-    /// println!("Centroids: {:?}", result.centroids);
-    /// println!("Cluster-Assignments: {:?}", result.assignments);
-    /// println!("Error: {}", result.distsum);
+    /// ## Returns
+    /// Matrix of the distances (as calculated by the configured [`DistanceFunction`]) of every sample to every
+    /// centroid [row-major] = [<sample0-centroid0>,<sample0-centroid1>,...,<sample1-centroid0>,...]
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0];
+    /// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 6, 1, EuclideanDistance);
+    /// let result = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![1.0, 11.0]), &KMeansConfig::default());
+    ///
+    /// let distances = kmean.transform(&result, &[0.0, 6.0]);
+    /// assert_eq!(distances, vec![1.0, 121.0, 25.0, 25.0]);
     /// ```
-    pub fn kmeans_minibatch<F>(&self, batch_size: usize, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
-    where
-        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
-        T: Primitive,
-        LaneCount<LANES>: SupportedLaneCount,
-        Simd<T, LANES>: SupportedSimdArray<T, LANES>,
-    {
-        crate::variants::Minibatch::calculate(self, batch_size, k, max_iter, init, config)
+    pub fn transform(&self, state: &KMeansState<T>, samples: &[T]) -> Vec<T> {
+        assert_eq!(samples.len() % self.sample_dims, 0);
+        let padding = self.distance_fn.padding_value();
+        let p_samples = StrideBuffer::from_slice::<LANES>(self.sample_dims, samples).padded(padding);
+        let centroids = &state.centroids.clone().padded(padding);
+        let mut distances = vec![T::zero(); p_samples.centroid_cnt * centroids.centroid_cnt];
+        p_samples
+            .bfr
+            .par_chunks_exact(p_samples.stride)
+            .zip(distances.par_chunks_exact_mut(centroids.centroid_cnt))
+            .for_each(|(s, row)| {
+                row.iter_mut()
+                    .zip(centroids.chunks_exact_stride())
+                    .for_each(|(d, c)| *d = self.distance_fn.distance(s, c));
+            });
+        distances
     }
 
-    /// K-Means++ initialization method, as implemented in Matlab
+    /// Cost of moving each sample of a k-means result into its second-best cluster.
     ///
     /// ## Description
-    /// This initialization method starts by selecting one sample as first centroid.
-    /// Proceeding from there, the method iteratively selects one new centroid (per iteration) by calculating
-    /// each sample's probability of "being a centroid". This probability is bigger, the farther away a sample
-    /// is from its centroid. Then, one sample is randomly selected, while taking their probability of being
-    /// the next centroid into account. This leads to a tendency of selecting centroids, that are far away from
-    /// their currently assigned cluster's centroid.
-    /// (see: https://uk.mathworks.com/help/stats/kmeans.html#bueq7aj-5    Section: More About)
+    /// For every sample, the change of the total sum of squared distances is calculated, that moving the sample into
+    /// the cluster with the nearest other centroid would cause (including the resulting shift of both centroids).
+    /// Samples with a small cost are "boundary" samples, which is useful for active-learning or cluster-quality review
+    /// workflows. See [`ReassignmentCost`] for details. The costs assume a squared euclidean distance function.
     ///
-    /// ## Note
-    /// This method is not meant for direct invocation. Pass a reference to it, to an instance-method of [`KMeans`].
-    pub fn init_kmeanplusplus(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>) {
-        crate::inits::kmeanplusplus::calculate(kmean, state, config);
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result (with at least two clusters), on the samples of this [`KMeans`]
+    ///   instance
+    ///
+    /// ## Returns
+    /// One [`ReassignmentCost`] per sample.
+    pub fn reassignment_costs(&self, state: &KMeansState<T>) -> Vec<ReassignmentCost<T>> {
+        crate::analysis::reassignment::calculate(self, state)
     }
 
-    /// Random-Parition initialization method
+    /// Anomaly scores of the given samples, with respect to a previously calculated k-means result.
     ///
     /// ## Description
-    /// This initialization method randomly partitions the samples into k partitions, and then calculates these partion's means.
-    /// These means are then used as initial clusters.
+    /// Each sample's score is its distance to the nearest centroid, normalized by that cluster's radius (the mean
+    /// distance of the cluster's members to its centroid, as stored in **state**). Scores around `1` are typical
+    /// for members of a cluster, while much higher scores indicate outliers. Samples that do not exactly match a
+    /// cluster without any spread (e.g. a single-sample cluster) get a score of infinity.
     ///
-    pub fn init_random_partition(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>) {
-        crate::inits::randompartition::calculate(kmean, state, config);
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result, on the samples of this [`KMeans`] instance
+    /// - **samples**: Samples to score [row-major] = [<sample0>,<sample1>,<sample2>,...], with the same dimensions as
+    ///   the samples of this [`KMeans`] instance
+    ///
+    /// ## Returns
+    /// Vector with the anomaly score of each of the given samples.
+    pub fn anomaly_scores(&self, state: &KMeansState<T>, samples: &[T]) -> Vec<T> {
+        crate::analysis::anomaly::calculate(self, state, samples)
     }
 
-    /// Random sample initialization method (a.k.a. Forgy)
+    /// Drift statistics of a new batch of samples, compared to the samples of a previously calculated k-means result.
     ///
     /// ## Description
-    /// This initialization method randomly selects k centroids from the samples as initial centroids.
+    /// The new samples are assigned to the centroids of **state**, and compared to the original samples using the
+    /// ratio of their inertias, the shift in cluster occupancy, and the population stability index over the cluster
+    /// assignments. This is meant for the monitoring of deployed clusterings. See [`DriftStatistics`] for details.
     ///
-    /// ## Note
-    /// This method is not meant for direct invocation. Pass a reference to it, to an instance-method of [`KMeans`].
-    pub fn init_random_sample(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>) {
-        crate::inits::randomsample::calculate(kmean, state, config);
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result, on the samples of this [`KMeans`] instance
+    /// - **samples**: New batch of samples [row-major] = [<sample0>,<sample1>,<sample2>,...], with the same dimensions as
+    ///   the samples of this [`KMeans`] instance
+    pub fn drift_statistics(&self, state: &KMeansState<T>, samples: &[T]) -> DriftStatistics<T> {
+        crate::analysis::drift::calculate(self, state, samples)
     }
 
-    /// Precomputed centroids initialization method
+    /// Compare two k-means results, that were calculated on the samples of this [`KMeans`] instance.
     ///
     /// ## Description
-    /// This initialization method requires a precomputed list of k centroids to use as initial
-    /// centroids.
+    /// The centroids of both results are matched one-to-one, such that the sum of distances between matched centroids is
+    /// minimal (Hungarian algorithm). For every matched pair, the centroid's displacement and the membership overlap
+    /// of both clusters is reported. This allows to quantify how much a retrained model changed.
+    /// See [`ResultComparison`] for details.
     ///
-    /// ## Note
-    /// This method must be invoked with a precomputed list of centroids. It then
-    /// returns a closure that can be passed to the [`KMeans`] object.
-    pub fn init_precomputed(centroids: Vec<T>) -> impl Fn(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'_, T>) {
-        move |kmean, state, config| {
-            crate::inits::precomputed::calculate(kmean, state, config, &centroids);
-        }
+    /// ## Arguments
+    /// - **a**: First k-means result
+    /// - **b**: Second k-means result (e.g. after retraining), may use a different **k**
+    pub fn compare_results(&self, a: &KMeansState<T>, b: &KMeansState<T>) -> ResultComparison<T> {
+        crate::analysis::comparison::calculate(self, a, b)
+    }
+
+    /// Per-dimension feature importance of a k-means result, calculated on the samples of this [`KMeans`] instance.
+    ///
+    /// ## Description
+    /// For every dimension, the ratio of between-cluster to within-cluster variance is calculated, which shows how
+    /// well that dimension separates the clusters. Additionally, z-scored centroid profiles describe each cluster
+    /// relative to the global distribution of all samples. See [`FeatureImportance`] for details.
+    ///
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result, on the samples of this [`KMeans`] instance
+    pub fn feature_importance(&self, state: &KMeansState<T>) -> FeatureImportance<T> {
+        crate::analysis::feature_importance::calculate(self, state)
+    }
+
+    /// Descriptive statistics (count, mean, standard deviation, minimum and maximum per dimension) of every cluster of
+    /// a k-means result, calculated on the samples of this [`KMeans`] instance in one parallel SIMD pass.
+    ///
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result, on the samples of this [`KMeans`] instance
+    ///
+    /// ## Returns
+    /// One [`ClusterProfile`] per cluster.
+    pub fn cluster_profiles(&self, state: &KMeansState<T>) -> Vec<ClusterProfile<T>> { crate::analysis::profiles::calculate(self, state) }
+
+    /// Compactness (mean and maximum distance to the centroid, and optionally the approximate diameter) of every
+    /// cluster of a k-means result, e.g. for building IVF search indexes. See [`ClusterRadius`] for details.
+    ///
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result, on the samples of this [`KMeans`] instance
+    /// - **with_diameter**: Whether to calculate the approximate diameters (requires one additional pass over all samples)
+    ///
+    /// ## Returns
+    /// One [`ClusterRadius`] per cluster.
+    pub fn cluster_radii(&self, state: &KMeansState<T>, with_diameter: bool) -> Vec<ClusterRadius<T>> {
+        crate::analysis::radius::calculate(self, state, with_diameter)
+    }
+
+    /// Bounding box (with its volume) of every cluster of a k-means result, and for 2-D samples additionally the
+    /// convex hull (with its area), for 3-D samples the convex hull (with its surface area and volume), e.g. for
+    /// spatial reporting like the design of delivery zones. See [`ClusterExtent`] for details.
+    ///
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result, on the samples of this [`KMeans`] instance
+    ///
+    /// ## Returns
+    /// One [`ClusterExtent`] per cluster.
+    pub fn cluster_extents(&self, state: &KMeansState<T>) -> Vec<ClusterExtent<T>> { crate::analysis::extents::calculate(self, state) }
+
+    /// Histogram of the relative margins `(d2 - d1) / d1` between the nearest (`d1`) and the runner-up centroid
+    /// (`d2`) of all samples, to judge how well separated the clusters are, and whether an approximate assignment is
+    /// safe. See [`MarginHistogram`] for details.
+    ///
+    /// ## Description
+    /// This compares every sample with all centroids (`O(sample_cnt * k)`, in parallel). As for
+    /// [`crate::metrics::silhouette_samples`], squared euclidean distances are converted to euclidean distances, all
+    /// other distance functions are used as they are.
+    ///
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result (with at least two centroids), on the samples of this
+    ///   [`KMeans`] instance
+    /// - **bins**: Amount of equally wide bins
+    /// - **max_margin**: End of the last bin; All larger margins are counted as overflow
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k) = (2000, 4, 8);
+    /// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_lloyd(k, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    /// let histogram = kmean.margin_histogram(&result, 10, 1.0);
+    /// println!("Samples with a margin below 10%: {}", histogram.fraction_below_edge(0));
+    /// ```
+    pub fn margin_histogram(&self, state: &KMeansState<T>, bins: usize, max_margin: T) -> MarginHistogram<T> {
+        crate::analysis::margins::calculate(self, state, bins, max_margin)
+    }
+
+    /// Project the samples and centroids of a k-means result to two dimensions, for visualization.
+    ///
+    /// ## Description
+    /// The samples are centered on their mean, and projected onto two orthonormal directions, chosen by the given
+    /// [`ProjectionMethod`]. The centroids are projected the same way. The returned [`Projection2D`] contains the
+    /// coordinates and labels in a plot-ready form, and can be written as CSV or JSON.
+    ///
+    /// ## Arguments
+    /// - **state**: Previously calculated k-means result, on the samples of this [`KMeans`] instance
+    /// - **method**: How to choose both directions, see [`ProjectionMethod`]
+    ///
+    /// ## Example
+    /// ```rust
+    /// use kmeans::*;
+    ///
+    /// let (sample_cnt, sample_dims, k) = (1000, 20, 4);
+    /// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+    ///
+    /// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+    /// let result = kmean.kmeans_lloyd(k, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+    /// let projection = kmean.project_2d(&result, ProjectionMethod::Pca);
+    ///
+    /// let mut csv = Vec::new();
+    /// projection.write_csv(&mut csv).unwrap();
+    /// assert_eq!(String::from_utf8(csv).unwrap().lines().count(), 1 + sample_cnt + k);
+    /// ```
+    pub fn project_2d(&self, state: &KMeansState<T>, method: ProjectionMethod) -> Projection2D<T> {
+        crate::projection::calculate(self, state, method)
     }
 }
 
@@ -381,7 +2733,6 @@ where
 mod tests {
     use super::*;
     use crate::EuclideanDistance;
-    use test::Bencher;
 
     #[test]
     fn padding_and_cluster_assignments() {
@@ -464,48 +2815,376 @@ mod tests {
         assert_eq!(state.assignments, should_assignments);
     }
 
-    #[bench]
-    fn distance_matrix_calculation_benchmark_f64x8(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f64, 8>(b); }
-    #[bench]
-    fn distance_matrix_calculation_benchmark_f64x4(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f64, 4>(b); }
-    #[bench]
-    fn distance_matrix_calculation_benchmark_f64x2(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f64, 2>(b); }
+    #[test]
+    fn dyn_distance_function() {
+        let sample_cnt = 1000;
+        let sample_dims = 10;
+        let mut samples = vec![0.0f64; sample_cnt * sample_dims];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1337);
+        samples.iter_mut().for_each(|i| *i = rng.gen_range(0.0..1.0));
 
-    #[bench]
-    fn distance_matrix_calculation_benchmark_f32x16(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f32, 16>(b); }
-    #[bench]
-    fn distance_matrix_calculation_benchmark_f32x8(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f32, 8>(b); }
-    #[bench]
-    fn distance_matrix_calculation_benchmark_f32x4(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f32, 4>(b); }
-    #[bench]
-    fn distance_matrix_calculation_benchmark_f32x2(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f32, 2>(b); }
+        let kmean_static: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let distance_fn: DynDistanceFunction<f64, 8> = Box::new(EuclideanDistance);
+        let kmean_dyn: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, distance_fn);
 
-    fn distance_matrix_calculation_benchmark<T, const LANES: usize>(b: &mut Bencher)
-    where
-        T: Primitive,
-        LaneCount<LANES>: SupportedLaneCount,
-        Simd<T, LANES>: SupportedSimdArray<T, LANES>,
-    {
-        let sample_cnt = 20000;
-        let sample_dims = 2000;
-        let k = LANES;
+        let conf = KMeansConfig::build().random_generator(rand::rngs::StdRng::seed_from_u64(1)).build();
+        let res_static = kmean_static.kmeans_lloyd(5, 100, KMeans::init_kmeanplusplus, &conf);
+        let conf = KMeansConfig::build().random_generator(rand::rngs::StdRng::seed_from_u64(1)).build();
+        let res_dyn = kmean_dyn.kmeans_lloyd(5, 100, KMeans::init_kmeanplusplus, &conf);
 
-        let mut samples = vec![T::zero(); sample_cnt * sample_dims];
+        assert_eq!(res_static.assignments, res_dyn.assignments);
+        assert_eq!(res_static.centroids.to_vec(), res_dyn.centroids.to_vec());
+        assert_eq!(res_static.distsum, res_dyn.distsum);
+    }
+
+    #[test]
+    fn neutral_padding() {
+        // The χ² distance is undefined for two zeros, so the padding (3 dimensions -> 4 lanes) has to be 1
+        struct ChiSquaredDistance;
+        impl DistanceFunction<f64, 4> for ChiSquaredDistance {
+            fn distance(&self, a: &[f64], b: &[f64]) -> f64 { a.iter().zip(b.iter()).map(|(a, b)| (a - b).powi(2) / (a + b)).sum() }
+
+            fn padding_value(&self) -> f64 { 1.0 }
+        }
+        let chi_squared = |a: &[f64], b: &[f64]| ChiSquaredDistance.distance(a, b);
+
+        let samples = vec![1.0, 2.0, 3.0, 1.0, 2.0, 4.0, 8.0, 1.0, 1.0, 9.0, 1.0, 2.0];
+        let kmean: KMeans<f64, 4, _> = KMeans::new(&samples, 4, 3, ChiSquaredDistance);
+        let res = kmean.kmeans_lloyd(
+            2,
+            100,
+            KMeans::init_precomputed(vec![1.0, 2.0, 3.0, 8.0, 1.0, 1.0]),
+            &KMeansConfig::default(),
+        );
+        assert_eq!(res.assignments, vec![0, 0, 1, 1]);
+        assert!(res.centroids.chunks_exact_stride().all(|c| c[3] == 1.0));
+        let centroids = res.centroids.to_vec();
+        let expected: f64 = samples
+            .chunks_exact(3)
+            .zip(res.assignments.iter())
+            .map(|(s, &c)| chi_squared(s, &centroids[c * 3..(c + 1) * 3]))
+            .sum();
+        assert!((res.distsum - expected).abs() < 1e-12);
+        assert_eq!(kmean.predict(&res, &[1.0, 2.0, 3.5]), vec![0]);
+    }
+
+    #[test]
+    fn tie_breaking_noise() {
+        // Integer-like samples, many of which are exactly equidistant to multiple centroids
+        let samples: Vec<f64> = (0..600).map(|i| ((i * 7 + i / 5 * 3) % 4) as f64).collect();
+        let init = vec![
+            0.0, 0.0, 0.0, 0.0, 0.0, 3.0, 3.0, 3.0, 3.0, 3.0, 0.0, 3.0, 0.0, 3.0, 0.0, 3.0, 0.0, 3.0, 0.0, 3.0,
+        ];
+        let conf = KMeansConfig::default();
+
+        let noisy: KMeans<f64, 8, _> = KMeans::new(&samples, 120, 5, EuclideanDistance).with_tie_breaking_noise(1e-7, 3);
+        assert!(noisy
+            .p_samples
+            .iter()
+            .flatten()
+            .zip(samples.iter())
+            .all(|(p, s)| p != s && (p - s).abs() < 1e-7));
+        let res = noisy.kmeans_lloyd(4, 100, KMeans::init_precomputed(init.clone()), &conf);
+
+        // The same noise separates the ties equally for all amounts of lanes
+        let narrow: KMeans<f64, 2, _> = KMeans::new(&samples, 120, 5, EuclideanDistance).with_tie_breaking_noise(1e-7, 3);
+        assert_eq!(
+            narrow
+                .kmeans_lloyd(4, 100, KMeans::init_precomputed(init.clone()), &conf)
+                .assignments,
+            res.assignments
+        );
+        let medium: KMeans<f64, 4, _> = KMeans::new(&samples, 120, 5, EuclideanDistance).with_tie_breaking_noise(1e-7, 3);
+        assert_eq!(
+            medium
+                .kmeans_lloyd(4, 100, KMeans::init_precomputed(init.clone()), &conf)
+                .assignments,
+            res.assignments
+        );
+
+        // An existing norm cache is updated to the perturbed samples
+        let cached: KMeans<f64, 8, _> = KMeans::new(&samples, 120, 5, EuclideanDistance)
+            .with_norm_cache()
+            .with_tie_breaking_noise(1e-7, 3);
+        assert_eq!(cached.p_samples.to_vec(), noisy.p_samples.to_vec());
+        assert_eq!(
+            cached.kmeans_lloyd(4, 100, KMeans::init_precomputed(init), &conf).assignments,
+            res.assignments
+        );
+        assert_eq!(cached.sample_norms, noisy.with_norm_cache().sample_norms);
+    }
+
+    #[test]
+    fn config_clone_across_threads() {
+        fn assert_send_sync<V: Send + Sync>(_: &V) {}
+
+        let sample_cnt = 1000;
+        let sample_dims = 4;
+        let mut samples = vec![0.0f64; sample_cnt * sample_dims];
         let mut rng = rand::rngs::StdRng::seed_from_u64(1337);
-        samples.iter_mut().for_each(|v| *v = rng.gen_range(T::zero()..T::one()));
-        let kmean: KMeans<T, LANES, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        samples.iter_mut().for_each(|i| *i = rng.gen_range(0.0..1.0));
+        let kmean: KMeans<f64, 4, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
 
-        let mut state = KMeansState::new::<LANES>(kmean.sample_cnt, sample_dims, k);
-        state
-            .centroids
-            .bfr
-            .iter_mut()
-            .zip(kmean.p_samples.bfr.iter())
-            .for_each(|(c, s)| *c = *s);
+        let finished_runs = std::sync::atomic::AtomicUsize::new(0);
+        let run_jobs = || {
+            let conf = KMeansConfig::build()
+                .random_generator(rand::rngs::StdRng::seed_from_u64(1))
+                .init_done(|_| {
+                    finished_runs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                })
+                .build();
+            assert_send_sync(&conf);
+            let confs: Vec<_> = (0..4).map(|_| conf.clone()).collect();
+            std::thread::scope(|s| {
+                let handles: Vec<_> = confs
+                    .iter()
+                    .map(|conf| s.spawn(|| kmean.kmeans_lloyd(3, 100, KMeans::init_kmeanplusplus, conf)))
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap().distsum).collect::<Vec<_>>()
+            })
+        };
 
-        b.iter(|| {
-            KMeans::update_cluster_assignments(&kmean, &mut state, None);
-            state.clone()
-        });
+        // Clones of a seeded configuration are seeded deterministically
+        assert_eq!(run_jobs(), run_jobs());
+        assert_eq!(finished_runs.load(std::sync::atomic::Ordering::SeqCst), 8);
+    }
+
+    #[test]
+    fn memory_usage() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 6, 1, EuclideanDistance);
+        let estimate = kmean.estimated_memory(2);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![1.0, 11.0]), &KMeansConfig::default());
+        assert_eq!(res.memory_usage.samples, estimate.samples);
+        assert_eq!(res.memory_usage.state, estimate.state);
+        assert!(res.memory_usage.temporaries > 0);
+        assert!(res.memory_usage.total() <= estimate.total());
+
+        let res = kmean.kmeans_minibatch(3, 2, 100, KMeans::init_precomputed(vec![1.0, 11.0]), &KMeansConfig::default());
+        assert!(res.memory_usage.total() <= estimate.total());
+    }
+
+    #[test]
+    fn parallel_chunk_size() {
+        let mut rnd = StdRng::seed_from_u64(3);
+        let samples: Vec<f64> = (0..3000).map(|i| (i % 3) as f64 * 5.0 + rnd.gen_range(-1.0..1.0)).collect();
+        let init = || KMeans::init_precomputed(vec![0.0, 1.0, 2.0]);
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let expected = kmean.kmeans_lloyd(3, 100, init(), &KMeansConfig::default());
+
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance).with_parallel_chunk_size(16);
+        assert_eq!(kmean.work_packet_size(samples.len()), 16);
+        let res = kmean.kmeans_lloyd(3, 100, init(), &KMeansConfig::default());
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.centroids.to_vec(), expected.centroids.to_vec());
+    }
+
+    #[test]
+    fn sample_weights_equal_duplicated_samples() {
+        // Every sample with weight w behaves as if it was contained w times
+        let mut rnd = StdRng::seed_from_u64(11);
+        let (sample_cnt, sample_dims) = (300, 2);
+        let samples: Vec<f64> = (0..sample_cnt * sample_dims)
+            .map(|i| ((i / sample_dims) % 3) as f64 * 4.0 + rnd.gen_range(-2.0..2.0))
+            .collect();
+        let weights: Vec<f64> = (0..sample_cnt).map(|_| rnd.gen_range(1..5) as f64).collect();
+        let duplicated: Vec<f64> = samples
+            .chunks_exact(sample_dims)
+            .zip(weights.iter())
+            .flat_map(|(s, &w)| std::iter::repeat_n(s, w as usize).flatten().cloned())
+            .collect();
+        let init = || KMeans::init_precomputed(samples[..3 * sample_dims].to_vec());
+        let conf = KMeansConfig::default();
+        let expected = KMeans::<f64, 8, _>::new(&duplicated, duplicated.len() / sample_dims, sample_dims, EuclideanDistance).kmeans_lloyd(
+            3,
+            100,
+            init(),
+            &conf,
+        );
+
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance).with_sample_weights(&weights);
+        let results = [
+            kmean.kmeans_lloyd(3, 100, init(), &conf),
+            kmean.kmeans_elkan(3, 100, init(), &conf),
+            kmean.kmeans_hamerly(3, 100, init(), &conf),
+            kmean.kmeans_overlapping(3, 100, 1.0, init(), &conf).state,
+        ];
+        for res in results {
+            res.centroids
+                .to_vec()
+                .iter()
+                .zip(expected.centroids.to_vec())
+                .for_each(|(c, e)| assert!((c - e).abs() < 1e-9));
+            assert!((res.distsum - expected.distsum).abs() < 1e-9 * expected.distsum);
+            assert_eq!(res.centroid_frequency.iter().sum::<usize>(), sample_cnt);
+        }
+
+        // With the default learning schedule, mini-batch centroids are weighted means as well
+        let conf = KMeansConfig::build().random_generator(StdRng::seed_from_u64(3)).build();
+        let res = kmean.kmeans_minibatch(sample_cnt / 2, 3, 200, init(), &conf);
+        res.centroids
+            .to_vec()
+            .iter()
+            .zip(expected.centroids.to_vec())
+            .for_each(|(c, e)| assert!((c - e).abs() < 0.2));
+    }
+
+    #[test]
+    fn sample_weights_in_random_inits() {
+        // A single sample with a dominating weight is (almost) always selected
+        let samples: Vec<f64> = (0..100).map(|i| i as f64).collect();
+        let mut weights = vec![1e-9; samples.len()];
+        weights[42] = 1.0;
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance).with_sample_weights(&weights);
+        let conf = KMeansConfig::build().random_generator(StdRng::seed_from_u64(1)).build();
+        for init in [KMeans::init_kmeanplusplus, KMeans::init_random_sample] {
+            let mut state = KMeansState::new::<8>(samples.len(), 1, 1);
+            init(&kmean, &mut state, &conf);
+            assert_eq!(state.centroids.to_vec(), vec![42.0]);
+        }
+        let mut state = KMeansState::new::<8>(samples.len(), 1, 1);
+        KMeans::init_random_partition(&kmean, &mut state, &conf);
+        assert!((state.centroids.to_vec()[0] - 42.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn cluster_inertias() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 14.0, 30.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let mut res = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![1.0, 12.0, 30.0]), &KMeansConfig::default());
+        assert_eq!(res.cluster_sizes(), &[3, 2, 1]);
+        assert_eq!(res.cluster_inertias(), &[2.0, 8.0, 0.0]);
+        assert_eq!(res.cluster_inertias().iter().sum::<f64>(), res.distsum);
+
+        // Weighted samples
+        let weighted: KMeans<f64, 8, _> =
+            KMeans::new(&samples, samples.len(), 1, EuclideanDistance).with_sample_weights(&[1.0, 1.0, 1.0, 3.0, 1.0, 1.0]);
+        let res_weighted = weighted.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![1.0, 11.0, 30.0]), &KMeansConfig::default());
+        assert_eq!(res_weighted.cluster_inertias(), &[2.0, 12.0, 0.0]);
+
+        kmean.remove_clusters(&mut res, &[2]);
+        assert_eq!(res.cluster_inertias(), &[2.0, 8.0 + 18.0 * 18.0]);
+    }
+
+    #[test]
+    fn predict() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![1.0, 11.0]), &KMeansConfig::default());
+        assert_eq!(kmean.predict(&res, &[-5.0, 5.9, 6.1, 100.0]), vec![0, 0, 1, 1]);
+        assert_eq!(kmean.predict(&res, &samples), res.assignments);
+        assert!(kmean.predict(&res, &[]).is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_state() {
+        let samples: Vec<f32> = (0..300).map(|i| (i % 3) as f32 * 10.0 + (i % 7) as f32 * 0.1).collect();
+        let kmean: KMeans<f32, 8, _> = KMeans::new(&samples, samples.len() / 3, 3, EuclideanDistance);
+        let conf = KMeansConfig::build().min_cluster_size(1).record_trajectory(1).build();
+        let res = kmean.kmeans_lloyd(4, 100, KMeans::init_precomputed(samples[..12].to_vec()), &conf);
+
+        let json = serde_json::to_value(&res).unwrap();
+        // Centroids are stored without their padding
+        assert_eq!(json["centroids"]["stride"], 8);
+        assert_eq!(json["centroids"]["centroids"].as_array().unwrap().len(), 4 * 3);
+
+        let restored: KMeansState<f32> = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.centroids.bfr.to_vec(), res.centroids.bfr.to_vec());
+        assert_eq!(restored.centroids.bfr.alignment(), res.centroids.bfr.alignment());
+        assert_eq!(restored.assignments, res.assignments);
+        assert_eq!(
+            restored.trajectory.as_ref().unwrap().centroids,
+            res.trajectory.as_ref().unwrap().centroids
+        );
+        assert_eq!(kmean.predict(&restored, &samples), res.assignments);
+
+        let invalid = serde_json::json!({ "stride": 2, "centroid_dim": 3, "centroids": [0.0, 1.0, 2.0] });
+        assert!(serde_json::from_value::<StrideBuffer<f32>>(invalid).is_err());
+    }
+
+    #[test]
+    fn transform() {
+        let samples = vec![0.0f64, 0.0, 1.0, 0.0, 10.0, 0.0, 11.0, 0.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 4, 2, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(
+            2,
+            100,
+            KMeans::init_precomputed(vec![0.0, 0.0, 10.0, 0.0]),
+            &KMeansConfig::default(),
+        );
+        let distances = kmean.transform(&res, &[0.5, 1.0, 5.5, 0.0, 10.5, 0.0]);
+        assert_eq!(distances, vec![1.0, 101.0, 25.0, 25.0, 100.0, 0.0]);
+        // The nearest centroid of every row is the predicted cluster
+        let nearest: Vec<usize> = distances.chunks_exact(2).map(|row| if row[0] <= row[1] { 0 } else { 1 }).collect();
+        assert_eq!(nearest, kmean.predict(&res, &[0.5, 1.0, 5.5, 0.0, 10.5, 0.0]));
+        assert!(kmean.transform(&res, &[]).is_empty());
+    }
+
+    #[test]
+    fn assign_with_distances() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 6, 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![1.0, 11.0]), &KMeansConfig::default());
+
+        let assigned = kmean.assign_with_distances(&res, &[0.0, 5.0, 13.0], true);
+        assert_eq!(assigned.assignments, vec![0, 0, 1]);
+        assert_eq!(assigned.centroid_distances, vec![1.0, 16.0, 4.0]);
+        assert_eq!(assigned.margins, Some(vec![120.0, 20.0, 140.0]));
+        assert_eq!(kmean.assign_with_distances(&res, &[0.0], false).margins, None);
+
+        let res = kmean.kmeans_lloyd(1, 100, KMeans::init_precomputed(vec![6.0]), &KMeansConfig::default());
+        assert_eq!(kmean.assign_with_distances(&res, &[0.0], true).margins, Some(vec![f64::INFINITY]));
+    }
+
+    #[cfg(not(feature = "stable"))]
+    mod benches {
+        use super::*;
+        use test::Bencher;
+
+        #[bench]
+        fn distance_matrix_calculation_benchmark_f64x8(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f64, 8>(b); }
+        #[bench]
+        fn distance_matrix_calculation_benchmark_f64x4(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f64, 4>(b); }
+        #[bench]
+        fn distance_matrix_calculation_benchmark_f64x2(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f64, 2>(b); }
+
+        #[bench]
+        fn distance_matrix_calculation_benchmark_f32x16(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f32, 16>(b); }
+        #[bench]
+        fn distance_matrix_calculation_benchmark_f32x8(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f32, 8>(b); }
+        #[bench]
+        fn distance_matrix_calculation_benchmark_f32x4(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f32, 4>(b); }
+        #[bench]
+        fn distance_matrix_calculation_benchmark_f32x2(b: &mut Bencher) { distance_matrix_calculation_benchmark::<f32, 2>(b); }
+
+        fn distance_matrix_calculation_benchmark<T, const LANES: usize>(b: &mut Bencher)
+        where
+            T: Primitive,
+            LaneCount<LANES>: SupportedLaneCount,
+            Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+        {
+            let sample_cnt = 20000;
+            let sample_dims = 2000;
+            let k = LANES;
+
+            let mut samples = vec![T::zero(); sample_cnt * sample_dims];
+            let mut rng = rand::rngs::StdRng::seed_from_u64(1337);
+            samples.iter_mut().for_each(|v| *v = rng.gen_range(T::zero()..T::one()));
+            let kmean: KMeans<T, LANES, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+
+            let mut state = KMeansState::new::<LANES>(kmean.sample_cnt, sample_dims, k);
+            state
+                .centroids
+                .bfr
+                .iter_mut()
+                .zip(kmean.p_samples.bfr.iter())
+                .for_each(|(c, s)| *c = *s);
+
+            b.iter(|| {
+                KMeans::update_cluster_assignments(&kmean, &mut state, None);
+                state.clone()
+            });
+        }
     }
 }
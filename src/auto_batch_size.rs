@@ -0,0 +1,82 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::KMeans;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Assumed size of the last-level cache. A batch that fits into it is still cached, when the centroid update reads
+/// it again after the assignment step.
+const ASSUMED_CACHE_SIZE: usize = 8 << 20;
+/// Minimum amount of samples per thread and batch, below which the parallelization overhead dominates.
+const MIN_SAMPLES_PER_THREAD: usize = 64;
+/// Amount of samples and centroids of the calibration run.
+const CALIBRATION_SAMPLES: usize = 64;
+
+/// Measure the time of one distance calculation between two samples, by comparing a few samples with each other.
+fn distance_cost<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>) -> Duration
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let probe = kmean.sample_cnt.min(CALIBRATION_SAMPLES);
+    let probes: Vec<&[T]> = kmean.p_samples.chunks_exact_stride().take(probe).collect();
+    let start = Instant::now();
+    let sum: T = probes
+        .iter()
+        .flat_map(|a| probes.iter().map(|b| kmean.distance_fn.distance(a, b)))
+        .sum();
+    black_box(sum);
+    start.elapsed() / (probe * probe) as u32
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, k: usize, target_batch_time: Duration) -> usize
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    assert!(k > 0 && k <= kmean.sample_cnt);
+    let threads = rayon::current_num_threads();
+    // The assignment step dominates each iteration: batch_size * k distance calculations, spread over all threads
+    let cost = distance_cost(kmean).as_secs_f64().max(1e-10);
+    let by_time = (target_batch_time.as_secs_f64() * threads as f64 / (cost * k as f64)) as usize;
+
+    let sample_size = kmean.p_samples.stride * std::mem::size_of::<T>();
+    let by_cache = ASSUMED_CACHE_SIZE.saturating_sub(k * sample_size) / sample_size;
+    let lower = (threads * MIN_SAMPLES_PER_THREAD).max(k);
+    let upper = by_cache.max(lower);
+    // Batches are drawn from the range 0..sample_cnt - batch_size
+    by_time.clamp(lower, upper).min(kmean.sample_cnt - 1).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+    use std::time::Duration;
+
+    #[test]
+    fn auto_batch_size() {
+        let (sample_cnt, sample_dims) = (200000, 16);
+        let samples: Vec<f32> = (0..sample_cnt * sample_dims).map(|i| (i % 1013) as f32).collect();
+        let kmean: KMeans<f32, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let threads = rayon::current_num_threads();
+
+        // Bounded by the parallelization overhead, and by the cache
+        let smallest = kmean.auto_batch_size(10, Duration::ZERO);
+        assert_eq!(smallest, threads * 64);
+        let largest = kmean.auto_batch_size(10, Duration::from_secs(1000));
+        assert_eq!(largest, ((8 << 20) - 10 * 64) / 64);
+        // Never more than the amount of samples
+        let tiny = KMeans::<f32, 8, _>::new(&samples[..100 * sample_dims].to_vec(), 100, sample_dims, EuclideanDistance);
+        assert_eq!(tiny.auto_batch_size(10, Duration::from_secs(1)), 99);
+
+        let batch_size = kmean.auto_batch_size(10, Duration::from_millis(1));
+        assert!(batch_size >= smallest && batch_size <= largest);
+        kmean.kmeans_minibatch(batch_size, 10, 5, KMeans::init_random_sample, &KMeansConfig::default());
+    }
+}
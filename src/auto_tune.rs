@@ -0,0 +1,239 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Amount of distance evaluations of one timed assignment step, the calibration run is sized with.
+const PROBE_DISTANCE_EVALUATIONS: usize = 1 << 20;
+/// Bounds of the amount of samples of the calibration run.
+const PROBE_SAMPLES: (usize, usize) = (256, 4096);
+/// Amount of timed assignment steps per candidate (the fastest one counts).
+const REPEATS: usize = 3;
+/// Candidates of the parallel chunk size (see [`KMeans::with_parallel_chunk_size`]).
+const CHUNK_SIZES: [Option<usize>; 3] = [None, Some(256), Some(2048)];
+/// Candidates of the amount of dimensions per chunk (in multiples of LANES, see [`KMeans::with_dimension_chunking`]).
+const DIMENSION_CHUNKS: [usize; 3] = [4, 16, 64];
+
+/// Configuration of the assignment step's kernel, as chosen by [`KMeans::with_auto_tuning`].
+///
+/// ## Fields
+/// - **norm_cache**: Whether the norm cache is used (see [`KMeans::with_norm_cache`])
+/// - **blocked_scan**: Whether the blocked centroid scan is used (see [`KMeans::with_blocked_centroid_scan`])
+/// - **dimension_chunk**: Amount of dimensions per chunk, if the dimension-chunked processing is used (see
+///   [`KMeans::with_dimension_chunking`])
+/// - **parallel_chunk_size**: Amount of samples per work packet, if not the default static partitioning (see
+///   [`KMeans::with_parallel_chunk_size`])
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KernelConfig {
+    pub norm_cache: bool,
+    pub blocked_scan: bool,
+    pub dimension_chunk: Option<usize>,
+    pub parallel_chunk_size: Option<usize>,
+}
+
+/// Data shape (and types) a [`KernelConfig`] was tuned for.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct TuningKey {
+    primitive: &'static str,
+    lanes: usize,
+    distance_fn: &'static str,
+    probe_cnt: usize,
+    sample_dims: usize,
+    k: usize,
+    threads: usize,
+}
+
+/// Best kernel configurations found so far in this process.
+fn tuning_cache() -> &'static Mutex<HashMap<TuningKey, KernelConfig>> {
+    static CACHE: OnceLock<Mutex<HashMap<TuningKey, KernelConfig>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Kernel configuration of **kmean**, as currently set.
+pub(crate) fn kernel_config<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>) -> KernelConfig
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    KernelConfig {
+        norm_cache: kmean.sample_norms.is_some(),
+        blocked_scan: kmean.blocked_scan,
+        dimension_chunk: kmean.dimension_chunk,
+        parallel_chunk_size: kmean.parallel_chunk_size,
+    }
+}
+
+/// Replace the kernel configuration of **kmean** with **config**.
+fn apply<T, const LANES: usize, D>(mut kmean: KMeans<T, LANES, D>, config: KernelConfig) -> KMeans<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    kmean.blocked_scan = config.blocked_scan;
+    kmean.dimension_chunk = config.dimension_chunk;
+    kmean.parallel_chunk_size = config.parallel_chunk_size;
+    match config.norm_cache {
+        true if kmean.sample_norms.is_none() => kmean.with_norm_cache(),
+        true => kmean,
+        false => {
+            kmean.sample_norms = None;
+            kmean
+        },
+    }
+}
+
+/// All candidate configurations, that are supported by the distance function.
+fn candidates<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>) -> Vec<KernelConfig>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let mut kernels = vec![KernelConfig::default(), KernelConfig {
+        blocked_scan: true,
+        ..Default::default()
+    }];
+    if kmean.distance_fn.is_squared_euclidean() {
+        kernels.push(KernelConfig {
+            norm_cache: true,
+            ..Default::default()
+        });
+    }
+    if kmean.distance_fn.is_separable() {
+        kernels.extend(
+            DIMENSION_CHUNKS
+                .iter()
+                .map(|&chunks| chunks * LANES)
+                .filter(|&chunk_dims| chunk_dims < kmean.p_samples.stride)
+                .map(|chunk_dims| KernelConfig {
+                    dimension_chunk: Some(chunk_dims),
+                    ..Default::default()
+                }),
+        );
+    }
+    kernels
+        .into_iter()
+        .flat_map(|kernel| {
+            CHUNK_SIZES.iter().map(move |&parallel_chunk_size| KernelConfig {
+                parallel_chunk_size,
+                ..kernel
+            })
+        })
+        .collect()
+}
+
+/// Fastest of all **timed** assignment steps of **probe**, with the given kernel configuration.
+fn time_candidate<T, const LANES: usize, D>(probe: KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: KernelConfig) -> Duration
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let probe = apply(probe, config);
+    (0..REPEATS)
+        .map(|_| {
+            let start = Instant::now();
+            probe.update_cluster_assignments(state, None);
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, k: usize) -> KernelConfig
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    assert!(k > 0);
+    let probe_cnt = (PROBE_DISTANCE_EVALUATIONS / k)
+        .clamp(PROBE_SAMPLES.0, PROBE_SAMPLES.1)
+        .min(kmean.sample_cnt);
+    let key = TuningKey {
+        primitive: std::any::type_name::<T>(),
+        lanes: LANES,
+        distance_fn: std::any::type_name::<D>(),
+        probe_cnt,
+        sample_dims: kmean.sample_dims,
+        k,
+        threads: rayon::current_num_threads(),
+    };
+    if let Some(&config) = tuning_cache().lock().unwrap().get(&key) {
+        return config;
+    }
+
+    // Calibration run on the first samples, with centroids evenly spread over all samples
+    let probe_samples: Vec<T> = kmean.p_samples.iter().take(probe_cnt).flatten().cloned().collect();
+    let mut state = KMeansState::new::<LANES>(probe_cnt, kmean.sample_dims, k);
+    (0..k).for_each(|c| {
+        let sample_id = (c * kmean.sample_cnt / k) % kmean.sample_cnt;
+        state
+            .centroids
+            .set_nth_from_iter(c, kmean.p_samples.nth_stride(sample_id).iter().cloned());
+    });
+    let config = candidates(kmean)
+        .into_iter()
+        .map(|config| {
+            let probe = KMeans::from_slice(&probe_samples, probe_cnt, kmean.sample_dims, &kmean.distance_fn);
+            (time_candidate(probe, &mut state, config), config)
+        })
+        .min_by_key(|(duration, _)| *duration)
+        .unwrap()
+        .1;
+    tuning_cache().lock().unwrap().insert(key, config);
+    config
+}
+
+#[inline(always)]
+pub fn with_auto_tuning<T, const LANES: usize, D>(kmean: KMeans<T, LANES, D>, k: usize) -> KMeans<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let config = calculate(&kmean, k);
+    apply(kmean, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CosineDistance, EuclideanDistance, KMeans, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn auto_tuning() {
+        let mut rnd = StdRng::seed_from_u64(1337);
+        let (sample_cnt, sample_dims, k) = (2000, 100, 8);
+        let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rnd.gen_range(-1.0..1.0)).collect();
+        let conf = || KMeansConfig::build().random_generator(StdRng::seed_from_u64(42)).build();
+
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, CosineDistance);
+        let expected = kmean.kmeans_lloyd(k, 10, KMeans::init_random_sample, &conf());
+        let tuned = kmean.with_auto_tuning(k);
+        // Chunked processing is only supported by separable distance functions, the norm cache only by the squared
+        // euclidean distance
+        assert!(!tuned.kernel_config().norm_cache && tuned.kernel_config().dimension_chunk.is_none());
+        let res = tuned.kmeans_lloyd(k, 10, KMeans::init_random_sample, &conf());
+        assert_eq!(res.assignments, expected.assignments);
+
+        // The choice for the same data shape is cached
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let config = kmean.with_auto_tuning(k).kernel_config();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        assert_eq!(kmean.with_auto_tuning(k).kernel_config(), config);
+    }
+}
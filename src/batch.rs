@@ -0,0 +1,51 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use rayon::prelude::*;
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D, F>(datasets: &[&[T]], sample_dims: usize, distance_fn: D, run: F) -> Vec<KMeansState<T>>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES> + Clone,
+    F: Fn(usize, &KMeans<T, LANES, D>) -> KMeansState<T> + Sync,
+{
+    assert!(sample_dims > 0);
+    // Every dataset is one work packet: The calculations are too small to be split efficiently
+    datasets
+        .par_iter()
+        .with_max_len(1)
+        .enumerate()
+        .map(|(idx, samples)| {
+            let kmean = KMeans::from_slice(samples, samples.len() / sample_dims, sample_dims, distance_fn.clone());
+            run(idx, &kmean)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn batch_equals_sequential() {
+        let datasets: Vec<Vec<f64>> = (0..50)
+            .map(|d| (0..20).map(|i| (i % 2) as f64 * (d + 1) as f64 + (i / 2) as f64 * 0.01).collect())
+            .collect();
+        let slices: Vec<&[f64]> = datasets.iter().map(|d| d.as_slice()).collect();
+        let run = |kmean: &KMeans<f64, 8, EuclideanDistance>| {
+            kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 0.5]), &KMeansConfig::default())
+        };
+
+        let results = KMeans::batch(&slices, 1, EuclideanDistance, |_, kmean| run(kmean));
+        assert_eq!(results.len(), datasets.len());
+        datasets.iter().zip(results.iter()).for_each(|(samples, res)| {
+            let expected = run(&KMeans::new(samples, samples.len(), 1, EuclideanDistance));
+            assert_eq!(res.centroids.to_vec(), expected.centroids.to_vec());
+            assert_eq!(res.assignments, expected.assignments);
+        });
+    }
+}
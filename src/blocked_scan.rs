@@ -0,0 +1,96 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{self, LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use rayon::prelude::*;
+use std::mem::size_of;
+
+/// Target size of one tile of centroids (in bytes), that stays in the L1 / L2 cache while a block of samples is
+/// compared against it.
+const TILE_BYTES: usize = 64 << 10;
+/// Amount of samples that share each tile of centroids.
+const SAMPLE_BLOCK: usize = 16;
+
+/// Blocked variant of [`KMeans::update_cluster_assignments`], for large amounts of centroids.
+///
+/// The samples are processed in blocks of [`SAMPLE_BLOCK`], that are compared against one tile of centroids after
+/// the other. Every centroid is thus loaded from memory once per block of samples (instead of once per sample),
+/// while the next tile is software-prefetched during the scan of the current one. Every sample still visits the
+/// centroids in ascending order, so the result is identical to the plain assignment step.
+pub(crate) fn update_cluster_assignments<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, k: usize)
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let centroids = &state.centroids;
+    let stride = kmean.p_samples.stride;
+    let tile = (TILE_BYTES / (stride * size_of::<T>())).max(1);
+
+    let work_packet_size = kmean.work_packet_size(kmean.sample_cnt).div_ceil(SAMPLE_BLOCK);
+    kmean
+        .p_samples
+        .bfr
+        .par_chunks(stride * SAMPLE_BLOCK)
+        .with_min_len(work_packet_size)
+        .zip(state.assignments.par_chunks_mut(SAMPLE_BLOCK))
+        .zip(state.centroid_distances.par_chunks_mut(SAMPLE_BLOCK))
+        .for_each(|((samples, assignments), centroid_distances)| {
+            simd::dispatch(|| {
+                let mut best = [(0, T::infinity()); SAMPLE_BLOCK];
+                (0..k).step_by(tile).for_each(|tile_start| {
+                    let tile_end = (tile_start + tile).min(k);
+                    samples
+                        .chunks_exact(stride)
+                        .zip(best.iter_mut())
+                        .enumerate()
+                        .for_each(|(i, (s, best))| {
+                            (tile_start..tile_end).for_each(|idx| {
+                                // Spread the prefetches of the next tile over the scan of the current one
+                                if i == 0 && idx + tile < k {
+                                    crate::helpers::prefetch(centroids.nth_stride(idx + tile));
+                                }
+                                let dist = kmean.distance_fn.distance_bounded(s, centroids.nth_stride(idx), best.1);
+                                if dist < best.1 {
+                                    *best = (idx, dist);
+                                }
+                            });
+                        });
+                });
+                assignments.iter_mut().zip(centroid_distances.iter_mut()).zip(best).for_each(
+                    |((assignment, centroid_dist), (best_idx, best_dist))| {
+                        *assignment = best_idx;
+                        *centroid_dist = best_dist;
+                    },
+                );
+            })
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EuclideanDistance, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn blocked_scan_equals_plain_scan() {
+        let mut rnd = StdRng::seed_from_u64(9);
+        // Sample count is not a multiple of the sample block, and k not a multiple of the tile
+        let (sample_cnt, sample_dims, k) = (1003, 300, 250);
+        let samples: Vec<f32> = (0..sample_cnt * sample_dims).map(|_| rnd.gen_range(-1.0..1.0)).collect();
+        let kmean: KMeans<f32, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let conf = KMeansConfig::default();
+        let expected = kmean.kmeans_lloyd(k, 1, KMeans::init_precomputed(samples[..k * sample_dims].to_vec()), &conf);
+
+        let mut plain = expected.clone();
+        kmean.update_cluster_assignments(&mut plain, None);
+        let kmean: KMeans<f32, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance).with_blocked_centroid_scan();
+        let mut state = expected.clone();
+        state.assignments.iter_mut().for_each(|a| *a = usize::MAX);
+        kmean.update_cluster_assignments(&mut state, None);
+        assert_eq!(state.assignments, plain.assignments);
+        assert_eq!(state.centroid_distances, plain.centroid_distances);
+    }
+}
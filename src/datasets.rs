@@ -0,0 +1,146 @@
+//! Generators for synthetic datasets with known ground truth.
+//!
+//! All generators write their samples directly into the flat row-major layout expected by [`crate::KMeans::new`],
+//! which makes them useful for examples, tests and benchmarks.
+//!
+//! ## Example
+//! ```rust
+//! use kmeans::*;
+//! use rand::prelude::*;
+//!
+//! let mut rnd = rand::rngs::StdRng::seed_from_u64(42);
+//! let blobs = datasets::make_blobs::<f64, _>(&mut rnd, 1000, 2, 3, 0.5);
+//!
+//! let kmean: KMeans<_, 4, _> = KMeans::new(&blobs.samples, blobs.sample_cnt, blobs.sample_dims, EuclideanDistance);
+//! let mut result = kmean.kmeans_lloyd(3, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+//! result.relabel_to_match(&blobs.labels);
+//! ```
+
+use crate::memory::Primitive;
+use rand::prelude::*;
+
+/// Synthetic dataset, together with its ground truth.
+///
+/// ## Fields
+/// - **samples**: Generated samples [row-major] = [<sample0>,<sample1>,<sample2>,...]
+/// - **sample_cnt**: Amount of samples in **samples**
+/// - **sample_dims**: Amount of dimensions of each sample
+/// - **labels**: Ground-truth cluster of each sample
+/// - **centers**: Cluster centers the samples were generated around [row-major] = [<center0>,<center1>,...]
+#[derive(Clone, Debug)]
+pub struct Dataset<T: Primitive> {
+    pub samples: Vec<T>,
+    pub sample_cnt: usize,
+    pub sample_dims: usize,
+    pub labels: Vec<usize>,
+    pub centers: Vec<T>,
+}
+
+/// Draw one value from the standard normal distribution (Box-Muller transform).
+pub(crate) fn standard_normal<R: Rng + ?Sized>(rnd: &mut R) -> f64 {
+    let u1: f64 = 1.0 - rnd.gen::<f64>(); // (0, 1], to avoid ln(0)
+    let u2: f64 = rnd.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Generate isotropic Gaussian blobs.
+///
+/// ## Description
+/// Cluster centers are drawn uniformly from the box `[-10, 10]` in every dimension. Samples are distributed as evenly
+/// as possible over all clusters, and drawn from a normal distribution with standard deviation **cluster_std** around
+/// their cluster's center. The samples are returned in random order.
+///
+/// ## Arguments
+/// - **rnd**: Random number generator to use (use a seeded generator for repeatable datasets)
+/// - **sample_cnt**: Amount of samples to generate
+/// - **sample_dims**: Amount of dimensions of each sample
+/// - **centers**: Amount of clusters to generate
+/// - **cluster_std**: Standard deviation of each cluster
+pub fn make_blobs<T: Primitive, R: Rng + ?Sized>(
+    rnd: &mut R, sample_cnt: usize, sample_dims: usize, centers: usize, cluster_std: T,
+) -> Dataset<T> {
+    assert!(centers > 0 && sample_dims > 0);
+    let cluster_std = cluster_std.to_f64().unwrap();
+    let center_values: Vec<f64> = (0..centers * sample_dims).map(|_| rnd.gen_range(-10.0..10.0)).collect();
+
+    let mut labels: Vec<usize> = (0..sample_cnt).map(|i| i % centers).collect();
+    labels.shuffle(rnd);
+    let samples = labels
+        .iter()
+        .flat_map(|&l| center_values[l * sample_dims..(l + 1) * sample_dims].to_vec())
+        .map(|c| T::from(c + cluster_std * standard_normal(rnd)).unwrap())
+        .collect();
+
+    Dataset {
+        samples,
+        sample_cnt,
+        sample_dims,
+        labels,
+        centers: center_values.into_iter().map(|c| T::from(c).unwrap()).collect(),
+    }
+}
+
+/// Generate anisotropic (stretched and rotated) Gaussian blobs.
+///
+/// ## Description
+/// Generates isotropic blobs like [`make_blobs`], and then applies the linear **transformation** to every sample and
+/// center, which makes all clusters elongated into the same direction. Such datasets show the limits of k-means, which
+/// assumes spherical clusters.
+///
+/// ## Arguments
+/// - **rnd**: Random number generator to use (use a seeded generator for repeatable datasets)
+/// - **sample_cnt**: Amount of samples to generate
+/// - **sample_dims**: Amount of dimensions of each sample
+/// - **centers**: Amount of clusters to generate
+/// - **cluster_std**: Standard deviation of each cluster (before the transformation)
+/// - **transformation**: Transformation matrix [row-major] with `sample_dims * sample_dims` entries, applied as
+///   `x' = x * transformation`
+pub fn make_anisotropic_blobs<T: Primitive, R: Rng + ?Sized>(
+    rnd: &mut R, sample_cnt: usize, sample_dims: usize, centers: usize, cluster_std: T, transformation: &[T],
+) -> Dataset<T> {
+    assert_eq!(transformation.len(), sample_dims * sample_dims);
+    let mut dataset = make_blobs(rnd, sample_cnt, sample_dims, centers, cluster_std);
+    let transform = |v: &mut [T]| {
+        let src = v.to_vec();
+        for (col, dst) in v.iter_mut().enumerate() {
+            *dst = (0..sample_dims).map(|row| src[row] * transformation[row * sample_dims + col]).sum();
+        }
+    };
+    dataset.samples.chunks_exact_mut(sample_dims).for_each(transform);
+    dataset.centers.chunks_exact_mut(sample_dims).for_each(transform);
+    dataset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn make_blobs_ground_truth() {
+        let mut rnd = rand::rngs::StdRng::seed_from_u64(1337);
+        let blobs = make_blobs::<f64, _>(&mut rnd, 999, 3, 3, 0.1);
+        assert_eq!(blobs.samples.len(), 999 * 3);
+        assert_eq!(blobs.centers.len(), 3 * 3);
+        assert_eq!(
+            (0..3).map(|l| blobs.labels.iter().filter(|&&v| v == l).count()).collect::<Vec<_>>(),
+            vec![333, 333, 333]
+        );
+
+        let kmean: KMeans<f64, 4, _> = KMeans::new(&blobs.samples, blobs.sample_cnt, blobs.sample_dims, EuclideanDistance);
+        let conf = KMeansConfig::build().random_generator(rnd).build();
+        let mut res = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(blobs.centers.clone()), &conf);
+        res.relabel_to_match(&blobs.labels);
+        assert_eq!(res.assignments, blobs.labels);
+    }
+
+    #[test]
+    fn make_anisotropic_blobs_transformation() {
+        let mut rnd = rand::rngs::StdRng::seed_from_u64(1337);
+        let blobs = make_anisotropic_blobs::<f64, _>(&mut rnd, 10, 2, 2, 0.0, &[2.0, 0.0, 0.0, 0.5]);
+        // Without spread, all samples lie exactly on their (transformed) center
+        blobs.samples.chunks_exact(2).zip(blobs.labels.iter()).for_each(|(s, &l)| {
+            assert_eq!(s, &blobs.centers[l * 2..(l + 1) * 2]);
+        });
+    }
+}
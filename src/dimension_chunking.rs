@@ -0,0 +1,106 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{self, LaneCount, Simd, SupportedLaneCount};
+use crate::{scratch, KMeans, KMeansState};
+use rayon::prelude::*;
+
+/// Amount of samples that are processed together, while iterating over the chunks of dimensions.
+const SAMPLE_BLOCK: usize = 32;
+
+/// Dimension-chunked variant of [`KMeans::update_cluster_assignments`] (loop interchange), for very wide samples.
+///
+/// For every block of samples, the dimensions are processed in chunks of **chunk_dims**, accumulating the partial
+/// distances of all samples in the block to the first **k** centroids. Only the current chunk of the block's samples
+/// and the centroids thus has to be kept in the cache, instead of a full row of every centroid per sample.
+/// Requires a separable distance function (see [`DistanceFunction::is_separable`]).
+pub(crate) fn update_cluster_assignments<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, k: usize, chunk_dims: usize,
+) where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let centroids = &state.centroids;
+    let stride = kmean.p_samples.stride;
+    state
+        .memory_usage
+        .record_temporaries(rayon::current_num_threads() * SAMPLE_BLOCK * k * std::mem::size_of::<T>());
+
+    kmean
+        .p_samples
+        .bfr
+        .par_chunks(stride * SAMPLE_BLOCK)
+        .zip(state.assignments.par_chunks_mut(SAMPLE_BLOCK))
+        .zip(state.centroid_distances.par_chunks_mut(SAMPLE_BLOCK))
+        .for_each(|((samples, assignments), centroid_distances)| {
+            simd::dispatch(|| {
+                let mut partial = scratch::vec(assignments.len() * k, T::zero());
+                (0..stride).step_by(chunk_dims).for_each(|start| {
+                    let dims = start..(start + chunk_dims).min(stride);
+                    centroids.chunks_exact_stride().take(k).enumerate().for_each(|(c_idx, c)| {
+                        let c = &c[dims.clone()];
+                        samples
+                            .chunks_exact(stride)
+                            .zip(partial.chunks_exact_mut(k))
+                            .for_each(|(s, partial)| {
+                                partial[c_idx] += kmean.distance_fn.distance(&s[dims.clone()], c);
+                            });
+                    });
+                });
+                partial
+                    .chunks_exact(k)
+                    .zip(assignments.iter_mut())
+                    .zip(centroid_distances.iter_mut())
+                    .for_each(|((partial, assignment), centroid_dist)| {
+                        let (best_idx, best_dist) = partial
+                            .iter()
+                            .cloned()
+                            .enumerate()
+                            .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+                            .unwrap();
+                        *assignment = best_idx;
+                        *centroid_dist = best_dist;
+                    });
+            })
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, HistogramDistance, KMeans, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn chunked_equals_row_wise() {
+        let (sample_cnt, sample_dims) = (100, 250);
+        let mut rnd = rand::rngs::StdRng::seed_from_u64(1337);
+        let samples: Vec<f64> = (0..sample_cnt * sample_dims)
+            .map(|i| (i / sample_dims % 3) as f64 * 5.0 + rnd.gen::<f64>())
+            .collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let chunked: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance).with_dimension_chunking(30);
+        assert_eq!(chunked.dimension_chunk, Some(32));
+
+        let conf = KMeansConfig::default();
+        let init = || KMeans::init_precomputed(samples[..3 * sample_dims].to_vec());
+        let expected = kmean.kmeans_lloyd(3, 100, init(), &conf);
+        let res = chunked.kmeans_lloyd(3, 100, init(), &conf);
+        assert_eq!(res.assignments, expected.assignments);
+        assert!(res
+            .centroid_distances
+            .iter()
+            .zip(expected.centroid_distances.iter())
+            .all(|(a, b)| (a - b).abs() < 1e-9));
+
+        // Non-separable distance functions ignore the setting
+        let centroids = samples[..3 * sample_dims].to_vec();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, HistogramDistance);
+        let expected = kmean.kmeans_lloyd(3, 10, KMeans::init_precomputed(centroids.clone()), &conf);
+        let kmean = kmean.with_dimension_chunking(32);
+        assert_eq!(
+            kmean.kmeans_lloyd(3, 10, KMeans::init_precomputed(centroids), &conf).distsum,
+            expected.distsum
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+
+/// Amount of distance evaluations of a k-means calculation, as counted by a [`crate::CountingDistance`].
+///
+/// ## Fields
+/// - **initialization**: Evaluations until the initialization of the centroids finished
+/// - **per_iteration**: Evaluations of every iteration
+/// - **total**: Evaluations until the calculation finished (including the initialization, and the final assignment
+///   and post-processing passes after the last iteration)
+///
+/// All values are counted from the creation (or the last reset) of the counter, so **initialization** and **total**
+/// contain the evaluations of earlier calculations, unless the counter was reset in between.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DistanceStatistics {
+    pub initialization: u64,
+    pub per_iteration: Vec<u64>,
+    pub total: u64,
+}
+
+/// Start the statistics of **state** after the initialization, if the distance function counts its evaluations.
+pub(crate) fn start<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>)
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    state.distance_statistics = kmean.distance_fn.distance_evaluations().map(|evaluations| DistanceStatistics {
+        initialization: evaluations,
+        per_iteration: Vec::new(),
+        total: evaluations,
+    });
+}
+
+/// Record the evaluations of the iteration that just finished.
+pub(crate) fn record_iteration<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>)
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    if let (Some(statistics), Some(evaluations)) = (state.distance_statistics.as_mut(), kmean.distance_fn.distance_evaluations()) {
+        statistics.per_iteration.push(evaluations.saturating_sub(statistics.total));
+        statistics.total = evaluations;
+    }
+}
+
+/// Update the total evaluations, once the calculation finished.
+pub(crate) fn finish<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>)
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    if let (Some(statistics), Some(evaluations)) = (state.distance_statistics.as_mut(), kmean.distance_fn.distance_evaluations()) {
+        statistics.total = evaluations;
+    }
+}
@@ -0,0 +1,55 @@
+use crate::memory::SupportedSimdArray;
+use crate::simd::{LaneCount, Simd, SimdFloat, SupportedLaneCount};
+use crate::{DistanceFunction, Primitive};
+
+/// Cosine distance: `1 - cos(a, b)`, which only compares the direction of both samples, ignoring their length.
+///
+/// This is the standard distance for tf-idf vectors and embeddings. The distance to a sample of length zero is `1`
+/// (as for orthogonal samples). Use it with [`crate::KMeans::kmeans_spherical`], which keeps the centroids at unit
+/// length; The centroids of the other variants are means, whose length shrinks with the spread of their samples.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CosineDistance;
+
+impl<T, const LANES: usize> DistanceFunction<T, LANES> for CosineDistance
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    #[inline(always)]
+    fn distance(&self, a: &[T], b: &[T]) -> T {
+        let (dot, norm_a, norm_b) = a
+            .chunks_exact(LANES)
+            .map(|i| Simd::from_slice(i))
+            .zip(b.chunks_exact(LANES).map(|i| Simd::from_slice(i)))
+            .fold(
+                (Simd::splat(T::zero()), Simd::splat(T::zero()), Simd::splat(T::zero())),
+                |(dot, norm_a, norm_b), (a, b)| (dot + a * b, norm_a + a * a, norm_b + b * b),
+            );
+        let norm = (norm_a.reduce_sum() * norm_b.reduce_sum()).sqrt();
+        match norm > T::zero() {
+            // Rounding may push the cosine of (almost) parallel samples slightly above 1
+            true => (T::one() - dot.reduce_sum() / norm).max(T::zero()),
+            false => T::one(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::StrideBuffer;
+
+    #[test]
+    fn cosine_distance() {
+        let p_samples = StrideBuffer::from_slice::<8>(3, &[
+            1.0f64, 0.0, 0.0, 3.0, 0.0, 0.0, 0.0, 2.0, 0.0, -1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0,
+        ]);
+        let distance = |a, b| DistanceFunction::<f64, 8>::distance(&CosineDistance, p_samples.nth_stride(a), p_samples.nth_stride(b));
+        assert_eq!(distance(0, 1), 0.0);
+        assert_eq!(distance(0, 2), 1.0);
+        assert_eq!(distance(0, 3), 2.0);
+        assert!((distance(0, 4) - (1.0 - 0.5f64.sqrt())).abs() < 1e-12);
+        assert_eq!(distance(0, 5), 1.0);
+    }
+}
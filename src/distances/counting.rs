@@ -0,0 +1,120 @@
+use crate::{DistanceFunction, Primitive};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Wrapper of a distance function, that counts its evaluations, e.g. to validate that the accelerated variants
+/// ([`crate::KMeans::kmeans_elkan`], [`crate::KMeans::kmeans_hamerly`]) actually prune work on a dataset.
+///
+/// Results of calculations with this distance function report the counted evaluations (in total and per iteration)
+/// in [`crate::KMeansState::distance_statistics`]. Early abandoned evaluations (see
+/// [`DistanceFunction::distance_bounded`]) count as full evaluations. Kernels that calculate distances without the
+/// distance function (the norm cache, see [`crate::KMeans::with_norm_cache`]) are not counted.
+///
+/// Every evaluation increments one shared atomic counter, which noticeably slows down calculations with many threads,
+/// so this is meant for diagnosis. The counter is shared by all calculations on the same [`crate::KMeans`] instance,
+/// so reset it before each calculation (or do not run them concurrently), to get the statistics of one calculation.
+///
+/// ## Example
+/// ```rust
+/// use kmeans::*;
+///
+/// let (sample_cnt, sample_dims, k, max_iter) = (2000, 10, 8, 100);
+/// let samples: Vec<f64> = (0..sample_cnt * sample_dims).map(|_| rand::random()).collect();
+///
+/// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, CountingDistance::new(EuclideanDistance));
+/// let lloyd = kmean.kmeans_lloyd(k, max_iter, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+/// kmean.distance_fn().reset();
+/// let elkan = kmean.kmeans_elkan(k, max_iter, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+///
+/// let (lloyd, elkan) = (lloyd.distance_statistics.unwrap(), elkan.distance_statistics.unwrap());
+/// println!("Lloyd: {} evaluations, Elkan: {} evaluations", lloyd.total, elkan.total);
+/// ```
+#[derive(Debug, Default)]
+pub struct CountingDistance<D> {
+    inner: D,
+    evaluations: AtomicU64,
+}
+impl<D> CountingDistance<D> {
+    /// Wrap the given distance function.
+    pub fn new(inner: D) -> Self {
+        Self {
+            inner,
+            evaluations: AtomicU64::new(0),
+        }
+    }
+
+    /// Amount of evaluations since the creation, or the last [`CountingDistance::reset`].
+    pub fn evaluations(&self) -> u64 { self.evaluations.load(Ordering::Relaxed) }
+
+    /// Reset the amount of evaluations to `0`.
+    pub fn reset(&self) { self.evaluations.store(0, Ordering::Relaxed) }
+
+    /// The wrapped distance function.
+    pub fn inner(&self) -> &D { &self.inner }
+}
+
+impl<T, const LANES: usize, D: DistanceFunction<T, LANES>> DistanceFunction<T, LANES> for CountingDistance<D> {
+    #[inline(always)]
+    fn distance(&self, a: &[T], b: &[T]) -> T {
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+        self.inner.distance(a, b)
+    }
+
+    fn is_separable(&self) -> bool { self.inner.is_separable() }
+
+    #[inline(always)]
+    fn distance_bounded(&self, a: &[T], b: &[T], bound: T) -> T {
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+        self.inner.distance_bounded(a, b, bound)
+    }
+
+    fn is_squared_euclidean(&self) -> bool { self.inner.is_squared_euclidean() }
+
+    fn distance_evaluations(&self) -> Option<u64> { Some(self.evaluations()) }
+
+    fn padding_value(&self) -> T
+    where
+        T: Primitive,
+    {
+        self.inner.padding_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CountingDistance, EuclideanDistance, KMeans, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn count_distance_evaluations() {
+        let samples = vec![0.0f64, 1.0, 10.0, 11.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, CountingDistance::new(EuclideanDistance));
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &KMeansConfig::default());
+
+        // Every Lloyd iteration compares all samples with all centroids
+        let statistics = res.distance_statistics.unwrap();
+        assert_eq!(statistics.initialization, 0);
+        assert_eq!(statistics.per_iteration.len(), res.n_iterations);
+        assert!(statistics.per_iteration.iter().all(|&evaluations| evaluations == 4 * 2));
+        assert!(statistics.total >= statistics.per_iteration.iter().sum::<u64>());
+        assert_eq!(statistics.total, kmean.distance_fn().evaluations());
+
+        // Without a counting distance function, nothing is recorded
+        let plain: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = plain.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &KMeansConfig::default());
+        assert!(res.distance_statistics.is_none());
+    }
+
+    #[test]
+    fn elkan_prunes_distance_evaluations() {
+        let mut rnd = StdRng::seed_from_u64(1337);
+        let samples: Vec<f64> = (0..2000).map(|i| (i % 8) as f64 * 10.0 + rnd.gen_range(-1.0..1.0)).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 1000, 2, CountingDistance::new(EuclideanDistance));
+        let conf = || KMeansConfig::build().random_generator(StdRng::seed_from_u64(42)).build();
+
+        let lloyd = kmean.kmeans_lloyd(8, 100, KMeans::init_kmeanplusplus, &conf());
+        kmean.distance_fn().reset();
+        let elkan = kmean.kmeans_elkan(8, 100, KMeans::init_kmeanplusplus, &conf());
+        let (lloyd, elkan) = (lloyd.distance_statistics.unwrap(), elkan.distance_statistics.unwrap());
+        assert!(elkan.per_iteration.last() < lloyd.per_iteration.last());
+    }
+}
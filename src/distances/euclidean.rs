@@ -1,8 +1,12 @@
 use crate::memory::SupportedSimdArray;
+use crate::simd::{LaneCount, Simd, SimdFloat, SupportedLaneCount};
 use crate::{DistanceFunction, Primitive};
-use std::simd::num::SimdFloat;
-use std::simd::{LaneCount, Simd, SupportedLaneCount};
 
+/// Amount of SIMD vectors that are accumulated between two checks against the bound, in
+/// [`DistanceFunction::distance_bounded`]. Checking after every vector would stall the pipeline.
+const BOUND_CHECK_BLOCK: usize = 8;
+
+#[derive(Clone, Copy, Debug, Default)]
 pub struct EuclideanDistance;
 
 impl<T, const LANES: usize> DistanceFunction<T, LANES> for EuclideanDistance
@@ -21,4 +25,38 @@ where
             .sum::<Simd<T, LANES>>()
             .reduce_sum()
     }
+
+    fn is_separable(&self) -> bool { true }
+
+    fn is_squared_euclidean(&self) -> bool { true }
+
+    #[inline(always)]
+    fn distance_bounded(&self, a: &[T], b: &[T], bound: T) -> T {
+        let mut total = T::zero();
+        for (a, b) in a.chunks(BOUND_CHECK_BLOCK * LANES).zip(b.chunks(BOUND_CHECK_BLOCK * LANES)) {
+            total += self.distance(a, b);
+            if total > bound {
+                break;
+            }
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded_distance() {
+        let a: Vec<f64> = (0..200).map(|i| i as f64).collect();
+        let b: Vec<f64> = (0..200).map(|i| (i % 7) as f64).collect();
+        let exact = DistanceFunction::<f64, 8>::distance(&EuclideanDistance, &a, &b);
+        let bounded = |bound| DistanceFunction::<f64, 8>::distance_bounded(&EuclideanDistance, &a, &b, bound);
+        assert!((bounded(f64::INFINITY) - exact).abs() < 1e-6 * exact);
+        assert!((bounded(exact * 1.001) - exact).abs() < 1e-6 * exact);
+        // Abandoned early, but still larger than the bound
+        assert!(bounded(10.0) > 10.0);
+        assert!(bounded(10.0) < exact);
+    }
 }
@@ -1,7 +1,8 @@
 use crate::memory::SupportedSimdArray;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
 use crate::{DistanceFunction, Primitive};
-use std::simd::{LaneCount, Simd, SupportedLaneCount};
 
+#[derive(Clone, Copy, Debug, Default)]
 pub struct HistogramDistance;
 
 impl<T, const LANES: usize> DistanceFunction<T, LANES> for HistogramDistance
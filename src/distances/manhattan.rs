@@ -0,0 +1,79 @@
+use crate::memory::SupportedSimdArray;
+use crate::simd::{LaneCount, Simd, SimdFloat, SupportedLaneCount};
+use crate::{DistanceFunction, Primitive};
+
+/// Amount of SIMD vectors that are accumulated between two checks against the bound, in
+/// [`DistanceFunction::distance_bounded`].
+const BOUND_CHECK_BLOCK: usize = 8;
+
+/// Manhattan (L1) distance: the sum of the absolute differences of all dimensions.
+///
+/// This suits (e.g. robust-scaled) features, where the L1 geometry matches the data better than the euclidean
+/// one. Note that the default centroid update still moves every centroid into the mean of its samples; for the
+/// centroids that minimize the sum of L1 distances (k-medians), combine it with [`crate::MedianUpdater`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ManhattanDistance;
+
+impl<T, const LANES: usize> DistanceFunction<T, LANES> for ManhattanDistance
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    #[inline(always)]
+    fn distance(&self, a: &[T], b: &[T]) -> T {
+        a.chunks_exact(LANES)
+            .map(|i| Simd::from_slice(i))
+            .zip(b.chunks_exact(LANES).map(|i| Simd::from_slice(i)))
+            .map(|(sp, cp)| (sp - cp).abs())
+            .sum::<Simd<T, LANES>>()
+            .reduce_sum()
+    }
+
+    fn is_separable(&self) -> bool { true }
+
+    #[inline(always)]
+    fn distance_bounded(&self, a: &[T], b: &[T], bound: T) -> T {
+        let mut total = T::zero();
+        for (a, b) in a.chunks(BOUND_CHECK_BLOCK * LANES).zip(b.chunks(BOUND_CHECK_BLOCK * LANES)) {
+            total += self.distance(a, b);
+            if total > bound {
+                break;
+            }
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::StrideBuffer;
+    use crate::{KMeans, KMeansConfig, MedianUpdater};
+
+    #[test]
+    fn manhattan_distance() {
+        for dims in [1, 7, 8, 9, 100] {
+            let a: Vec<f32> = (0..dims).map(|i| i as f32 * 0.5).collect();
+            let b: Vec<f32> = (0..dims).map(|i| (i % 3) as f32 - 1.0).collect();
+            let expected: f32 = a.iter().zip(b.iter()).map(|(a, b)| (a - b).abs()).sum();
+            // Distances are calculated on the padded samples
+            let p_samples = StrideBuffer::from_slice::<8>(dims, &[a, b].concat());
+            let (a, b) = (p_samples.nth_stride(0), p_samples.nth_stride(1));
+            let distance = DistanceFunction::<f32, 8>::distance(&ManhattanDistance, a, b);
+            assert!((distance - expected).abs() < 1e-4);
+            assert!(DistanceFunction::<f32, 8>::distance_bounded(&ManhattanDistance, a, b, 0.0) > 0.0);
+        }
+    }
+
+    #[test]
+    fn k_medians() {
+        // With the median updater, the outliers do not pull the centroids away
+        let samples = vec![0.0f64, 1.0, 2.0, 50.0, 10.0, 11.0, 12.0, 60.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, ManhattanDistance);
+        let conf = KMeansConfig::build().centroid_updater(MedianUpdater).build();
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &conf);
+        assert_eq!(res.assignments, vec![0, 0, 0, 1, 1, 1, 1, 1]);
+        assert_eq!(res.centroids.to_vec(), vec![1.0, 12.0]);
+    }
+}
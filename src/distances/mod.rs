@@ -1,5 +1,15 @@
+mod cosine;
+mod counting;
 mod euclidean;
 mod histogram;
+mod manhattan;
+mod multi_view;
+mod normalized_histogram;
 
+pub use cosine::CosineDistance;
+pub use counting::CountingDistance;
 pub use euclidean::EuclideanDistance;
 pub use histogram::HistogramDistance;
+pub use manhattan::ManhattanDistance;
+pub use multi_view::MultiViewDistance;
+pub use normalized_histogram::NormalizedHistogramDistance;
@@ -0,0 +1,264 @@
+use crate::memory::StrideBuffer;
+use crate::{DistanceFunction, Primitive};
+use std::ops::Range;
+
+struct View<T, const LANES: usize> {
+    offset: usize,
+    dims: usize,
+    stride: usize,
+    weight: T,
+    scale: T,
+    distance: Box<dyn DistanceFunction<T, LANES>>,
+}
+
+/// Distance of samples that are described by multiple blocks of features (views), such as an embedding and a few
+/// numeric telemetry values: The weighted sum `sum_v weight_v * d_v(a_v, b_v)` of the distances of all views, each
+/// calculated with its own distance function.
+///
+/// The views are laid out one after another within every sample, each starting at a multiple of `LANES` dimensions
+/// (padded with the padding value of its distance function), so every view distance runs on aligned SIMD vectors.
+/// [`MultiViewDistance::concat`] builds this layout from the separate (row-major) buffers of all views, and
+/// [`MultiViewDistance::sample_dims`] is the amount of dimensions to pass to [`crate::KMeans::new`]. The result can be
+/// clustered by all variants that move the centroids into the means of their samples (e.g.
+/// [`crate::KMeans::kmeans_lloyd`]), which is the mean of every view; [`MultiViewDistance::view_range`] locates the
+/// views within the centroids.
+///
+/// Views of differently scaled features (e.g. cosine distances in `[0, 2]` and squared euclidean distances of raw
+/// telemetry) do not need to be rescaled manually: [`MultiViewDistance::balanced`] scales the distances of every view
+/// by the average distance of its samples to their mean, so each view contributes according to its weight.
+///
+/// ## Example
+/// ```rust
+/// use kmeans::*;
+///
+/// let sample_cnt = 1000;
+/// let embeddings: Vec<f64> = (0..sample_cnt * 16).map(|_| rand::random()).collect();
+/// let telemetry: Vec<f64> = (0..sample_cnt * 3).map(|_| rand::random::<f64>() * 1000.0).collect();
+///
+/// let distance = MultiViewDistance::<f64, 8>::new()
+///     .view(16, 1.0, CosineDistance)
+///     .view(3, 0.5, EuclideanDistance)
+///     .balanced(&[&embeddings, &telemetry]);
+/// let samples = distance.concat(&[&embeddings, &telemetry]);
+/// let sample_dims = distance.sample_dims();
+///
+/// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, distance);
+/// let result = kmean.kmeans_lloyd(4, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+/// let telemetry_range = kmean.distance_fn().view_range(1);
+/// println!("Telemetry of the first centroid: {:?}", &result.centroids[0][telemetry_range]);
+/// ```
+pub struct MultiViewDistance<T, const LANES: usize> {
+    views: Vec<View<T, LANES>>,
+    sample_dims: usize,
+}
+impl<T: Primitive, const LANES: usize> MultiViewDistance<T, LANES> {
+    /// Create a distance without any views.
+    pub fn new() -> Self {
+        Self {
+            views: Vec::new(),
+            sample_dims: 0,
+        }
+    }
+
+    /// Append a view to the layout of the samples.
+    ///
+    /// ## Arguments
+    /// - **dims**: Amount of dimensions (features) of the view
+    /// - **weight**: Factor (`>= 0`) on the distances of the view
+    /// - **distance**: Distance function of the view
+    pub fn view(mut self, dims: usize, weight: T, distance: impl DistanceFunction<T, LANES> + 'static) -> Self {
+        assert!(dims > 0);
+        assert!(weight >= T::zero());
+        let stride = dims.div_ceil(LANES) * LANES;
+        self.views.push(View {
+            offset: self.sample_dims,
+            dims,
+            stride,
+            weight,
+            scale: T::one(),
+            distance: Box::new(distance),
+        });
+        self.sample_dims += stride;
+        self
+    }
+
+    /// Scale the distances of every view by the reciprocal of the average distance of the given samples of the view to
+    /// their mean, so that the views contribute to the distances according to their weights, independent of the scale
+    /// of their features. Views whose samples are all equal keep their scale of `1`.
+    ///
+    /// ## Arguments
+    /// - **views**: The samples of every view [row-major] = [<sample0>,<sample1>,...], with the dimensions of the view
+    pub fn balanced(mut self, views: &[&[T]]) -> Self {
+        assert_eq!(views.len(), self.views.len());
+        self.views.iter_mut().zip(views.iter()).for_each(|(view, samples)| {
+            assert_eq!(samples.len() % view.dims, 0);
+            let sample_cnt = samples.len() / view.dims;
+            if sample_cnt == 0 {
+                return;
+            }
+            let samples = StrideBuffer::from_slice::<LANES>(view.dims, samples).padded(view.distance.padding_value());
+            let mut mean = StrideBuffer::new::<LANES>(1, view.dims).padded(view.distance.padding_value());
+            samples
+                .iter()
+                .for_each(|s| mean[0].iter_mut().zip(s.iter()).for_each(|(m, &v)| *m += v));
+            let cnt = T::from(sample_cnt).unwrap();
+            mean[0].iter_mut().for_each(|m| *m = *m / cnt);
+
+            let avg_distance = samples
+                .chunks_exact_stride()
+                .map(|s| view.distance.distance(s, mean.nth_stride(0)))
+                .sum::<T>()
+                / cnt;
+            view.scale = match avg_distance > T::zero() {
+                true => T::one() / avg_distance,
+                false => T::one(),
+            };
+        });
+        self
+    }
+
+    /// Amount of dimensions of the samples in the layout of this distance (including the padding of every view).
+    pub fn sample_dims(&self) -> usize { self.sample_dims }
+
+    /// Amount of views.
+    pub fn view_cnt(&self) -> usize { self.views.len() }
+
+    /// Dimensions of the given view within the samples (and centroids), without its padding.
+    pub fn view_range(&self, view: usize) -> Range<usize> { self.views[view].offset..self.views[view].offset + self.views[view].dims }
+
+    /// Effective factor on the distances of the given view (its weight, including the scale of
+    /// [`MultiViewDistance::balanced`]).
+    pub fn view_weight(&self, view: usize) -> T { self.views[view].weight * self.views[view].scale }
+
+    /// Build the samples in the layout of this distance from the separate samples of all views.
+    ///
+    /// ## Arguments
+    /// - **views**: The samples of every view [row-major] = [<sample0>,<sample1>,...], with the dimensions of the view.
+    ///   All views have to contain the same amount of samples.
+    ///
+    /// ## Returns
+    /// The combined samples [row-major], with [`MultiViewDistance::sample_dims`] dimensions each.
+    pub fn concat(&self, views: &[&[T]]) -> Vec<T> {
+        assert_eq!(views.len(), self.views.len());
+        let sample_cnt = self.views.first().map_or(0, |view| views[0].len() / view.dims);
+        let mut samples = vec![T::zero(); sample_cnt * self.sample_dims];
+        self.views.iter().zip(views.iter()).for_each(|(view, values)| {
+            assert_eq!(values.len(), sample_cnt * view.dims);
+            let padding = view.distance.padding_value();
+            samples
+                .chunks_exact_mut(self.sample_dims)
+                .zip(values.chunks_exact(view.dims))
+                .for_each(|(sample, values)| {
+                    let (target, pad) = sample[view.offset..view.offset + view.stride].split_at_mut(view.dims);
+                    target.copy_from_slice(values);
+                    pad.fill(padding);
+                });
+        });
+        samples
+    }
+
+    /// Unweighted distances of both samples (in the layout of this distance) in every view.
+    pub fn view_distances(&self, a: &[T], b: &[T]) -> Vec<T> {
+        self.views
+            .iter()
+            .map(|view| {
+                let range = view.offset..view.offset + view.stride;
+                view.distance.distance(&a[range.clone()], &b[range])
+            })
+            .collect()
+    }
+}
+impl<T: Primitive, const LANES: usize> Default for MultiViewDistance<T, LANES> {
+    fn default() -> Self { Self::new() }
+}
+
+impl<T: Primitive, const LANES: usize> DistanceFunction<T, LANES> for MultiViewDistance<T, LANES> {
+    #[inline(always)]
+    fn distance(&self, a: &[T], b: &[T]) -> T {
+        self.views
+            .iter()
+            .filter(|view| view.weight > T::zero())
+            .map(|view| {
+                let range = view.offset..view.offset + view.stride;
+                view.weight * view.scale * view.distance.distance(&a[range.clone()], &b[range])
+            })
+            .sum()
+    }
+
+    #[inline(always)]
+    fn distance_bounded(&self, a: &[T], b: &[T], bound: T) -> T {
+        let mut total = T::zero();
+        for view in self.views.iter().filter(|view| view.weight > T::zero()) {
+            let (range, factor) = (view.offset..view.offset + view.stride, view.weight * view.scale);
+            total += factor
+                * view
+                    .distance
+                    .distance_bounded(&a[range.clone()], &b[range], (bound - total) / factor);
+            if total > bound {
+                break;
+            }
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CosineDistance, EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn view_layout() {
+        let distance = MultiViewDistance::<f64, 4>::new()
+            .view(3, 1.0, EuclideanDistance)
+            .view(2, 2.0, CosineDistance);
+        assert_eq!(distance.sample_dims(), 8);
+        assert_eq!((distance.view_range(0), distance.view_range(1)), (0..3, 4..6));
+
+        let samples = distance.concat(&[&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[1.0, 0.0, 0.0, 1.0]]);
+        assert_eq!(samples, vec![
+            1.0, 2.0, 3.0, 0.0, 1.0, 0.0, 0.0, 0.0, 4.0, 5.0, 6.0, 0.0, 0.0, 1.0, 0.0, 0.0
+        ]);
+        let (a, b) = (&samples[..8], &samples[8..]);
+        assert_eq!(distance.view_distances(a, b), vec![27.0, 1.0]);
+        assert_eq!(DistanceFunction::<f64, 4>::distance(&distance, a, b), 29.0);
+        assert!(DistanceFunction::<f64, 4>::distance_bounded(&distance, a, b, 10.0) > 10.0);
+
+        // Balancing divides by the average distance to the mean of each view (squared euclidean: 125 and 1.25)
+        let balanced = MultiViewDistance::<f64, 4>::new()
+            .view(1, 1.0, EuclideanDistance)
+            .view(1, 1.0, EuclideanDistance)
+            .balanced(&[&[0.0, 10.0, 20.0, 30.0], &[0.0, 1.0, 2.0, 3.0]]);
+        assert_eq!((balanced.view_weight(0), balanced.view_weight(1)), (1.0 / 125.0, 0.8));
+    }
+
+    #[test]
+    fn weighted_views_equal_scaled_concatenation() {
+        // Weighted squared euclidean views equal the euclidean distance of the views scaled by the root of the weights
+        let sample_cnt = 60;
+        let view0: Vec<f64> = (0..sample_cnt * 3)
+            .map(|i| ((i * 7) % 11) as f64 + (i / 30 % 2) as f64 * 8.0)
+            .collect();
+        let view1: Vec<f64> = (0..sample_cnt * 2).map(|i| ((i * 5) % 13) as f64 * 0.5).collect();
+        let distance = MultiViewDistance::<f64, 8>::new()
+            .view(3, 1.0, EuclideanDistance)
+            .view(2, 4.0, EuclideanDistance);
+        let samples = distance.concat(&[&view0, &view1]);
+        let init = distance.concat(&[&view0[..9], &view1[..6]]);
+        let sample_dims = distance.sample_dims();
+        let multi_view: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, distance);
+        let multi_view = multi_view.kmeans_lloyd(3, 100, KMeans::init_precomputed(init), &KMeansConfig::default());
+
+        let scaled: Vec<f64> = view0
+            .chunks_exact(3)
+            .zip(view1.chunks_exact(2))
+            .flat_map(|(a, b)| a.iter().cloned().chain(b.iter().map(|v| v * 2.0)))
+            .collect();
+        let init = scaled[..15].to_vec();
+        let reference: KMeans<f64, 8, _> = KMeans::new(&scaled, sample_cnt, 5, EuclideanDistance);
+        let reference = reference.kmeans_lloyd(3, 100, KMeans::init_precomputed(init), &KMeansConfig::default());
+
+        assert_eq!(multi_view.assignments, reference.assignments);
+        assert!((multi_view.distsum - reference.distsum).abs() < 1e-9 * reference.distsum);
+    }
+}
@@ -0,0 +1,63 @@
+use crate::memory::SupportedSimdArray;
+use crate::simd::{LaneCount, Simd, SimdFloat, SupportedLaneCount};
+use crate::{DistanceFunction, Primitive};
+
+/// Variant of [`crate::HistogramDistance`] for unnormalized histograms (e.g. raw counts).
+///
+/// Both histograms are normalized to a total mass of `1` on the fly, so the samples do not have to be
+/// pre-normalized into a separate buffer. Histograms with a total mass of `0` are treated as empty (all-zero CDF).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NormalizedHistogramDistance;
+
+impl<T, const LANES: usize> DistanceFunction<T, LANES> for NormalizedHistogramDistance
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    #[inline(always)]
+    fn distance(&self, a: &[T], b: &[T]) -> T {
+        let mass = |v: &[T]| {
+            let mass = v
+                .chunks_exact(LANES)
+                .map(|i| Simd::from_slice(i))
+                .sum::<Simd<T, LANES>>()
+                .reduce_sum();
+            if mass > T::zero() {
+                T::one() / mass
+            } else {
+                T::zero()
+            }
+        };
+        let (scale_a, scale_b) = (mass(a), mass(b));
+
+        let mut total = T::zero();
+        let mut cdf_a = T::zero();
+        let mut cdf_b = T::zero();
+        for (&x, &y) in a.iter().zip(b.iter()) {
+            cdf_a += x * scale_a;
+            cdf_b += y * scale_b;
+            total += (cdf_a - cdf_b).abs();
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HistogramDistance;
+
+    #[test]
+    fn scale_invariance() {
+        let (a, b) = ([1.0f64, 1.0, 2.0, 0.0], [0.0f64, 2.0, 1.0, 1.0]);
+        let (a_raw, b_raw) = (a.map(|v| v * 10.0), b.map(|v| v * 3.0));
+        let expected = DistanceFunction::<f64, 4>::distance(&HistogramDistance, &a.map(|v| v / 4.0), &b.map(|v| v / 4.0));
+        let actual = DistanceFunction::<f64, 4>::distance(&NormalizedHistogramDistance, &a_raw, &b_raw);
+        assert!((expected - actual).abs() < 1e-12);
+        assert_eq!(
+            DistanceFunction::<f64, 4>::distance(&NormalizedHistogramDistance, &[0.0; 4], &[0.0; 4]),
+            0.0
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::variants::Lloyd;
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rand::distributions::weighted::WeightedIndex;
+use rand::prelude::*;
+
+/// Probability, that a centroid of a child is taken from the second parent (per matched pair of centroids)
+const CROSSOVER_PROBABILITY: f64 = 0.5;
+/// Probability, that one centroid of a child is re-seeded onto a sample, before the child is refined
+const MUTATION_PROBABILITY: f64 = 0.5;
+
+/// Binary tournament: the better one of two randomly drawn individuals of the (sorted) population.
+fn tournament(population_size: usize, rnd: &mut dyn RngCore) -> usize {
+    rnd.gen_range(0..population_size).min(rnd.gen_range(0..population_size))
+}
+
+/// Recombine the centroids of both parents: Every centroid of the **first** parent is matched with the nearest,
+/// not yet matched centroid of the **second** parent, and the child randomly inherits one of both.
+fn crossover<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, first: &KMeansState<T>, second: &KMeansState<T>, rnd: &mut dyn RngCore,
+) -> StrideBuffer<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let mut child = first.centroids.clone();
+    let mut matched = vec![false; second.k];
+    for ci in 0..first.k {
+        let centroid = first.centroids.nth_stride(ci);
+        let nearest = (0..second.k)
+            .filter(|&cj| !matched[cj])
+            .map(|cj| (cj, kmean.distance_fn.distance(centroid, second.centroids.nth_stride(cj))))
+            .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+            .unwrap()
+            .0;
+        matched[nearest] = true;
+        if rnd.gen_bool(CROSSOVER_PROBABILITY) {
+            child.nth_stride_mut(ci).copy_from_slice(second.centroids.nth_stride(nearest));
+        }
+    }
+    child
+}
+
+/// Re-seed one random centroid of the child onto a sample, drawn with a probability proportional to its (weighted)
+/// distance to its centroid in the **parent** (an approximation of the distances to the child's centroids).
+fn mutate<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, parent: &KMeansState<T>, child: &mut StrideBuffer<T>, rnd: &mut dyn RngCore,
+) where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let probabilities = (0..kmean.sample_cnt).map(|idx| parent.centroid_distances[idx] * kmean.sample_weight(idx));
+    // All samples coincide with a centroid: there is nothing to gain
+    if let Ok(distribution) = WeightedIndex::new(probabilities) {
+        let sample = distribution.sample(rnd);
+        let centroid = rnd.gen_range(0..child.centroid_cnt);
+        child.nth_stride_mut(centroid).copy_from_slice(kmean.p_samples.nth_stride(sample));
+    }
+}
+
+fn sort_by_distsum<T: Primitive>(population: &mut [KMeansState<T>]) {
+    population.sort_by(|a, b| a.distsum.partial_cmp(&b.distsum).unwrap());
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D, F>(
+    kmean: &KMeans<T, LANES, D>, k: usize, population_size: usize, generations: usize, max_iter: usize, init: F,
+    config: &KMeansConfig<'_, T>,
+) -> KMeansState<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+    for<'c> F: Fn(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+{
+    assert!(k <= kmean.sample_cnt);
+    assert!(population_size >= 2);
+
+    let mut population: Vec<KMeansState<T>> = (0..population_size)
+        .map(|_| Lloyd::calculate_unprocessed(kmean, k, max_iter, &init, config))
+        .collect();
+    sort_by_distsum(&mut population);
+
+    for _ in 0..generations {
+        let children: Vec<KMeansState<T>> = (0..population_size)
+            .map(|_| {
+                let child = {
+                    let mut rnd = config.rnd.borrow_mut();
+                    let (first, second) = (tournament(population_size, &mut *rnd), tournament(population_size, &mut *rnd));
+                    let mut child = crossover(kmean, &population[first], &population[second], &mut *rnd);
+                    if rnd.gen_bool(MUTATION_PROBABILITY) {
+                        mutate(kmean, &population[first], &mut child, &mut *rnd);
+                    }
+                    child
+                };
+                Lloyd::calculate_unprocessed(kmean, k, max_iter, move |_, state, _| state.centroids = child, config)
+            })
+            .collect();
+
+        // Elitist selection: the best individuals of both, parents and children survive
+        population.extend(children);
+        sort_by_distsum(&mut population);
+        population.truncate(population_size);
+    }
+
+    let mut best = population.swap_remove(0);
+    let individual_size = best.memory_usage.state;
+    best.memory_usage.record_temporaries(2 * population_size * individual_size);
+    crate::postprocessing::apply(kmean, &mut best, config);
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn evolutionary_finds_all_clusters() {
+        // Grid of 16 separated clusters, where single Lloyd runs from random samples get stuck in local minima
+        let mut rnd = StdRng::seed_from_u64(3);
+        let (per_cluster, k) = (50, 16);
+        let samples: Vec<f64> = (0..k * per_cluster)
+            .flat_map(|i| {
+                let cluster = i % k;
+                [(cluster % 4) as f64 * 10.0, (cluster / 4) as f64 * 10.0]
+            })
+            .map(|v| v + rnd.gen_range(-1.0..1.0))
+            .collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, k * per_cluster, 2, EuclideanDistance);
+        let conf = KMeansConfig::build().random_generator(StdRng::seed_from_u64(7)).build();
+
+        let res = kmean.kmeans_evolutionary(k, 8, 10, 100, KMeans::init_random_sample, &conf);
+        let mut clusters: Vec<usize> = res.assignments[..k].to_vec();
+        clusters.sort();
+        clusters.dedup();
+        assert_eq!(clusters.len(), k);
+        assert!(res.centroid_distances.iter().all(|&d| d <= 4.0));
+
+        // Never worse than the best of the initial population (which are independent restarts)
+        let restarts = (0..8)
+            .map(|_| kmean.kmeans_lloyd(k, 100, KMeans::init_random_sample, &conf).distsum)
+            .fold(f64::INFINITY, f64::min);
+        assert!(res.distsum <= restarts + 1e-9);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::memory::*;
+use crate::KMeansState;
+use arrow_array::{ArrayRef, Float32Array, Float64Array, RecordBatch, UInt64Array};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::ArrowError;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Float column with the native precision of **T** (f32 or f64).
+fn float_column<T: Primitive>(values: impl Iterator<Item = T>) -> ArrayRef {
+    if std::mem::size_of::<T>() == std::mem::size_of::<f32>() {
+        Arc::new(values.map(|v| v.to_f32().unwrap()).collect::<Float32Array>())
+    } else {
+        Arc::new(values.map(|v| v.to_f64().unwrap()).collect::<Float64Array>())
+    }
+}
+
+fn index_column(values: impl Iterator<Item = usize>) -> ArrayRef { Arc::new(values.map(|v| v as u64).collect::<UInt64Array>()) }
+
+fn write_ipc<W: Write>(writer: W, batch: &RecordBatch) -> Result<(), ArrowError> {
+    let mut writer = FileWriter::try_new(writer, &batch.schema())?;
+    writer.write(batch)?;
+    writer.finish()
+}
+
+pub(crate) fn assignments<T: Primitive>(state: &KMeansState<T>) -> Result<RecordBatch, ArrowError> {
+    RecordBatch::try_from_iter([
+        ("sample", index_column(0..state.assignments.len())),
+        ("cluster", index_column(state.assignments.iter().cloned())),
+        ("distance", float_column(state.centroid_distances.iter().cloned())),
+    ])
+}
+
+pub(crate) fn centroids<T: Primitive>(state: &KMeansState<T>) -> Result<RecordBatch, ArrowError> {
+    let mut columns = vec![
+        ("cluster".to_string(), index_column(0..state.k)),
+        ("frequency".to_string(), index_column(state.centroid_frequency.iter().cloned())),
+    ];
+    columns.extend((0..state.centroids.centroid_dim).map(|d| (format!("dim_{}", d), float_column(state.centroids.iter().map(|c| c[d])))));
+    RecordBatch::try_from_iter(columns)
+}
+
+pub(crate) fn write_assignments_ipc<T: Primitive, W: Write>(state: &KMeansState<T>, writer: W) -> Result<(), ArrowError> {
+    write_ipc(writer, &assignments(state)?)
+}
+
+pub(crate) fn write_centroids_ipc<T: Primitive, W: Write>(state: &KMeansState<T>, writer: W) -> Result<(), ArrowError> {
+    write_ipc(writer, &centroids(state)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+    use arrow_array::{Float32Array, UInt64Array};
+    use arrow_ipc::reader::FileReader;
+    use std::io::Cursor;
+
+    #[test]
+    fn arrow_export() {
+        let samples = vec![0.0f32, 0.0, 1.0, 1.0, 10.0, 10.0, 11.0, 11.0, 12.0, 12.0];
+        let kmean: KMeans<f32, 8, _> = KMeans::new(&samples, 5, 2, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(
+            2,
+            100,
+            KMeans::init_precomputed(vec![0.0, 0.0, 10.0, 10.0]),
+            &KMeansConfig::default(),
+        );
+
+        let assignments = res.assignments_record_batch().unwrap();
+        assert_eq!(assignments.num_rows(), 5);
+        let cluster = assignments
+            .column_by_name("cluster")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!(cluster.values().to_vec(), vec![0, 0, 1, 1, 1]);
+
+        let mut file = Vec::new();
+        res.write_centroids_ipc(&mut file).unwrap();
+        let batches: Vec<_> = FileReader::try_new(Cursor::new(file), None).unwrap().map(|b| b.unwrap()).collect();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(
+            batches[0].schema().fields().iter().map(|f| f.name().as_str()).collect::<Vec<_>>(),
+            vec!["cluster", "frequency", "dim_0", "dim_1"]
+        );
+        let dim_1 = batches[0].column(3).as_any().downcast_ref::<Float32Array>().unwrap();
+        assert_eq!(dim_1.values().to_vec(), vec![0.5, 11.0]);
+
+        let mut file = Vec::new();
+        res.write_assignments_ipc(&mut file).unwrap();
+        assert_eq!(
+            FileReader::try_new(Cursor::new(file), None).unwrap().next().unwrap().unwrap(),
+            assignments
+        );
+    }
+}
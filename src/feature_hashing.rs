@@ -0,0 +1,176 @@
+use crate::memory::Primitive;
+
+/// Feature hashing ("hashing trick") vectorizer, converting records of tokens (e.g. the words of a log line, or
+/// categorical values such as `"country=de"`) into the flat sample layout expected by [`crate::KMeans::new`].
+///
+/// Every token is hashed into one of **n_features** dimensions, and the value of that dimension is incremented by the
+/// token's weight (`1` for plain tokens). With signed hashing, a second hash decides whether the weight is added or
+/// subtracted, so the collisions of different tokens cancel out in expectation instead of accumulating. The hashes are
+/// stable across platforms and program runs (FNV-1a, mixed with the seed), so records vectorized at different times
+/// can be clustered together.
+///
+/// ## Example
+/// ```rust
+/// use kmeans::*;
+///
+/// let records = vec![
+///     vec!["method=GET", "status=200", "path=/index"],
+///     vec!["method=GET", "status=200", "path=/about"],
+///     vec!["method=POST", "status=500", "path=/login"],
+/// ];
+/// let hasher = FeatureHasher::new(64);
+/// let samples: Vec<f64> = hasher.transform(&records);
+/// assert_eq!(samples.len(), records.len() * 64);
+///
+/// let kmean: KMeans<_, 8, _> = KMeans::new(&samples, records.len(), hasher.n_features(), EuclideanDistance);
+/// let result = kmean.kmeans_lloyd(2, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeatureHasher {
+    n_features: usize,
+    signed: bool,
+    seed: u64,
+}
+impl FeatureHasher {
+    /// Create a new vectorizer, with signed hashing and a seed of `0`.
+    ///
+    /// ## Arguments
+    /// - **n_features**: Amount of dimensions of the created samples. Higher values cause fewer collisions between
+    ///   different tokens, at the cost of memory and speed of the clustering.
+    pub fn new(n_features: usize) -> Self {
+        assert!(n_features > 0);
+        Self {
+            n_features,
+            signed: true,
+            seed: 0,
+        }
+    }
+
+    /// Enable or disable signed hashing, which adds or subtracts the weight of every token depending on a second hash.
+    /// Disable this for non-negative samples (e.g. for the [`crate::HistogramDistance`]).
+    ///
+    /// ## Default
+    /// `true`
+    pub fn signed(mut self, signed: bool) -> Self {
+        self.signed = signed;
+        self
+    }
+
+    /// Seed of the hash function. Vectorizers with different seeds map the tokens to different dimensions.
+    ///
+    /// ## Default
+    /// `0`
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Amount of dimensions of the created samples.
+    pub fn n_features(&self) -> usize { self.n_features }
+
+    /// Dimension the given token is hashed into, and the sign its weight is added with (`1` or `-1`).
+    pub fn index(&self, token: &str) -> (usize, i8) {
+        let hash = self.hash(token);
+        let sign = if self.signed && (hash >> 63) == 1 { -1 } else { 1 };
+        ((hash % self.n_features as u64) as usize, sign)
+    }
+
+    /// Add the tokens of one record (each with a weight of `1`) onto **sample**, which has **n_features** dimensions.
+    pub fn hash_into<T: Primitive, S: AsRef<str>>(&self, tokens: impl IntoIterator<Item = S>, sample: &mut [T]) {
+        self.hash_weighted_into(tokens.into_iter().map(|token| (token, T::one())), sample);
+    }
+
+    /// Add the weighted tokens of one record (e.g. `("duration", 0.25)`) onto **sample**, which has **n_features**
+    /// dimensions.
+    pub fn hash_weighted_into<T: Primitive, S: AsRef<str>>(&self, tokens: impl IntoIterator<Item = (S, T)>, sample: &mut [T]) {
+        assert_eq!(sample.len(), self.n_features);
+        tokens.into_iter().for_each(|(token, weight)| {
+            let (idx, sign) = self.index(token.as_ref());
+            match sign {
+                1 => sample[idx] += weight,
+                _ => sample[idx] -= weight,
+            }
+        });
+    }
+
+    /// Vectorize all given records, each of which is a list of tokens (with a weight of `1` each).
+    ///
+    /// ## Returns
+    /// The samples of all records [row-major] = [<sample0>,<sample1>,...], with **n_features** dimensions each.
+    pub fn transform<T: Primitive, R: AsRef<[S]>, S: AsRef<str>>(&self, records: &[R]) -> Vec<T> {
+        let mut samples = vec![T::zero(); records.len() * self.n_features];
+        samples
+            .chunks_exact_mut(self.n_features)
+            .zip(records.iter())
+            .for_each(|(sample, record)| self.hash_into(record.as_ref(), sample));
+        samples
+    }
+
+    /// Vectorize all given records, each of which is a list of weighted tokens.
+    ///
+    /// ## Returns
+    /// The samples of all records [row-major] = [<sample0>,<sample1>,...], with **n_features** dimensions each.
+    pub fn transform_weighted<T: Primitive, R: AsRef<[(S, T)]>, S: AsRef<str>>(&self, records: &[R]) -> Vec<T> {
+        let mut samples = vec![T::zero(); records.len() * self.n_features];
+        samples
+            .chunks_exact_mut(self.n_features)
+            .zip(records.iter())
+            .for_each(|(sample, record)| self.hash_weighted_into(record.as_ref().iter().map(|(s, w)| (s, *w)), sample));
+        samples
+    }
+
+    /// FNV-1a hash of the seed and the token, with a final avalanche step (of SplitMix64), so the low bits (used for
+    /// the dimension) and the highest bit (used for the sign) are independent.
+    fn hash(&self, token: &str) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+        let hash = self
+            .seed
+            .to_le_bytes()
+            .iter()
+            .chain(token.as_bytes())
+            .fold(OFFSET_BASIS, |h, &b| (h ^ b as u64).wrapping_mul(PRIME));
+        let hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        let hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+        hash ^ (hash >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FeatureHasher;
+
+    #[test]
+    fn hashed_records() {
+        let hasher = FeatureHasher::new(1 << 10);
+        let records = vec![vec!["a", "b", "a"], vec![], vec!["c"]];
+        let samples: Vec<f64> = hasher.transform(&records);
+        assert_eq!(samples.len(), 3 * 1024);
+
+        let value = |sample: usize, token: &str| {
+            let (idx, sign) = hasher.index(token);
+            samples[sample * 1024 + idx] * sign as f64
+        };
+        assert_eq!((value(0, "a"), value(0, "b"), value(2, "c")), (2.0, 1.0, 1.0));
+        assert!(samples[1024..2048].iter().all(|&v| v == 0.0));
+        assert_eq!(samples.iter().filter(|&&v| v != 0.0).count(), 3);
+
+        let weighted: Vec<f32> = hasher.transform_weighted(&[vec![("a", 2.0), ("b", 1.0), ("c", 0.0)], vec![], vec![("c", 1.0)]]);
+        assert_eq!(weighted, samples.iter().map(|&v| v as f32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn signs_and_seeds() {
+        let tokens: Vec<String> = (0..1000).map(|i| format!("token{i}")).collect();
+        let hasher = FeatureHasher::new(16);
+        let negative = tokens.iter().filter(|t| hasher.index(t).1 == -1).count();
+        assert!(negative > 400 && negative < 600);
+        assert!(tokens.iter().all(|t| FeatureHasher::new(16).signed(false).index(t).1 == 1));
+
+        // Stable hashes, that depend on the seed
+        assert_eq!(hasher.index("token0"), FeatureHasher::new(16).index("token0"));
+        let reseeded = FeatureHasher::new(16).seed(1);
+        assert!(tokens.iter().any(|t| reseeded.index(t) != hasher.index(t)));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::memory::Primitive;
+
 pub(crate) fn multiple_roundup(val: usize, multiple_of: usize) -> usize {
     if val % multiple_of != 0 {
         val + multiple_of - (val % multiple_of)
@@ -6,6 +8,101 @@ pub(crate) fn multiple_roundup(val: usize, multiple_of: usize) -> usize {
     }
 }
 
+/// Hint the cpu to load the given data into the cache, ahead of its use (no-op on platforms without a stable
+/// prefetch instruction).
+#[inline(always)]
+pub(crate) fn prefetch<T>(data: &[T]) {
+    #[cfg(target_arch = "x86_64")]
+    (0..std::mem::size_of_val(data)).step_by(64).for_each(|offset| unsafe {
+        // SAFETY: prefetching is only a hint, and offset stays within data
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>((data.as_ptr() as *const i8).add(offset));
+    });
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = data;
+}
+
+/// Sum of all **values**, using Kahan's compensated summation. The error of the result is independent of the amount
+/// of values, instead of growing with it (as for naive summation).
+pub(crate) fn kahan_sum<T: Primitive>(values: impl Iterator<Item = T>) -> T {
+    let (mut sum, mut compensation) = (T::zero(), T::zero());
+    values.for_each(|v| {
+        let y = v - compensation;
+        let t = sum + y;
+        compensation = (t - sum) - y;
+        sum = t;
+    });
+    sum
+}
+
+/// Solve the (rectangular) linear assignment problem for the given row-major **cost** matrix, using the
+/// Hungarian algorithm in O(n³).
+/// ## Returns
+/// For each row, the column it was assigned to (**None** for unassigned rows, if there are more rows than columns),
+/// such that the sum of the costs of all assignments is minimal.
+pub(crate) fn hungarian(cost: &[f64], rows: usize, cols: usize) -> Vec<Option<usize>> {
+    assert_eq!(cost.len(), rows * cols);
+    // Pad to a square matrix, with zero-cost dummy rows / columns
+    let n = rows.max(cols);
+    let c = |r: usize, col: usize| if r < rows && col < cols { cost[r * cols + col] } else { 0.0 };
+
+    // Potentials (u, v) and matching (p: column -> row) are 1-based, index 0 is used as sentinel
+    let (mut u, mut v) = (vec![0.0f64; n + 1], vec![0.0f64; n + 1]);
+    let mut p = vec![0usize; n + 1];
+    let mut way = vec![0usize; n + 1];
+    for i in 1..=n {
+        p[0] = i;
+        let mut j0 = 0;
+        let mut minv = vec![f64::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+        loop {
+            used[j0] = true;
+            let (i0, mut delta, mut j1) = (p[j0], f64::INFINITY, 0);
+            for j in 1..=n {
+                if !used[j] {
+                    let cur = c(i0 - 1, j - 1) - u[i0] - v[j];
+                    if cur < minv[j] {
+                        minv[j] = cur;
+                        way[j] = j0;
+                    }
+                    if minv[j] < delta {
+                        delta = minv[j];
+                        j1 = j;
+                    }
+                }
+            }
+            for j in 0..=n {
+                if used[j] {
+                    u[p[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    minv[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if p[j0] == 0 {
+                break;
+            }
+        }
+        loop {
+            let j1 = way[j0];
+            p[j0] = p[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut assignment = vec![None; rows];
+    for j in 1..=n {
+        if p[j] != 0 && p[j] <= rows && j <= cols {
+            assignment[p[j] - 1] = Some(j - 1);
+        }
+    }
+    assignment
+}
+
 #[cfg(test)]
 macro_rules! assert_approx_eq {
     ($left: expr, $right: expr, $tol: expr) => {{
@@ -94,6 +191,28 @@ pub(crate) mod testing {
 
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn hungarian() {
+        let cost = [4.0, 1.0, 3.0, 2.0, 0.0, 5.0, 3.0, 2.0, 2.0];
+        assert_eq!(super::hungarian(&cost, 3, 3), vec![Some(1), Some(0), Some(2)]);
+        // More rows than columns
+        let cost = [1.0, 10.0, 10.0, 1.0, 5.0, 5.0];
+        assert_eq!(super::hungarian(&cost, 3, 2), vec![Some(0), Some(1), None]);
+        // More columns than rows
+        let cost = [5.0, 1.0, 7.0];
+        assert_eq!(super::hungarian(&cost, 1, 3), vec![Some(1)]);
+    }
+
+    #[test]
+    fn kahan_sum() {
+        let values = vec![0.1f32; 1_000_000];
+        let naive: f32 = values.iter().sum();
+        let compensated = super::kahan_sum(values.iter().cloned());
+        assert!((compensated - 100_000.0).abs() < 0.01);
+        assert!((naive - 100_000.0).abs() > 1.0);
+        assert_eq!(super::kahan_sum(std::iter::empty::<f64>()), 0.0);
+    }
+
     #[test]
     fn multiple_roundup() {
         for o in 1..20 {
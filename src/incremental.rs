@@ -0,0 +1,144 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+
+/// Maximum amount of iterations of the local 2-means, used to split the worst cluster
+const SPLIT_MAX_ITER: usize = 100;
+
+/// Split the members of one cluster into two, using a local (sample-weighted) 2-means on them.
+/// The two initial centroids are the member farthest from the cluster's **centroid**, and the member farthest from that one.
+/// ## Returns
+/// The two resulting (padded) centroids
+pub(crate) fn split_cluster<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, centroid: &[T], members: &[usize]) -> StrideBuffer<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let farthest_from = |p: &[T]| {
+        members
+            .iter()
+            .map(|&m| (m, kmean.distance_fn.distance(kmean.p_samples.nth_stride(m), p)))
+            .max_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+            .unwrap()
+            .0
+    };
+    let first = farthest_from(centroid);
+    let second = farthest_from(kmean.p_samples.nth_stride(first));
+
+    let mut split = StrideBuffer::new::<LANES>(2, kmean.sample_dims);
+    split.nth_stride_mut(0).copy_from_slice(kmean.p_samples.nth_stride(first));
+    split.nth_stride_mut(1).copy_from_slice(kmean.p_samples.nth_stride(second));
+
+    let mut sides = vec![usize::MAX; members.len()];
+    for _ in 0..SPLIT_MAX_ITER {
+        let mut changed = false;
+        members.iter().zip(sides.iter_mut()).for_each(|(&m, side)| {
+            let s = kmean.p_samples.nth_stride(m);
+            let new_side =
+                (kmean.distance_fn.distance(s, split.nth_stride(1)) < kmean.distance_fn.distance(s, split.nth_stride(0))) as usize;
+            changed |= *side != new_side;
+            *side = new_side;
+        });
+        if !changed {
+            break;
+        }
+
+        let mut sums = StrideBuffer::<T>::new::<LANES>(2, kmean.sample_dims);
+        let mut weights = [T::zero(); 2];
+        members.iter().zip(sides.iter().cloned()).for_each(|(&m, side)| {
+            let w = kmean.sample_weight(m);
+            weights[side] += w;
+            sums.nth_stride_mut(side)
+                .iter_mut()
+                .zip(kmean.p_samples.nth_stride(m))
+                .for_each(|(c, &v)| *c += v * w);
+        });
+        // Keep the previous centroid for an empty side (only happens for duplicate samples)
+        split
+            .chunks_exact_stride_mut()
+            .zip(sums.chunks_exact_stride())
+            .zip(weights)
+            .filter(|(_, w)| *w > T::zero())
+            .for_each(|((c, s), w)| {
+                c.iter_mut().zip(s).for_each(|(c, &s)| *c = s / w);
+            });
+    }
+    split
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>, max_iter: usize, config: &KMeansConfig<'_, T>,
+) -> KMeansState<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    assert!(state.k < kmean.sample_cnt);
+
+    // Worst cluster: the one with the highest (weighted) sum of distances, that has at least two members to split
+    let mut members = vec![Vec::new(); state.k];
+    let mut sse = vec![T::zero(); state.k];
+    state
+        .assignments
+        .iter()
+        .zip(state.centroid_distances.iter())
+        .enumerate()
+        .for_each(|(idx, (&c, &d))| {
+            members[c].push(idx);
+            sse[c] += d * kmean.sample_weight(idx);
+        });
+    let worst = (0..state.k)
+        .filter(|&c| members[c].len() > 1)
+        .max_by(|&a, &b| sse[a].partial_cmp(&sse[b]).unwrap())
+        .unwrap();
+
+    // Replace the worst cluster by the first half of its split, and append the second half as new cluster
+    let split = split_cluster(kmean, state.centroids.nth_stride(worst), &members[worst]);
+    let mut centroids = state.centroids.to_vec();
+    let dims = kmean.sample_dims;
+    centroids[worst * dims..(worst + 1) * dims].copy_from_slice(&split.nth_stride(0)[..dims]);
+    centroids.extend_from_slice(&split.nth_stride(1)[..dims]);
+
+    kmean.kmeans_lloyd(state.k + 1, max_iter.max(1), KMeans::init_precomputed(centroids), config)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn add_cluster() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0, 20.0, 21.0, 22.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::default();
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![1.0, 16.0]), &conf);
+        assert_eq!(res.centroids.to_vec(), vec![1.0, 16.0]);
+
+        let grown = kmean.add_cluster(&res, 10, &conf);
+        assert_eq!(grown.k, 3);
+        assert_eq!(grown.centroids.to_vec(), vec![1.0, 21.0, 11.0]);
+        assert_eq!(grown.assignments, vec![0, 0, 0, 2, 2, 2, 1, 1, 1]);
+        assert_eq!(grown.distsum, 6.0);
+    }
+
+    #[test]
+    fn add_cluster_weighted() {
+        // Unweighted, the wide left cluster has the higher sum of distances. Weighted, the narrow right one is split
+        let samples = vec![0.0f64, 4.0, 20.0, 21.0, 22.0];
+        let weights = vec![1.0, 1.0, 10.0, 10.0, 10.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance).with_sample_weights(&weights);
+        let conf = KMeansConfig::default();
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![2.0, 21.0]), &conf);
+
+        let grown = kmean.add_cluster(&res, 10, &conf);
+        assert_eq!(grown.centroids.to_vec(), vec![2.0, 21.5, 20.0]);
+        assert_eq!(grown.assignments, vec![0, 0, 2, 1, 1]);
+        assert_eq!(grown.distsum, 13.0);
+    }
+}
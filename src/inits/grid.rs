@@ -0,0 +1,138 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rand::prelude::*;
+use std::collections::BTreeMap;
+use std::ops::DerefMut;
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>, cells_per_dim: usize,
+) where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    assert!(cells_per_dim > 0);
+    let dims = kmean.sample_dims;
+
+    // Bounding box of all samples
+    let mut min = vec![T::infinity(); dims];
+    let mut max = vec![T::neg_infinity(); dims];
+    kmean.p_samples.chunks_exact_stride().for_each(|s| {
+        s[..dims]
+            .iter()
+            .zip(min.iter_mut())
+            .zip(max.iter_mut())
+            .for_each(|((&v, min), max)| {
+                *min = min.min(v);
+                *max = max.max(v);
+            });
+    });
+    let cells = T::from(cells_per_dim).unwrap();
+    let scale: Vec<T> = min
+        .iter()
+        .zip(max.iter())
+        .map(|(&min, &max)| if max > min { cells / (max - min) } else { T::zero() })
+        .collect();
+
+    // Histogram of the samples over the grid cells, with the (weighted) amount and sum of the samples in each cell
+    let mut histogram: BTreeMap<Vec<usize>, (T, Vec<T>)> = BTreeMap::new();
+    kmean.p_samples.chunks_exact_stride().enumerate().for_each(|(sample_id, s)| {
+        let cell = s[..dims]
+            .iter()
+            .zip(min.iter().zip(scale.iter()))
+            .map(|(&v, (&min, &scale))| ((v - min) * scale).to_usize().unwrap().min(cells_per_dim - 1))
+            .collect();
+        let weight = kmean.sample_weight(sample_id);
+        let (cnt, sum) = histogram.entry(cell).or_insert_with(|| (T::zero(), vec![T::zero(); dims]));
+        *cnt += weight;
+        sum.iter_mut().zip(s.iter().cloned()).for_each(|(sum, v)| *sum += v * weight);
+    });
+
+    // Seed the centroids in the means of the densest cells
+    let mut densest: Vec<(T, Vec<T>)> = histogram.into_values().collect();
+    densest.sort_by(|(cnt0, _), (cnt1, _)| cnt1.partial_cmp(cnt0).unwrap());
+    densest.iter().take(state.k).enumerate().for_each(|(ci, (cnt, sum))| {
+        let cnt_factor = T::one() / *cnt;
+        state.centroids.set_nth_from_iter(ci, sum.iter().map(|&v| v * cnt_factor));
+    });
+
+    // Less occupied cells than requested clusters: Fill the remaining centroids with random samples (with a
+    // probability proportional to their weight, if given)
+    let seeded = densest.len().min(state.k);
+    let chosen: Vec<&[T]> = match &kmean.sample_weights {
+        Some(weights) => (0..kmean.sample_cnt)
+            .collect::<Vec<_>>()
+            .choose_multiple_weighted(config.rnd.borrow_mut().deref_mut(), state.k - seeded, |&i| {
+                weights[i].to_f64().unwrap()
+            })
+            .unwrap()
+            .map(|&i| kmean.p_samples.nth_stride(i))
+            .collect(),
+        None => kmean
+            .p_samples
+            .chunks_exact_stride()
+            .choose_multiple(config.rnd.borrow_mut().deref_mut(), state.k - seeded),
+    };
+    chosen
+        .into_iter()
+        .enumerate()
+        .for_each(|(ci, c)| state.centroids.set_nth_from_iter(seeded + ci, c.iter().cloned()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EuclideanDistance;
+
+    #[test]
+    fn seeds_in_densest_cells() {
+        // Three dense groups, and sparse samples spread over the whole area
+        let mut rnd = StdRng::seed_from_u64(1337);
+        let mut samples = Vec::new();
+        for (cnt, (x, y)) in [(100, (1.0, 1.0)), (80, (8.0, 2.0)), (60, (5.0, 8.0))] {
+            (0..cnt).for_each(|_| samples.extend([x + rnd.gen_range(-0.2..0.2), y + rnd.gen_range(-0.2..0.2)]));
+        }
+        (0..30).for_each(|_| samples.extend([rnd.gen_range(0.0..10.0), rnd.gen_range(0.0..10.0f64)]));
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len() / 2, 2, EuclideanDistance);
+        let conf = KMeansConfig::default();
+
+        let mut state = KMeansState::new::<8>(kmean.sample_cnt, kmean.sample_dims, 3);
+        calculate(&kmean, &mut state, &conf, 10);
+        let centroids: Vec<Vec<f64>> = state.centroids.chunks_exact_stride().map(|c| c[..2].to_vec()).collect();
+        centroids
+            .iter()
+            .zip([[1.0, 1.0], [8.0, 2.0], [5.0, 8.0]])
+            .for_each(|(c, expected)| {
+                assert!(
+                    (c[0] - expected[0]).abs() < 0.5 && (c[1] - expected[1]).abs() < 0.5,
+                    "{c:?} != {expected:?}"
+                );
+            });
+
+        // More clusters than occupied cells
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples[..200].to_vec(), 100, 2, EuclideanDistance);
+        let mut state = KMeansState::new::<8>(kmean.sample_cnt, kmean.sample_dims, 5);
+        calculate(&kmean, &mut state, &conf, 1);
+        assert_eq!(state.centroids.centroid_cnt, 5);
+        assert!(state
+            .centroids
+            .chunks_exact_stride()
+            .all(|c| (c[0] - 1.0).abs() < 0.5 && (c[1] - 1.0).abs() < 0.5));
+    }
+
+    #[test]
+    fn weighted_cells() {
+        // The sparse cell outweighs the dense one, and its centroid is the weighted mean of its samples
+        let samples = vec![0.0f64, 0.1, 0.2, 0.3, 9.0, 10.0];
+        let weights = vec![1.0, 1.0, 1.0, 1.0, 1.0, 9.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let kmean = kmean.with_sample_weights(&weights);
+        let mut state = KMeansState::new::<8>(kmean.sample_cnt, kmean.sample_dims, 1);
+        calculate(&kmean, &mut state, &KMeansConfig::default(), 2);
+        assert!((state.centroids[0][0] - 9.9).abs() < 1e-12);
+    }
+}
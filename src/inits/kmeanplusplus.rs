@@ -1,10 +1,11 @@
 use crate::api::DistanceFunction;
 use crate::memory::*;
+use crate::simd::{self, LaneCount, Simd, SupportedLaneCount};
 use crate::{KMeans, KMeansConfig, KMeansState};
 use rand::distributions::weighted::WeightedIndex;
 use rand::prelude::*;
+use rayon::prelude::*;
 use std::ops::DerefMut;
-use std::simd::{LaneCount, Simd, SupportedLaneCount};
 
 #[inline(always)]
 pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>)
@@ -15,72 +16,168 @@ where
     D: DistanceFunction<T, LANES>,
 {
     {
-        // Randomly select first centroid
-        let first_idx = config.rnd.borrow_mut().gen_range(0..kmean.sample_cnt);
+        // Randomly select first centroid (with a probability proportional to the sample weights, if given)
+        let first_idx = match &kmean.sample_weights {
+            Some(weights) => WeightedIndex::new(weights).unwrap().sample(config.rnd.borrow_mut().deref_mut()),
+            None => config.rnd.borrow_mut().gen_range(0..kmean.sample_cnt),
+        };
         state.centroids.set_nth_from_iter(0, kmean.p_samples[first_idx].iter().cloned());
     }
-    for k in 1..state.k {
+    complete(kmean, state, config, 1);
+}
+
+/// Select the centroids **seeded**..k using K-Means++, given the already initialized centroids 0..**seeded**.
+/// With the greedy variant (see [`crate::KMeansConfigBuilder::greedy_kmeanplusplus`]), multiple candidates are drawn
+/// per centroid, and the one that reduces the (weighted) sum of distances the most is chosen.
+pub(crate) fn complete<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>, seeded: usize,
+) where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let local_trials = match config.greedy_kmeanplusplus {
+        None => 1,
+        Some(0) => 2 + (state.k as f64).ln() as usize,
+        Some(trials) => trials,
+    };
+    for k in seeded..state.k {
         // For each following centroid...
         // Calculate distances & update cluster-assignments
         kmean.update_cluster_assignments(state, Some(k));
 
         //NOTE: following two calculations are not what Matlab lists on documentation, but what Matlab actually implemented...
-        // Calculate sum of distances per centroid
-        let distsum = state.centroid_distances.iter().cloned().sum();
+        // Calculate (weighted) distances and their sum
+        let distances: Vec<T> = match &kmean.sample_weights {
+            Some(weights) => state.centroid_distances.iter().zip(weights.iter()).map(|(&d, &w)| d * w).collect(),
+            None => state.centroid_distances.clone(),
+        };
+        let distsum: T = distances.iter().cloned().sum();
 
         // Calculate probabilities for each of the samples, to be the new centroid
-        let centroid_probabilities: Vec<T> = state.centroid_distances.iter().cloned().map(|d| d / distsum).collect();
+        let centroid_probabilities: Vec<T> = distances.iter().map(|&d| d / distsum).collect();
         // Use rand's WeightedIndex to randomly draw a centroid, while respecting their probabilities
         let centroid_index = WeightedIndex::new(centroid_probabilities).unwrap();
-        let sampled_centroid_id = centroid_index.sample(config.rnd.borrow_mut().deref_mut());
+        let candidates: Vec<usize> = (0..local_trials)
+            .map(|_| centroid_index.sample(config.rnd.borrow_mut().deref_mut()))
+            .collect();
+        let sampled_centroid_id = match candidates.len() {
+            1 => candidates[0],
+            _ => {
+                candidates
+                    .into_iter()
+                    .map(|candidate| (candidate, potential(kmean, &distances, candidate)))
+                    .min_by(|(_, p0), (_, p1)| p0.partial_cmp(p1).unwrap())
+                    .unwrap()
+                    .0
+            },
+        };
         state
             .centroids
             .set_nth_from_iter(k, kmean.p_samples[sampled_centroid_id].iter().cloned());
     }
 }
 
+/// (Weighted) sum of distances of all samples to their nearest centroid, if the **candidate** sample was added as
+/// centroid, given the (weighted) **distances** to the nearest current centroid.
+fn potential<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, distances: &[T], candidate: usize) -> T
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let c = kmean.p_samples.nth_stride(candidate);
+    kmean
+        .p_samples
+        .bfr
+        .par_chunks_exact(kmean.p_samples.stride)
+        .zip(distances.par_iter())
+        .enumerate()
+        .chunks(simd::PACKET_SIZE)
+        .map(|packet| {
+            simd::dispatch(|| {
+                packet
+                    .into_iter()
+                    .map(|(sample_id, (s, &d))| d.min(kmean.distance_fn.distance(s, c) * kmean.sample_weight(sample_id)))
+                    .sum::<T>()
+            })
+        })
+        .sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::EuclideanDistance;
-    use test::Bencher;
 
-    #[bench]
-    fn init_kmeanplusplus_f32x16(b: &mut Bencher) { init_kmeanplusplus::<f32, 16>(b); }
-    #[bench]
-    fn init_kmeanplusplus_f32x8(b: &mut Bencher) { init_kmeanplusplus::<f32, 8>(b); }
-    #[bench]
-    fn init_kmeanplusplus_f32x4(b: &mut Bencher) { init_kmeanplusplus::<f32, 4>(b); }
-    #[bench]
-    fn init_kmeanplusplus_f32x2(b: &mut Bencher) { init_kmeanplusplus::<f32, 2>(b); }
+    #[test]
+    fn greedy_kmeanplusplus() {
+        // Three tight blobs, which the greedy variant always covers with one centroid each
+        let samples: Vec<f64> = (0..90).map(|i| (i / 30) as f64 * 100.0 + (i % 30) as f64 * 0.01).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        for seed in 0..10 {
+            let mut state = KMeansState::new::<8>(samples.len(), 1, 3);
+            let conf = KMeansConfig::build().seed(seed).greedy_kmeanplusplus(0).build();
+            KMeans::init_kmeanplusplus(&kmean, &mut state, &conf);
+            let mut blobs: Vec<usize> = state.centroids.iter().map(|c| (c[0] / 100.0).round() as usize).collect();
+            blobs.sort();
+            assert_eq!(blobs, vec![0, 1, 2]);
+        }
 
-    #[bench]
-    fn init_kmeanplusplus_f64x8(b: &mut Bencher) { init_kmeanplusplus::<f64, 8>(b); }
-    #[bench]
-    fn init_kmeanplusplus_f64x4(b: &mut Bencher) { init_kmeanplusplus::<f64, 4>(b); }
-    #[bench]
-    fn init_kmeanplusplus_f64x2(b: &mut Bencher) { init_kmeanplusplus::<f64, 2>(b); }
+        // A single candidate per centroid is the plain k-means++
+        let (mut plain, mut single) = (
+            KMeansState::new::<8>(samples.len(), 1, 3),
+            KMeansState::new::<8>(samples.len(), 1, 3),
+        );
+        KMeans::init_kmeanplusplus(&kmean, &mut plain, &KMeansConfig::build().seed(1).build());
+        KMeans::init_kmeanplusplus(&kmean, &mut single, &KMeansConfig::build().seed(1).greedy_kmeanplusplus(1).build());
+        assert_eq!(plain.centroids.to_vec(), single.centroids.to_vec());
+    }
 
-    fn init_kmeanplusplus<T: Primitive, const LANES: usize>(b: &mut Bencher)
-    where
-        T: Primitive,
-        LaneCount<LANES>: SupportedLaneCount,
-        Simd<T, LANES>: SupportedSimdArray<T, LANES>,
-    {
-        let sample_cnt = 20000;
-        let sample_dims = 16;
-        let k = 32;
+    #[cfg(not(feature = "stable"))]
+    mod benches {
+        use super::*;
+        use test::Bencher;
 
-        let mut rnd = rand::rngs::StdRng::seed_from_u64(1337);
-        let mut samples = vec![T::zero(); sample_cnt * sample_dims];
-        samples.iter_mut().for_each(|v| *v = rnd.gen_range(T::zero()..T::one()));
-        let kmean: KMeans<_, LANES, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
-        let mut state = KMeansState::new::<LANES>(sample_cnt, sample_dims, k);
-        let conf = KMeansConfig::build().random_generator(rnd).build();
+        #[bench]
+        fn init_kmeanplusplus_f32x16(b: &mut Bencher) { init_kmeanplusplus::<f32, 16>(b); }
+        #[bench]
+        fn init_kmeanplusplus_f32x8(b: &mut Bencher) { init_kmeanplusplus::<f32, 8>(b); }
+        #[bench]
+        fn init_kmeanplusplus_f32x4(b: &mut Bencher) { init_kmeanplusplus::<f32, 4>(b); }
+        #[bench]
+        fn init_kmeanplusplus_f32x2(b: &mut Bencher) { init_kmeanplusplus::<f32, 2>(b); }
 
-        b.iter(|| {
-            KMeans::init_kmeanplusplus(&kmean, &mut state, &conf);
-            state.distsum
-        });
+        #[bench]
+        fn init_kmeanplusplus_f64x8(b: &mut Bencher) { init_kmeanplusplus::<f64, 8>(b); }
+        #[bench]
+        fn init_kmeanplusplus_f64x4(b: &mut Bencher) { init_kmeanplusplus::<f64, 4>(b); }
+        #[bench]
+        fn init_kmeanplusplus_f64x2(b: &mut Bencher) { init_kmeanplusplus::<f64, 2>(b); }
+
+        fn init_kmeanplusplus<T: Primitive, const LANES: usize>(b: &mut Bencher)
+        where
+            T: Primitive,
+            LaneCount<LANES>: SupportedLaneCount,
+            Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+        {
+            let sample_cnt = 20000;
+            let sample_dims = 16;
+            let k = 32;
+
+            let mut rnd = rand::rngs::StdRng::seed_from_u64(1337);
+            let mut samples = vec![T::zero(); sample_cnt * sample_dims];
+            samples.iter_mut().for_each(|v| *v = rnd.gen_range(T::zero()..T::one()));
+            let kmean: KMeans<_, LANES, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+            let mut state = KMeansState::new::<LANES>(sample_cnt, sample_dims, k);
+            let conf = KMeansConfig::build().random_generator(rnd).build();
+
+            b.iter(|| {
+                KMeans::init_kmeanplusplus(&kmean, &mut state, &conf);
+                state.distsum
+            });
+        }
     }
 }
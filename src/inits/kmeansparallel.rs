@@ -0,0 +1,184 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rand::distributions::weighted::WeightedIndex;
+use rand::prelude::*;
+use rayon::prelude::*;
+use std::mem::size_of_val;
+use std::ops::DerefMut;
+
+/// Amount of samples of the oversampling step, that share one random generator. This is fixed (instead of depending
+/// on the amount of threads), so the selected candidates only depend on the seed of the [`KMeansConfig`].
+const SAMPLING_BLOCK: usize = 4096;
+
+/// Lower the distance of every sample to its nearest candidate, by the distances to the **new** candidates.
+fn update_nearest<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, nearest: &mut [T], new: &[usize])
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    kmean
+        .p_samples
+        .bfr
+        .par_chunks_exact(kmean.p_samples.stride)
+        .zip(nearest.par_iter_mut())
+        .for_each(|(s, nearest)| {
+            new.iter().for_each(|&c| {
+                *nearest = nearest.min(kmean.distance_fn.distance(s, kmean.p_samples.nth_stride(c)));
+            });
+        });
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>, rounds: usize, oversampling: T,
+) where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let k = state.k;
+    let weights = kmean.sample_weights.as_deref();
+
+    // Randomly select the first candidate (with a probability proportional to the sample weights, if given)
+    let first_idx = match weights {
+        Some(weights) => WeightedIndex::new(weights).unwrap().sample(config.rnd.borrow_mut().deref_mut()),
+        None => config.rnd.borrow_mut().gen_range(0..kmean.sample_cnt),
+    };
+    let mut candidates = vec![first_idx];
+    let mut nearest = vec![T::infinity(); kmean.sample_cnt];
+    update_nearest(kmean, &mut nearest, &candidates);
+
+    // Oversampling: in each round, every sample independently becomes a candidate with a probability proportional to
+    // its (weighted) distance to the nearest candidate, so that oversampling * k candidates are selected per round
+    let expected_per_round = oversampling * T::from(k).unwrap();
+    for _ in 0..rounds {
+        let cost = config.distsum(&nearest, weights);
+        if cost <= T::zero() {
+            break;
+        }
+        let seed: u64 = config.rnd.borrow_mut().gen();
+        let selected: Vec<usize> = nearest
+            .par_chunks(SAMPLING_BLOCK)
+            .enumerate()
+            .flat_map_iter(|(block, distances)| {
+                let mut rnd = StdRng::seed_from_u64(seed.wrapping_add(block as u64));
+                let offset = block * SAMPLING_BLOCK;
+                distances
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, &d)| {
+                        let probability = expected_per_round * d * kmean.sample_weight(offset + i) / cost;
+                        (rnd.gen::<f64>() < probability.to_f64().unwrap()).then_some(offset + i)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        update_nearest(kmean, &mut nearest, &selected);
+        candidates.extend(selected);
+    }
+
+    // Weight every candidate with the (weighted) amount of samples, to which it is the nearest candidate
+    let nearest_candidate: Vec<usize> = kmean
+        .p_samples
+        .bfr
+        .par_chunks_exact(kmean.p_samples.stride)
+        .map(|s| {
+            candidates
+                .iter()
+                .map(|&c| kmean.distance_fn.distance(s, kmean.p_samples.nth_stride(c)))
+                .enumerate()
+                .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+                .unwrap()
+                .0
+        })
+        .collect();
+    let mut candidate_weights = vec![T::zero(); candidates.len()];
+    nearest_candidate
+        .iter()
+        .enumerate()
+        .for_each(|(sample_id, &c)| candidate_weights[c] += kmean.sample_weight(sample_id));
+    state.memory_usage.record_temporaries(
+        size_of_val(nearest.as_slice())
+            + size_of_val(candidates.as_slice())
+            + size_of_val(nearest_candidate.as_slice())
+            + 2 * size_of_val(candidate_weights.as_slice()),
+    );
+
+    // Too few candidates: take all of them, and select the remaining centroids using K-Means++
+    if candidates.len() <= k {
+        candidates.iter().enumerate().for_each(|(ci, &c)| {
+            state.centroids.set_nth_from_iter(ci, kmean.p_samples[c].iter().cloned());
+        });
+        if candidates.len() < k {
+            crate::inits::kmeanplusplus::complete(kmean, state, config, candidates.len());
+        }
+        return;
+    }
+
+    // Recluster the weighted candidates into k centroids, using K-Means++
+    let mut candidate_distances = vec![T::infinity(); candidates.len()];
+    let mut rnd = config.rnd.borrow_mut();
+    let mut selected = WeightedIndex::new(&candidate_weights).unwrap().sample(rnd.deref_mut());
+    for ci in 0..k {
+        let centroid = kmean.p_samples.nth_stride(candidates[selected]);
+        state
+            .centroids
+            .set_nth_from_iter(ci, kmean.p_samples[candidates[selected]].iter().cloned());
+        candidate_distances
+            .par_iter_mut()
+            .zip(candidates.par_iter())
+            .for_each(|(d, &c)| *d = d.min(kmean.distance_fn.distance(kmean.p_samples.nth_stride(c), centroid)));
+        let probabilities = candidate_distances.iter().zip(candidate_weights.iter()).map(|(&d, &w)| d * w);
+        // If all remaining candidates coincide with a centroid, duplicates are unavoidable
+        selected = match WeightedIndex::new(probabilities) {
+            Ok(distribution) => distribution.sample(rnd.deref_mut()),
+            Err(_) => WeightedIndex::new(&candidate_weights).unwrap().sample(rnd.deref_mut()),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig, KMeansState};
+    use rand::prelude::*;
+
+    #[test]
+    fn kmeans_parallel_finds_separated_clusters() {
+        let mut rnd = StdRng::seed_from_u64(5);
+        let (sample_cnt, sample_dims, k) = (20000, 4, 25);
+        let centers: Vec<f64> = (0..k * sample_dims).map(|_| rnd.gen_range(-1000.0..1000.0)).collect();
+        let samples: Vec<f64> = (0..sample_cnt * sample_dims)
+            .map(|i| centers[((i / sample_dims) % k) * sample_dims + i % sample_dims] + rnd.gen_range(-1.0..1.0))
+            .collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let conf = KMeansConfig::build().random_generator(rnd).build();
+
+        // Every cluster gets exactly one initial centroid
+        let mut state = KMeansState::new::<8>(sample_cnt, sample_dims, k);
+        KMeans::init_kmeans_parallel(5, 2.0)(&kmean, &mut state, &conf);
+        kmean.update_cluster_assignments(&mut state, None);
+        let mut clusters: Vec<usize> = (0..k).map(|c| state.assignments[c]).collect();
+        clusters.sort();
+        clusters.dedup();
+        assert_eq!(clusters.len(), k);
+        assert!(state.centroid_distances.iter().all(|&d| d <= 4.0 * sample_dims as f64));
+    }
+
+    #[test]
+    fn kmeans_parallel_without_rounds() {
+        // Without oversampling rounds, only one candidate exists, so all other centroids are selected using K-Means++
+        let samples: Vec<f64> = (0..100).map(|i| (i % 4) as f64 * 10.0).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::build().random_generator(StdRng::seed_from_u64(1)).build();
+        let mut state = KMeansState::new::<8>(samples.len(), 1, 4);
+        KMeans::init_kmeans_parallel(0, 2.0)(&kmean, &mut state, &conf);
+        let mut centroids = state.centroids.to_vec();
+        centroids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(centroids, vec![0.0, 10.0, 20.0, 30.0]);
+    }
+}
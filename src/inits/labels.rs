@@ -0,0 +1,83 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>, labels: &[Option<usize>],
+) where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    assert_eq!(labels.len(), kmean.sample_cnt);
+
+    // (Weighted) class means of all labeled samples
+    let mut sums = StrideBuffer::<T>::new::<LANES>(state.k, kmean.sample_dims);
+    let mut counts = vec![0usize; state.k];
+    let mut class_weights = vec![T::zero(); state.k];
+    kmean
+        .p_samples
+        .chunks_exact_stride()
+        .zip(labels.iter())
+        .enumerate()
+        .filter_map(|(sample_id, (s, label))| label.map(|l| (sample_id, s, l)))
+        .for_each(|(sample_id, s, label)| {
+            assert!(label < state.k, "label {} out of range for k = {}", label, state.k);
+            let weight = kmean.sample_weight(sample_id);
+            counts[label] += 1;
+            class_weights[label] += weight;
+            sums.nth_stride_mut(label)
+                .iter_mut()
+                .zip(s)
+                .for_each(|(sum, &v)| *sum += v * weight);
+        });
+
+    // Labeled classes are seeded first, so K-Means++ can select the remaining centroids in the order 0..k afterwards
+    let (labeled, unlabeled): (Vec<usize>, Vec<usize>) = (0..state.k).partition(|&c| counts[c] > 0);
+    if labeled.is_empty() {
+        return crate::inits::kmeanplusplus::calculate(kmean, state, config);
+    }
+    labeled.iter().enumerate().for_each(|(ci, &c)| {
+        let cnt_factor = T::one() / class_weights[c];
+        state
+            .centroids
+            .set_nth_from_iter(ci, sums.nth_stride(c).iter().map(|&v| v * cnt_factor));
+    });
+    crate::inits::kmeanplusplus::complete(kmean, state, config, labeled.len());
+
+    // Move every centroid to its final position: class c becomes cluster c
+    let seeded = state.centroids.clone();
+    labeled.iter().chain(unlabeled.iter()).enumerate().for_each(|(ci, &c)| {
+        state.centroids.nth_stride_mut(c).copy_from_slice(seeded.nth_stride(ci));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EuclideanDistance;
+
+    #[test]
+    fn seed_from_labels() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0, 100.0, 101.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::default();
+
+        // Class 2 has labeled samples, class 0 only a partial label, class 1 none at all
+        let labels = vec![Some(0), None, None, None, None, None, Some(2), Some(2)];
+        let mut state = KMeansState::new::<8>(kmean.sample_cnt, kmean.sample_dims, 3);
+        calculate(&kmean, &mut state, &conf, &labels);
+        let centroids = state.centroids.to_vec();
+        assert_eq!(centroids[0], 0.0);
+        assert_eq!(centroids[2], 100.5);
+        // K-Means++ selects a sample for the unlabeled class
+        assert!(samples.contains(&centroids[1]));
+
+        let res = kmean.kmeans_lloyd(3, 100, KMeans::init_labels(labels), &conf);
+        assert_eq!(res.assignments[0], 0);
+        assert_eq!(res.assignments[7], 2);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rand::distributions::weighted::WeightedIndex;
+use rand::prelude::*;
+use rayon::prelude::*;
+use std::ops::DerefMut;
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>)
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    // Randomly select first centroid (with a probability proportional to the sample weights, if given)
+    let mut chosen = match &kmean.sample_weights {
+        Some(weights) => WeightedIndex::new(weights).unwrap().sample(config.rnd.borrow_mut().deref_mut()),
+        None => config.rnd.borrow_mut().gen_range(0..kmean.sample_cnt),
+    };
+
+    // Distance of every sample to its nearest chosen centroid, updated with every new centroid
+    let mut nearest = vec![T::infinity(); kmean.sample_cnt];
+    state.memory_usage.record_temporaries(std::mem::size_of_val(nearest.as_slice()));
+    for k in 0..state.k {
+        let c = kmean.p_samples.nth_stride(chosen);
+        state.centroids.set_nth_from_iter(k, kmean.p_samples[chosen].iter().cloned());
+        if k + 1 == state.k {
+            break;
+        }
+        // The farthest sample (the first one, on ties) becomes the next centroid
+        chosen = kmean
+            .p_samples
+            .bfr
+            .par_chunks_exact(kmean.p_samples.stride)
+            .zip(nearest.par_iter_mut())
+            .enumerate()
+            .map(|(sample_id, (s, nearest))| {
+                *nearest = nearest.min(kmean.distance_fn.distance(s, c));
+                (sample_id, *nearest)
+            })
+            .reduce(
+                || (usize::MAX, T::neg_infinity()),
+                |(i0, d0), (i1, d1)| match d1 > d0 || (d1 == d0 && i1 < i0) {
+                    true => (i1, d1),
+                    false => (i0, d0),
+                },
+            )
+            .0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EuclideanDistance;
+
+    #[test]
+    fn farthest_first() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0, 20.0, 21.0, 22.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        for seed in 0..10 {
+            let mut state = KMeansState::new::<8>(samples.len(), 1, 3);
+            calculate(&kmean, &mut state, &KMeansConfig::build().seed(seed).build());
+            let mut groups: Vec<usize> = state.centroids.iter().map(|c| (c[0] / 10.0).round() as usize).collect();
+            groups.sort();
+            assert_eq!(groups, vec![0, 1, 2]);
+        }
+
+        // Deterministic, given the first centroid (which is drawn proportional to the sample weights)
+        let mut weights = vec![1e-12; samples.len()];
+        weights[4] = 1.0;
+        let kmean = kmean.with_sample_weights(&weights);
+        let mut state = KMeansState::new::<8>(samples.len(), 1, 3);
+        calculate(&kmean, &mut state, &KMeansConfig::default());
+        assert_eq!(state.centroids.to_vec(), vec![11.0, 0.0, 22.0]);
+    }
+}
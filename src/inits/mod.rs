@@ -1,4 +1,8 @@
+pub(crate) mod grid;
 pub(crate) mod kmeanplusplus;
+pub(crate) mod kmeansparallel;
+pub(crate) mod labels;
+pub(crate) mod maximin;
 pub(crate) mod precomputed;
 pub(crate) mod randompartition;
 pub(crate) mod randomsample;
@@ -1,7 +1,7 @@
 use crate::api::DistanceFunction;
 use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
 use crate::{KMeans, KMeansConfig, KMeansState};
-use std::simd::{LaneCount, Simd, SupportedLaneCount};
 
 #[inline(always)]
 pub fn calculate<T, const LANES: usize, D>(
@@ -1,8 +1,8 @@
 use crate::api::DistanceFunction;
 use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
 use crate::{KMeans, KMeansConfig, KMeansState};
 use rand::prelude::*;
-use std::simd::{LaneCount, Simd, SupportedLaneCount};
 
 #[inline(always)]
 pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>)
@@ -15,20 +15,25 @@ where
     let (assignments, centroids, centroid_frequency, k) =
         (&mut state.assignments, &mut state.centroids, &mut state.centroid_frequency, state.k);
 
-    assignments.iter_mut().for_each(|a| {
+    // Total weight of every partition (its amount of samples, without sample weights)
+    let mut partition_weights = vec![T::zero(); k];
+    assignments.iter_mut().enumerate().for_each(|(sample_id, a)| {
         *a = config.rnd.borrow_mut().gen_range(0..k);
         centroid_frequency[*a] += 1;
+        partition_weights[*a] += kmean.sample_weight(sample_id);
     });
     kmean
         .p_samples
         .chunks_exact_stride()
         .zip(assignments.iter().cloned())
-        .for_each(|(sample, assignment)| {
+        .enumerate()
+        .for_each(|(sample_id, (sample, assignment))| {
+            let (weight, partition_weight) = (kmean.sample_weight(sample_id), partition_weights[assignment]);
             centroids
                 .bfr
                 .iter_mut()
                 .skip(centroids.stride * assignment)
                 .zip(sample.iter().cloned())
-                .for_each(|(cv, sv)| *cv += sv / T::from(centroid_frequency[assignment]).unwrap());
+                .for_each(|(cv, sv)| *cv += sv * weight / partition_weight);
         });
 }
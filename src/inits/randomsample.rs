@@ -1,9 +1,9 @@
 use crate::api::DistanceFunction;
 use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
 use crate::{KMeans, KMeansConfig, KMeansState};
 use rand::prelude::*;
 use std::ops::DerefMut;
-use std::simd::{LaneCount, Simd, SupportedLaneCount};
 
 #[inline(always)]
 pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>)
@@ -13,15 +13,21 @@ where
     Simd<T, LANES>: SupportedSimdArray<T, LANES>,
     D: DistanceFunction<T, LANES>,
 {
-    kmean
-        .p_samples
-        .chunks_exact_stride()
-        .choose_multiple(config.rnd.borrow_mut().deref_mut(), state.k)
-        .iter()
-        .cloned()
-        .enumerate()
-        .for_each(|(ci, c)| {
-            // Copy randomly chosen centroids into state.centroids
-            state.centroids.set_nth_from_iter(ci, c.iter().cloned());
-        });
+    let chosen: Vec<&[T]> = match &kmean.sample_weights {
+        // Distinct samples, with a probability proportional to their weight
+        Some(weights) => (0..kmean.sample_cnt)
+            .collect::<Vec<_>>()
+            .choose_multiple_weighted(config.rnd.borrow_mut().deref_mut(), state.k, |&i| weights[i].to_f64().unwrap())
+            .unwrap()
+            .map(|&i| kmean.p_samples.nth_stride(i))
+            .collect(),
+        None => kmean
+            .p_samples
+            .chunks_exact_stride()
+            .choose_multiple(config.rnd.borrow_mut().deref_mut(), state.k),
+    };
+    chosen.iter().cloned().enumerate().for_each(|(ci, c)| {
+        // Copy randomly chosen centroids into state.centroids
+        state.centroids.set_nth_from_iter(ci, c.iter().cloned());
+    });
 }
@@ -0,0 +1,139 @@
+use crate::memory::*;
+
+/// Enum with possible learning-rate schedules for online centroid updates (as done by [`crate::KMeans::kmeans_minibatch`]).
+/// A schedule determines how far a centroid is moved towards a sample that was assigned to it: `c = (1 - rate) * c + rate * s`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LearningSchedule<T: Primitive> {
+    /// The classic mini-batch rule: `rate = 1 / count`, where **count** is the amount of samples the centroid has been
+    /// updated with so far. This turns each centroid into the exact mean of all samples it saw, but adapts slowly
+    /// once a centroid has seen many samples.
+    InverseCount,
+    /// Decay with the iteration count: `rate = initial / iteration^power`.
+    /// ## Fields:
+    /// - **initial**: Learning rate of the first iteration
+    /// - **power**: Speed of the decay (`1` = inverse time, lower values decay slower)
+    InverseTime { initial: T, power: T },
+    /// Exponential decay with the iteration count: `rate = initial * decay^(iteration - 1)`.
+    /// ## Fields:
+    /// - **initial**: Learning rate of the first iteration
+    /// - **decay**: Factor the learning rate is multiplied with after each iteration (`0 < decay <= 1`)
+    Exponential { initial: T, decay: T },
+    /// Triangular cyclical learning rate, rising from **min** to **max** and back within each **period** iterations.
+    /// This keeps the centroids adaptive, e.g. for drifting streams.
+    /// ## Fields:
+    /// - **min**: Minimum learning rate (at the start and end of each cycle)
+    /// - **max**: Maximum learning rate (in the middle of each cycle)
+    /// - **period**: Amount of iterations of one cycle
+    Cyclical { min: T, max: T, period: usize },
+}
+impl<T: Primitive> LearningSchedule<T> {
+    /// Whether all parameters of the schedule are valid: finite rates, a non-negative **power** / **decay**, and a
+    /// non-zero **period**.
+    pub(crate) fn is_valid(&self) -> bool {
+        match *self {
+            LearningSchedule::InverseCount => true,
+            LearningSchedule::InverseTime { initial, power } => initial.is_finite() && power.is_finite() && power >= T::zero(),
+            LearningSchedule::Exponential { initial, decay } => initial.is_finite() && decay.is_finite() && decay >= T::zero(),
+            LearningSchedule::Cyclical { min, max, period } => min.is_finite() && max.is_finite() && period > 0,
+        }
+    }
+
+    /// Learning rate for one centroid update.
+    /// ## Arguments
+    /// - **count**: Amount of samples the centroid has been updated with, including the current one (`>= 1`)
+    /// - **iteration**: Current iteration of the calculation (`>= 1`)
+    /// ## Returns
+    /// The learning rate, clamped to `[0, 1]`
+    pub(crate) fn learn_rate(&self, count: usize, iteration: usize) -> T {
+        let rate = match *self {
+            LearningSchedule::InverseCount => T::one() / T::from(count).unwrap(),
+            LearningSchedule::InverseTime { initial, power } => initial / T::from(iteration).unwrap().powf(power),
+            LearningSchedule::Exponential { initial, decay } => initial * decay.powi(iteration as i32 - 1),
+            LearningSchedule::Cyclical { min, max, period } => {
+                let position = T::from((iteration - 1) % period).unwrap() / T::from(period).unwrap();
+                let triangle = T::one() - (T::from(2).unwrap() * position - T::one()).abs();
+                min + (max - min) * triangle
+            },
+        };
+        rate.max(T::zero()).min(T::one())
+    }
+
+    /// Learning rate for one centroid update with a weighted sample (see [`crate::KMeans::with_sample_weights`]).
+    /// ## Arguments
+    /// - **count**: Amount of samples the centroid has been updated with, including the current one (`>= 1`)
+    /// - **weight**: Weight of the current sample
+    /// - **weight_sum**: Total weight of all samples the centroid has been updated with, including the current one
+    /// - **iteration**: Current iteration of the calculation (`>= 1`)
+    /// ## Returns
+    /// For [`LearningSchedule::InverseCount`] `weight / weight_sum`, which keeps each centroid the exact weighted mean
+    /// of all samples it saw. For all other schedules `1 - (1 - rate)^weight`, the same as **weight** consecutive
+    /// updates with the unweighted rate.
+    pub(crate) fn weighted_learn_rate(&self, count: usize, weight: T, weight_sum: T, iteration: usize) -> T {
+        let rate = match *self {
+            LearningSchedule::InverseCount => weight / weight_sum,
+            _ => T::one() - (T::one() - self.learn_rate(count, iteration)).powf(weight),
+        };
+        rate.max(T::zero()).min(T::one())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_learn_rates() {
+        assert_eq!(LearningSchedule::<f64>::InverseCount.learn_rate(4, 1), 0.25);
+        let inverse_time = LearningSchedule::InverseTime { initial: 0.5, power: 1.0 };
+        assert_eq!(inverse_time.learn_rate(1, 1), 0.5);
+        assert_eq!(inverse_time.learn_rate(1, 5), 0.1);
+        let exponential = LearningSchedule::Exponential { initial: 2.0, decay: 0.5 };
+        assert_eq!(exponential.learn_rate(1, 1), 1.0); // clamped
+        assert_eq!(exponential.learn_rate(1, 3), 0.5);
+        let cyclical = LearningSchedule::Cyclical {
+            min: 0.1,
+            max: 0.5,
+            period: 4,
+        };
+        let rates: Vec<f64> = (1..=5).map(|i| cyclical.learn_rate(1, i)).collect();
+        rates
+            .iter()
+            .zip([0.1, 0.3, 0.5, 0.3, 0.1])
+            .for_each(|(&rate, expected)| assert!((rate - expected).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_valid_schedules() {
+        assert!(LearningSchedule::<f64>::InverseCount.is_valid());
+        assert!(LearningSchedule::InverseTime { initial: 0.5, power: 0.0 }.is_valid());
+        assert!(!LearningSchedule::InverseTime {
+            initial: f64::NAN,
+            power: 1.0
+        }
+        .is_valid());
+        assert!(!LearningSchedule::InverseTime { initial: 0.5, power: -1.0 }.is_valid());
+        assert!(LearningSchedule::Exponential { initial: 0.5, decay: 0.9 }.is_valid());
+        assert!(!LearningSchedule::Exponential {
+            initial: f64::INFINITY,
+            decay: 0.9
+        }
+        .is_valid());
+        assert!(!LearningSchedule::Exponential { initial: 0.5, decay: -0.5 }.is_valid());
+        let cyclical = |period| LearningSchedule::Cyclical {
+            min: 0.1,
+            max: 0.5,
+            period,
+        };
+        assert!(cyclical(1).is_valid());
+        assert!(!cyclical(0).is_valid());
+    }
+
+    #[test]
+    fn test_weighted_learn_rates() {
+        assert_eq!(LearningSchedule::<f64>::InverseCount.weighted_learn_rate(2, 3.0, 4.0, 1), 0.75);
+        let exponential = LearningSchedule::Exponential { initial: 0.5, decay: 0.5 };
+        assert_eq!(exponential.weighted_learn_rate(1, 1.0, 1.0, 1), 0.5);
+        assert_eq!(exponential.weighted_learn_rate(1, 2.0, 2.0, 1), 0.75);
+    }
+}
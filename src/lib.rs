@@ -1,5 +1,5 @@
-#![feature(test)]
-#![feature(portable_simd)]
+#![cfg_attr(not(feature = "stable"), feature(test))]
+#![cfg_attr(not(feature = "stable"), feature(portable_simd))]
 
 //! # kmeans - API documentation
 //!
@@ -61,8 +61,8 @@
 //! samples.iter_mut().for_each(|v| *v = rand::random());
 //!
 //! let conf = KMeansConfig::build()
-//!     .init_done(&|_| println!("Initialization completed."))
-//!     .iteration_done(&|s, nr, new_distsum|
+//!     .init_done(|_| println!("Initialization completed."))
+//!     .iteration_done(|s, nr, new_distsum|
 //!         println!("Iteration {} - Error: {:.2} -> {:.2} | Improvement: {:.2}",
 //!             nr, s.distsum, new_distsum, s.distsum - new_distsum))
 //!     .build();
@@ -92,50 +92,129 @@
 //! All of the instance-methods take multiple arguments. One of which is the chosen centroid initialization method. These
 //! initialization-method implementations are static methods within the [`KMeans`] struct, which are simply passed in as reference.
 
+#[cfg(not(feature = "stable"))]
 extern crate test;
 #[macro_use]
 mod helpers;
 mod abort_strategy;
+#[cfg(feature = "core-pinning")]
+mod affinity;
+mod analysis;
+mod annealing;
 mod api;
+mod auto_batch_size;
+mod auto_tune;
+mod batch;
+mod blocked_scan;
+pub mod datasets;
+mod dimension_chunking;
+mod distance_statistics;
 mod distances;
+mod evolutionary;
+#[cfg(feature = "arrow")]
+mod export;
+mod feature_hashing;
+mod incremental;
 mod inits;
+mod learning_schedule;
+mod lsh;
+mod manifest;
 mod memory;
+mod memory_usage;
+pub mod metrics;
+mod norm_cache;
+mod online;
+mod out_of_core;
+mod postprocessing;
+mod projection;
+mod registry;
+mod scratch;
+pub mod simd;
+mod simd_level;
+mod sliding_window;
+mod sparse_centroids;
+mod split_merge;
+mod sweep;
+mod trajectory;
+mod updaters;
 mod variants;
+mod xmeans;
 
-pub use abort_strategy::AbortStrategy;
-pub use api::{DistanceFunction, KMeans, KMeansConfig, KMeansConfigBuilder, KMeansState};
-pub use distances::{EuclideanDistance, HistogramDistance};
+pub use abort_strategy::{AbortStrategy, ComputeBudget, Divergence, StopReason};
+pub use analysis::{
+    CentroidMatch, ClusterExtent, ClusterProfile, ClusterRadius, DriftStatistics, Exemplar, FeatureImportance, MarginHistogram,
+    ReassignmentCost, ResultComparison, StratifiedSampling,
+};
+pub use api::{
+    CentroidUpdater, DistanceFunction, DynDistanceFunction, KMeans, KMeansConfig, KMeansConfigBuilder, KMeansF32, KMeansF64, KMeansState,
+    SampleAssignments,
+};
+pub use auto_tune::KernelConfig;
+pub use distance_statistics::DistanceStatistics;
+pub use distances::{
+    CosineDistance, CountingDistance, EuclideanDistance, HistogramDistance, ManhattanDistance, MultiViewDistance,
+    NormalizedHistogramDistance,
+};
+pub use feature_hashing::FeatureHasher;
+pub use learning_schedule::LearningSchedule;
+pub use lsh::LshFamily;
+pub use manifest::RunManifest;
 pub use memory::Primitive;
+pub use memory_usage::MemoryUsage;
+pub use online::OnlineKMeans;
+pub use out_of_core::{OutOfCoreKMeans, PagedSamples, ReaderSamples, SampleSource};
+pub use postprocessing::{CentroidSnapping, ClusterSizeRepair, DissolvedCluster};
+pub use projection::{Projection2D, ProjectionMethod};
+pub use registry::{ModelRegistry, RegisteredModel};
+pub use scratch::release_scratch_buffers;
+pub use simd_level::{SimdLevel, LANES_F32, LANES_F64};
+pub use sliding_window::SlidingWindowKMeans;
+pub use sparse_centroids::SparseCentroids;
+pub use sweep::{KSweep, KSweepPoint};
+pub use trajectory::CentroidTrajectory;
+pub use updaters::{GeometricMedianUpdater, MeanUpdater, MedianUpdater, NormalizedMeanUpdater};
+pub use variants::{
+    FuzzyKMeansState, IterationSnapshot, IterationStats, KMeansRun, KMeansSnapshots, KMedoidsState, OverlappingKMeansState,
+};
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "stable")))]
 mod tests {
     use super::*;
+    use crate::simd::{LaneCount, Simd, SupportedLaneCount};
     use distances::EuclideanDistance;
     use memory::SupportedSimdArray;
     use rand::prelude::*;
-    use std::simd::{LaneCount, Simd, SupportedLaneCount};
     use test::Bencher;
 
     #[bench]
-    fn complete_benchmark_lloyd_small_f64x8(b: &mut Bencher) { complete_benchmark_lloyd::<f64, 8>(b, 200, 2000, 10, 32); }
+    fn complete_benchmark_lloyd_small_f64x8(b: &mut Bencher) { complete_benchmark_lloyd::<f64, 8>(b, 200, 2000, 10, 32, false); }
     #[bench]
-    fn complete_benchmark_lloyd_mid_f64x8(b: &mut Bencher) { complete_benchmark_lloyd::<f64, 8>(b, 2000, 200, 10, 32); }
+    fn complete_benchmark_lloyd_mid_f64x8(b: &mut Bencher) { complete_benchmark_lloyd::<f64, 8>(b, 2000, 200, 10, 32, false); }
     #[bench]
-    fn complete_benchmark_lloyd_big_f64x8(b: &mut Bencher) { complete_benchmark_lloyd::<f64, 8>(b, 10000, 8, 10, 32); }
+    fn complete_benchmark_lloyd_big_f64x8(b: &mut Bencher) { complete_benchmark_lloyd::<f64, 8>(b, 10000, 8, 10, 32, false); }
     #[bench]
-    fn complete_benchmark_lloyd_huge_f64x8(b: &mut Bencher) { complete_benchmark_lloyd::<f64, 8>(b, 20000, 256, 1, 32); }
+    fn complete_benchmark_lloyd_huge_f64x8(b: &mut Bencher) { complete_benchmark_lloyd::<f64, 8>(b, 20000, 256, 1, 32, false); }
 
     #[bench]
-    fn complete_benchmark_lloyd_small_f32x8(b: &mut Bencher) { complete_benchmark_lloyd::<f32, 8>(b, 200, 2000, 10, 32); }
+    fn complete_benchmark_lloyd_small_f32x8(b: &mut Bencher) { complete_benchmark_lloyd::<f32, 8>(b, 200, 2000, 10, 32, false); }
     #[bench]
-    fn complete_benchmark_lloyd_mid_f32x8(b: &mut Bencher) { complete_benchmark_lloyd::<f32, 8>(b, 2000, 200, 10, 32); }
+    fn complete_benchmark_lloyd_mid_f32x8(b: &mut Bencher) { complete_benchmark_lloyd::<f32, 8>(b, 2000, 200, 10, 32, false); }
     #[bench]
-    fn complete_benchmark_lloyd_big_f32x8(b: &mut Bencher) { complete_benchmark_lloyd::<f32, 8>(b, 10000, 8, 10, 32); }
+    fn complete_benchmark_lloyd_big_f32x8(b: &mut Bencher) { complete_benchmark_lloyd::<f32, 8>(b, 10000, 8, 10, 32, false); }
     #[bench]
-    fn complete_benchmark_lloyd_huge_f32x8(b: &mut Bencher) { complete_benchmark_lloyd::<f32, 8>(b, 20000, 256, 1, 32); }
+    fn complete_benchmark_lloyd_huge_f32x8(b: &mut Bencher) { complete_benchmark_lloyd::<f32, 8>(b, 20000, 256, 1, 32, false); }
+
+    #[bench]
+    fn complete_benchmark_lloyd_compensated_mid_f64x8(b: &mut Bencher) { complete_benchmark_lloyd::<f64, 8>(b, 2000, 200, 10, 32, true); }
+    #[bench]
+    fn complete_benchmark_lloyd_compensated_big_f64x8(b: &mut Bencher) { complete_benchmark_lloyd::<f64, 8>(b, 10000, 8, 10, 32, true); }
+    #[bench]
+    fn complete_benchmark_lloyd_compensated_mid_f32x8(b: &mut Bencher) { complete_benchmark_lloyd::<f32, 8>(b, 2000, 200, 10, 32, true); }
+    #[bench]
+    fn complete_benchmark_lloyd_compensated_big_f32x8(b: &mut Bencher) { complete_benchmark_lloyd::<f32, 8>(b, 10000, 8, 10, 32, true); }
 
     fn complete_benchmark_lloyd<T: Primitive, const LANES: usize>(
-        b: &mut Bencher, sample_cnt: usize, sample_dims: usize, max_iter: usize, k: usize,
+        b: &mut Bencher, sample_cnt: usize, sample_dims: usize, max_iter: usize, k: usize, compensated: bool,
     ) where
         T: Primitive,
         LaneCount<LANES>: SupportedLaneCount,
@@ -145,7 +224,10 @@ mod tests {
         let mut samples = vec![T::zero(); sample_cnt * sample_dims];
         samples.iter_mut().for_each(|v| *v = rnd.gen_range(T::zero()..T::one()));
         let kmean: KMeans<T, LANES, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
-        let conf = KMeansConfig::build().random_generator(rnd).build();
+        let conf = KMeansConfig::build()
+            .random_generator(rnd)
+            .compensated_summation(compensated)
+            .build();
         b.iter(|| kmean.kmeans_lloyd(k, max_iter, KMeans::init_kmeanplusplus, &conf));
     }
 
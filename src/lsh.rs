@@ -0,0 +1,199 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SimdFloat, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use rand::prelude::*;
+use rayon::prelude::*;
+use std::mem::size_of_val;
+
+/// Family of locality-sensitive hash functions, used by the candidate pre-filter of the assignment step (see
+/// [`KMeans::with_lsh_prefilter`]).
+#[derive(Clone, Debug)]
+pub enum LshFamily<T: Primitive> {
+    /// Quantized random projections `floor((a·x + b) / bucket_width)` (p-stable hashing), for the euclidean distance.
+    /// ## Fields:
+    /// - **bucket_width**: Width of the buckets on each projection. It should be in the order of the typical
+    ///   (non-squared) distance between a sample and its centroid.
+    Euclidean { bucket_width: T },
+    /// Signs of random projections (random hyperplanes), for the cosine distance (or the euclidean distance of
+    /// normalized samples).
+    Cosine,
+}
+
+/// Random projections and cached sample hashes of the pre-filter.
+#[derive(Clone, Debug)]
+pub(crate) struct LshPrefilter<T: Primitive> {
+    family: LshFamily<T>,
+    /// Random projection vectors (padded like the samples)
+    projections: StrideBuffer<T>,
+    /// Random offsets of the projections (only used for [`LshFamily::Euclidean`])
+    offsets: Vec<T>,
+    /// Amount of candidate centroids whose exact distance is calculated per sample
+    candidates: usize,
+    /// Hashes of all samples [row-major] = [<sample0-hash0>,<sample0-hash1>,...]
+    pub(crate) sample_hashes: Vec<i32>,
+}
+
+#[inline(always)]
+fn dot<T, const LANES: usize>(a: &[T], b: &[T]) -> T
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    a.chunks_exact(LANES)
+        .map(|i| Simd::from_slice(i))
+        .zip(b.chunks_exact(LANES).map(|i| Simd::from_slice(i)))
+        .map(|(a, b)| a * b)
+        .sum::<Simd<T, LANES>>()
+        .reduce_sum()
+}
+
+impl<T: Primitive> LshPrefilter<T> {
+    pub(crate) fn new<const LANES: usize>(
+        p_samples: &StrideBuffer<T>, family: LshFamily<T>, hashes: usize, candidates: usize, seed: u64,
+    ) -> Self
+    where
+        LaneCount<LANES>: SupportedLaneCount,
+        Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    {
+        assert!(hashes > 0 && candidates > 0);
+        let mut rnd = StdRng::seed_from_u64(seed);
+        let mut projections = StrideBuffer::<T>::new::<LANES>(hashes, p_samples.centroid_dim);
+        projections.bfr.chunks_exact_mut(projections.stride).for_each(|p| {
+            p[..p_samples.centroid_dim]
+                .iter_mut()
+                .for_each(|v| *v = T::from(crate::datasets::standard_normal(&mut rnd)).unwrap());
+        });
+        let offsets = match family {
+            LshFamily::Euclidean { bucket_width } => (0..hashes).map(|_| bucket_width * T::from(rnd.gen::<f64>()).unwrap()).collect(),
+            LshFamily::Cosine => vec![T::zero(); hashes],
+        };
+        let mut res = Self {
+            family,
+            projections,
+            offsets,
+            candidates,
+            sample_hashes: Vec::new(),
+        };
+        res.sample_hashes = res.hash_all::<LANES>(p_samples, p_samples.centroid_cnt);
+        res
+    }
+
+    /// Hashes of the first **cnt** (padded) rows of the given buffer [row-major].
+    fn hash_all<const LANES: usize>(&self, bfr: &StrideBuffer<T>, cnt: usize) -> Vec<i32>
+    where
+        LaneCount<LANES>: SupportedLaneCount,
+        Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    {
+        let hashes = self.offsets.len();
+        let mut res = vec![0; cnt * hashes];
+        bfr.bfr
+            .par_chunks_exact(bfr.stride)
+            .take(cnt)
+            .zip(res.par_chunks_exact_mut(hashes))
+            .for_each(|(row, h)| {
+                h.iter_mut()
+                    .zip(self.projections.chunks_exact_stride())
+                    .zip(self.offsets.iter().cloned())
+                    .for_each(|((h, p), offset)| {
+                        let projected = dot::<T, LANES>(row, p);
+                        *h = match self.family {
+                            LshFamily::Euclidean { bucket_width } => ((projected + offset) / bucket_width).floor().to_i32().unwrap_or(0),
+                            LshFamily::Cosine => (projected >= T::zero()) as i32,
+                        };
+                    });
+            });
+        res
+    }
+}
+
+/// Variant of [`KMeans::update_cluster_assignments`], that only calculates the exact distances to the candidate
+/// centroids of each sample: The centroids with the most similar hashes, as well as the sample's current centroid.
+pub(crate) fn update_cluster_assignments<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, k: usize, lsh: &LshPrefilter<T>,
+) where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    // Centroids changed since the last assignment step, so they have to be hashed again
+    let hashes = lsh.offsets.len();
+    let centroid_hashes = lsh.hash_all::<LANES>(&state.centroids, k);
+    state.memory_usage.record_temporaries(size_of_val(centroid_hashes.as_slice()));
+    let centroids = &state.centroids;
+    let candidates = lsh.candidates.min(k);
+
+    let work_packet_size = kmean.work_packet_size(kmean.sample_cnt);
+    kmean
+        .p_samples
+        .bfr
+        .par_chunks_exact(kmean.p_samples.stride)
+        .with_min_len(work_packet_size)
+        .zip(lsh.sample_hashes.par_chunks_exact(hashes))
+        .zip(state.assignments.par_iter_mut())
+        .zip(state.centroid_distances.par_iter_mut())
+        .for_each_init(
+            || Vec::with_capacity(k),
+            |ranking, (((s, s_hashes), assignment), centroid_dist)| {
+                // Rank all centroids by the distance of their hashes to the sample's hashes
+                ranking.clear();
+                ranking.extend(centroid_hashes.chunks_exact(hashes).enumerate().map(|(idx, c_hashes)| {
+                    let hash_dist: u32 = s_hashes.iter().zip(c_hashes).map(|(a, b)| a.abs_diff(*b)).sum();
+                    (hash_dist, idx)
+                }));
+                if candidates < k {
+                    ranking.select_nth_unstable(candidates - 1);
+                }
+                let current = (*assignment < k).then_some(*assignment);
+                let (best_idx, best_dist) = ranking[..candidates]
+                    .iter()
+                    .map(|&(_, idx)| idx)
+                    .chain(current)
+                    .map(|idx| (idx, kmean.distance_fn.distance(s, centroids.nth_stride(idx))))
+                    .fold((usize::MAX, T::infinity()), |best, (idx, dist)| {
+                        if dist < best.1 || (dist == best.1 && idx < best.0) {
+                            (idx, dist)
+                        } else {
+                            best
+                        }
+                    });
+                *assignment = best_idx;
+                *centroid_dist = best_dist;
+            },
+        );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig, LshFamily};
+    use rand::prelude::*;
+
+    #[test]
+    fn lsh_prefilter_finds_separated_clusters() {
+        let mut rnd = StdRng::seed_from_u64(7);
+        let (sample_cnt, sample_dims, k) = (4000, 16, 40);
+        let centers: Vec<f64> = (0..k * sample_dims).map(|_| rnd.gen_range(-100.0..100.0)).collect();
+        let samples: Vec<f64> = (0..sample_cnt * sample_dims)
+            .map(|i| centers[((i / sample_dims) % k) * sample_dims + i % sample_dims] + rnd.gen_range(-1.0..1.0))
+            .collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let init = || KMeans::init_precomputed(samples[..k * sample_dims].to_vec());
+        let conf = KMeansConfig::default();
+        let expected = kmean.kmeans_lloyd(k, 100, init(), &conf);
+
+        for family in [LshFamily::Euclidean { bucket_width: 50.0 }, LshFamily::Cosine] {
+            let approx = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance).with_lsh_prefilter(family, 16, 4, 42);
+            let res = approx.kmeans_lloyd(k, 100, init(), &conf);
+            let agreement = res
+                .assignments
+                .iter()
+                .zip(expected.assignments.iter())
+                .filter(|(a, b)| a == b)
+                .count();
+            assert!(agreement as f64 / sample_cnt as f64 > 0.95);
+            assert!(res.distsum <= expected.distsum * 1.1);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{AbortStrategy, KMeans, KMeansConfig, KernelConfig, LearningSchedule};
+
+/// Manifest of a k-means calculation, as captured by [`KMeans::run_manifest`], to audit a result and exactly reproduce
+/// it later. With the `serde` feature, it can be serialized alongside the result.
+///
+/// ## Fields
+/// - **crate_version**: Version of this crate, that did the calculation
+/// - **variant**: Name of the chosen variant (and initialization method), as given by the caller
+/// - **k**: Amount of clusters that were requested
+/// - **max_iter**: Maximum amount of iterations
+/// - **seed**: Seed of the random number generator, if the configuration was seeded (see
+///   [`crate::KMeansConfigBuilder::seed`])
+/// - **primitive**: Type of primitive of the calculation
+/// - **lanes**: Amount of SIMD lanes of the calculation
+/// - **distance_fn**: Type of the distance function
+/// - **sample_cnt**: Amount of samples
+/// - **sample_dims**: Amount of dimensions of each sample
+/// - **data_fingerprint**: FNV-1a hash of the shape, all values and all sample weights of the samples
+/// - **weighted**: Whether the samples were weighted (see [`KMeans::with_sample_weights`])
+/// - **kernel**: Configuration of the assignment step's kernel, see [`KernelConfig`]
+/// - **abort_strategy**, **tol**, **min_cluster_size**, **snap_to_samples**, **learning_schedule**,
+///   **split_merge_interval**, **trajectory_interval**, **high_precision**, **compensated_summation**,
+///   **sparse_centroid_updates**, **reassignment_ratio**, **max_no_improvement**: Values of the configuration, as set
+///   with the equally named methods of [`crate::KMeansConfigBuilder`]
+/// - **stratified_min_per_cluster**: Value of [`crate::KMeansConfigBuilder::minibatch_stratified_sampling`]
+/// - **inertia_sample_size**: Value of [`crate::KMeansConfigBuilder::estimated_inertia`]
+/// - **greedy_kmeanplusplus**: Value of [`crate::KMeansConfigBuilder::greedy_kmeanplusplus`] (**None** = disabled)
+/// - **custom_centroid_updater**: Whether a custom centroid update rule was configured (which can not be recorded)
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunManifest<T: Primitive> {
+    pub crate_version: String,
+    pub variant: String,
+    pub k: usize,
+    pub max_iter: usize,
+    pub seed: Option<u64>,
+    pub primitive: String,
+    pub lanes: usize,
+    pub distance_fn: String,
+    pub sample_cnt: usize,
+    pub sample_dims: usize,
+    pub data_fingerprint: u64,
+    pub weighted: bool,
+    pub kernel: KernelConfig,
+    pub abort_strategy: AbortStrategy<T>,
+    pub tol: T,
+    pub min_cluster_size: usize,
+    pub snap_to_samples: bool,
+    pub learning_schedule: LearningSchedule<T>,
+    pub custom_centroid_updater: bool,
+    pub split_merge_interval: usize,
+    pub trajectory_interval: usize,
+    pub high_precision: bool,
+    pub compensated_summation: bool,
+    pub sparse_centroid_updates: bool,
+    pub reassignment_ratio: T,
+    pub max_no_improvement: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stratified_min_per_cluster: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub inertia_sample_size: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub greedy_kmeanplusplus: Option<usize>,
+}
+impl<T: Primitive> RunManifest<T> {
+    /// Rebuild the recorded configuration, seeded with the recorded seed (if any). Callbacks, cancellation tokens and
+    /// custom centroid update rules are not part of the manifest, and have to be set up again.
+    pub fn config(&self) -> KMeansConfig<'static, T> {
+        let mut config = KMeansConfig::build()
+            .abort_strategy(self.abort_strategy.clone())
+            .tol(self.tol)
+            .min_cluster_size(self.min_cluster_size)
+            .snap_centroids_to_samples(self.snap_to_samples)
+            .learning_schedule(self.learning_schedule.clone())
+            .split_merge_interval(self.split_merge_interval)
+            .record_trajectory(self.trajectory_interval)
+            .high_precision_accumulation(self.high_precision)
+            .compensated_summation(self.compensated_summation)
+            .sparse_centroid_updates(self.sparse_centroid_updates)
+            .minibatch_reassignment_ratio(self.reassignment_ratio)
+            .minibatch_max_no_improvement(self.max_no_improvement)
+            .minibatch_stratified_sampling(self.stratified_min_per_cluster)
+            .estimated_inertia(self.inertia_sample_size);
+        if let Some(seed) = self.seed {
+            config = config.seed(seed);
+        }
+        if let Some(local_trials) = self.greedy_kmeanplusplus {
+            config = config.greedy_kmeanplusplus(local_trials);
+        }
+        config.build()
+    }
+
+    /// Whether **kmean** holds the same data (shape, values and weights) as the calculation of this manifest.
+    pub fn matches_data<const LANES: usize, D>(&self, kmean: &KMeans<T, LANES, D>) -> bool
+    where
+        LaneCount<LANES>: SupportedLaneCount,
+        Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+        D: DistanceFunction<T, LANES>,
+    {
+        (self.sample_cnt, self.sample_dims, self.data_fingerprint) == (kmean.sample_cnt, kmean.sample_dims, fingerprint(kmean))
+    }
+}
+
+/// FNV-1a hash of the shape, the (unpadded) values and the weights of the samples of **kmean**.
+fn fingerprint<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>) -> u64
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    let hash_u64 = |hash: u64, v: u64| v.to_le_bytes().iter().fold(hash, |h, &b| (h ^ b as u64).wrapping_mul(PRIME));
+    let hash_values = |hash: u64, values: &[T]| values.iter().fold(hash, |h, v| hash_u64(h, v.to_f64().unwrap().to_bits()));
+
+    let hash = hash_u64(hash_u64(OFFSET_BASIS, kmean.sample_cnt as u64), kmean.sample_dims as u64);
+    let hash = kmean.p_samples.iter().fold(hash, hash_values);
+    match &kmean.sample_weights {
+        Some(weights) => hash_values(hash_u64(hash, 1), weights),
+        None => hash_u64(hash, 0),
+    }
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, variant: &str, k: usize, max_iter: usize, config: &KMeansConfig<'_, T>,
+) -> RunManifest<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    RunManifest {
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        variant: variant.to_string(),
+        k,
+        max_iter,
+        seed: config.seed,
+        primitive: std::any::type_name::<T>().to_string(),
+        lanes: LANES,
+        distance_fn: std::any::type_name::<D>().to_string(),
+        sample_cnt: kmean.sample_cnt,
+        sample_dims: kmean.sample_dims,
+        data_fingerprint: fingerprint(kmean),
+        weighted: kmean.sample_weights.is_some(),
+        kernel: kmean.kernel_config(),
+        abort_strategy: config.abort_strategy.clone(),
+        tol: config.tol,
+        min_cluster_size: config.min_cluster_size,
+        snap_to_samples: config.snap_to_samples,
+        learning_schedule: config.learning_schedule.clone(),
+        custom_centroid_updater: config.centroid_updater.is_some(),
+        split_merge_interval: config.split_merge_interval,
+        trajectory_interval: config.trajectory_interval,
+        high_precision: config.high_precision,
+        compensated_summation: config.compensated_summation,
+        sparse_centroid_updates: config.sparse_centroid_updates,
+        reassignment_ratio: config.reassignment_ratio,
+        max_no_improvement: config.max_no_improvement,
+        stratified_min_per_cluster: config.stratified_min_per_cluster,
+        inertia_sample_size: config.inertia_sample_size,
+        greedy_kmeanplusplus: config.greedy_kmeanplusplus,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn reproduce_from_manifest() {
+        let samples: Vec<f64> = (0..400).map(|i| (i % 4) as f64 * 10.0 + (i % 7) as f64 * 0.3).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len() / 2, 2, EuclideanDistance);
+        let conf = KMeansConfig::build().seed(42).tol(1e-6).build();
+        let manifest = kmean.run_manifest("lloyd/kmeans++", 4, 100, &conf);
+        let res = kmean.kmeans_lloyd(4, 100, KMeans::init_kmeanplusplus, &conf);
+
+        assert_eq!((manifest.seed, manifest.tol, manifest.lanes), (Some(42), 1e-6, 8));
+        assert_eq!(manifest.crate_version, env!("CARGO_PKG_VERSION"));
+        assert!(manifest.matches_data(&kmean));
+        let reproduced = kmean.kmeans_lloyd(manifest.k, manifest.max_iter, KMeans::init_kmeanplusplus, &manifest.config());
+        assert_eq!(reproduced.centroids.to_vec(), res.centroids.to_vec());
+        assert_eq!(reproduced.assignments, res.assignments);
+
+        // Any change of the data changes the fingerprint
+        let mut changed = samples.clone();
+        changed[123] += 1e-9;
+        let other: KMeans<f64, 8, _> = KMeans::new(&changed, samples.len() / 2, 2, EuclideanDistance);
+        assert!(!manifest.matches_data(&other));
+        let reshaped: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        assert!(!manifest.matches_data(&reshaped));
+        let weighted: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len() / 2, 2, EuclideanDistance).with_sample_weights(&[1.0; 200]);
+        assert!(!manifest.matches_data(&weighted));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_manifest() {
+        let samples: Vec<f32> = (0..100).map(|i| (i % 5) as f32).collect();
+        let kmean: KMeans<f32, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::build()
+            .seed(7)
+            .abort_strategy(crate::AbortStrategy::TimeBudget(std::time::Duration::from_millis(50)))
+            .build();
+        let manifest = kmean.run_manifest("minibatch", 3, 10, &conf);
+
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(json["seed"], 7);
+        let restored: super::RunManifest<f32> = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.data_fingerprint, manifest.data_fingerprint);
+        assert!(restored.matches_data(&kmean));
+        assert!(matches!(restored.abort_strategy, crate::AbortStrategy::TimeBudget(d) if d.as_millis() == 50));
+    }
+}
@@ -1,9 +1,8 @@
 use crate::helpers;
+use crate::simd::{LaneCount, Simd, SimdElement, SimdFloat, SupportedLaneCount};
 use core::fmt;
 use rand::distributions::uniform::SampleUniform;
 use std::ops::{Index, IndexMut};
-use std::simd::num::SimdFloat;
-use std::simd::{LaneCount, Simd, SimdElement, SupportedLaneCount};
 use std::{iter, ops};
 
 pub trait Primitive:
@@ -134,6 +133,21 @@ impl<T: Primitive> StrideBuffer<T> {
         res
     }
 
+    /// Set the padding of every stride (all values after the first **centroid_dim** values) to **value**, see
+    /// [`crate::DistanceFunction::padding_value`].
+    pub(crate) fn fill_padding(&mut self, value: T) {
+        let centroid_dim = self.centroid_dim;
+        if centroid_dim < self.stride {
+            self.bfr.chunks_exact_mut(self.stride).for_each(|s| s[centroid_dim..].fill(value));
+        }
+    }
+
+    /// This buffer, with its padding set to **value** (see [`StrideBuffer::fill_padding`]).
+    pub(crate) fn padded(mut self, value: T) -> Self {
+        self.fill_padding(value);
+        self
+    }
+
     /// Set the nth stride from the given iterator
     pub fn set_nth_from_iter(&mut self, index: usize, iter: impl IntoIterator<Item = T>) {
         self.index_mut(index).iter_mut().zip(iter).for_each(|(dst, src)| {
@@ -189,6 +203,54 @@ impl<T> IndexMut<usize> for StrideBuffer<T> {
     }
 }
 
+/// Serialized form of a [`StrideBuffer`]: The elements without their SIMD padding, as well as the stride they are
+/// padded to again, when deserialized.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "StrideBuffer")]
+struct CompactStrideBuffer<T> {
+    stride: usize,
+    centroid_dim: usize,
+    centroids: Vec<T>,
+}
+#[cfg(feature = "serde")]
+impl<T: Primitive + serde::Serialize> serde::Serialize for StrideBuffer<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let compact = CompactStrideBuffer {
+            stride: self.stride,
+            centroid_dim: self.centroid_dim,
+            centroids: self.to_vec(),
+        };
+        compact.serialize(serializer)
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de, T: Primitive + serde::Deserialize<'de>> serde::Deserialize<'de> for StrideBuffer<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let compact = CompactStrideBuffer::<T>::deserialize(deserializer)?;
+        let (stride, centroid_dim) = (compact.stride, compact.centroid_dim);
+        if centroid_dim == 0 || stride < centroid_dim || compact.centroids.len() % centroid_dim != 0 {
+            return Err(D::Error::custom("inconsistent dimensions of serialized StrideBuffer"));
+        }
+        // The stride is a multiple of the (power of two) LANES the buffer was created with, which define the alignment
+        let lanes = (1usize << stride.trailing_zeros()).min(64);
+        let centroid_cnt = compact.centroids.len() / centroid_dim;
+        let mut bfr = Self {
+            bfr: aligned_vec::avec_rt!([ lanes * std::mem::size_of::<T>() ]| T::default(); stride * centroid_cnt),
+            stride,
+            centroid_cnt,
+            centroid_dim,
+        };
+        compact
+            .centroids
+            .chunks_exact(centroid_dim)
+            .enumerate()
+            .for_each(|(i, c)| bfr.set_nth_from_iter(i, c.iter().cloned()));
+        Ok(bfr)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::StrideBuffer;
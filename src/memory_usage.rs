@@ -0,0 +1,53 @@
+use crate::helpers;
+use crate::memory::*;
+use std::mem::size_of;
+
+/// Memory usage (in bytes) of a k-means calculation, split by purpose.
+/// This is returned as estimate by [`crate::KMeans::estimated_memory`], and reported for a finished calculation in
+/// [`crate::KMeansState::memory_usage`].
+///
+/// ## Fields
+/// - **samples**: Internal (padded) copy of the samples, held by the [`crate::KMeans`] instance
+/// - **state**: Buffers of the [`crate::KMeansState`] (centroids, assignments, distances, frequencies)
+/// - **temporaries**: Peak size of the temporary buffers allocated during the calculation (e.g. for the centroid
+///   update, or the shuffled samples of a mini-batch calculation)
+///
+/// Sizes only cover the data buffers. Small bookkeeping allocations and the stack are not included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryUsage {
+    pub samples: usize,
+    pub state: usize,
+    pub temporaries: usize,
+}
+impl MemoryUsage {
+    pub(crate) fn new<T: Primitive, const LANES: usize>(sample_cnt: usize, sample_dims: usize, k: usize) -> Self {
+        let stride = helpers::multiple_roundup(sample_dims, LANES);
+        Self {
+            samples: sample_cnt * stride * size_of::<T>(),
+            state: k * stride * size_of::<T>() + k * size_of::<usize>() + sample_cnt * (size_of::<usize>() + size_of::<T>()),
+            temporaries: 0,
+        }
+    }
+
+    /// Total amount of bytes of all buffers.
+    pub fn total(&self) -> usize { self.samples + self.state + self.temporaries }
+
+    /// Record temporary buffers of the given size, that are alive at the same time.
+    #[inline(always)]
+    pub(crate) fn record_temporaries(&mut self, bytes: usize) { self.temporaries = self.temporaries.max(bytes); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_usage_layout() {
+        // 3 dims are padded to 4 lanes
+        let usage = MemoryUsage::new::<f64, 4>(10, 3, 2);
+        assert_eq!(usage.samples, 10 * 4 * 8);
+        assert_eq!(usage.state, 2 * 4 * 8 + 2 * 8 + 10 * (8 + 8));
+        assert_eq!(usage.total(), usage.samples + usage.state);
+    }
+}
@@ -0,0 +1,504 @@
+//! Metrics for the evaluation of k-means results.
+//!
+//! ## Example
+//! ```rust
+//! use kmeans::*;
+//!
+//! // Inertia (distsum) of the results for k = 1..=6, e.g. as returned by KSweep::curve()
+//! let curve = vec![(1, 100.0f64), (2, 50.0), (3, 10.0), (4, 8.0), (5, 7.0), (6, 6.0)];
+//! assert_eq!(metrics::elbow_k(&curve), Some(3));
+//! ```
+
+use crate::api::DistanceFunction;
+use crate::memory::{Primitive, SupportedSimdArray};
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use rayon::prelude::*;
+
+/// Detect the elbow of a decreasing, convex curve using the (offline) kneedle algorithm.
+/// (see: https://raghavan.usc.edu/papers/kneedle-simplex11.pdf)
+///
+/// ## Arguments
+/// - **xs**: Ascending x-coordinates of the curve's points
+/// - **ys**: y-coordinates of the curve's points
+///
+/// ## Returns
+/// Index of the elbow point within **xs** / **ys**, or **None** if there is none (e.g. for less than three points,
+/// or a straight line).
+pub fn kneedle(xs: &[f64], ys: &[f64]) -> Option<usize> {
+    assert_eq!(xs.len(), ys.len());
+    if xs.len() < 3 {
+        return None;
+    }
+    let (x_min, x_max) = xs
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let (y_min, y_max) = ys
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    if x_max <= x_min || y_max <= y_min {
+        return None;
+    }
+    // Normalize to the unit square and flip y, so the decreasing convex curve becomes an increasing concave one.
+    // The elbow then is the point with the maximum distance above the diagonal.
+    let (best_idx, best_diff) = xs
+        .iter()
+        .zip(ys.iter())
+        .map(|(&x, &y)| (1.0 - (y - y_min) / (y_max - y_min)) - (x - x_min) / (x_max - x_min))
+        .enumerate()
+        .max_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+        .unwrap();
+    (best_diff > 0.0).then_some(best_idx)
+}
+
+/// Select **k** at the elbow of an inertia-vs-k curve, using the kneedle algorithm (see [`kneedle`]).
+///
+/// ## Arguments
+/// - **curve**: (k, inertia) pairs, e.g. as returned by [`crate::KSweep::curve`] (in any order)
+///
+/// ## Returns
+/// The **k** at the elbow of the curve, or **None** if there is none. For the same curve, this always selects the
+/// same **k**.
+pub fn elbow_k<T: Primitive>(curve: &[(usize, T)]) -> Option<usize> {
+    let mut curve = curve.to_vec();
+    curve.sort_by_key(|&(k, _)| k);
+    let xs: Vec<f64> = curve.iter().map(|&(k, _)| k as f64).collect();
+    let ys: Vec<f64> = curve.iter().map(|&(_, inertia)| inertia.to_f64().unwrap()).collect();
+    kneedle(&xs, &ys).map(|idx| curve[idx].0)
+}
+
+/// Per-cluster inertia (sum of the distances of the cluster's samples to their centroid) of a k-means result.
+///
+/// ## Description
+/// The distances are weighted by the sample weights of the calculation (see [`KMeans::with_sample_weights`]), e.g.
+/// the multiplicity of each sample after deduplication, or coreset weights. Results calculated on such compressed data
+/// thus report numbers comparable to a calculation on the full data. This is the same as
+/// [`KMeansState::cluster_inertias`].
+///
+/// ## Arguments
+/// - **state**: Calculated k-means result
+///
+/// ## Returns
+/// The (weighted) inertia of each cluster, which sum up to the **distsum** of **state**.
+pub fn cluster_inertia<T: Primitive>(state: &KMeansState<T>) -> &[T] { state.cluster_inertias() }
+
+/// Total inertia (sum of the distances of all samples to their centroid) of a k-means result, weighted by the sample
+/// weights of the calculation (see [`cluster_inertia`]).
+pub fn inertia<T: Primitive>(state: &KMeansState<T>) -> T { state.cluster_inertias().iter().cloned().sum() }
+
+/// Silhouette coefficient of every sample of a k-means result.
+///
+/// ## Description
+/// For every sample, `a` is the mean distance to all other samples of its own cluster, and `b` the mean distance to
+/// the samples of the nearest other cluster. The coefficient `(b - a) / max(a, b)` ranges from `-1` (probably
+/// assigned to the wrong cluster) over `0` (on the border between two clusters) to `1` (well separated). Samples in
+/// clusters with only one member get a coefficient of `0`. Squared euclidean distances (see
+/// [`DistanceFunction::is_squared_euclidean`]) are converted to euclidean distances, all other distance functions are
+/// used as they are. With sample weights (see [`KMeans::with_sample_weights`]), `a` and `b` are the means of the
+/// distances weighted by the weights of the other samples (for `a` divided by the weight of the own cluster without
+/// the sample's own weight). Scaling all weights by the same factor thus leaves the coefficients unchanged.
+///
+/// This requires the distances between all pairs of samples (`O(sample_cnt²)`), which are calculated in parallel with
+/// the SIMD distance function of **kmean**, without storing them.
+///
+/// ## Arguments
+/// - **kmean**: The samples **state** was calculated on
+/// - **state**: Calculated k-means result, with at least two non-empty clusters
+pub fn silhouette_samples<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>) -> Vec<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    assert_eq!(state.assignments.len(), kmean.sample_cnt);
+    let cluster_sizes = non_empty_cluster_sizes(state, "silhouette");
+    let cluster_weights = cluster_weights(kmean, state);
+    let squared = kmean.distance_fn.is_squared_euclidean();
+
+    let samples = &kmean.p_samples;
+    samples
+        .bfr
+        .par_chunks_exact(samples.stride)
+        .zip(state.assignments.par_iter().cloned())
+        .enumerate()
+        .map_init(
+            || vec![T::zero(); state.k],
+            |distance_sums, (sample_id, (s, own))| {
+                if cluster_sizes[own] <= 1 {
+                    return T::zero();
+                }
+                distance_sums.fill(T::zero());
+                samples
+                    .chunks_exact_stride()
+                    .zip(state.assignments.iter().cloned())
+                    .enumerate()
+                    .for_each(|(other_id, (other, c))| {
+                        let distance = kmean.distance_fn.distance(s, other);
+                        distance_sums[c] += kmean.sample_weight(other_id) * if squared { distance.sqrt() } else { distance };
+                    });
+                // The distance of the sample to itself is 0
+                let a = distance_sums[own] / (cluster_weights[own] - kmean.sample_weight(sample_id));
+                let b = (0..state.k)
+                    .filter(|&c| c != own && cluster_sizes[c] > 0)
+                    .map(|c| distance_sums[c] / cluster_weights[c])
+                    .fold(T::infinity(), T::min);
+                match a.max(b) > T::zero() {
+                    true => (b - a) / a.max(b),
+                    false => T::zero(),
+                }
+            },
+        )
+        .collect()
+}
+
+/// Mean silhouette coefficient of all samples of a k-means result (see [`silhouette_samples`]), weighted by the sample
+/// weights. Higher values indicate denser and better separated clusters, e.g. for selecting **k**.
+pub fn silhouette_score<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>) -> T
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let coefficients = silhouette_samples(kmean, state);
+    let weighted_sum = coefficients
+        .iter()
+        .enumerate()
+        .map(|(sample_id, &c)| kmean.sample_weight(sample_id) * c)
+        .sum::<T>();
+    weighted_sum / total_weight(kmean)
+}
+
+/// Sizes of all clusters of **state**, asserting that at least two of them are non-empty.
+fn non_empty_cluster_sizes<T: Primitive>(state: &KMeansState<T>, metric: &str) -> Vec<usize> {
+    let mut cluster_sizes = vec![0usize; state.k];
+    state.assignments.iter().for_each(|&c| cluster_sizes[c] += 1);
+    assert!(
+        cluster_sizes.iter().filter(|&&size| size > 0).count() >= 2,
+        "{metric} requires at least two non-empty clusters"
+    );
+    cluster_sizes
+}
+
+/// (Weighted) amount of samples in each cluster of **state**.
+fn cluster_weights<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>) -> Vec<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let mut cluster_weights = vec![T::zero(); state.k];
+    state
+        .assignments
+        .iter()
+        .enumerate()
+        .for_each(|(sample_id, &c)| cluster_weights[c] += kmean.sample_weight(sample_id));
+    cluster_weights
+}
+
+/// (Weighted) amount of all samples of **kmean**.
+fn total_weight<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>) -> T
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    match &kmean.sample_weights {
+        Some(weights) => weights.iter().cloned().sum(),
+        None => T::from(kmean.sample_cnt).unwrap(),
+    }
+}
+
+/// Davies-Bouldin index of a k-means result.
+///
+/// ## Description
+/// For every cluster `i`, the scatter `S_i` is the mean distance of its samples to the centroid. The index is the mean
+/// over all clusters of `max_j (S_i + S_j) / d(c_i, c_j)` (the similarity to the most similar other cluster), so lower
+/// values indicate more compact and better separated clusters, with `0` as the lowest possible value. Empty
+/// clusters are ignored. As for [`silhouette_samples`], squared euclidean distances are converted to euclidean
+/// distances, all other distance functions are used as they are, and the scatters are weighted by the sample weights.
+///
+/// This only uses the distances of the samples to their centroids (`centroid_distances`), and the distances between
+/// the centroids (`O(sample_cnt + k²)`), which makes it a cheap alternative to [`silhouette_score`] for selecting
+/// **k**.
+///
+/// ## Arguments
+/// - **kmean**: The samples **state** was calculated on
+/// - **state**: Calculated k-means result, with at least two non-empty clusters
+pub fn davies_bouldin_index<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>) -> T
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    assert_eq!(state.assignments.len(), kmean.sample_cnt);
+    let cluster_sizes = non_empty_cluster_sizes(state, "davies_bouldin_index");
+    let cluster_weights = cluster_weights(kmean, state);
+    let squared = kmean.distance_fn.is_squared_euclidean();
+    let distance = |d: T| if squared { d.sqrt() } else { d };
+
+    let mut scatter = vec![T::zero(); state.k];
+    state
+        .assignments
+        .iter()
+        .zip(state.centroid_distances.iter())
+        .enumerate()
+        .for_each(|(sample_id, (&c, &d))| scatter[c] += kmean.sample_weight(sample_id) * distance(d));
+    scatter
+        .iter_mut()
+        .zip(cluster_sizes.iter().zip(cluster_weights.iter()))
+        .filter(|(_, (&size, _))| size > 0)
+        .for_each(|(s, (_, &weight))| *s = *s / weight);
+
+    let clusters: Vec<usize> = (0..state.k).filter(|&c| cluster_sizes[c] > 0).collect();
+    let similarities = clusters.iter().map(|&i| {
+        clusters
+            .iter()
+            .filter(|&&j| j != i)
+            .map(|&j| {
+                let separation = distance(
+                    kmean
+                        .distance_fn
+                        .distance(state.centroids.nth_stride(i), state.centroids.nth_stride(j)),
+                );
+                (scatter[i] + scatter[j]) / separation
+            })
+            .fold(T::zero(), T::max)
+    });
+    similarities.sum::<T>() / T::from(clusters.len()).unwrap()
+}
+
+/// Calinski-Harabasz index (variance ratio criterion) of a k-means result.
+///
+/// ## Description
+/// Ratio of the dispersion between the clusters (squared euclidean distances of the centroids to the mean of all
+/// samples, weighted by the cluster sizes) to the dispersion within the clusters (squared euclidean distances of the
+/// samples to their centroids), each normalized by its degrees of freedom: `(B / (k - 1)) / (W / (n - k))`, where `k`
+/// is the amount of non-empty clusters. Higher values indicate denser and better separated clusters. If all samples
+/// are identical to their centroids, `1` is returned (as scikit-learn does). With sample weights, the mean and both
+/// dispersions are weighted, while the amount of samples `n` stays unweighted. Scaling all weights by the same factor
+/// thus leaves the index unchanged.
+///
+/// This always uses squared euclidean distances, independent of the distance function of **kmean**, and needs one
+/// pass over the samples (`O(sample_cnt)`), which makes it a cheap alternative to [`silhouette_score`] for selecting
+/// **k**.
+///
+/// ## Arguments
+/// - **kmean**: The samples **state** was calculated on
+/// - **state**: Calculated k-means result, with at least two non-empty clusters, and fewer clusters than samples
+pub fn calinski_harabasz_index<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>) -> T
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    assert_eq!(state.assignments.len(), kmean.sample_cnt);
+    let cluster_sizes = non_empty_cluster_sizes(state, "calinski_harabasz_index");
+    let cluster_weights = cluster_weights(kmean, state);
+    let k = cluster_sizes.iter().filter(|&&size| size > 0).count();
+    assert!(kmean.sample_cnt > k, "calinski_harabasz_index requires more samples than clusters");
+    let squared_distance = |a: &[T], b: &[T]| a.iter().zip(b.iter()).map(|(&a, &b)| (a - b) * (a - b)).sum::<T>();
+
+    let mut mean = vec![T::zero(); kmean.sample_dims];
+    kmean.p_samples.iter().enumerate().for_each(|(sample_id, s)| {
+        let weight = kmean.sample_weight(sample_id);
+        mean.iter_mut().zip(s.iter()).for_each(|(m, &v)| *m += weight * v)
+    });
+    let total_weight = total_weight(kmean);
+    mean.iter_mut().for_each(|m| *m = *m / total_weight);
+
+    let within: T = kmean
+        .p_samples
+        .bfr
+        .par_chunks_exact(kmean.p_samples.stride)
+        .zip(state.assignments.par_iter().cloned())
+        .enumerate()
+        .map(|(sample_id, (s, c))| {
+            kmean.sample_weight(sample_id) * squared_distance(&s[..kmean.sample_dims], &state.centroids.nth_stride(c)[..kmean.sample_dims])
+        })
+        .sum();
+    let between: T = (0..state.k)
+        .filter(|&c| cluster_sizes[c] > 0)
+        .map(|c| cluster_weights[c] * squared_distance(&state.centroids.nth_stride(c)[..kmean.sample_dims], &mean))
+        .sum();
+
+    if within == T::zero() {
+        return T::one();
+    }
+    (between / T::from(k - 1).unwrap()) / (within / T::from(kmean.sample_cnt - k).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kneedle_elbow() {
+        let xs = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        assert_eq!(kneedle(&xs, &[100.0, 50.0, 10.0, 8.0, 7.0, 6.0]), Some(2));
+        // Straight line has no elbow
+        assert_eq!(kneedle(&xs, &[6.0, 5.0, 4.0, 3.0, 2.0, 1.0]), None);
+        assert_eq!(kneedle(&xs[..2], &[2.0, 1.0]), None);
+    }
+
+    #[test]
+    fn elbow_k_unordered() {
+        let curve = [(4, 8.0f32), (2, 50.0), (6, 6.0), (1, 100.0), (3, 10.0), (5, 7.0)];
+        assert_eq!(elbow_k(&curve), Some(3));
+        assert_eq!(elbow_k::<f64>(&[]), None);
+    }
+
+    #[test]
+    fn weighted_inertia() {
+        use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+        // The deduplicated samples, weighted by their multiplicity, report the inertia of the full data
+        let full = vec![0.0f64, 2.0, 2.0, 2.0, 10.0, 12.0];
+        let (dedup, weights) = (vec![0.0f64, 2.0, 10.0, 12.0], vec![1.0, 3.0, 1.0, 1.0]);
+        let conf = KMeansConfig::default();
+        let init = vec![1.5, 11.0];
+        let full = KMeans::<f64, 8, _>::new(&full, full.len(), 1, EuclideanDistance);
+        let full_res = full.kmeans_lloyd(2, 100, KMeans::init_precomputed(init.clone()), &conf);
+        let dedup_res = KMeans::<f64, 8, _>::new(&dedup, dedup.len(), 1, EuclideanDistance)
+            .with_sample_weights(&weights)
+            .kmeans_lloyd(2, 100, KMeans::init_precomputed(init), &conf);
+
+        assert_eq!(cluster_inertia(&full_res), &[3.0, 2.0]);
+        assert_eq!(inertia(&full_res), full_res.distsum);
+        assert_eq!(cluster_inertia(&dedup_res), &[3.0, 2.0]);
+        assert_eq!(inertia(&dedup_res), full_res.distsum);
+    }
+
+    #[test]
+    fn weighted_validation_indices() {
+        use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+        // The deduplicated samples, weighted by their multiplicity, report the indices of the full data
+        let full = vec![0.0f64, 2.0, 2.0, 2.0, 10.0, 12.0, 30.0, 30.0];
+        let (dedup, weights) = (vec![0.0f64, 2.0, 10.0, 12.0, 30.0], vec![1.0, 3.0, 1.0, 1.0, 2.0]);
+        let conf = KMeansConfig::default();
+        let init = vec![1.5, 11.0, 30.0];
+        let full = KMeans::<f64, 8, _>::new(&full, full.len(), 1, EuclideanDistance);
+        let full_res = full.kmeans_lloyd(3, 100, KMeans::init_precomputed(init.clone()), &conf);
+        let dedup = KMeans::<f64, 8, _>::new(&dedup, dedup.len(), 1, EuclideanDistance).with_sample_weights(&weights);
+        let dedup_res = dedup.kmeans_lloyd(3, 100, KMeans::init_precomputed(init), &conf);
+        assert_eq!(dedup_res.centroids.to_vec(), full_res.centroids.to_vec());
+
+        // The distances of a deduplicated sample to its own duplicates are gone, so only the samples with a weight of 1
+        // have the coefficients of the full data
+        let full_coefficients = silhouette_samples(&full, &full_res);
+        let dedup_coefficients = silhouette_samples(&dedup, &dedup_res);
+        [(0, 0), (4, 2), (5, 3)]
+            .iter()
+            .for_each(|&(full_id, dedup_id)| assert!((full_coefficients[full_id] - dedup_coefficients[dedup_id]).abs() < 1e-12));
+        assert!((davies_bouldin_index(&full, &full_res) - davies_bouldin_index(&dedup, &dedup_res)).abs() < 1e-12);
+        // Both dispersions are those of the full data, but the degrees of freedom use the 5 instead of 8 samples
+        let expected = calinski_harabasz_index(&full, &full_res) * (5.0 - 3.0) / (8.0 - 3.0);
+        assert!((calinski_harabasz_index(&dedup, &dedup_res) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn silhouette() {
+        use crate::{EuclideanDistance, KMeans, KMeansConfig, ManhattanDistance};
+
+        let samples = vec![0.0f64, 1.0, 10.0, 11.0, 30.0];
+        let kmean = KMeans::<f64, 8, _>::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![0.0, 10.0, 30.0]), &KMeansConfig::default());
+        assert_eq!(res.assignments, vec![0, 0, 1, 1, 2]);
+
+        // Euclidean (not squared) distances, the single-sample cluster has a coefficient of 0
+        let expected = [9.5 / 10.5, 8.5 / 9.5, 8.5 / 9.5, 9.5 / 10.5, 0.0];
+        let coefficients = silhouette_samples(&kmean, &res);
+        coefficients.iter().zip(expected).for_each(|(c, e)| assert!((c - e).abs() < 1e-12));
+        let mean = expected.iter().sum::<f64>() / 5.0;
+        assert!((silhouette_score(&kmean, &res) - mean).abs() < 1e-12);
+
+        // Other distance functions are used as they are
+        let manhattan = KMeans::<f64, 8, _>::new(&samples, samples.len(), 1, ManhattanDistance);
+        let res = manhattan.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![0.0, 10.0, 30.0]), &KMeansConfig::default());
+        let coefficients = silhouette_samples(&manhattan, &res);
+        coefficients.iter().zip(expected).for_each(|(c, e)| assert!((c - e).abs() < 1e-12));
+    }
+
+    #[test]
+    fn silhouette_fractional_weights() {
+        use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+        // Scaling all weights (e.g. to fractional weights below 1) leaves the coefficients unchanged
+        let samples = vec![0.0f64, 2.0, 3.0, 10.0, 12.0, 30.0];
+        let weights = vec![1.0, 3.0, 2.0, 1.0, 4.0, 2.0];
+        let scaled: Vec<f64> = weights.iter().map(|w| w * 0.01).collect();
+        let init = || KMeans::init_precomputed(vec![1.5, 11.0, 30.0]);
+        let kmean = KMeans::<f64, 8, _>::new(&samples, samples.len(), 1, EuclideanDistance).with_sample_weights(&weights);
+        let res = kmean.kmeans_lloyd(3, 100, init(), &KMeansConfig::default());
+        let scaled_kmean = KMeans::<f64, 8, _>::new(&samples, samples.len(), 1, EuclideanDistance).with_sample_weights(&scaled);
+        let scaled_res = scaled_kmean.kmeans_lloyd(3, 100, init(), &KMeansConfig::default());
+        assert_eq!(scaled_res.assignments, res.assignments);
+
+        let coefficients = silhouette_samples(&kmean, &res);
+        // Sample 0: a = (3 * 2 + 2 * 3) / (6 - 1), b = (1 * 10 + 4 * 12) / 5
+        let (a, b) = (12.0 / 5.0, 58.0 / 5.0);
+        assert!((coefficients[0] - (b - a) / b).abs() < 1e-12);
+        // The single-sample cluster has a coefficient of 0, independent of its weight
+        assert_eq!(coefficients[5], 0.0);
+        silhouette_samples(&scaled_kmean, &scaled_res)
+            .iter()
+            .zip(coefficients.iter())
+            .for_each(|(s, c)| assert!((s - c).abs() < 1e-12));
+        assert!((silhouette_score(&scaled_kmean, &scaled_res) - silhouette_score(&kmean, &res)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn calinski_harabasz_fractional_weights() {
+        use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+        // Weights that sum up to fewer than k, are valid as long as there are more samples than clusters
+        let samples = vec![0.0f64, 2.0, 10.0, 14.0];
+        let weights = vec![0.01, 0.03, 0.02, 0.02];
+        let kmean = KMeans::<f64, 8, _>::new(&samples, samples.len(), 1, EuclideanDistance).with_sample_weights(&weights);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &KMeansConfig::default());
+        res.centroids
+            .to_vec()
+            .iter()
+            .zip([1.5, 12.0])
+            .for_each(|(c, expected)| assert!((c - expected).abs() < 1e-12));
+
+        // Mean 6.75: B = 0.04 * 5.25² + 0.04 * 5.25², W = 0.01 * 1.5² + 0.03 * 0.5² + 0.02 * 2² + 0.02 * 2²
+        let (between, within) = (0.08 * 5.25 * 5.25, 0.01 * 2.25 + 0.03 * 0.25 + 0.16);
+        let expected = between / (within / 2.0);
+        assert!((calinski_harabasz_index(&kmean, &res) - expected).abs() < 1e-9);
+
+        // Scaling all weights leaves the index unchanged
+        let scaled: Vec<f64> = weights.iter().map(|w| w * 100.0).collect();
+        let scaled_kmean = KMeans::<f64, 8, _>::new(&samples, samples.len(), 1, EuclideanDistance).with_sample_weights(&scaled);
+        let scaled_res = scaled_kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &KMeansConfig::default());
+        assert!((calinski_harabasz_index(&scaled_kmean, &scaled_res) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn davies_bouldin_calinski_harabasz() {
+        use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+        let samples = vec![0.0f64, 2.0, 10.0, 14.0];
+        let kmean = KMeans::<f64, 8, _>::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &KMeansConfig::default());
+        assert_eq!(res.centroids.to_vec(), vec![1.0, 12.0]);
+
+        // Scatters 1 and 2, centroids 11 apart
+        assert!((davies_bouldin_index(&kmean, &res) - 3.0 / 11.0).abs() < 1e-12);
+        // Mean 6.5: B = 2 * 5.5² + 2 * 5.5², W = 1 + 1 + 4 + 4
+        assert!((calinski_harabasz_index(&kmean, &res) - (4.0 * 5.5 * 5.5) / (10.0 / 2.0)).abs() < 1e-9);
+
+        // Better separated clusters have a lower Davies-Bouldin and a higher Calinski-Harabasz index
+        let separated = vec![0.0f64, 2.0, 100.0, 104.0];
+        let kmean = KMeans::<f64, 8, _>::new(&separated, separated.len(), 1, EuclideanDistance);
+        let separated_res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 100.0]), &KMeansConfig::default());
+        assert!(davies_bouldin_index(&kmean, &separated_res) < 3.0 / 11.0);
+        assert!(calinski_harabasz_index(&kmean, &separated_res) > 4.0 * 5.5 * 5.5 / 5.0);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{self, LaneCount, Simd, SimdFloat, SupportedLaneCount};
+use crate::{scratch, KMeans, KMeansState};
+use rayon::prelude::*;
+use std::mem::size_of_val;
+
+#[inline(always)]
+fn dot<T, const LANES: usize>(a: &[T], b: &[T]) -> T
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    a.chunks_exact(LANES)
+        .map(|i| Simd::from_slice(i))
+        .zip(b.chunks_exact(LANES).map(|i| Simd::from_slice(i)))
+        .map(|(a, b)| a * b)
+        .sum::<Simd<T, LANES>>()
+        .reduce_sum()
+}
+
+/// Squared norms of all (padded) rows of the given buffer.
+pub(crate) fn squared_norms<T, const LANES: usize>(bfr: &StrideBuffer<T>) -> Vec<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    simd::map_packets(bfr.bfr.par_chunks_exact(bfr.stride), simd::PACKET_SIZE, |r| dot::<T, LANES>(r, r))
+}
+
+/// Variant of [`KMeans::update_cluster_assignments`] for the squared euclidean distance, using the decomposition
+/// `|s - c|² = |s|² - 2 s·c + |c|²` with the cached **sample_norms** (see [`KMeans::with_norm_cache`]).
+///
+/// The rounding error of the decomposition grows with the norms instead of the distance. If the runner-up centroid is
+/// within that error of the nearest one, the nearest centroid is determined from the exact distances instead, so
+/// near-ties are resolved as by the plain assignment step.
+pub(crate) fn update_cluster_assignments<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, k: usize, sample_norms: &[T],
+) where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    // Centroids changed since the last assignment step, so their norms have to be recalculated
+    let mut centroid_norms = scratch::vec(k, T::zero());
+    simd::dispatch(|| {
+        centroid_norms
+            .iter_mut()
+            .zip(state.centroids.chunks_exact_stride())
+            .for_each(|(norm, c)| *norm = dot::<T, LANES>(c, c))
+    });
+    state.memory_usage.record_temporaries(size_of_val(centroid_norms.as_slice()));
+    let centroids = &state.centroids;
+    // Bound of the error of the difference of two decomposed distances, relative to the norms (the dot products and
+    // norms are sums of `stride` products, each of which may be off by `stride` epsilons)
+    let relative_error = T::epsilon() * T::from(4 * kmean.p_samples.stride + 8).unwrap();
+    let max_centroid_norm = centroid_norms.iter().cloned().fold(T::zero(), T::max);
+
+    let work_packet_size = kmean.work_packet_size(kmean.sample_cnt);
+    let samples = kmean
+        .p_samples
+        .bfr
+        .par_chunks_exact(kmean.p_samples.stride)
+        .zip(sample_norms.par_iter().cloned())
+        .zip(state.assignments.par_iter_mut())
+        .zip(state.centroid_distances.par_iter_mut());
+    simd::for_each_packet(samples, work_packet_size, |(((s, s_norm), assignment), centroid_dist)| {
+        let (mut best_idx, mut best_dist, mut second_dist) = (0, T::infinity(), T::infinity());
+        centroids
+            .chunks_exact_stride()
+            .zip(centroid_norms.iter().cloned())
+            .enumerate()
+            .for_each(|(idx, (c, c_norm))| {
+                let dist = s_norm + c_norm - T::from(2.0).unwrap() * dot::<T, LANES>(s, c);
+                if dist < best_dist {
+                    (best_idx, best_dist, second_dist) = (idx, dist, best_dist);
+                } else if dist < second_dist {
+                    second_dist = dist;
+                }
+            });
+        if second_dist - best_dist <= relative_error * (s_norm + max_centroid_norm) {
+            (best_idx, best_dist) =
+                centroids
+                    .chunks_exact_stride()
+                    .take(k)
+                    .enumerate()
+                    .fold((0, T::infinity()), |(best_idx, best_dist), (idx, c)| {
+                        let dist = kmean.distance_fn.distance(s, c);
+                        if dist < best_dist {
+                            (idx, dist)
+                        } else {
+                            (best_idx, best_dist)
+                        }
+                    });
+        }
+        *assignment = best_idx;
+        *centroid_dist = best_dist.max(T::zero());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, HistogramDistance, KMeans, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn norm_cache_equals_direct() {
+        let (sample_cnt, sample_dims) = (200, 37);
+        let mut rnd = StdRng::seed_from_u64(1337);
+        let samples: Vec<f64> = (0..sample_cnt * sample_dims)
+            .map(|i| (i / sample_dims % 4) as f64 * 2.0 + rnd.gen::<f64>())
+            .collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let cached: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance).with_norm_cache();
+        assert_eq!(cached.sample_norms.as_ref().map(|n| n.len()), Some(sample_cnt));
+        assert!(cached.estimated_memory(4).samples > kmean.estimated_memory(4).samples);
+
+        let conf = KMeansConfig::default();
+        let centroids = samples[..4 * sample_dims].to_vec();
+        let expected = kmean.kmeans_lloyd(4, 100, KMeans::init_precomputed(centroids.clone()), &conf);
+        let res = cached.kmeans_lloyd(4, 100, KMeans::init_precomputed(centroids), &conf);
+        assert_eq!(res.assignments, expected.assignments);
+        assert!(res
+            .centroid_distances
+            .iter()
+            .zip(expected.centroid_distances.iter())
+            .all(|(a, b)| (a - b).abs() < 1e-9));
+
+        // Other distance functions do not use the cache
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, HistogramDistance).with_norm_cache();
+        assert!(kmean.sample_norms.is_none());
+    }
+}
@@ -0,0 +1,214 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::LearningSchedule;
+use rayon::prelude::*;
+use std::marker::PhantomData;
+
+/// Online k-means (MacQueen) over a stream of samples, that can not be materialized as a whole.
+///
+/// Samples are fed one at a time, or in small slices, using [`OnlineKMeans::update`]. Until `k` samples were seen,
+/// every sample becomes a centroid of its own (unless the centroids were given upfront, see
+/// [`OnlineKMeans::with_centroids`]). Afterwards, every sample is assigned to its nearest centroid, which is moved
+/// towards it using the centroid's own learning rate: `c = (1 - rate) * c + rate * s`. With the default
+/// [`LearningSchedule::InverseCount`], the rate of a centroid is `1 / count`, which keeps every centroid the exact mean
+/// of all samples that were assigned to it. Samples are never reassigned, and no sample is kept in memory.
+///
+/// ## Generics
+/// - `T`: The type of primitive of the samples
+/// - `LANES`: The amount of SIMD lanes to use for the distance calculations
+/// - `D`: The distance function to assign samples with
+///
+/// ## Example
+/// ```rust
+/// use kmeans::*;
+///
+/// let mut online: OnlineKMeans<f64, 8, _> = OnlineKMeans::new(1, 2, EuclideanDistance);
+/// // The first two samples become the centroids
+/// assert_eq!(online.update(&[0.0, 10.0]), vec![0, 1]);
+/// assert_eq!(online.update(&[2.0, 12.0, 1.0]), vec![0, 1, 0]);
+/// assert_eq!(online.centroids(), vec![1.0, 11.0]);
+/// assert_eq!(online.cluster_sizes(), &[3, 2]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct OnlineKMeans<T: Primitive, const LANES: usize, D: DistanceFunction<T, LANES>>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    distance_fn: D,
+    learning_schedule: LearningSchedule<T>,
+    centroids: StrideBuffer<T>,
+    /// Amount of centroids that were initialized so far (the first samples of the stream become the centroids)
+    initialized: usize,
+    /// Amount of samples each centroid has been updated with
+    counts: Vec<usize>,
+    /// Padded copy of the sample that is currently processed
+    sample: StrideBuffer<T>,
+    samples_seen: usize,
+    _p: PhantomData<Simd<T, LANES>>,
+}
+impl<T: Primitive, const LANES: usize, D: DistanceFunction<T, LANES>> OnlineKMeans<T, LANES, D>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    /// Create a new online clustering, whose centroids are initialized with the first **k** samples of the stream.
+    ///
+    /// ## Arguments
+    /// - **sample_dims**: Amount of dimensions of the samples
+    /// - **k**: Amount of clusters
+    /// - **distance_fn**: Distance function to assign samples with
+    pub fn new(sample_dims: usize, k: usize, distance_fn: D) -> Self {
+        assert!(k > 0);
+        let padding = distance_fn.padding_value();
+        Self {
+            distance_fn,
+            learning_schedule: LearningSchedule::InverseCount,
+            centroids: StrideBuffer::new::<LANES>(k, sample_dims).padded(padding),
+            initialized: 0,
+            counts: vec![0; k],
+            sample: StrideBuffer::new::<LANES>(1, sample_dims).padded(padding),
+            samples_seen: 0,
+            _p: PhantomData,
+        }
+    }
+
+    /// Create a new online clustering, starting from the given centroids.
+    ///
+    /// ## Arguments
+    /// - **sample_dims**: Amount of dimensions of the samples
+    /// - **centroids**: Initial centroids [row-major] = [<centroid0>,<centroid1>,...] (e.g. of a previous k-means
+    ///   result on historic samples)
+    /// - **distance_fn**: Distance function to assign samples with
+    pub fn with_centroids(sample_dims: usize, centroids: &[T], distance_fn: D) -> Self {
+        assert!(!centroids.is_empty());
+        let padding = distance_fn.padding_value();
+        let centroids = StrideBuffer::from_slice::<LANES>(sample_dims, centroids).padded(padding);
+        let k = centroids.centroid_cnt;
+        Self {
+            distance_fn,
+            learning_schedule: LearningSchedule::InverseCount,
+            centroids,
+            initialized: k,
+            counts: vec![0; k],
+            sample: StrideBuffer::new::<LANES>(1, sample_dims).padded(padding),
+            samples_seen: 0,
+            _p: PhantomData,
+        }
+    }
+
+    /// Set the learning-rate schedule of the centroid updates. The **count** of the schedule is the amount of samples
+    /// the updated centroid has seen, its **iteration** the amount of samples the whole clustering has seen.
+    ///
+    /// ## Default
+    /// [`LearningSchedule::InverseCount`], which keeps every centroid the exact mean of its samples
+    pub fn learning_schedule(mut self, learning_schedule: LearningSchedule<T>) -> Self {
+        self.learning_schedule = learning_schedule;
+        self
+    }
+
+    /// Feed the given samples [row-major] = [<sample0>,<sample1>,...] into the clustering, one after the other.
+    ///
+    /// ## Returns
+    /// The centroid each sample was assigned to.
+    pub fn update(&mut self, samples: &[T]) -> Vec<usize> {
+        let sample_dims = self.sample.centroid_dim;
+        assert_eq!(samples.len() % sample_dims, 0);
+        samples.chunks_exact(sample_dims).map(|s| self.update_one(s)).collect()
+    }
+
+    /// Assign the given samples [row-major] = [<sample0>,<sample1>,...] to their nearest centroid, without updating
+    /// the centroids.
+    pub fn predict(&self, samples: &[T]) -> Vec<usize> {
+        assert!(self.is_initialized());
+        assert_eq!(samples.len() % self.sample.centroid_dim, 0);
+        let p_samples = StrideBuffer::from_slice::<LANES>(self.sample.centroid_dim, samples).padded(self.distance_fn.padding_value());
+        p_samples.bfr.par_chunks_exact(p_samples.stride).map(|s| self.nearest(s)).collect()
+    }
+
+    /// Current centroids [row-major] = [<centroid0>,<centroid1>,...]. Centroids that were not initialized yet (as
+    /// fewer than `k` samples were seen) are zero.
+    pub fn centroids(&self) -> Vec<T> { self.centroids.to_vec() }
+
+    /// Amount of samples that were assigned to each centroid.
+    pub fn cluster_sizes(&self) -> &[usize] { &self.counts }
+
+    /// Amount of samples that were fed into the clustering.
+    pub fn samples_seen(&self) -> usize { self.samples_seen }
+
+    /// Whether all `k` centroids are initialized.
+    pub fn is_initialized(&self) -> bool { self.initialized == self.counts.len() }
+
+    // #############################################
+    // INTERNAL
+
+    fn update_one(&mut self, sample: &[T]) -> usize {
+        self.sample.set_nth_from_iter(0, sample.iter().cloned());
+        self.samples_seen += 1;
+        if !self.is_initialized() {
+            let centroid = self.initialized;
+            self.centroids.set_nth_from_iter(centroid, sample.iter().cloned());
+            self.counts[centroid] = 1;
+            self.initialized += 1;
+            return centroid;
+        }
+
+        let centroid = self.nearest(self.sample.nth_stride(0));
+        self.counts[centroid] += 1;
+        let rate = self.learning_schedule.learn_rate(self.counts[centroid], self.samples_seen);
+        self.centroids
+            .nth_stride_mut(centroid)
+            .iter_mut()
+            .zip(self.sample.nth_stride(0))
+            .for_each(|(c, &v)| *c += rate * (v - *c));
+        centroid
+    }
+
+    fn nearest(&self, sample: &[T]) -> usize {
+        self.centroids
+            .chunks_exact_stride()
+            .take(self.initialized)
+            .map(|c| self.distance_fn.distance(sample, c))
+            .enumerate()
+            .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+            .map_or(0, |(idx, _)| idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, LearningSchedule, OnlineKMeans};
+
+    #[test]
+    fn macqueen_means() {
+        let mut online: OnlineKMeans<f64, 8, _> = OnlineKMeans::new(2, 2, EuclideanDistance);
+        assert!(!online.is_initialized());
+        assert_eq!(online.update(&[0.0, 0.0]), vec![0]);
+        assert!(!online.is_initialized());
+        assert_eq!(online.update(&[10.0, 10.0]), vec![1]);
+        assert!(online.is_initialized());
+
+        // Feed the stream in small slices, every centroid stays the mean of its samples
+        for i in 1..=100 {
+            let offset = (i % 5) as f64 * 0.1;
+            online.update(&[offset, -offset, 10.0 + offset, 10.0 - offset]);
+        }
+        assert_eq!(online.samples_seen(), 202);
+        assert_eq!(online.cluster_sizes(), &[101, 101]);
+        let centroids = online.centroids();
+        let mean = (1..=100).map(|i| (i % 5) as f64 * 0.1).sum::<f64>() / 101.0;
+        assert!((centroids[0] - mean).abs() < 1e-9);
+        assert!((centroids[1] + mean).abs() < 1e-9);
+        assert!((centroids[2] - 10.0 - mean).abs() < 1e-9);
+        assert_eq!(online.predict(&[1.0, 1.0, 8.0, 9.0]), vec![0, 1]);
+    }
+
+    #[test]
+    fn learning_schedule() {
+        let mut online: OnlineKMeans<f64, 8, _> = OnlineKMeans::with_centroids(1, &[0.0, 10.0], EuclideanDistance)
+            .learning_schedule(LearningSchedule::Exponential { initial: 0.5, decay: 1.0 });
+        assert!(online.is_initialized());
+        assert_eq!(online.update(&[2.0, 4.0, 12.0]), vec![0, 0, 1]);
+        assert_eq!(online.centroids(), vec![2.5, 11.0]);
+    }
+}
@@ -0,0 +1,660 @@
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::variants::EarlyStopping;
+use crate::{KMeans, KMeansConfig, KMeansState, StopReason};
+use rand::prelude::*;
+use std::cell::RefCell;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem::{size_of, size_of_val};
+use std::ops::DerefMut;
+
+/// Source of samples, that are read chunk by chunk instead of being held in memory as a whole (see
+/// [`OutOfCoreKMeans`]).
+///
+/// Implement this for custom storage backends, e.g. a memory-mapped file that is split into pages. Ready-made sources
+/// are [`PagedSamples`] (for samples split over multiple slices, e.g. pages of a memory-mapped file) and
+/// [`ReaderSamples`] (for raw little-endian values, e.g. from a file).
+pub trait SampleSource<T: Primitive> {
+    /// Amount of samples of the source.
+    fn sample_cnt(&self) -> usize;
+    /// Amount of dimensions of each sample.
+    fn sample_dims(&self) -> usize;
+    /// Copy the `out.len() / sample_dims` consecutive samples starting with **first_sample** into **out**
+    /// [row-major] = [<sample0>,<sample1>,...].
+    fn read_samples(&self, first_sample: usize, out: &mut [T]) -> io::Result<()>;
+}
+
+fn out_of_bounds() -> io::Error { io::Error::new(io::ErrorKind::UnexpectedEof, "samples out of bounds of the sample source") }
+
+/// Samples that are split over multiple slices (pages), e.g. the pages of a memory-mapped file.
+/// Every page has to contain whole samples.
+pub struct PagedSamples<'a, T: Primitive> {
+    pages: Vec<&'a [T]>,
+    /// Offset (in values) of the first value of each page
+    page_starts: Vec<usize>,
+    sample_cnt: usize,
+    sample_dims: usize,
+}
+impl<'a, T: Primitive> PagedSamples<'a, T> {
+    /// ## Arguments
+    /// - **pages**: The pages, each in the format [row-major] = [<sample0>,<sample1>,...]
+    /// - **sample_dims**: Amount of dimensions of each sample
+    pub fn new(pages: Vec<&'a [T]>, sample_dims: usize) -> Self {
+        assert!(sample_dims > 0);
+        assert!(pages.iter().all(|p| p.len() % sample_dims == 0));
+        let page_starts = pages
+            .iter()
+            .scan(0, |start, p| {
+                let page_start = *start;
+                *start += p.len();
+                Some(page_start)
+            })
+            .collect();
+        let sample_cnt = pages.iter().map(|p| p.len()).sum::<usize>() / sample_dims;
+        Self {
+            pages,
+            page_starts,
+            sample_cnt,
+            sample_dims,
+        }
+    }
+}
+impl<T: Primitive> SampleSource<T> for PagedSamples<'_, T> {
+    fn sample_cnt(&self) -> usize { self.sample_cnt }
+
+    fn sample_dims(&self) -> usize { self.sample_dims }
+
+    fn read_samples(&self, first_sample: usize, out: &mut [T]) -> io::Result<()> {
+        if first_sample * self.sample_dims + out.len() > self.sample_cnt * self.sample_dims {
+            return Err(out_of_bounds());
+        }
+        let mut offset = first_sample * self.sample_dims;
+        let mut page = self.page_starts.partition_point(|&start| start <= offset).saturating_sub(1);
+        let mut written = 0;
+        while written < out.len() {
+            let src = &self.pages[page][offset - self.page_starts[page]..];
+            let len = src.len().min(out.len() - written);
+            out[written..written + len].copy_from_slice(&src[..len]);
+            written += len;
+            offset += len;
+            page += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Samples stored as raw little-endian values of `T` [row-major] = [<sample0>,<sample1>,...], read from any seekable
+/// reader (e.g. a [`std::fs::File`]).
+pub struct ReaderSamples<T: Primitive, R: Read + Seek> {
+    reader: RefCell<R>,
+    sample_cnt: usize,
+    sample_dims: usize,
+    _p: std::marker::PhantomData<T>,
+}
+impl<T: Primitive, R: Read + Seek> ReaderSamples<T, R> {
+    /// ## Arguments
+    /// - **reader**: Reader of the raw values, from its start to its end
+    /// - **sample_dims**: Amount of dimensions of each sample
+    ///
+    /// ## Returns
+    /// An error, if the length of the reader can not be determined, or does not consist of whole samples.
+    pub fn new(mut reader: R, sample_dims: usize) -> io::Result<Self> {
+        assert!(sample_dims > 0);
+        let sample_bytes = (sample_dims * size_of::<T>()) as u64;
+        let len = reader.seek(SeekFrom::End(0))?;
+        if len % sample_bytes != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "reader does not consist of whole samples",
+            ));
+        }
+        Ok(Self {
+            reader: RefCell::new(reader),
+            sample_cnt: (len / sample_bytes) as usize,
+            sample_dims,
+            _p: std::marker::PhantomData,
+        })
+    }
+}
+impl<T: Primitive, R: Read + Seek> SampleSource<T> for ReaderSamples<T, R> {
+    fn sample_cnt(&self) -> usize { self.sample_cnt }
+
+    fn sample_dims(&self) -> usize { self.sample_dims }
+
+    fn read_samples(&self, first_sample: usize, out: &mut [T]) -> io::Result<()> {
+        let value_size = size_of::<T>();
+        let mut bytes = vec![0u8; size_of_val(out)];
+        let mut reader = self.reader.borrow_mut();
+        reader.seek(SeekFrom::Start((first_sample * self.sample_dims * value_size) as u64))?;
+        reader.read_exact(&mut bytes)?;
+        out.iter_mut().zip(bytes.chunks_exact(value_size)).for_each(|(v, b)| {
+            *v = match value_size {
+                4 => T::from(f32::from_le_bytes(b.try_into().unwrap())),
+                _ => T::from(f64::from_le_bytes(b.try_into().unwrap())),
+            }
+            .unwrap();
+        });
+        Ok(())
+    }
+}
+
+/// Out-of-core k-means, for datasets larger than the available memory.
+///
+/// Other than [`KMeans`], which copies all samples into one owned (SIMD-aligned) buffer, this only holds one chunk of
+/// samples in memory at a time, which is read from a [`SampleSource`] whenever it is needed. Every Lloyd iteration
+/// thus streams once over the whole source, while every Mini-Batch iteration only reads one batch. The centroids are
+/// initialized on a random subsample of one chunk's size, using any of the initialization methods of [`KMeans`].
+///
+/// The returned [`KMeansState`] contains the assignments and distances of all samples, unless disabled with
+/// [`OutOfCoreKMeans::without_assignments`] for sources whose label vector alone exceeds the available memory. The
+/// assignments can then be streamed chunk by chunk with [`OutOfCoreKMeans::for_each_assignment`] or
+/// [`OutOfCoreKMeans::write_assignments`]. The post-processing steps of the configuration (such as the minimum cluster
+/// size, or snapping centroids to samples) are not applied, as they need random access to all samples.
+///
+/// ## Generics
+/// - `T`: The type of primitive of the samples
+/// - `LANES`: The amount of SIMD lanes to use for the distance calculations
+/// - `D`: The distance function to use
+/// - `S`: The source of the samples
+///
+/// ## Example
+/// ```rust
+/// use kmeans::*;
+///
+/// // E.g. the pages of a memory-mapped file
+/// let page0 = vec![0.0f64, 0.0, 1.0, 1.0];
+/// let page1 = vec![10.0f64, 10.0, 11.0, 11.0, 0.5, 0.5];
+/// let source = PagedSamples::new(vec![page0.as_slice(), page1.as_slice()], 2);
+///
+/// let mut kmean: OutOfCoreKMeans<f64, 8, _, _> = OutOfCoreKMeans::new(source, 2, EuclideanDistance);
+/// let result = kmean.kmeans_lloyd(2, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default()).unwrap();
+/// println!("Centroids: {:?}", result.centroids);
+/// ```
+pub struct OutOfCoreKMeans<T: Primitive, const LANES: usize, D: DistanceFunction<T, LANES>, S: SampleSource<T>>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    source: S,
+    chunk_size: usize,
+    /// The currently loaded chunk of samples
+    chunk: KMeans<T, LANES, D>,
+    /// Unpadded values of the currently loaded chunk
+    raw: Vec<T>,
+    /// Whether the result contains the assignments and distances of all samples
+    materialize: bool,
+}
+impl<T: Primitive, const LANES: usize, D: DistanceFunction<T, LANES>, S: SampleSource<T>> OutOfCoreKMeans<T, LANES, D, S>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    /// ## Arguments
+    /// - **source**: Source of the samples
+    /// - **chunk_size**: Maximum amount of samples held in memory at a time (also the amount of samples the centroids
+    ///   are initialized on)
+    /// - **distance_fn**: Distance function to use
+    pub fn new(source: S, chunk_size: usize, distance_fn: D) -> Self {
+        assert!(chunk_size > 0);
+        let sample_dims = source.sample_dims();
+        Self {
+            source,
+            chunk_size,
+            chunk: KMeans::from_slice(&[], 0, sample_dims, distance_fn),
+            raw: Vec::new(),
+            materialize: true,
+        }
+    }
+
+    /// Do not keep the assignments and distances of all samples in the result. The **assignments** and
+    /// **centroid_distances** of the returned [`KMeansState`] are empty, memory only grows with the chunk size and
+    /// **k**. Use [`OutOfCoreKMeans::for_each_assignment`] or [`OutOfCoreKMeans::write_assignments`] to retrieve the
+    /// assignments afterwards.
+    pub fn without_assignments(mut self) -> Self {
+        self.materialize = false;
+        self
+    }
+
+    /// The source of the samples.
+    pub fn source(&self) -> &S { &self.source }
+
+    /// k-Means clustering (Lloyd), with one pass over all chunks of the source per iteration.
+    ///
+    /// ## Arguments
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Limit the maximum amount of iterations (just pass a high number for infinite)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids (on a random subsample)
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result), or the error of the sample source.
+    pub fn kmeans_lloyd<F>(&mut self, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> io::Result<KMeansState<T>>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        let mut state = self.init_state(k, init, config)?;
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (self.source.sample_cnt() * k) as u64,
+            sample_dims: self.source.sample_dims(),
+        });
+
+        for i in 1..=max_iter {
+            let mut sums = StrideBuffer::new::<LANES>(k, self.source.sample_dims());
+            // The samples farthest away from their centroid, as candidates for empty clusters
+            let mut farthest: Vec<(usize, usize, T)> = Vec::new();
+            let mut new_distsum = self.assign_all(&mut state, Some(&mut sums), config, |first_sample, assignments, distances| {
+                farthest.extend(
+                    assignments
+                        .iter()
+                        .zip(distances.iter())
+                        .enumerate()
+                        .map(|(idx, (&assignment, &dist))| (first_sample + idx, assignment, dist)),
+                );
+                if farthest.len() >= 4 * k {
+                    farthest.sort_unstable_by(|(_, _, d0), (_, _, d1)| d1.partial_cmp(d0).unwrap());
+                    farthest.truncate(2 * k);
+                }
+                Ok(())
+            })?;
+            if state.centroid_frequency.contains(&0) {
+                new_distsum -= self.assign_empty_clusters(&mut state, &mut sums, farthest)?;
+            }
+
+            // Move every centroid into the mean of its samples
+            state
+                .centroids
+                .chunks_exact_stride_mut()
+                .zip(sums.chunks_exact_stride())
+                .zip(state.centroid_frequency.iter().cloned())
+                .filter(|(_, cnt)| *cnt > 0)
+                .for_each(|((c, sum), cnt)| {
+                    let cnt = T::from(cnt).unwrap();
+                    c.iter_mut().zip(sum.iter()).for_each(|(c, &sum)| *c = sum / cnt);
+                });
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(&self.chunk, &mut state, i, new_distsum);
+            state.n_iterations = i;
+            if let Some(reason) = stop_criteria.next(&self.chunk, &state, new_distsum) {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = new_distsum;
+        }
+        self.finish(state, config)
+    }
+
+    /// Mini-Batch k-Means clustering, where every batch is a block of consecutive samples at a random position of the
+    /// source. The samples should thus be stored in random order.
+    ///
+    /// ## Description
+    /// Every centroid is moved towards each sample of the batch assigned to it, according to the configured
+    /// [`crate::LearningSchedule`]. The batches only cover small parts of the source, so their inertia is too noisy
+    /// for the abort strategy and the convergence tolerance, which are not applied. The calculation stops after
+    /// **max_iter** batches, or earlier with [`crate::KMeansConfigBuilder::minibatch_max_no_improvement`].
+    ///
+    /// ## Arguments
+    /// - **batch_size**: Amount of samples to use per iteration
+    /// - **k**: Amount of clusters to search for
+    /// - **max_iter**: Maximum amount of iterations (batches)
+    /// - **init**: Initialization-Method to use for the initialization of the **k** centroids (on a random subsample)
+    /// - **config**: [`KMeansConfig`] instance, containing several configuration options for the calculation.
+    ///
+    /// ## Returns
+    /// Instance of [`KMeansState`], containing the final state (result), or the error of the sample source.
+    pub fn kmeans_minibatch<F>(
+        &mut self, batch_size: usize, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+    ) -> io::Result<KMeansState<T>>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        let sample_cnt = self.source.sample_cnt();
+        assert!(batch_size > 0 && batch_size <= sample_cnt);
+        let mut state = self.init_state(k, init, config)?;
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (batch_size * k) as u64,
+            sample_dims: self.source.sample_dims(),
+        });
+        let mut early_stopping =
+            (config.max_no_improvement > 0).then(|| EarlyStopping::new(config.max_no_improvement, batch_size, sample_cnt));
+        let mut batch_state = KMeansState::new::<LANES>(batch_size, self.source.sample_dims(), k);
+
+        for i in 1..=max_iter {
+            let start = config.rnd.borrow_mut().gen_range(0..=sample_cnt - batch_size);
+            self.load(start, batch_size)?;
+            batch_state.centroids.bfr.copy_from_slice(&state.centroids.bfr);
+            self.chunk.update_cluster_assignments(&mut batch_state, None);
+            let new_distsum = config.distsum(&batch_state.centroid_distances, None);
+            let no_improvement = early_stopping
+                .as_mut()
+                .is_some_and(|early_stopping| early_stopping.next(new_distsum / T::from(batch_size).unwrap()));
+
+            self.chunk
+                .p_samples
+                .chunks_exact_stride()
+                .zip(batch_state.assignments.iter().cloned())
+                .for_each(|(s, assignment)| {
+                    state.centroid_frequency[assignment] += 1;
+                    let learn_rate = config.learning_schedule.learn_rate(state.centroid_frequency[assignment], i);
+                    state
+                        .centroids
+                        .nth_stride_mut(assignment)
+                        .iter_mut()
+                        .zip(s.iter())
+                        .for_each(|(c, &s)| *c = (T::one() - learn_rate) * *c + learn_rate * s);
+                });
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(&self.chunk, &mut state, i, new_distsum);
+            state.n_iterations = i;
+            let reason = stop_criteria
+                .next(&self.chunk, &state, new_distsum)
+                .filter(|reason| reason.is_forced());
+            if let Some(reason) = reason.or(no_improvement.then_some(StopReason::NoBatchImprovement)) {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = new_distsum;
+        }
+        self.finish(state, config)
+    }
+
+    /// Assign all samples of the source to the centroids of **state** (e.g. the result of one of the calculations),
+    /// chunk by chunk, without materializing the assignments of all samples.
+    ///
+    /// ## Arguments
+    /// - **state**: State whose centroids the samples are assigned to
+    /// - **f**: Called once per chunk, with the index of the chunk's first sample, the assignments of its samples and
+    ///   their distances to the assigned centroid. Errors are passed through.
+    pub fn for_each_assignment<F>(&mut self, state: &KMeansState<T>, f: F) -> io::Result<()>
+    where
+        F: FnMut(usize, &[usize], &[T]) -> io::Result<()>,
+    {
+        let mut assign_state = KMeansState::new::<LANES>(0, self.source.sample_dims(), state.k);
+        assign_state.centroids = state.centroids.clone();
+        self.assign_all(&mut assign_state, None, &KMeansConfig::default(), f)?;
+        Ok(())
+    }
+
+    /// Assign all samples of the source to the centroids of **state**, and write the assignment of every sample as
+    /// little-endian `u32` into **writer**, chunk by chunk.
+    pub fn write_assignments<W: Write>(&mut self, state: &KMeansState<T>, mut writer: W) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(self.chunk_size * size_of::<u32>());
+        self.for_each_assignment(state, |_, assignments, _| {
+            bytes.clear();
+            assignments.iter().for_each(|&a| bytes.extend_from_slice(&(a as u32).to_le_bytes()));
+            writer.write_all(&bytes)
+        })?;
+        writer.flush()
+    }
+
+    // #############################################
+    // INTERNAL
+
+    /// Load **cnt** samples starting with **first_sample** into the chunk.
+    fn load(&mut self, first_sample: usize, cnt: usize) -> io::Result<()> {
+        let sample_dims = self.source.sample_dims();
+        self.raw.resize(cnt * sample_dims, T::zero());
+        self.source.read_samples(first_sample, &mut self.raw)?;
+        self.fill_chunk(cnt);
+        Ok(())
+    }
+
+    /// Copy the first **cnt** samples of the raw buffer into the (padded) chunk.
+    fn fill_chunk(&mut self, cnt: usize) {
+        let sample_dims = self.source.sample_dims();
+        if self.chunk.p_samples.centroid_cnt != cnt {
+            self.chunk.p_samples = StrideBuffer::new::<LANES>(cnt, sample_dims).padded(self.chunk.distance_fn.padding_value());
+            self.chunk.sample_cnt = cnt;
+        }
+        self.raw
+            .chunks_exact(sample_dims)
+            .take(cnt)
+            .enumerate()
+            .for_each(|(idx, s)| self.chunk.p_samples.set_nth_from_iter(idx, s.iter().cloned()));
+    }
+
+    /// Initialize the centroids on a random subsample of (at most) one chunk's size.
+    fn init_state<F>(&mut self, k: usize, init: F, config: &KMeansConfig<'_, T>) -> io::Result<KMeansState<T>>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        let (sample_cnt, sample_dims) = (self.source.sample_cnt(), self.source.sample_dims());
+        let subsample_cnt = self.chunk_size.min(sample_cnt);
+        assert!(k > 0 && k <= subsample_cnt);
+        let mut subsample_ids = rand::seq::index::sample(config.rnd.borrow_mut().deref_mut(), sample_cnt, subsample_cnt).into_vec();
+        subsample_ids.sort_unstable();
+        self.raw.resize(subsample_cnt * sample_dims, T::zero());
+        for (idx, &sample_id) in subsample_ids.iter().enumerate() {
+            self.source
+                .read_samples(sample_id, &mut self.raw[idx * sample_dims..(idx + 1) * sample_dims])?;
+        }
+        self.fill_chunk(subsample_cnt);
+        let mut subsample_state = KMeansState::new::<LANES>(subsample_cnt, sample_dims, k);
+        init(&self.chunk, &mut subsample_state, config);
+
+        let mut state = KMeansState::new::<LANES>(if self.materialize { sample_cnt } else { 0 }, sample_dims, k);
+        state.distsum = T::infinity();
+        state.centroids = subsample_state.centroids;
+        state
+            .memory_usage
+            .record_temporaries(self.chunk_size * (sample_dims + self.chunk.p_samples.stride) * size_of::<T>());
+        config.notify_init(&self.chunk, &mut state);
+        Ok(state)
+    }
+
+    /// Assign all samples of the source to their nearest centroid, chunk by chunk, and count the samples of each
+    /// cluster. The samples of each cluster are summed up into **sums**, if given. The assignments and distances of
+    /// every chunk are copied into **state** (if it holds all samples) and passed to **f**.
+    ///
+    /// ## Returns
+    /// The sum of the distances of all samples to their centroid.
+    fn assign_all<F>(
+        &mut self, state: &mut KMeansState<T>, mut sums: Option<&mut StrideBuffer<T>>, config: &KMeansConfig<'_, T>, mut f: F,
+    ) -> io::Result<T>
+    where
+        F: FnMut(usize, &[usize], &[T]) -> io::Result<()>,
+    {
+        let sample_cnt = self.source.sample_cnt();
+        let mut chunk_state = KMeansState::new::<LANES>(self.chunk_size.min(sample_cnt), self.source.sample_dims(), state.k);
+        chunk_state.centroids = state.centroids.clone();
+        state.centroid_frequency.iter_mut().for_each(|freq| *freq = 0);
+        state.per_cluster_distsum.iter_mut().for_each(|d| *d = T::zero());
+        let mut distsum = T::zero();
+        for first_sample in (0..sample_cnt).step_by(self.chunk_size) {
+            let cnt = self.chunk_size.min(sample_cnt - first_sample);
+            self.load(first_sample, cnt)?;
+            self.chunk.update_cluster_assignments(&mut chunk_state, None);
+            let (assignments, distances) = (&chunk_state.assignments[..cnt], &chunk_state.centroid_distances[..cnt]);
+            distsum += config.distsum(distances, None);
+
+            if state.assignments.len() == sample_cnt {
+                let range = first_sample..first_sample + cnt;
+                state.assignments[range.clone()].copy_from_slice(assignments);
+                state.centroid_distances[range].copy_from_slice(distances);
+            }
+            f(first_sample, assignments, distances)?;
+            self.chunk
+                .p_samples
+                .chunks_exact_stride()
+                .zip(assignments.iter().cloned().zip(distances.iter().cloned()))
+                .for_each(|(s, (assignment, dist))| {
+                    state.centroid_frequency[assignment] += 1;
+                    state.per_cluster_distsum[assignment] += dist;
+                    if let Some(sums) = sums.as_deref_mut() {
+                        sums.nth_stride_mut(assignment)
+                            .iter_mut()
+                            .zip(s.iter())
+                            .for_each(|(sum, &v)| *sum += v);
+                    }
+                });
+        }
+        Ok(distsum)
+    }
+
+    /// Move the samples with the highest distance to their centroid (that are not alone in their cluster) into the
+    /// empty clusters, like [`KMeans::kmeans_lloyd`] does. The skipped samples are the only samples of their clusters,
+    /// so at most `k` of them precede the (at most `k - 1`) moved samples within the **farthest** `2k` samples.
+    ///
+    /// ## Returns
+    /// The sum of the distances of the moved samples to their previous centroid.
+    fn assign_empty_clusters(
+        &self, state: &mut KMeansState<T>, sums: &mut StrideBuffer<T>, mut farthest: Vec<(usize, usize, T)>,
+    ) -> io::Result<T> {
+        let sample_dims = self.source.sample_dims();
+        let mut sample = vec![T::zero(); sample_dims];
+        let mut moved_distsum = T::zero();
+        farthest.sort_unstable_by(|(_, _, d0), (_, _, d1)| d1.partial_cmp(d0).unwrap());
+        for i in 0..state.k {
+            if state.centroid_frequency[i] != 0 {
+                continue;
+            }
+            // Find the sample with the highest distance to its centroid, that is not alone in its cluster
+            let Some(&(sample_id, prev_centroid_id, dist)) =
+                farthest.iter().find(|(_, assignment, _)| state.centroid_frequency[*assignment] > 1)
+            else {
+                break;
+            };
+            moved_distsum += dist;
+            state.centroid_frequency[prev_centroid_id] -= 1;
+            state.centroid_frequency[i] += 1;
+            state.per_cluster_distsum[prev_centroid_id] -= dist;
+            // Re-Assign found sample to centroid without any samples, which is moved into it
+            farthest
+                .iter_mut()
+                .filter(|(id, _, _)| *id == sample_id)
+                .for_each(|(_, assignment, dist)| (*assignment, *dist) = (i, T::zero()));
+            if state.assignments.len() == self.source.sample_cnt() {
+                state.assignments[sample_id] = i;
+                state.centroid_distances[sample_id] = T::zero();
+            }
+
+            self.source.read_samples(sample_id, &mut sample)?;
+            sums.nth_stride_mut(prev_centroid_id)
+                .iter_mut()
+                .zip(sample.iter())
+                .for_each(|(sum, &v)| *sum -= v);
+            sums.nth_stride_mut(i).iter_mut().zip(sample.iter()).for_each(|(sum, &v)| *sum = v);
+        }
+        Ok(moved_distsum)
+    }
+
+    /// Assign all samples to the final centroids, and calculate the final distsum.
+    fn finish(&mut self, mut state: KMeansState<T>, config: &KMeansConfig<'_, T>) -> io::Result<KMeansState<T>> {
+        state.distsum = self.assign_all(&mut state, None, config, |_, _, _| Ok(()))?;
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EuclideanDistance;
+    use std::io::Cursor;
+
+    #[test]
+    fn paged_reads() {
+        let (page0, page1, page2) = (vec![0.0f32, 1.0, 2.0, 3.0], Vec::<f32>::new(), vec![4.0f32, 5.0]);
+        let source = PagedSamples::new(vec![page0.as_slice(), page1.as_slice(), page2.as_slice()], 2);
+        assert_eq!((source.sample_cnt(), source.sample_dims()), (3, 2));
+        let mut out = vec![0.0; 4];
+        source.read_samples(1, &mut out).unwrap();
+        assert_eq!(out, vec![2.0, 3.0, 4.0, 5.0]);
+        assert!(source.read_samples(2, &mut out).is_err());
+
+        let bytes: Vec<u8> = [0.0f32, 1.0, 2.0, 3.0, 4.0, 5.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let source: ReaderSamples<f32, _> = ReaderSamples::new(Cursor::new(bytes.clone()), 2).unwrap();
+        assert_eq!(source.sample_cnt(), 3);
+        source.read_samples(1, &mut out).unwrap();
+        assert_eq!(out, vec![2.0, 3.0, 4.0, 5.0]);
+        assert!(ReaderSamples::<f32, _>::new(Cursor::new(bytes), 4).is_err());
+    }
+
+    #[test]
+    fn equals_in_memory_lloyd() {
+        let mut rnd = StdRng::seed_from_u64(3);
+        // Three clusters of 3-dimensional samples around (0, 0, 0), (10, 10, 10) and (20, 20, 20)
+        let samples: Vec<f64> = (0..3000).map(|i| (i / 3 % 3) as f64 * 10.0 + rnd.gen::<f64>()).collect();
+        let pages: Vec<&[f64]> = samples.chunks(690).collect();
+        let init = vec![0.0, 0.0, 0.0, 10.0, 10.0, 10.0, 20.0, 20.0, 20.0];
+        let conf = KMeansConfig::default();
+
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 1000, 3, EuclideanDistance);
+        let expected = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(init.clone()), &conf);
+        // Chunks that do not line up with the pages
+        let mut ooc: OutOfCoreKMeans<f64, 8, _, _> = OutOfCoreKMeans::new(PagedSamples::new(pages, 3), 150, EuclideanDistance);
+        let res = ooc.kmeans_lloyd(3, 100, KMeans::init_precomputed(init.clone()), &conf).unwrap();
+
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.centroid_frequency, expected.centroid_frequency);
+        res.centroids
+            .to_vec()
+            .iter()
+            .zip(expected.centroids.to_vec())
+            .for_each(|(a, b)| assert!((a - b).abs() < 1e-9));
+        assert!((res.distsum - expected.distsum).abs() < 1e-6);
+
+        let res = ooc.kmeans_minibatch(100, 3, 50, KMeans::init_precomputed(init), &conf).unwrap();
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.n_iterations, 50);
+    }
+
+    #[test]
+    fn empty_clusters_like_in_memory_lloyd() {
+        // All samples are close to (0, 10, 20), so the outer initial centroids are left without samples
+        let mut rnd = StdRng::seed_from_u64(3);
+        let samples: Vec<f64> = (0..3000).map(|i| (i % 3) as f64 * 10.0 + rnd.gen::<f64>()).collect();
+        let init = vec![0.0, 0.0, 0.0, 10.0, 10.0, 10.0, 20.0, 20.0, 20.0];
+        let conf = KMeansConfig::default();
+
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 1000, 3, EuclideanDistance);
+        let expected = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(init.clone()), &conf);
+        let source = PagedSamples::new(vec![samples.as_slice()], 3);
+        let mut ooc: OutOfCoreKMeans<f64, 8, _, _> = OutOfCoreKMeans::new(source, 150, EuclideanDistance);
+        let res = ooc.kmeans_lloyd(3, 100, KMeans::init_precomputed(init), &conf).unwrap();
+
+        assert!(res.centroid_frequency.iter().all(|&cnt| cnt > 0));
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.centroid_frequency, expected.centroid_frequency);
+        assert!((res.distsum - expected.distsum).abs() < 1e-6);
+    }
+
+    #[test]
+    fn streamed_assignments() {
+        let samples: Vec<f32> = (0..1000).map(|i| (i % 2) as f32 * 10.0 + (i % 7) as f32 * 0.1).collect();
+        let bytes: Vec<u8> = samples.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let init = vec![0.0, 10.0];
+        let conf = KMeansConfig::default();
+
+        let source: ReaderSamples<f32, _> = ReaderSamples::new(Cursor::new(bytes), 1).unwrap();
+        let mut ooc: OutOfCoreKMeans<f32, 8, _, _> = OutOfCoreKMeans::new(source, 64, EuclideanDistance).without_assignments();
+        let res = ooc.kmeans_lloyd(2, 100, KMeans::init_precomputed(init.clone()), &conf).unwrap();
+        assert!(res.assignments.is_empty() && res.centroid_distances.is_empty());
+        assert_eq!(res.centroid_frequency, vec![500, 500]);
+        assert!((res.cluster_inertias().iter().sum::<f32>() - res.distsum).abs() < 1e-3);
+
+        let kmean: KMeans<f32, 8, _> = KMeans::new(&samples, 1000, 1, EuclideanDistance);
+        let expected = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(init), &conf);
+        let mut chunks = Vec::new();
+        let mut streamed = Vec::new();
+        ooc.for_each_assignment(&res, |first_sample, assignments, distances| {
+            assert_eq!(assignments.len(), distances.len());
+            chunks.push(first_sample);
+            streamed.extend_from_slice(assignments);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(chunks, (0..1000).step_by(64).collect::<Vec<_>>());
+        assert_eq!(streamed, expected.assignments);
+
+        let mut written = Vec::new();
+        ooc.write_assignments(&res, &mut written).unwrap();
+        let labels: Vec<usize> = written
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+            .collect();
+        assert_eq!(labels, expected.assignments);
+    }
+}
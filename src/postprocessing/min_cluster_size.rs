@@ -0,0 +1,209 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState, MemoryUsage};
+use std::mem::size_of_val;
+
+/// Cluster that was dissolved by the repair pass of the minimum cluster size constraint.
+///
+/// ## Fields
+/// - **cluster**: Id of the dissolved cluster (before the remaining clusters were re-numbered)
+/// - **size**: Amount of samples the cluster contained when it was dissolved
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DissolvedCluster {
+    pub cluster: usize,
+    pub size: usize,
+}
+
+/// Report of the repair pass, that enforces the minimum cluster size (see [`crate::KMeansConfigBuilder::min_cluster_size`]).
+///
+/// ## Fields
+/// - **dissolved**: Dissolved clusters, in the order they were dissolved
+/// - **reassigned_samples**: Total amount of samples that were moved into another cluster
+/// - **id_map**: Mapping from the cluster ids before the repair to the cluster ids after the repair
+///   (**None** for dissolved clusters)
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClusterSizeRepair {
+    pub dissolved: Vec<DissolvedCluster>,
+    pub reassigned_samples: usize,
+    pub id_map: Vec<Option<usize>>,
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, min_cluster_size: usize)
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    kmean.update_cluster_frequencies(&state.assignments, &mut state.centroid_frequency);
+    let mut alive = vec![true; state.k];
+    let mut dissolved = Vec::new();
+    let mut reassigned_samples = 0;
+
+    // Iteratively dissolve the smallest undersized cluster, and move its samples into the nearest remaining cluster.
+    // Sizes change with every dissolved cluster, so the smallest one has to be searched anew each time.
+    while alive.iter().filter(|&&a| a).count() > 1 {
+        let smallest = (0..state.k)
+            .filter(|&c| alive[c] && state.centroid_frequency[c] < min_cluster_size)
+            .min_by_key(|&c| state.centroid_frequency[c]);
+        let Some(cluster) = smallest else {
+            break;
+        };
+        alive[cluster] = false;
+        dissolved.push(DissolvedCluster {
+            cluster,
+            size: state.centroid_frequency[cluster],
+        });
+        reassigned_samples += dissolve(kmean, state, &alive, cluster);
+    }
+    if dissolved.is_empty() {
+        return;
+    }
+
+    let id_map = compact(kmean, state, &alive, true);
+    state.cluster_size_repair = Some(ClusterSizeRepair {
+        dissolved,
+        reassigned_samples,
+        id_map,
+    });
+}
+
+/// Move all samples of the given (no longer **alive**) cluster into their nearest alive cluster.
+/// ## Returns
+/// Amount of reassigned samples
+pub(crate) fn dissolve<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, alive: &[bool], cluster: usize,
+) -> usize
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let mut reassigned_samples = 0;
+    for sample_id in 0..kmean.sample_cnt {
+        if state.assignments[sample_id] != cluster {
+            continue;
+        }
+        let sample = kmean.p_samples.nth_stride(sample_id);
+        let (best_idx, best_dist) = state
+            .centroids
+            .chunks_exact_stride()
+            .enumerate()
+            .filter(|(c, _)| alive[*c])
+            .map(|(c, centroid)| (c, kmean.distance_fn.distance(sample, centroid)))
+            .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+            .unwrap();
+        state.assignments[sample_id] = best_idx;
+        state.centroid_distances[sample_id] = best_dist;
+        state.centroid_frequency[cluster] -= 1;
+        state.centroid_frequency[best_idx] += 1;
+        reassigned_samples += 1;
+    }
+    reassigned_samples
+}
+
+/// Drop all clusters that are no longer **alive**, and re-number the remaining ones (keeping their order).
+/// If **recompute_centroids** is set, the remaining centroids are recalculated from their (new) members, otherwise
+/// they are kept as they are.
+/// ## Returns
+/// Mapping from the old cluster ids to the new cluster ids (**None** for dropped clusters)
+pub(crate) fn compact<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, alive: &[bool], recompute_centroids: bool,
+) -> Vec<Option<usize>>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let mut id_map = vec![None; state.k];
+    let mut new_k = 0;
+    for c in 0..state.k {
+        if alive[c] {
+            id_map[c] = Some(new_k);
+            new_k += 1;
+        }
+    }
+    let mut new_centroids = StrideBuffer::new::<LANES>(new_k, kmean.sample_dims);
+    let mut new_frequency = vec![0usize; new_k];
+    kmean
+        .p_samples
+        .chunks_exact_stride()
+        .zip(state.assignments.iter_mut())
+        .for_each(|(s, assignment)| {
+            *assignment = id_map[*assignment].unwrap();
+            new_frequency[*assignment] += 1;
+            new_centroids
+                .nth_stride_mut(*assignment)
+                .iter_mut()
+                .zip(s.iter().cloned())
+                .for_each(|(cv, sv)| *cv += sv);
+        });
+    if recompute_centroids {
+        new_centroids
+            .chunks_exact_stride_mut()
+            .zip(new_frequency.iter().cloned())
+            .for_each(|(c, cfreq)| {
+                let cfreq_factor = T::one() / T::from(cfreq).unwrap();
+                c.iter_mut().for_each(|cv| *cv = *cv * cfreq_factor);
+            });
+    } else {
+        (0..state.k)
+            .filter_map(|c| id_map[c].map(|new_id| (c, new_id)))
+            .for_each(|(c, new_id)| new_centroids.nth_stride_mut(new_id).copy_from_slice(state.centroids.nth_stride(c)));
+    }
+
+    state
+        .memory_usage
+        .record_temporaries(size_of_val(new_centroids.bfr.as_slice()) + size_of_val(id_map.as_slice()));
+    state.memory_usage.state = MemoryUsage::new::<T, LANES>(kmean.sample_cnt, kmean.sample_dims, new_k).state;
+    state.k = new_k;
+    state.centroids = new_centroids;
+    state.centroid_frequency = new_frequency;
+    kmean.update_centroid_distances(state);
+    state.distsum = state
+        .centroid_distances
+        .iter()
+        .enumerate()
+        .map(|(sample_id, &d)| d * kmean.sample_weight(sample_id))
+        .sum();
+    id_map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EuclideanDistance, KMeansConfig};
+
+    #[test]
+    fn dissolve_undersized_clusters() {
+        let samples = vec![0.0f64, 0.1, 0.2, 0.3, 10.0, 10.1, 10.2, 10.3, 5.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::build().min_cluster_size(2).build();
+        let res = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![0.0, 5.0, 10.0]), &conf);
+
+        let repair = res.cluster_size_repair.as_ref().unwrap();
+        assert_eq!(repair.dissolved, vec![DissolvedCluster { cluster: 1, size: 1 }]);
+        assert_eq!(repair.reassigned_samples, 1);
+        assert_eq!(repair.id_map, vec![Some(0), None, Some(1)]);
+        assert_eq!(res.k, 2);
+        assert_eq!(res.centroid_frequency.iter().sum::<usize>(), samples.len());
+        assert!(res.centroid_frequency.iter().all(|&f| f >= 2));
+        assert_eq!(res.centroids.centroid_cnt, 2);
+    }
+
+    #[test]
+    fn keeps_valid_clusters() {
+        let samples = vec![0.0f64, 0.1, 0.2, 10.0, 10.1, 10.2];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::build().min_cluster_size(3).build();
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &conf);
+        assert!(res.cluster_size_repair.is_none());
+        assert_eq!(res.centroid_frequency, vec![3, 3]);
+    }
+}
@@ -0,0 +1,29 @@
+pub(crate) mod min_cluster_size;
+pub(crate) mod remove_clusters;
+pub(crate) mod snap_to_samples;
+
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+pub use min_cluster_size::{ClusterSizeRepair, DissolvedCluster};
+pub use snap_to_samples::CentroidSnapping;
+
+/// Apply all post-processing steps enabled in the given config to the final result of a k-means calculation.
+#[inline(always)]
+pub(crate) fn apply<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>)
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    if config.min_cluster_size > 0 {
+        min_cluster_size::calculate(kmean, state, config.min_cluster_size);
+    }
+    if config.snap_to_samples {
+        snap_to_samples::calculate(kmean, state, config);
+    }
+    kmean.update_per_cluster_distsum(state);
+    crate::distance_statistics::finish(kmean, state);
+}
@@ -0,0 +1,59 @@
+use super::min_cluster_size::{compact, dissolve};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, ids: &[usize]) -> Vec<Option<usize>>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let mut alive = vec![true; state.k];
+    ids.iter().for_each(|&c| {
+        assert!(c < state.k, "cluster id {c} out of range (k = {})", state.k);
+        alive[c] = false;
+    });
+    assert!(alive.iter().any(|&a| a), "at least one cluster has to remain");
+
+    kmean.update_cluster_frequencies(&state.assignments, &mut state.centroid_frequency);
+    (0..state.k).filter(|&c| !alive[c]).for_each(|c| {
+        dissolve(kmean, state, &alive, c);
+    });
+    let id_map = compact(kmean, state, &alive, false);
+    kmean.update_per_cluster_distsum(state);
+    if let Some(repair) = state.cluster_size_repair.as_mut() {
+        repair.id_map.iter_mut().for_each(|c| *c = c.and_then(|c| id_map[c]));
+    }
+    if let Some(snapping) = state.centroid_snapping.as_mut() {
+        snapping.exemplars = (0..id_map.len())
+            .filter(|&c| id_map[c].is_some())
+            .map(|c| snapping.exemplars[c])
+            .collect();
+    }
+    id_map
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn remove_clusters() {
+        let samples = vec![0.0f64, 1.0, 2.0, 5.0, 10.0, 11.0, 12.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let mut res = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![1.0, 5.0, 11.0]), &KMeansConfig::default());
+        assert_eq!(res.centroid_frequency, vec![3, 1, 3]);
+
+        let id_map = kmean.remove_clusters(&mut res, &[1]);
+        assert_eq!(id_map, vec![Some(0), None, Some(1)]);
+        assert_eq!(res.k, 2);
+        assert_eq!(res.centroids.to_vec(), vec![1.0, 11.0]);
+        assert_eq!(res.assignments, vec![0, 0, 0, 0, 1, 1, 1]);
+        assert_eq!(res.centroid_frequency, vec![4, 3]);
+        assert_eq!(res.distsum, 2.0 + 16.0 + 2.0);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rayon::prelude::*;
+
+/// Report of the pass, that replaces every centroid with its nearest sample (see
+/// [`crate::KMeansConfigBuilder::snap_centroids_to_samples`]).
+///
+/// ## Fields
+/// - **exemplars**: Id of the sample each centroid was replaced with
+/// - **previous_distsum**: Sum of distances before the centroids were replaced
+/// - **distsum_change**: Change of the sum of distances by replacing the centroids (`distsum - previous_distsum`),
+///   which usually is positive, since the samples are no optimal centers of their clusters
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CentroidSnapping<T> {
+    pub exemplars: Vec<usize>,
+    pub previous_distsum: T,
+    pub distsum_change: T,
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>)
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let exemplars: Vec<usize> = state
+        .centroids
+        .bfr
+        .par_chunks_exact(state.centroids.stride)
+        .map(|centroid| {
+            kmean
+                .p_samples
+                .chunks_exact_stride()
+                .enumerate()
+                .fold((0, T::infinity()), |(best_idx, best_dist), (idx, s)| {
+                    let dist = kmean.distance_fn.distance_bounded(s, centroid, best_dist);
+                    if dist < best_dist {
+                        (idx, dist)
+                    } else {
+                        (best_idx, best_dist)
+                    }
+                })
+                .0
+        })
+        .collect();
+    exemplars.iter().enumerate().for_each(|(c, &sample_id)| {
+        state
+            .centroids
+            .nth_stride_mut(c)
+            .copy_from_slice(kmean.p_samples.nth_stride(sample_id))
+    });
+
+    // Samples may now be closer to another centroid
+    let previous_distsum = state.distsum;
+    kmean.update_cluster_assignments(state, None);
+    kmean.update_cluster_frequencies(&state.assignments, &mut state.centroid_frequency);
+    state.distsum = config.distsum(&state.centroid_distances, kmean.sample_weights.as_deref());
+    state.centroid_snapping = Some(CentroidSnapping {
+        exemplars,
+        previous_distsum,
+        distsum_change: state.distsum - previous_distsum,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn snap_centroids_to_samples() {
+        let samples = vec![0.0f64, 1.0, 3.0, 10.0, 11.0, 13.0, 14.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::build().snap_centroids_to_samples(true).build();
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &conf);
+
+        // The means 4/3 and 12 are replaced with the samples 1 and 11 (ties are resolved to the first sample)
+        let snapping = res.centroid_snapping.as_ref().unwrap();
+        assert_eq!(snapping.exemplars, vec![1, 4]);
+        assert_eq!(res.centroids.to_vec(), vec![1.0, 11.0]);
+        assert_eq!(res.assignments, vec![0, 0, 0, 1, 1, 1, 1]);
+        assert_eq!(res.distsum, 1.0 + 0.0 + 4.0 + 1.0 + 0.0 + 4.0 + 9.0);
+        assert!((snapping.previous_distsum - (14.0 / 3.0 + 10.0)).abs() < 1e-9);
+        assert!((snapping.distsum_change - (res.distsum - snapping.previous_distsum)).abs() < 1e-12);
+        assert!(snapping.distsum_change > 0.0);
+
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &KMeansConfig::default());
+        assert!(res.centroid_snapping.is_none());
+    }
+}
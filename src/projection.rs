@@ -0,0 +1,252 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use rand::prelude::*;
+use rayon::prelude::*;
+use std::io::Write;
+
+/// Maximum amount of power iterations per principal component.
+const PCA_MAX_ITER: usize = 100;
+
+/// Method to project samples to two dimensions with (see [`KMeans::project_2d`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectionMethod {
+    /// Principal component analysis: Project onto the two directions of the highest variance (approximated by power
+    /// iterations), which preserves the spread of the samples as well as possible.
+    Pca,
+    /// Project onto two random (orthonormal) directions, drawn using the given seed. This is cheaper than the PCA for
+    /// many dimensions, but may hide the structure of the samples.
+    Random { seed: u64 },
+}
+
+/// Plot-ready two-dimensional projection of a k-means result, as created by [`KMeans::project_2d`].
+///
+/// ## Fields
+/// - **samples**: Projected coordinates of every sample
+/// - **labels**: Assigned cluster of every sample
+/// - **centroids**: Projected coordinates of every centroid
+/// - **axes**: Both (unit-length) directions the samples were projected onto, in the space of the samples
+///
+/// Both coordinates are relative to the mean of all samples.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Projection2D<T: Primitive> {
+    pub samples: Vec<[T; 2]>,
+    pub labels: Vec<usize>,
+    pub centroids: Vec<[T; 2]>,
+    pub axes: [Vec<T>; 2],
+}
+impl<T: Primitive> Projection2D<T> {
+    /// Write this projection as CSV, with the columns `kind` (`sample` or `centroid`), `index`, `cluster`, `x` and `y`.
+    /// Every sample and every centroid is written as one row, all samples first.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writeln!(writer, "kind,index,cluster,x,y")?;
+        for (idx, ([x, y], label)) in self.samples.iter().zip(self.labels.iter()).enumerate() {
+            writeln!(writer, "sample,{idx},{label},{x},{y}")?;
+        }
+        for (idx, [x, y]) in self.centroids.iter().enumerate() {
+            writeln!(writer, "centroid,{idx},{idx},{x},{y}")?;
+        }
+        Ok(())
+    }
+
+    /// Write this projection as JSON object, with the fields `samples` and `centroids` (arrays of `[x, y]` pairs) and
+    /// `labels` (array of the assigned clusters).
+    pub fn write_json<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let points = |points: &[[T; 2]]| points.iter().map(|[x, y]| format!("[{x},{y}]")).collect::<Vec<_>>().join(",");
+        let labels = self.labels.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(",");
+        write!(
+            writer,
+            "{{\"samples\":[{}],\"labels\":[{}],\"centroids\":[{}]}}",
+            points(&self.samples),
+            labels,
+            points(&self.centroids)
+        )
+    }
+}
+
+/// Dot product of `sample - mean` and **axis**.
+fn project<T: Primitive>(sample: &[T], mean: &[T], axis: &[T]) -> T {
+    sample
+        .iter()
+        .zip(mean.iter())
+        .zip(axis.iter())
+        .map(|((&s, &m), &a)| (s - m) * a)
+        .sum()
+}
+
+/// Remove the components along all **previous** (orthonormal) axes from **axis**, and scale it to unit length.
+/// ## Returns
+/// Whether **axis** had a length larger than zero.
+fn orthonormalize<T: Primitive>(axis: &mut [T], previous: &[Vec<T>]) -> bool {
+    for p in previous {
+        let dot: T = axis.iter().zip(p.iter()).map(|(&a, &b)| a * b).sum();
+        axis.iter_mut().zip(p.iter()).for_each(|(a, &b)| *a -= dot * b);
+    }
+    let norm = axis.iter().map(|&a| a * a).sum::<T>().sqrt();
+    if norm > T::zero() {
+        axis.iter_mut().for_each(|a| *a = *a / norm);
+    }
+    norm > T::zero()
+}
+
+/// Power iteration on the (implicit) covariance matrix of the samples, for the principal component orthogonal to
+/// all **previous** ones. Every iteration is one parallel pass over the samples, without building the matrix.
+fn principal_component<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, mean: &[T], previous: &[Vec<T>], mut axis: Vec<T>) -> Vec<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let dims = kmean.sample_dims;
+    let tolerance = T::from(1e-6).unwrap();
+    orthonormalize(&mut axis, previous);
+    for _ in 0..PCA_MAX_ITER {
+        let mut next = kmean
+            .p_samples
+            .bfr
+            .par_chunks_exact(kmean.p_samples.stride)
+            .map(|s| &s[..dims])
+            .fold(
+                || vec![T::zero(); dims],
+                |mut acc, s| {
+                    let coordinate = project(s, mean, &axis);
+                    acc.iter_mut()
+                        .zip(s.iter().zip(mean.iter()))
+                        .for_each(|(a, (&s, &m))| *a += (s - m) * coordinate);
+                    acc
+                },
+            )
+            .reduce(
+                || vec![T::zero(); dims],
+                |mut a, b| {
+                    a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+                    a
+                },
+            );
+        // No variance left in the remaining directions: any orthogonal axis is as good as another
+        if !orthonormalize(&mut next, previous) {
+            break;
+        }
+        let change = next.iter().zip(axis.iter()).map(|(&n, &a)| (n - a).abs()).fold(T::zero(), T::max);
+        axis = next;
+        if change < tolerance {
+            break;
+        }
+    }
+    axis
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>, method: ProjectionMethod) -> Projection2D<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let dims = kmean.sample_dims;
+    let mut mean = vec![T::zero(); dims];
+    kmean
+        .p_samples
+        .iter()
+        .for_each(|s| mean.iter_mut().zip(s.iter()).for_each(|(m, &v)| *m += v));
+    mean.iter_mut().for_each(|m| *m = *m / T::from(kmean.sample_cnt).unwrap());
+
+    // Random start directions (also for the power iterations, which then almost surely are not orthogonal to the
+    // principal components)
+    let (seed, pca) = match method {
+        ProjectionMethod::Pca => (0, true),
+        ProjectionMethod::Random { seed } => (seed, false),
+    };
+    let mut rnd = StdRng::seed_from_u64(seed);
+    let mut axes: Vec<Vec<T>> = Vec::with_capacity(2);
+    for _ in 0..2 {
+        let mut axis: Vec<T> = (0..dims).map(|_| rnd.gen_range(-T::one()..T::one())).collect();
+        if pca {
+            axis = principal_component(kmean, &mean, &axes, axis);
+        } else {
+            orthonormalize(&mut axis, &axes);
+        }
+        axes.push(axis);
+    }
+
+    let project_2d = |s: &[T]| [project(s, &mean, &axes[0]), project(s, &mean, &axes[1])];
+    let samples = kmean
+        .p_samples
+        .bfr
+        .par_chunks_exact(kmean.p_samples.stride)
+        .map(|s| project_2d(&s[..dims]))
+        .collect();
+    let centroids = state.centroids.iter().map(project_2d).collect();
+    let [x, y]: [Vec<T>; 2] = axes.try_into().unwrap();
+    Projection2D {
+        samples,
+        labels: state.assignments.clone(),
+        centroids,
+        axes: [x, y],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EuclideanDistance, KMeansConfig};
+
+    #[test]
+    fn pca_projection() {
+        // Two clusters along the direction (1, 1, 0), with a small (uncorrelated) spread along (0, 0, 1)
+        let samples = vec![
+            0.0f64, 0.0, 0.1, 1.0, 1.0, -0.1, 2.0, 2.0, 0.0, 10.0, 10.0, 0.0, 11.0, 11.0, -0.1, 12.0, 12.0, 0.1,
+        ];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 6, 3, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(
+            2,
+            100,
+            KMeans::init_precomputed(vec![0.0, 0.0, 0.0, 10.0, 10.0, 0.0]),
+            &KMeansConfig::default(),
+        );
+
+        let projection = kmean.project_2d(&res, ProjectionMethod::Pca);
+        assert_eq!(projection.labels, res.assignments);
+        let axis = &projection.axes[0];
+        let sign = axis[0].signum();
+        assert!((axis[0] * sign - 0.5f64.sqrt()).abs() < 1e-6);
+        assert!((axis[1] * sign - 0.5f64.sqrt()).abs() < 1e-6);
+        assert!(axis[2].abs() < 1e-3);
+        assert!(projection.axes[1][2].abs() > 0.99);
+
+        // Distances along the first axis are preserved
+        let first = |p: [f64; 2]| p[0] * sign;
+        assert!((first(projection.samples[5]) - first(projection.samples[0]) - 12.0 * 2.0f64.sqrt()).abs() < 1e-3);
+        assert!((first(projection.centroids[1]) - first(projection.centroids[0]) - 10.0 * 2.0f64.sqrt()).abs() < 1e-3);
+
+        let mut csv = Vec::new();
+        projection.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 1 + 6 + 2);
+        assert!(csv.lines().nth(7).unwrap().starts_with("centroid,0,0,"));
+
+        let mut json = Vec::new();
+        projection.write_json(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.starts_with("{\"samples\":[["));
+        assert!(json.contains("\"labels\":[0,0,0,1,1,1]"));
+    }
+
+    #[test]
+    fn random_projection() {
+        let samples: Vec<f32> = (0..200).map(|i| (i % 7) as f32).collect();
+        let kmean: KMeans<f32, 8, _> = KMeans::new(&samples, 20, 10, EuclideanDistance);
+        let res = kmean.kmeans_lloyd(3, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+        let projection = kmean.project_2d(&res, ProjectionMethod::Random { seed: 42 });
+        assert_eq!(projection.samples.len(), 20);
+        assert_eq!(projection.centroids.len(), 3);
+        let dot: f32 = projection.axes[0].iter().zip(projection.axes[1].iter()).map(|(a, b)| a * b).sum();
+        assert!(dot.abs() < 1e-5);
+        let norm: f32 = projection.axes[1].iter().map(|a| a * a).sum();
+        assert!((norm - 1.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::KMeansState;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, RwLock};
+
+/// Fitted model within a [`ModelRegistry`].
+///
+/// ## Fields
+/// - **version**: Version the model was registered with (unique within its registry, increasing with every
+///   registration)
+/// - **centroids**: Centroids of the k-means result, the model was registered from
+#[derive(Clone, Debug)]
+pub struct RegisteredModel<T: Primitive> {
+    pub version: u64,
+    pub centroids: StrideBuffer<T>,
+}
+
+#[derive(Debug)]
+struct Models<T: Primitive> {
+    by_name: HashMap<String, Arc<RegisteredModel<T>>>,
+    next_version: u64,
+}
+
+/// Registry of multiple named, fitted models, for services hosting several clusterings at once.
+///
+/// Only the centroids of each registered result are kept, so models do not hold on to the samples they were trained
+/// with. Registering a model under an existing name atomically swaps it (e.g. after retraining): Predictions that
+/// are in progress finish with the model they started with, while all following predictions use the new one.
+/// Predictions never block each other, and only briefly wait for a concurrent swap.
+///
+/// ## Generics
+/// - `T`: The type of primitive of the models
+/// - `LANES`: The amount of SIMD lanes, the models were calculated with
+/// - `D`: The distance function to assign samples with
+///
+/// ## Example
+/// ```rust
+/// use kmeans::*;
+///
+/// let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0];
+/// let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+/// let registry: ModelRegistry<f64, 8, _> = ModelRegistry::new(1, EuclideanDistance);
+///
+/// let result = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![1.0, 11.0]), &KMeansConfig::default());
+/// let version = registry.register("sensors", &result);
+/// assert_eq!(registry.predict("sensors", &[0.5, 10.5]), Some((version, vec![0, 1])));
+/// assert_eq!(registry.predict("unknown", &[0.5]), None);
+/// ```
+#[derive(Debug)]
+pub struct ModelRegistry<T: Primitive, const LANES: usize, D: DistanceFunction<T, LANES>>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    sample_dims: usize,
+    distance_fn: D,
+    models: RwLock<Models<T>>,
+    _p: PhantomData<Simd<T, LANES>>,
+}
+impl<T: Primitive, const LANES: usize, D: DistanceFunction<T, LANES>> ModelRegistry<T, LANES, D>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    /// Create a new, empty registry.
+    ///
+    /// ## Arguments
+    /// - **sample_dims**: Amount of dimensions of the samples of all models
+    /// - **distance_fn**: Distance function to assign samples with
+    pub fn new(sample_dims: usize, distance_fn: D) -> Self {
+        Self {
+            sample_dims,
+            distance_fn,
+            models: RwLock::new(Models {
+                by_name: HashMap::new(),
+                next_version: 1,
+            }),
+            _p: PhantomData,
+        }
+    }
+
+    /// Register the centroids of the given result under the given name, atomically replacing a previously
+    /// registered model of that name.
+    ///
+    /// ## Returns
+    /// The version of the registered model.
+    pub fn register(&self, name: &str, state: &KMeansState<T>) -> u64 {
+        assert_eq!(state.centroids.centroid_dim, self.sample_dims);
+        assert_eq!(state.centroids.stride, crate::helpers::multiple_roundup(self.sample_dims, LANES));
+        let mut models = self.models.write().unwrap();
+        let version = models.next_version;
+        models.next_version += 1;
+        let model = RegisteredModel {
+            version,
+            centroids: state.centroids.clone().padded(self.distance_fn.padding_value()),
+        };
+        models.by_name.insert(name.to_string(), Arc::new(model));
+        version
+    }
+
+    /// Remove the model with the given name.
+    ///
+    /// ## Returns
+    /// The removed model, if one was registered under that name.
+    pub fn remove(&self, name: &str) -> Option<Arc<RegisteredModel<T>>> { self.models.write().unwrap().by_name.remove(name) }
+
+    /// Current model with the given name. The returned snapshot stays valid, even if the model is swapped afterwards.
+    pub fn get(&self, name: &str) -> Option<Arc<RegisteredModel<T>>> { self.models.read().unwrap().by_name.get(name).cloned() }
+
+    /// Names of all registered models (in arbitrary order).
+    pub fn names(&self) -> Vec<String> { self.models.read().unwrap().by_name.keys().cloned().collect() }
+
+    /// Assign the given samples to their nearest centroid of the model with the given name.
+    ///
+    /// ## Arguments
+    /// - **name**: Name of the model to use
+    /// - **samples**: Samples to assign [row-major] = [<sample0>,<sample1>,...], with the dimensions of the registry
+    ///
+    /// ## Returns
+    /// Tuple of (version of the used model, nearest centroid of each sample), or **None** if no model is registered
+    /// under that name.
+    pub fn predict(&self, name: &str, samples: &[T]) -> Option<(u64, Vec<usize>)> {
+        assert_eq!(samples.len() % self.sample_dims, 0);
+        let model = self.get(name)?;
+        let p_samples = StrideBuffer::from_slice::<LANES>(self.sample_dims, samples).padded(self.distance_fn.padding_value());
+        let assignments = p_samples
+            .bfr
+            .par_chunks_exact(p_samples.stride)
+            .map(|s| {
+                model
+                    .centroids
+                    .chunks_exact_stride()
+                    .map(|c| self.distance_fn.distance(s, c))
+                    .enumerate()
+                    .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+                    .map_or(0, |(idx, _)| idx)
+            })
+            .collect();
+        Some((model.version, assignments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig, ModelRegistry};
+
+    #[test]
+    fn swap_models_during_predictions() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::default();
+        let old = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![1.0, 11.0]), &conf);
+        let new = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![11.0, 1.0]), &conf);
+
+        let registry: ModelRegistry<f64, 8, _> = ModelRegistry::new(1, EuclideanDistance);
+        let old_version = registry.register("a", &old);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..100 {
+                        // Every prediction consistently uses one of both models
+                        let (version, assignments) = registry.predict("a", &[0.0, 12.0]).unwrap();
+                        match version {
+                            v if v == old_version => assert_eq!(assignments, vec![0, 1]),
+                            _ => assert_eq!(assignments, vec![1, 0]),
+                        }
+                    }
+                });
+            }
+            registry.register("a", &new);
+        });
+        assert_eq!(registry.get("a").unwrap().version, old_version + 1);
+        assert_eq!(registry.predict("a", &[0.0]).unwrap().1, vec![1]);
+
+        registry.register("b", &old);
+        let mut names = registry.names();
+        names.sort();
+        assert_eq!(names, vec!["a", "b"]);
+        assert!(registry.remove("a").is_some());
+        assert!(registry.predict("a", &[0.0]).is_none());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::helpers;
+use crate::memory::*;
+use std::any::Any;
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+
+/// Maximum amount of buffers kept in the pool of each thread.
+const MAX_POOLED: usize = 16;
+
+thread_local! {
+    /// Buffers that were handed back after use, ready to be handed out again on this thread.
+    static POOL: RefCell<Vec<Box<dyn Any>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Scratch buffer borrowed from the pool of the current thread, for the per-iteration temporaries of the assignment
+/// and update steps. Many short calculations executed back-to-back thus reuse the same allocations, instead of
+/// allocating and freeing them in every iteration. The buffer is handed back to the pool of the thread it is dropped
+/// on.
+pub(crate) struct Scratch<B: Any>(Option<B>);
+impl<B: Any> Deref for Scratch<B> {
+    type Target = B;
+
+    #[inline(always)]
+    fn deref(&self) -> &B { self.0.as_ref().unwrap() }
+}
+impl<B: Any> DerefMut for Scratch<B> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut B { self.0.as_mut().unwrap() }
+}
+impl<B: Any> Drop for Scratch<B> {
+    fn drop(&mut self) {
+        let bfr = self.0.take().unwrap();
+        // The pool is already gone while the thread shuts down, the buffer is then simply freed
+        let _ = POOL.try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < MAX_POOLED {
+                pool.push(Box::new(bfr));
+            }
+        });
+    }
+}
+
+/// Take the first pooled buffer of type **B** that is **reusable**, if there is any.
+fn take<B: Any>(reusable: impl Fn(&B) -> bool) -> Option<B> {
+    POOL.try_with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let idx = pool.iter().position(|bfr| bfr.downcast_ref::<B>().is_some_and(&reusable))?;
+        pool.swap_remove(idx).downcast::<B>().ok().map(|bfr| *bfr)
+    })
+    .ok()
+    .flatten()
+}
+
+/// Vector of **len** times **value**, reusing a pooled allocation.
+pub(crate) fn vec<V: Clone + 'static>(len: usize, value: V) -> Scratch<Vec<V>> {
+    let mut bfr = take::<Vec<V>>(|bfr| bfr.capacity() >= len)
+        .or_else(|| take::<Vec<V>>(|_| true))
+        .unwrap_or_default();
+    bfr.clear();
+    bfr.resize(len, value);
+    Scratch(Some(bfr))
+}
+
+/// Zeroed [`StrideBuffer`] with the given shape (as created by [`StrideBuffer::new`]), reusing a pooled buffer of the
+/// same shape and alignment.
+pub(crate) fn stride_buffer<T: Primitive, const LANES: usize>(centroid_cnt: usize, centroid_dim: usize) -> Scratch<StrideBuffer<T>> {
+    let stride = helpers::multiple_roundup(centroid_dim, LANES);
+    let alignment = LANES * std::mem::size_of::<T>();
+    let bfr =
+        match take::<StrideBuffer<T>>(|bfr| bfr.stride == stride && bfr.centroid_cnt == centroid_cnt && bfr.bfr.alignment() == alignment) {
+            Some(mut bfr) => {
+                bfr.bfr.fill(T::zero());
+                bfr.centroid_dim = centroid_dim;
+                bfr
+            },
+            None => StrideBuffer::new::<LANES>(centroid_cnt, centroid_dim),
+        };
+    Scratch(Some(bfr))
+}
+
+/// Free all scratch buffers that are pooled by the threads of the current rayon thread pool, and the calling thread.
+///
+/// ## Description
+/// The assignment and update steps of the k-means calculations keep their per-iteration temporaries in thread-local
+/// pools, so many short calculations executed back-to-back do not allocate them again and again. The pooled buffers
+/// are sized for the largest calculations that ran on each thread, which can be released with this function once
+/// no further calculations are expected.
+pub fn release_scratch_buffers() {
+    let release = || {
+        let _ = POOL.try_with(|pool| pool.borrow_mut().clear());
+    };
+    rayon::broadcast(|_| release());
+    release();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pooled() -> usize { POOL.with(|pool| pool.borrow().len()) }
+
+    #[test]
+    fn reuse() {
+        release_scratch_buffers();
+        let ptr = {
+            let mut bfr = vec(100, 1.0f64);
+            bfr[99] = 2.0;
+            bfr.as_ptr()
+        };
+        assert_eq!(pooled(), 1);
+        let bfr = vec(50, 0.0f64);
+        assert_eq!((bfr.as_ptr(), bfr.len()), (ptr, 50));
+        assert!(bfr.iter().all(|&v| v == 0.0));
+        assert_eq!(pooled(), 0);
+        drop(bfr);
+
+        let ptr = {
+            let mut bfr = stride_buffer::<f32, 8>(3, 5);
+            bfr.bfr.fill(1.0);
+            bfr.bfr.as_ptr()
+        };
+        // Different shape or alignment
+        assert_ne!(stride_buffer::<f32, 8>(4, 5).bfr.as_ptr(), ptr);
+        assert_ne!(stride_buffer::<f32, 4>(3, 5).bfr.as_ptr(), ptr);
+        let bfr = stride_buffer::<f32, 8>(3, 6);
+        assert_eq!((bfr.bfr.as_ptr(), bfr.centroid_dim), (ptr, 6));
+        assert!(bfr.bfr.iter().all(|&v| v == 0.0));
+        drop(bfr);
+
+        release_scratch_buffers();
+        assert_eq!(pooled(), 0);
+    }
+}
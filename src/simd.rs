@@ -0,0 +1,336 @@
+//! SIMD vector types, as used in the bounds of the generic API (e.g. `Simd<T, LANES>: SupportedSimdArray<T, LANES>`).
+//!
+//! By default, these are re-exports of the nightly-only `std::simd` (`portable_simd`). With the `stable` feature, they
+//! are replaced by an emulation on plain arrays, which compiles on stable toolchains: The compiler vectorizes its
+//! loops itself, and the assignment and update passes are compiled once per SIMD extension and dispatched at runtime to
+//! the widest extension of the running CPU (see [`crate::SimdLevel::detect`]). Code that is generic over the `LANES` of
+//! [`crate::KMeans`] should import these types from here, so it compiles with both backends.
+
+#[cfg(feature = "stable")]
+pub use emulated::{LaneCount, Simd, SimdElement, SimdFloat, SupportedLaneCount};
+use rayon::prelude::*;
+#[cfg(not(feature = "stable"))]
+pub use std::simd::num::SimdFloat;
+#[cfg(not(feature = "stable"))]
+pub use std::simd::{LaneCount, Simd, SimdElement, SupportedLaneCount};
+
+/// Work packet size (in samples) of the dispatched passes, whose cost per sample varies too much to split them into
+/// one packet per thread (see [`for_each_packet`]).
+pub(crate) const PACKET_SIZE: usize = 256;
+
+/// Run the given **kernel** (e.g. one work packet of an assignment or update pass).
+///
+/// With the `stable` feature, the kernel is compiled for every SIMD level of the target architecture (AVX-512, AVX2
+/// and SSE2 on x86, NEON on aarch64), and run with the one detected at runtime (see [`crate::SimdLevel::detect`]). On
+/// [`crate::SimdLevel::Scalar`] and other architectures, it is run for the build's target features. The distance
+/// kernels have to be inlined into the dispatched functions to be compiled for their target features, so everything
+/// the kernel calls should be `#[inline(always)]`. Dispatching costs a branch, so it is done once per work packet, not
+/// per distance. Without the `stable` feature, the kernel is run as is (for the build's target features, see
+/// [`crate::SimdLevel`]).
+#[inline(always)]
+#[allow(unreachable_code)] // With the `stable` feature on x86 and aarch64, every level returns early
+pub(crate) fn dispatch<R>(kernel: impl FnOnce() -> R) -> R {
+    #[cfg(all(feature = "stable", any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        use crate::SimdLevel;
+
+        #[target_feature(enable = "avx512f")]
+        unsafe fn avx512<R>(kernel: impl FnOnce() -> R) -> R { kernel() }
+
+        #[target_feature(enable = "avx2")]
+        unsafe fn avx2<R>(kernel: impl FnOnce() -> R) -> R { kernel() }
+
+        #[target_feature(enable = "sse2")]
+        unsafe fn sse2<R>(kernel: impl FnOnce() -> R) -> R { kernel() }
+
+        // SAFETY: The running CPU supports the target features of the detected level
+        return match SimdLevel::detect() {
+            SimdLevel::Avx512 => unsafe { avx512(kernel) },
+            SimdLevel::Avx2 => unsafe { avx2(kernel) },
+            SimdLevel::Vector128 => unsafe { sse2(kernel) },
+            SimdLevel::Scalar => kernel(),
+        };
+    }
+    #[cfg(all(feature = "stable", target_arch = "aarch64"))]
+    {
+        use crate::SimdLevel;
+
+        #[target_feature(enable = "neon")]
+        unsafe fn neon<R>(kernel: impl FnOnce() -> R) -> R { kernel() }
+
+        // SAFETY: The running CPU supports the target features of the detected level
+        return match SimdLevel::detect() {
+            SimdLevel::Vector128 => unsafe { neon(kernel) },
+            _ => kernel(),
+        };
+    }
+    kernel()
+}
+
+/// Run **op** for all **items** of a pass, in work packets of **packet_size** items (at least `1`), which are each
+/// [`dispatch`]ed once.
+pub(crate) fn for_each_packet<I, F>(items: I, packet_size: usize, op: F)
+where
+    I: IndexedParallelIterator,
+    F: Fn(I::Item) + Sync + Send,
+{
+    items
+        .chunks(packet_size.max(1))
+        .for_each(|packet| dispatch(|| packet.into_iter().for_each(&op)));
+}
+
+/// Map all **items** of a pass with **op**, in work packets of **packet_size** items (at least `1`), which are each
+/// [`dispatch`]ed once.
+pub(crate) fn map_packets<I, F, R>(items: I, packet_size: usize, op: F) -> Vec<R>
+where
+    I: IndexedParallelIterator,
+    F: Fn(I::Item) -> R + Sync + Send,
+    R: Send,
+{
+    items
+        .chunks(packet_size.max(1))
+        .flat_map_iter(|packet| dispatch(|| packet.into_iter().map(&op).collect::<Vec<_>>()))
+        .collect()
+}
+
+#[cfg(feature = "stable")]
+mod emulated {
+    use std::{iter, ops};
+
+    mod sealed {
+        pub trait Sealed {}
+    }
+    use sealed::Sealed;
+
+    /// Amount of lanes of a [`Simd`] vector (powers of two up to `64`, as in `std::simd`).
+    pub struct LaneCount<const N: usize>;
+
+    pub trait SupportedLaneCount: Sealed {}
+
+    macro_rules! supported_lane_counts {
+        ($($n:literal),*) => {$(
+            impl Sealed for LaneCount<$n> {}
+            impl SupportedLaneCount for LaneCount<$n> {}
+        )*};
+    }
+    supported_lane_counts!(1, 2, 4, 8, 16, 32, 64);
+
+    /// Primitive that can be stored in the lanes of a [`Simd`] vector.
+    pub trait SimdElement: Sealed + Copy + num::Float {}
+    impl Sealed for f32 {}
+    impl SimdElement for f32 {}
+    impl Sealed for f64 {}
+    impl SimdElement for f64 {}
+
+    /// Vector of **N** lanes of the primitive **T**, with the subset of the `std::simd::Simd` API this crate uses.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[repr(transparent)]
+    pub struct Simd<T, const N: usize>([T; N])
+    where
+        T: SimdElement,
+        LaneCount<N>: SupportedLaneCount;
+
+    impl<T, const N: usize> Simd<T, N>
+    where
+        T: SimdElement,
+        LaneCount<N>: SupportedLaneCount,
+    {
+        /// Vector with all lanes set to **value**.
+        #[inline(always)]
+        pub fn splat(value: T) -> Self { Self([value; N]) }
+
+        /// Vector of the first **N** values of **slice**.
+        ///
+        /// ## Panics
+        /// If **slice** is shorter than **N**.
+        #[inline(always)]
+        pub fn from_slice(slice: &[T]) -> Self {
+            assert!(slice.len() >= N, "slice length must be at least the number of lanes");
+            let mut lanes = [T::zero(); N];
+            lanes.copy_from_slice(&slice[..N]);
+            Self(lanes)
+        }
+
+        /// Write the lanes into the first **N** values of **slice**.
+        ///
+        /// ## Panics
+        /// If **slice** is shorter than **N**.
+        #[inline(always)]
+        pub fn copy_to_slice(self, slice: &mut [T]) { slice[..N].copy_from_slice(&self.0) }
+
+        #[inline(always)]
+        pub const fn from_array(lanes: [T; N]) -> Self { Self(lanes) }
+
+        #[inline(always)]
+        pub const fn to_array(self) -> [T; N] { self.0 }
+
+        #[inline(always)]
+        pub const fn as_array(&self) -> &[T; N] { &self.0 }
+
+        #[inline(always)]
+        pub fn as_mut_array(&mut self) -> &mut [T; N] { &mut self.0 }
+
+        #[inline(always)]
+        fn zip_with(mut self, rhs: Self, op: impl Fn(T, T) -> T) -> Self {
+            self.0.iter_mut().zip(rhs.0).for_each(|(a, b)| *a = op(*a, b));
+            self
+        }
+
+        #[inline(always)]
+        fn map(mut self, op: impl Fn(T) -> T) -> Self {
+            self.0.iter_mut().for_each(|a| *a = op(*a));
+            self
+        }
+    }
+
+    impl<T, const N: usize> Default for Simd<T, N>
+    where
+        T: SimdElement,
+        LaneCount<N>: SupportedLaneCount,
+    {
+        #[inline(always)]
+        fn default() -> Self { Self::splat(T::zero()) }
+    }
+
+    macro_rules! lanewise_ops {
+        ($($op:ident::$fn:ident, $op_assign:ident::$fn_assign:ident;)*) => {$(
+            impl<T, const N: usize> ops::$op for Simd<T, N>
+            where
+                T: SimdElement,
+                LaneCount<N>: SupportedLaneCount,
+            {
+                type Output = Self;
+
+                #[inline(always)]
+                fn $fn(self, rhs: Self) -> Self { self.zip_with(rhs, ops::$op::$fn) }
+            }
+
+            impl<T, const N: usize> ops::$op_assign for Simd<T, N>
+            where
+                T: SimdElement,
+                LaneCount<N>: SupportedLaneCount,
+            {
+                #[inline(always)]
+                fn $fn_assign(&mut self, rhs: Self) { *self = ops::$op::$fn(*self, rhs) }
+            }
+        )*};
+    }
+    lanewise_ops! {
+        Add::add, AddAssign::add_assign;
+        Sub::sub, SubAssign::sub_assign;
+        Mul::mul, MulAssign::mul_assign;
+        Div::div, DivAssign::div_assign;
+    }
+
+    impl<T, const N: usize> ops::Neg for Simd<T, N>
+    where
+        T: SimdElement,
+        LaneCount<N>: SupportedLaneCount,
+    {
+        type Output = Self;
+
+        #[inline(always)]
+        fn neg(self) -> Self { self.map(ops::Neg::neg) }
+    }
+
+    impl<T, const N: usize> iter::Sum for Simd<T, N>
+    where
+        T: SimdElement,
+        LaneCount<N>: SupportedLaneCount,
+    {
+        #[inline(always)]
+        fn sum<I: Iterator<Item = Self>>(iter: I) -> Self { iter.fold(Self::default(), ops::Add::add) }
+    }
+
+    impl<'a, T, const N: usize> iter::Sum<&'a Self> for Simd<T, N>
+    where
+        T: SimdElement,
+        LaneCount<N>: SupportedLaneCount,
+    {
+        #[inline(always)]
+        fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self { iter.copied().sum() }
+    }
+
+    /// Lanewise and horizontal floating point operations of [`Simd`] vectors (as `std::simd::num::SimdFloat`).
+    pub trait SimdFloat: Copy + Sealed {
+        type Scalar;
+
+        fn abs(self) -> Self;
+
+        fn simd_min(self, other: Self) -> Self;
+
+        fn simd_max(self, other: Self) -> Self;
+
+        /// Sum of all lanes, added in lane order.
+        fn reduce_sum(self) -> Self::Scalar;
+
+        fn reduce_min(self) -> Self::Scalar;
+
+        fn reduce_max(self) -> Self::Scalar;
+    }
+
+    impl<T, const N: usize> Sealed for Simd<T, N>
+    where
+        T: SimdElement,
+        LaneCount<N>: SupportedLaneCount,
+    {
+    }
+
+    impl<T, const N: usize> SimdFloat for Simd<T, N>
+    where
+        T: SimdElement,
+        LaneCount<N>: SupportedLaneCount,
+    {
+        type Scalar = T;
+
+        #[inline(always)]
+        fn abs(self) -> Self { self.map(T::abs) }
+
+        #[inline(always)]
+        fn simd_min(self, other: Self) -> Self { self.zip_with(other, T::min) }
+
+        #[inline(always)]
+        fn simd_max(self, other: Self) -> Self { self.zip_with(other, T::max) }
+
+        #[inline(always)]
+        fn reduce_sum(self) -> T { self.0.into_iter().fold(-T::zero(), |sum, v| sum + v) }
+
+        #[inline(always)]
+        fn reduce_min(self) -> T { self.0.into_iter().fold(T::infinity(), T::min) }
+
+        #[inline(always)]
+        fn reduce_max(self) -> T { self.0.into_iter().fold(T::neg_infinity(), T::max) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lanewise_operations() {
+        let a = Simd::<f64, 4>::from_slice(&[1.0, -2.0, 3.0, -4.0, 5.0]);
+        let b = Simd::<f64, 4>::splat(2.0);
+        assert_eq!((a + b).as_array(), &[3.0, 0.0, 5.0, -2.0]);
+        assert_eq!((a * b - b / b).as_array(), &[1.0, -5.0, 5.0, -9.0]);
+        assert_eq!(a.abs().as_array(), &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(a.simd_max(b).as_array(), &[2.0, 2.0, 3.0, 2.0]);
+        assert_eq!(a.simd_min(b).as_array(), &[1.0, -2.0, 2.0, -4.0]);
+        assert_eq!(a.reduce_sum(), -2.0);
+        assert_eq!([a, b, b].into_iter().sum::<Simd<f64, 4>>().as_array(), &[5.0, 2.0, 7.0, 0.0]);
+    }
+
+    #[test]
+    fn dispatched_kernel() {
+        // Whichever SIMD extension the kernel is dispatched to, it sums in the same order
+        let a: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.37).sin()).collect();
+        let kernel = || {
+            a.chunks_exact(8)
+                .map(Simd::<f32, 8>::from_slice)
+                .map(|v| v * v)
+                .sum::<Simd<f32, 8>>()
+                .reduce_sum()
+        };
+        assert_eq!(dispatch(kernel), kernel());
+    }
+}
@@ -0,0 +1,126 @@
+use crate::memory::Primitive;
+use std::mem::size_of;
+use std::sync::OnceLock;
+
+/// Widest SIMD instruction set extension of a CPU, that is relevant for the distance calculations.
+///
+/// The SIMD code of this crate is generated for the target features the crate was compiled with (e.g. with
+/// `RUSTFLAGS="-C target-cpu=native"`), for the amount of lanes chosen through the `LANES` generic. Vectors wider than
+/// the compiled target features support are split into multiple instructions. [`SimdLevel::compiled`] reports the
+/// level of the build, [`SimdLevel::detect`] the level of the CPU the program is running on. The latter is the
+/// level to choose the amount of lanes for (see [`SimdLevel::lanes`]), and to build for. With the `stable` feature, the
+/// assignment and update passes are compiled for every level, and dispatched at runtime to the detected one (see
+/// [`crate::simd`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SimdLevel {
+    /// No (known) SIMD extension
+    Scalar,
+    /// 128 bit vectors (SSE2 on x86, NEON on aarch64)
+    Vector128,
+    /// 256 bit vectors (AVX2 on x86)
+    Avx2,
+    /// 512 bit vectors (AVX-512F on x86)
+    Avx512,
+}
+impl SimdLevel {
+    /// Detect the widest SIMD extension of the CPU the program is running on (once, cached for all further calls).
+    pub fn detect() -> Self {
+        static DETECTED: OnceLock<SimdLevel> = OnceLock::new();
+        *DETECTED.get_or_init(|| {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            {
+                if std::arch::is_x86_feature_detected!("avx512f") {
+                    return SimdLevel::Avx512;
+                }
+                if std::arch::is_x86_feature_detected!("avx2") {
+                    return SimdLevel::Avx2;
+                }
+                if std::arch::is_x86_feature_detected!("sse2") {
+                    return SimdLevel::Vector128;
+                }
+            }
+            #[cfg(target_arch = "aarch64")]
+            {
+                if std::arch::is_aarch64_feature_detected!("neon") {
+                    return SimdLevel::Vector128;
+                }
+            }
+            SimdLevel::Scalar
+        })
+    }
+
+    /// The widest SIMD extension this crate was compiled for (see the documentation of [`SimdLevel`]).
+    pub const fn compiled() -> Self {
+        if cfg!(target_feature = "avx512f") {
+            SimdLevel::Avx512
+        } else if cfg!(target_feature = "avx2") {
+            SimdLevel::Avx2
+        } else if cfg!(any(target_feature = "sse2", target_feature = "neon")) {
+            SimdLevel::Vector128
+        } else {
+            SimdLevel::Scalar
+        }
+    }
+
+    /// Width of the vector registers of this level, in bits.
+    pub const fn vector_bits(self) -> usize {
+        match self {
+            SimdLevel::Scalar => 0,
+            SimdLevel::Vector128 => 128,
+            SimdLevel::Avx2 => 256,
+            SimdLevel::Avx512 => 512,
+        }
+    }
+
+    /// Amount of values of the primitive `T` that fit into one vector register of this level (at least `1`), which is
+    /// the natural choice for the `LANES` generic of [`crate::KMeans`].
+    pub const fn lanes<T: Primitive>(self) -> usize {
+        let lanes = self.vector_bits() / (8 * size_of::<T>());
+        if lanes > 1 {
+            lanes
+        } else {
+            1
+        }
+    }
+}
+
+/// Amount of SIMD lanes of `f32` calculations, that fills one vector register of the target features this crate was
+/// compiled for (see [`SimdLevel::compiled`]), as used by [`crate::KMeansF32`].
+pub const LANES_F32: usize = SimdLevel::compiled().lanes::<f32>();
+/// Amount of SIMD lanes of `f64` calculations, that fills one vector register of the target features this crate was
+/// compiled for (see [`SimdLevel::compiled`]), as used by [`crate::KMeansF64`].
+pub const LANES_F64: usize = SimdLevel::compiled().lanes::<f64>();
+
+#[cfg(test)]
+mod tests {
+    use super::SimdLevel;
+
+    #[test]
+    fn detected_level() {
+        // The build can only run on CPUs that support its target features
+        assert!(SimdLevel::detect() >= SimdLevel::compiled());
+        assert_eq!(SimdLevel::detect(), SimdLevel::detect());
+
+        assert_eq!((SimdLevel::Avx512.lanes::<f64>(), SimdLevel::Avx512.lanes::<f32>()), (8, 16));
+        assert_eq!((SimdLevel::Avx2.lanes::<f64>(), SimdLevel::Avx2.lanes::<f32>()), (4, 8));
+        assert_eq!((SimdLevel::Vector128.lanes::<f64>(), SimdLevel::Vector128.lanes::<f32>()), (2, 4));
+        assert_eq!(SimdLevel::Scalar.lanes::<f64>(), 1);
+    }
+
+    #[test]
+    fn automatic_lanes() {
+        use crate::{EuclideanDistance, KMeans, KMeansConfig, KMeansF64};
+
+        assert_eq!(super::LANES_F64 * 2, super::LANES_F32.max(2));
+        let samples: Vec<f64> = (0..300).map(|i| (i % 3) as f64 * 10.0 + (i % 7) as f64 * 0.1).collect();
+        let init = vec![0.0, 0.0, 0.0, 10.0, 10.0, 10.0, 20.0, 20.0, 20.0];
+        let auto: KMeansF64<_> = KMeans::new(&samples, 100, 3, EuclideanDistance);
+        let explicit: KMeans<f64, 8, _> = KMeans::new(&samples, 100, 3, EuclideanDistance);
+        let (auto, explicit) = (
+            auto.kmeans_lloyd(3, 100, KMeans::init_precomputed(init.clone()), &KMeansConfig::default()),
+            explicit.kmeans_lloyd(3, 100, KMeans::init_precomputed(init), &KMeansConfig::default()),
+        );
+        assert_eq!(auto.assignments, explicit.assignments);
+        assert_eq!(auto.centroids.to_vec(), explicit.centroids.to_vec());
+    }
+}
@@ -0,0 +1,267 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use rayon::prelude::*;
+use std::marker::PhantomData;
+
+/// Clustering over a sliding window of streaming samples, for monitoring use cases where only the most recent
+/// samples matter.
+///
+/// Every pushed sample is assigned to its nearest centroid, which is then moved into the mean of the samples
+/// assigned to it. Once the window is full, each new sample expires the oldest one: Its contribution is removed from
+/// the sums of the centroid it was assigned to, so every centroid always is the mean of its samples within the window.
+/// Centroids without any samples in the window keep their last position.
+///
+/// Samples are never reassigned while pushing (which keeps each push at `O(k * sample_dims)`). To follow shifting
+/// clusters more closely (and to discard the rounding errors accumulated by the running sums),
+/// [`SlidingWindowKMeans::recluster`] runs full Lloyd iterations over the current window.
+///
+/// ## Generics
+/// - `T`: The type of primitive of the samples
+/// - `LANES`: The amount of SIMD lanes to use for the distance calculations
+/// - `D`: The distance function to assign samples with
+///
+/// ## Example
+/// ```rust
+/// use kmeans::*;
+///
+/// let mut window: SlidingWindowKMeans<f64, 8, _> = SlidingWindowKMeans::new(1, 4, &[0.0, 10.0], EuclideanDistance);
+/// assert_eq!(window.push_samples(&[1.0, 2.0, 11.0, 12.0]), vec![0, 0, 1, 1]);
+/// assert_eq!(window.centroids(), vec![1.5, 11.5]);
+///
+/// // The two oldest samples (1.0 and 2.0) expire, the first centroid keeps the position of its last sample
+/// window.push_samples(&[13.0, 14.0]);
+/// assert_eq!(window.cluster_sizes(), &[0, 4]);
+/// assert_eq!(window.centroids(), vec![2.0, 12.5]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SlidingWindowKMeans<T: Primitive, const LANES: usize, D: DistanceFunction<T, LANES>>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+{
+    distance_fn: D,
+    centroids: StrideBuffer<T>,
+    /// Sum of all samples in the window, per centroid
+    sums: StrideBuffer<T>,
+    /// Amount of samples in the window, per centroid
+    counts: Vec<usize>,
+    /// Ring buffer of the samples in the window (occupying the slots `0..len`)
+    samples: StrideBuffer<T>,
+    /// Assigned centroid of every slot of the ring buffer
+    assignments: Vec<usize>,
+    /// Slot of the oldest sample in the window
+    oldest: usize,
+    len: usize,
+    _p: PhantomData<Simd<T, LANES>>,
+}
+impl<T: Primitive, const LANES: usize, D: DistanceFunction<T, LANES>> SlidingWindowKMeans<T, LANES, D>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    /// Create a new, empty window.
+    ///
+    /// ## Arguments
+    /// - **sample_dims**: Amount of dimensions of the samples
+    /// - **window_size**: Maximum amount of samples in the window, before the oldest samples expire
+    /// - **centroids**: Initial centroids [row-major] = [<centroid0>,<centroid1>,...] (e.g. of a previous k-means
+    ///   result on historic samples)
+    /// - **distance_fn**: Distance function to assign samples with
+    pub fn new(sample_dims: usize, window_size: usize, centroids: &[T], distance_fn: D) -> Self {
+        assert!(window_size > 0);
+        assert!(!centroids.is_empty());
+        let padding = distance_fn.padding_value();
+        let centroids = StrideBuffer::from_slice::<LANES>(sample_dims, centroids).padded(padding);
+        let k = centroids.centroid_cnt;
+        Self {
+            distance_fn,
+            centroids,
+            sums: StrideBuffer::new::<LANES>(k, sample_dims),
+            counts: vec![0; k],
+            samples: StrideBuffer::new::<LANES>(window_size, sample_dims).padded(padding),
+            assignments: vec![0; window_size],
+            oldest: 0,
+            len: 0,
+            _p: PhantomData,
+        }
+    }
+
+    /// Push one sample into the window, expiring the oldest sample if the window is full.
+    ///
+    /// ## Returns
+    /// The centroid the sample was assigned to.
+    pub fn push(&mut self, sample: &[T]) -> usize {
+        assert_eq!(sample.len(), self.samples.centroid_dim);
+        let capacity = self.samples.centroid_cnt;
+        let slot = if self.len < capacity {
+            self.len += 1;
+            self.len - 1
+        } else {
+            let slot = self.oldest;
+            self.expire(slot);
+            self.oldest = (self.oldest + 1) % capacity;
+            slot
+        };
+        self.samples.set_nth_from_iter(slot, sample.iter().cloned());
+
+        let centroid = self.nearest(self.samples.nth_stride(slot));
+        self.sums
+            .nth_stride_mut(centroid)
+            .iter_mut()
+            .zip(self.samples.nth_stride(slot))
+            .for_each(|(sum, &v)| *sum += v);
+        self.counts[centroid] += 1;
+        self.assignments[slot] = centroid;
+        self.update_centroid(centroid);
+        centroid
+    }
+
+    /// Push all given samples [row-major] = [<sample0>,<sample1>,...] into the window, one after the other.
+    ///
+    /// ## Returns
+    /// The centroid each sample was assigned to.
+    pub fn push_samples(&mut self, samples: &[T]) -> Vec<usize> {
+        assert_eq!(samples.len() % self.samples.centroid_dim, 0);
+        samples.chunks_exact(self.samples.centroid_dim).map(|s| self.push(s)).collect()
+    }
+
+    /// Run up to **max_iter** Lloyd iterations over the samples of the window: Reassign every sample to its nearest
+    /// centroid, and move every centroid into the mean of its samples, until no assignment changes anymore.
+    ///
+    /// ## Returns
+    /// The amount of iterations that were done.
+    pub fn recluster(&mut self, max_iter: usize) -> usize {
+        for iteration in 1..=max_iter {
+            let (samples, distance_fn, centroids) = (&self.samples, &self.distance_fn, &self.centroids);
+            let changed = samples
+                .bfr
+                .par_chunks_exact(samples.stride)
+                .zip(self.assignments.par_iter_mut())
+                .take(self.len)
+                .map(|(s, assignment)| {
+                    let nearest = Self::nearest_of(distance_fn, centroids, s);
+                    std::mem::replace(assignment, nearest) != nearest
+                })
+                .filter(|&changed| changed)
+                .count();
+
+            // Recalculate the sums from scratch, which also discards accumulated rounding errors
+            self.sums.bfr.fill(T::zero());
+            self.counts.fill(0);
+            for slot in 0..self.len {
+                let centroid = self.assignments[slot];
+                self.sums
+                    .nth_stride_mut(centroid)
+                    .iter_mut()
+                    .zip(self.samples.nth_stride(slot))
+                    .for_each(|(sum, &v)| *sum += v);
+                self.counts[centroid] += 1;
+            }
+            (0..self.counts.len()).for_each(|centroid| self.update_centroid(centroid));
+            if changed == 0 {
+                return iteration;
+            }
+        }
+        max_iter
+    }
+
+    /// Assign the given samples [row-major] = [<sample0>,<sample1>,...] to their nearest centroid, without pushing
+    /// them into the window.
+    pub fn predict(&self, samples: &[T]) -> Vec<usize> {
+        assert_eq!(samples.len() % self.samples.centroid_dim, 0);
+        let p_samples = StrideBuffer::from_slice::<LANES>(self.samples.centroid_dim, samples).padded(self.distance_fn.padding_value());
+        p_samples.bfr.par_chunks_exact(p_samples.stride).map(|s| self.nearest(s)).collect()
+    }
+
+    /// Current centroids [row-major] = [<centroid0>,<centroid1>,...]
+    pub fn centroids(&self) -> Vec<T> { self.centroids.to_vec() }
+
+    /// Amount of samples in the window, that are assigned to each centroid.
+    pub fn cluster_sizes(&self) -> &[usize] { &self.counts }
+
+    /// Amount of samples in the window.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Whether the window does not contain any samples.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    // #############################################
+    // INTERNAL
+
+    /// Remove the sample in the given slot from the sums of its centroid.
+    fn expire(&mut self, slot: usize) {
+        let centroid = self.assignments[slot];
+        self.sums
+            .nth_stride_mut(centroid)
+            .iter_mut()
+            .zip(self.samples.nth_stride(slot))
+            .for_each(|(sum, &v)| *sum -= v);
+        self.counts[centroid] -= 1;
+        self.update_centroid(centroid);
+    }
+
+    /// Move the given centroid into the mean of its samples (if it has any). The padding is kept as it is.
+    fn update_centroid(&mut self, centroid: usize) {
+        let count = self.counts[centroid];
+        if count > 0 {
+            let count = T::from(count).unwrap();
+            self.centroids
+                .nth_stride_mut(centroid)
+                .iter_mut()
+                .take(self.samples.centroid_dim)
+                .zip(self.sums.nth_stride(centroid))
+                .for_each(|(c, &sum)| *c = sum / count);
+        }
+    }
+
+    fn nearest(&self, sample: &[T]) -> usize { Self::nearest_of(&self.distance_fn, &self.centroids, sample) }
+
+    fn nearest_of(distance_fn: &D, centroids: &StrideBuffer<T>, sample: &[T]) -> usize {
+        centroids
+            .chunks_exact_stride()
+            .map(|c| distance_fn.distance(sample, c))
+            .enumerate()
+            .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+            .map_or(0, |(idx, _)| idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, SlidingWindowKMeans};
+
+    #[test]
+    fn expired_samples_leave_centroids() {
+        let mut window: SlidingWindowKMeans<f64, 8, _> = SlidingWindowKMeans::new(2, 3, &[0.0, 0.0, 10.0, 10.0], EuclideanDistance);
+        for i in 0..100 {
+            window.push(&[i as f64 * 0.01, 0.0]);
+        }
+        assert_eq!(window.len(), 3);
+        assert_eq!(window.cluster_sizes(), &[3, 0]);
+        let centroids = window.centroids();
+        assert!((centroids[0] - 0.98).abs() < 1e-9);
+        assert_eq!(&centroids[2..], &[10.0, 10.0]);
+
+        // The window moves over to the second cluster, until no sample of the first one is left
+        assert_eq!(window.push_samples(&[9.0, 9.0, 11.0, 11.0]), vec![1, 1]);
+        assert_eq!(window.cluster_sizes(), &[1, 2]);
+        window.push(&[10.0, 10.0]);
+        assert_eq!(window.cluster_sizes(), &[0, 3]);
+        assert!((window.centroids()[0] - 0.99).abs() < 1e-9);
+        assert_eq!(&window.centroids()[2..], &[10.0, 10.0]);
+        assert_eq!(window.predict(&[0.0, 0.0, 12.0, 12.0]), vec![0, 1]);
+    }
+
+    #[test]
+    fn recluster_window() {
+        // The second centroid moves away from the first sample, after it was assigned
+        let mut window: SlidingWindowKMeans<f64, 8, _> = SlidingWindowKMeans::new(1, 10, &[0.0, 10.0], EuclideanDistance);
+        assert_eq!(window.push_samples(&[6.0, 20.0]), vec![1, 1]);
+        assert_eq!(window.centroids(), vec![0.0, 13.0]);
+
+        assert_eq!(window.recluster(100), 2);
+        assert_eq!(window.cluster_sizes(), &[1, 1]);
+        assert_eq!(window.centroids(), vec![6.0, 20.0]);
+        assert_eq!(window.recluster(100), 1);
+    }
+}
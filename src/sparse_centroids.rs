@@ -0,0 +1,94 @@
+use crate::memory::*;
+use crate::KMeansState;
+
+/// Sparse (compressed sparse row) representation of the centroids of a k-means result, where all components with
+/// an absolute value below a threshold are dropped (see [`KMeansState::sparse_centroids`]). For high-dimensional
+/// sparse data (e.g. text features), most centroid components are tiny, so this keeps large models storable.
+///
+/// ## Fields
+/// - **k**: Amount of centroids
+/// - **dims**: Amount of dimensions of each centroid
+/// - **offsets**: Start of each centroid's components within **indices** and **values** (with a final entry for
+///   the end of the last centroid, so centroid `c` spans `offsets[c]..offsets[c + 1]`)
+/// - **indices**: Dimension of each stored component (ascending within each centroid)
+/// - **values**: Value of each stored component
+#[derive(Clone, Debug)]
+pub struct SparseCentroids<T: Primitive> {
+    pub k: usize,
+    pub dims: usize,
+    pub offsets: Vec<usize>,
+    pub indices: Vec<usize>,
+    pub values: Vec<T>,
+}
+impl<T: Primitive> SparseCentroids<T> {
+    /// Amount of stored (non-dropped) components over all centroids.
+    pub fn nnz(&self) -> usize { self.values.len() }
+
+    /// Share of stored components, compared to the dense representation.
+    pub fn density(&self) -> f64 {
+        match self.k * self.dims {
+            0 => 0.0,
+            total => self.nnz() as f64 / total as f64,
+        }
+    }
+
+    /// Stored components of the given centroid, as tuple of (indices, values).
+    pub fn centroid(&self, centroid: usize) -> (&[usize], &[T]) {
+        let range = self.offsets[centroid]..self.offsets[centroid + 1];
+        (&self.indices[range.clone()], &self.values[range])
+    }
+
+    /// Dense representation of all centroids, where dropped components are zero [row-major] = [<centroid0>,...]
+    pub fn to_dense(&self) -> Vec<T> {
+        let mut dense = vec![T::zero(); self.k * self.dims];
+        (0..self.k).for_each(|c| {
+            let (indices, values) = self.centroid(c);
+            indices.iter().zip(values.iter()).for_each(|(&d, &v)| dense[c * self.dims + d] = v);
+        });
+        dense
+    }
+}
+
+pub(crate) fn calculate<T: Primitive>(state: &KMeansState<T>, threshold: T) -> SparseCentroids<T> {
+    let (k, dims) = (state.centroids.centroid_cnt, state.centroids.centroid_dim);
+    let mut res = SparseCentroids {
+        k,
+        dims,
+        offsets: vec![0],
+        indices: Vec::new(),
+        values: Vec::new(),
+    };
+    state.centroids.iter().for_each(|c| {
+        c.iter().enumerate().filter(|(_, v)| v.abs() >= threshold).for_each(|(d, &v)| {
+            res.indices.push(d);
+            res.values.push(v);
+        });
+        res.offsets.push(res.values.len());
+    });
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn sparse_centroids() {
+        let samples = vec![0.0f64, 5.0, 0.001, 0.0, 5.0, -0.001, 3.0, 0.0, 0.0, 3.0, 0.0, 0.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 4, 3, EuclideanDistance);
+        let init = KMeans::init_precomputed(vec![0.0, 5.0, 0.0, 3.0, 0.0, 0.0]);
+        let res = kmean.kmeans_lloyd(2, 100, init, &KMeansConfig::default());
+
+        let sparse = res.sparse_centroids(0.01);
+        assert_eq!(sparse.offsets, vec![0, 1, 2]);
+        assert_eq!(sparse.centroid(0), (&[1usize][..], &[5.0][..]));
+        assert_eq!(sparse.centroid(1), (&[0usize][..], &[3.0][..]));
+        assert_eq!(sparse.nnz(), 2);
+        assert!((sparse.density() - 2.0 / 6.0).abs() < 1e-12);
+        assert_eq!(sparse.to_dense(), vec![0.0, 5.0, 0.0, 3.0, 0.0, 0.0]);
+
+        // Without a threshold, this is lossless (apart from exact zeros)
+        let sparse = res.sparse_centroids(f64::MIN_POSITIVE);
+        assert_eq!(sparse.to_dense(), res.centroids.to_vec());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::api::DistanceFunction;
+use crate::incremental::split_cluster;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansState};
+use rayon::prelude::*;
+use std::mem::size_of_val;
+
+/// Total (weighted) sum of distances of all samples to their nearest centroid in **centroids**.
+fn nearest_distsum<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, centroids: &StrideBuffer<T>) -> T
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    kmean
+        .p_samples
+        .bfr
+        .par_chunks_exact(kmean.p_samples.stride)
+        .enumerate()
+        .map(|(sample_id, s)| {
+            let nearest = centroids
+                .chunks_exact_stride()
+                .map(|c| kmean.distance_fn.distance(s, c))
+                .min_by(|d0, d1| d0.partial_cmp(d1).unwrap())
+                .unwrap();
+            nearest * kmean.sample_weight(sample_id)
+        })
+        .sum()
+}
+
+/// Split-merge refinement move, to escape local minima of the Lloyd iterations.
+///
+/// The worst cluster (highest sum of distances, with at least two members) is split into two using a local 2-means,
+/// while the closest pair of the remaining clusters is merged into their mean (weighted by the summed sample weights
+/// of each cluster), which keeps the amount of
+/// clusters constant. The move is only accepted, if it decreases the total sum of distances of all samples to their
+/// nearest centroid.
+///
+/// Uses the **assignments** / **centroid_distances** / **centroid_frequency** of **state** to find the candidates.
+/// ## Returns
+/// Whether the move was accepted (and the **centroids** of **state** were replaced, with all samples reassigned)
+pub(crate) fn refine<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>, state: &mut KMeansState<T>) -> bool
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    if state.k < 3 {
+        return false;
+    }
+
+    let mut members = vec![Vec::new(); state.k];
+    let mut sse = vec![T::zero(); state.k];
+    let mut weights = vec![T::zero(); state.k];
+    state
+        .assignments
+        .iter()
+        .zip(state.centroid_distances.iter())
+        .enumerate()
+        .for_each(|(idx, (&c, &d))| {
+            members[c].push(idx);
+            sse[c] += d * kmean.sample_weight(idx);
+            weights[c] += kmean.sample_weight(idx);
+        });
+    let Some(worst) = (0..state.k)
+        .filter(|&c| members[c].len() > 1)
+        .max_by(|&a, &b| sse[a].partial_cmp(&sse[b]).unwrap())
+    else {
+        return false;
+    };
+
+    // Closest pair of centroids, besides the worst cluster
+    let (keep, merged) = (0..state.k)
+        .filter(|&a| a != worst)
+        .flat_map(|a| ((a + 1)..state.k).filter(move |&b| b != worst).map(move |b| (a, b)))
+        .min_by(|&(a0, b0), &(a1, b1)| {
+            let d0 = kmean
+                .distance_fn
+                .distance(state.centroids.nth_stride(a0), state.centroids.nth_stride(b0));
+            let d1 = kmean
+                .distance_fn
+                .distance(state.centroids.nth_stride(a1), state.centroids.nth_stride(b1));
+            d0.partial_cmp(&d1).unwrap()
+        })
+        .unwrap();
+
+    // Candidate: merge both clusters into the one with id keep, and use the freed id merged for the second half of the split
+    let mut candidate = state.centroids.clone();
+    state.memory_usage.record_temporaries(2 * size_of_val(candidate.bfr.as_slice()));
+    let (w_keep, w_merged) = (weights[keep], weights[merged]);
+    if w_keep + w_merged > T::zero() {
+        let norm = T::one() / (w_keep + w_merged);
+        let merged_centroid: Vec<T> = state
+            .centroids
+            .nth_stride(keep)
+            .iter()
+            .zip(state.centroids.nth_stride(merged))
+            .map(|(&a, &b)| (a * w_keep + b * w_merged) * norm)
+            .collect();
+        candidate.nth_stride_mut(keep).copy_from_slice(&merged_centroid);
+    }
+    let split = split_cluster(kmean, state.centroids.nth_stride(worst), &members[worst]);
+    candidate.nth_stride_mut(worst).copy_from_slice(split.nth_stride(0));
+    candidate.nth_stride_mut(merged).copy_from_slice(split.nth_stride(1));
+
+    if nearest_distsum(kmean, &candidate) < nearest_distsum(kmean, &state.centroids) {
+        state.centroids = candidate;
+        kmean.update_cluster_assignments(state, None);
+        kmean.update_cluster_frequencies(&state.assignments, &mut state.centroid_frequency);
+        true
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EuclideanDistance, KMeansConfig};
+
+    #[test]
+    fn split_merge_escapes_local_minimum() {
+        // Two close groups get one centroid each, while two far-away groups share one
+        let samples = vec![-0.1f64, 0.0, 0.1, 0.9, 1.0, 1.1, 49.0, 50.0, 51.0, 59.0, 60.0, 61.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let init = || KMeans::init_precomputed(vec![0.0, 1.0, 55.0]);
+
+        let plain = kmean.kmeans_lloyd(3, 100, init(), &KMeansConfig::default());
+        assert_eq!(plain.centroids.to_vec(), vec![0.0, 1.0, 55.0]);
+
+        let conf = KMeansConfig::build().split_merge_interval(2).build();
+        let refined = kmean.kmeans_lloyd(3, 100, init(), &conf);
+        let mut centroids = refined.centroids.to_vec();
+        centroids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(centroids, vec![0.5, 50.0, 60.0]);
+        assert!(refined.distsum < plain.distsum);
+
+        // Refinement moves, that do not improve the result, are rejected
+        let mut state = refined.clone();
+        assert!(!refine(&kmean, &mut state));
+        assert_eq!(state.centroids.to_vec(), refined.centroids.to_vec());
+    }
+
+    #[test]
+    fn split_merge_weighted() {
+        // The merged centroid lies at the weighted mean of both clusters, which have the same amount of samples
+        let samples = vec![-0.1f64, 0.0, 0.1, 0.9, 1.0, 1.1, 49.0, 50.0, 51.0, 59.0, 60.0, 61.0];
+        let weights = vec![1.0, 1.0, 1.0, 3.0, 3.0, 3.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance).with_sample_weights(&weights);
+        let mut state = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![0.0, 1.0, 55.0]), &KMeansConfig::default());
+
+        assert!(refine(&kmean, &mut state));
+        let mut centroids = state.centroids.to_vec();
+        centroids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        centroids
+            .iter()
+            .zip([0.75, 50.0, 60.0])
+            .for_each(|(&c, expected)| assert!((c - expected).abs() < 1e-9));
+    }
+}
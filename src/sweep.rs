@@ -0,0 +1,167 @@
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::variants::Lloyd;
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rayon::prelude::*;
+use std::time::{Duration, Instant};
+
+/// Result of one k-means calculation within a sweep over **k**.
+///
+/// ## Fields
+/// - **k**: Amount of clusters used for this calculation
+/// - **inertia**: Resulting total sum of distances of all samples to their centroid (distsum)
+/// - **explained_variance**: Fraction of the total variance of the samples, that is explained by the clustering
+///   (`1 - inertia / total_sum_of_squares`, only meaningful for [`crate::EuclideanDistance`])
+/// - **runtime**: Wall-clock time the calculation took
+/// - **state**: The full k-means result
+#[derive(Clone, Debug)]
+pub struct KSweepPoint<T: Primitive> {
+    pub k: usize,
+    pub inertia: T,
+    pub explained_variance: T,
+    pub runtime: Duration,
+    pub state: KMeansState<T>,
+}
+
+/// Ready-to-plot result of a sweep over **k** (see [`KMeans::sweep_k`]).
+///
+/// ## Fields
+/// - **points**: One entry per calculated **k**, in the order they were calculated
+/// - **elbow**: The **k** at the elbow of the inertia-vs-k curve, as detected by the kneedle algorithm
+///   (**None** if less than three points were calculated, or the curve has no elbow)
+#[derive(Clone, Debug)]
+pub struct KSweep<T: Primitive> {
+    pub points: Vec<KSweepPoint<T>>,
+    pub elbow: Option<usize>,
+}
+impl<T: Primitive> KSweep<T> {
+    /// The (k, inertia) pairs of this sweep, e.g. for plotting the elbow curve.
+    pub fn curve(&self) -> Vec<(usize, T)> { self.points.iter().map(|p| (p.k, p.inertia)).collect() }
+}
+
+/// Total sum of squares of all samples to their mean.
+fn total_sum_of_squares<T, const LANES: usize, D>(kmean: &KMeans<T, LANES, D>) -> T
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let mut mean = vec![T::zero(); kmean.sample_dims];
+    kmean
+        .p_samples
+        .iter()
+        .for_each(|s| mean.iter_mut().zip(s.iter()).for_each(|(m, &v)| *m += v));
+    let sample_cnt = T::from(kmean.sample_cnt).unwrap();
+    mean.iter_mut().for_each(|m| *m = *m / sample_cnt);
+    kmean
+        .p_samples
+        .iter()
+        .map(|s| s.iter().zip(mean.iter()).map(|(&v, &m)| (v - m) * (v - m)).sum::<T>())
+        .sum()
+}
+
+/// Run one calculation of the sweep, and measure it.
+fn sweep_point<T: Primitive>(k: usize, total_ss: T, run: impl FnOnce() -> KMeansState<T>) -> KSweepPoint<T> {
+    let start = Instant::now();
+    let state = run();
+    let runtime = start.elapsed();
+    KSweepPoint {
+        k,
+        inertia: state.distsum,
+        explained_variance: if total_ss > T::zero() {
+            T::one() - state.distsum / total_ss
+        } else {
+            T::one()
+        },
+        runtime,
+        state,
+    }
+}
+
+fn finish<T: Primitive>(points: Vec<KSweepPoint<T>>) -> KSweep<T> {
+    let curve: Vec<(usize, T)> = points.iter().map(|p| (p.k, p.inertia)).collect();
+    let elbow = crate::metrics::elbow_k(&curve);
+    KSweep { points, elbow }
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D, F>(kmean: &KMeans<T, LANES, D>, ks: impl IntoIterator<Item = usize>, run: F) -> KSweep<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+    F: Fn(&KMeans<T, LANES, D>, usize) -> KMeansState<T>,
+{
+    let total_ss = total_sum_of_squares(kmean);
+    finish(ks.into_iter().map(|k| sweep_point(k, total_ss, || run(kmean, k))).collect())
+}
+
+#[inline(always)]
+pub fn calculate_lloyd<T, const LANES: usize, D, F>(
+    kmean: &KMeans<T, LANES, D>, ks: impl IntoIterator<Item = usize>, max_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+) -> KSweep<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+    for<'c> F: Fn(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>) + Sync,
+{
+    let total_ss = total_sum_of_squares(kmean);
+    // Every calculation gets its own clone of the configuration (and thus its own random number generator), which are
+    // created in order of the ks, so seeded sweeps stay repeatable independently of the scheduling
+    let runs: Vec<(usize, KMeansConfig<'_, T>)> = ks.into_iter().map(|k| (k, config.clone())).collect();
+    let points = runs
+        .par_iter()
+        .map(|(k, conf)| sweep_point(*k, total_ss, || Lloyd::calculate(kmean, *k, max_iter, &init, conf)))
+        .collect();
+    finish(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{datasets, EuclideanDistance, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn sweep_k() {
+        let mut rnd = rand::rngs::StdRng::seed_from_u64(1337);
+        let blobs = datasets::make_blobs::<f64, _>(&mut rnd, 400, 2, 4, 0.2);
+        let kmean: KMeans<_, 4, _> = KMeans::new(&blobs.samples, blobs.sample_cnt, blobs.sample_dims, EuclideanDistance);
+        let conf = KMeansConfig::build().random_generator(rnd).build();
+
+        let sweep = kmean.sweep_k(1..=8, |kmean, k| kmean.kmeans_lloyd(k, 100, KMeans::init_kmeanplusplus, &conf));
+        assert_eq!(sweep.points.len(), 8);
+        assert_eq!(
+            sweep.curve().iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            (1..=8).collect::<Vec<_>>()
+        );
+        assert!(sweep.points[0].explained_variance.abs() < 1e-10);
+        assert!(sweep.points[3].explained_variance > 0.95);
+        assert!(sweep.elbow.is_some());
+    }
+
+    #[test]
+    fn sweep_k_lloyd() {
+        let mut rnd = rand::rngs::StdRng::seed_from_u64(1337);
+        let blobs = datasets::make_blobs::<f64, _>(&mut rnd, 400, 2, 4, 0.2);
+        let kmean: KMeans<_, 4, _> = KMeans::new(&blobs.samples, blobs.sample_cnt, blobs.sample_dims, EuclideanDistance);
+        let conf = || KMeansConfig::build().random_generator(StdRng::seed_from_u64(7)).build();
+
+        let sweep = kmean.sweep_k_lloyd(1..=8, 100, KMeans::init_kmeanplusplus, &conf());
+        assert_eq!(
+            sweep.points.iter().map(|p| p.state.k).collect::<Vec<_>>(),
+            (1..=8).collect::<Vec<_>>()
+        );
+        assert!(sweep.points[3].explained_variance > 0.95);
+        assert!(sweep.elbow.is_some());
+
+        // Parallel calculations are repeatable with a seeded generator
+        let repeated = kmean.sweep_k_lloyd(1..=8, 100, KMeans::init_kmeanplusplus, &conf());
+        assert_eq!(repeated.curve(), sweep.curve());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::memory::*;
+use crate::KMeansState;
+
+/// History of the centroid positions during a k-means calculation (see
+/// [`crate::KMeansConfigBuilder::record_trajectory`]), e.g. for the visualization of the convergence, or the post-hoc
+/// debugging of oscillating centroids.
+///
+/// ## Fields
+/// - **k**: Amount of centroids in each snapshot
+/// - **dims**: Amount of dimensions of each centroid
+/// - **iterations**: Number of the iteration after which each snapshot was taken
+/// - **centroids**: All snapshots, stored compactly (without padding) [row-major] =
+///   [<snapshot0-centroid0>,<snapshot0-centroid1>,...,<snapshot1-centroid0>,...]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CentroidTrajectory<T: Primitive> {
+    pub k: usize,
+    pub dims: usize,
+    pub iterations: Vec<usize>,
+    pub centroids: Vec<T>,
+}
+impl<T: Primitive> CentroidTrajectory<T> {
+    /// Amount of recorded snapshots.
+    pub fn len(&self) -> usize { self.iterations.len() }
+
+    /// Whether no snapshot was recorded.
+    pub fn is_empty(&self) -> bool { self.iterations.is_empty() }
+
+    /// All centroids of the **idx**-th snapshot [row-major] = [<centroid0>,<centroid1>,...]
+    pub fn snapshot(&self, idx: usize) -> &[T] { &self.centroids[idx * self.k * self.dims..(idx + 1) * self.k * self.dims] }
+
+    /// Path of one centroid through all snapshots [row-major] = [<snapshot0>,<snapshot1>,...]
+    pub fn centroid_path(&self, centroid: usize) -> Vec<T> {
+        (0..self.len())
+            .flat_map(|idx| self.snapshot(idx)[centroid * self.dims..(centroid + 1) * self.dims].iter().cloned())
+            .collect()
+    }
+}
+
+/// Record a snapshot of the centroids of **state** after the given iteration, if it is a multiple of **interval**.
+pub(crate) fn record<T: Primitive>(state: &mut KMeansState<T>, iteration: usize, interval: usize) {
+    if interval == 0 || !iteration.is_multiple_of(interval) {
+        return;
+    }
+    let (k, dims) = (state.centroids.centroid_cnt, state.centroids.centroid_dim);
+    let trajectory = state.trajectory.get_or_insert_with(|| CentroidTrajectory {
+        k,
+        dims,
+        iterations: Vec::new(),
+        centroids: Vec::new(),
+    });
+    trajectory.iterations.push(iteration);
+    trajectory.centroids.extend(state.centroids.iter().flat_map(|c| c.iter().cloned()));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn record_trajectory() {
+        let samples = vec![0.0f64, 0.0, 1.0, 1.0, 10.0, 10.0, 11.0, 11.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 4, 2, EuclideanDistance);
+        let init = || KMeans::init_precomputed(vec![0.0, 0.0, 1.0, 1.0]);
+
+        let res = kmean.kmeans_lloyd(2, 100, init(), &KMeansConfig::default());
+        assert!(res.trajectory.is_none());
+
+        let conf = KMeansConfig::build().record_trajectory(1).build();
+        let res = kmean.kmeans_lloyd(2, 100, init(), &conf);
+        let trajectory = res.trajectory.unwrap();
+        assert!(trajectory.len() >= 2);
+        assert_eq!(trajectory.iterations[..2], [1, 2]);
+        // After the first iteration, the second centroid is the mean of the three samples closest to it
+        assert_eq!(trajectory.snapshot(0)[..2], [0.0, 0.0]);
+        assert!(trajectory.snapshot(0)[2..].iter().all(|v| (v - 22.0 / 3.0).abs() < 1e-10));
+        assert_eq!(trajectory.snapshot(trajectory.len() - 1), res.centroids.to_vec().as_slice());
+        assert_eq!(trajectory.centroid_path(1).len(), trajectory.len() * 2);
+        assert_eq!(trajectory.centroid_path(1)[2..4], [10.5, 10.5]);
+
+        let conf = KMeansConfig::build().record_trajectory(2).build();
+        let res = kmean.kmeans_minibatch(2, 2, 10, init(), &conf);
+        assert!(res.trajectory.unwrap().iterations.iter().all(|i| i % 2 == 0));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::memory::SupportedSimdArray;
+use crate::simd::{LaneCount, Simd, SimdFloat, SupportedLaneCount};
+use crate::{CentroidUpdater, Primitive};
+
+/// Centroid update rule using the multivariate geometric median of all members, which is the point minimizing the sum
+/// of (non-squared) Euclidean distances to the members. It is calculated using Weiszfeld's algorithm, starting from
+/// the mean. Since it is much less influenced by outliers than the mean, this makes k-means significantly more robust,
+/// while keeping the (Euclidean) assignment step.
+///
+/// ## Generics
+/// - `T`: The type of primitive to work with (e.g. f32 of f64)
+/// - `LANES`: The amount of SIMD lanes to use for the Weiszfeld iterations
+///
+/// ## Fields
+/// - **max_iter**: Maximum amount of Weiszfeld iterations per update
+/// - **tolerance**: The iterations end, once the centroid moved by less than **tolerance** (Euclidean distance)
+pub struct GeometricMedianUpdater<T: Primitive, const LANES: usize> {
+    pub max_iter: usize,
+    pub tolerance: T,
+}
+impl<T: Primitive, const LANES: usize> Default for GeometricMedianUpdater<T, LANES> {
+    fn default() -> Self {
+        Self {
+            max_iter: 100,
+            tolerance: T::from(1e-6).unwrap(),
+        }
+    }
+}
+
+/// Euclidean distance between **a** and **b** (of arbitrary, equal length)
+#[inline(always)]
+fn distance<T: Primitive, const LANES: usize>(a: &[T], b: &[T]) -> T
+where
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let remainder: T = a_chunks
+        .remainder()
+        .iter()
+        .zip(b_chunks.remainder())
+        .map(|(&a, &b)| (a - b) * (a - b))
+        .sum();
+    let simd = a_chunks
+        .zip(b_chunks)
+        .map(|(a, b)| Simd::from_slice(a) - Simd::from_slice(b))
+        .map(|v| v * v)
+        .sum::<Simd<T, LANES>>()
+        .reduce_sum();
+    (simd + remainder).sqrt()
+}
+
+/// acc += weight * v (of arbitrary, equal length)
+#[inline(always)]
+fn add_weighted<T: Primitive, const LANES: usize>(acc: &mut [T], v: &[T], weight: T)
+where
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    let weight_simd = Simd::splat(weight);
+    let (mut acc_chunks, v_chunks) = (acc.chunks_exact_mut(LANES), v.chunks_exact(LANES));
+    let v_remainder = v_chunks.remainder();
+    acc_chunks.by_ref().zip(v_chunks).for_each(|(a, v)| {
+        let result = Simd::from_slice(a) + Simd::from_slice(v) * weight_simd;
+        a.copy_from_slice(result.as_array());
+    });
+    acc_chunks
+        .into_remainder()
+        .iter_mut()
+        .zip(v_remainder)
+        .for_each(|(a, &v)| *a += v * weight);
+}
+
+impl<T: Primitive, const LANES: usize> CentroidUpdater<T> for GeometricMedianUpdater<T, LANES>
+where
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+{
+    fn update(&self, members: &[&[T]], centroid: &mut [T]) {
+        crate::MeanUpdater.update(members, centroid);
+        // Lower bound for distances, to avoid divisions by zero when the estimate coincides with a member
+        let epsilon = T::epsilon().sqrt();
+        let mut next = vec![T::zero(); centroid.len()];
+        for _ in 0..self.max_iter {
+            next.iter_mut().for_each(|v| *v = T::zero());
+            let mut weight_sum = T::zero();
+            members.iter().for_each(|m| {
+                let weight = T::one() / distance::<T, LANES>(m, centroid).max(epsilon);
+                add_weighted::<T, LANES>(&mut next, m, weight);
+                weight_sum += weight;
+            });
+            next.iter_mut().for_each(|v| *v = *v / weight_sum);
+            let shift = distance::<T, LANES>(&next, centroid);
+            centroid.copy_from_slice(&next);
+            if shift < self.tolerance {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometric_median_update() {
+        let updater = GeometricMedianUpdater::<f64, 4>::default();
+        // Symmetric square: the geometric median is its center
+        let mut centroid = vec![0.0f64; 2];
+        updater.update(&[&[0.0, 0.0], &[2.0, 0.0], &[0.0, 2.0], &[2.0, 2.0]], &mut centroid);
+        assert!((centroid[0] - 1.0).abs() < 1e-6 && (centroid[1] - 1.0).abs() < 1e-6);
+
+        // Collinear points: the geometric median is the median, regardless of the outlier
+        let mut centroid = vec![0.0f64; 5];
+        let members: Vec<Vec<f64>> = [0.0, 1.0, 2.0, 3.0, 1000.0].iter().map(|&v| vec![v; 5]).collect();
+        updater.update(&members.iter().map(|m| m.as_slice()).collect::<Vec<_>>(), &mut centroid);
+        assert!(centroid.iter().all(|&c| (c - 2.0).abs() < 1e-3));
+    }
+}
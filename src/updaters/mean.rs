@@ -0,0 +1,25 @@
+use crate::{CentroidUpdater, Primitive};
+
+/// Centroid update rule of the classic k-means: the (per-dimension) mean of all members.
+pub struct MeanUpdater;
+
+impl<T: Primitive> CentroidUpdater<T> for MeanUpdater {
+    fn update(&self, members: &[&[T]], centroid: &mut [T]) {
+        let cnt = T::from(members.len()).unwrap();
+        centroid.iter_mut().enumerate().for_each(|(d, c)| {
+            *c = members.iter().map(|m| m[d]).sum::<T>() / cnt;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_update() {
+        let mut centroid = vec![0.0f64; 2];
+        MeanUpdater.update(&[&[0.0, 1.0], &[2.0, 5.0]], &mut centroid);
+        assert_eq!(centroid, vec![1.0, 3.0]);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{CentroidUpdater, Primitive};
+
+/// Centroid update rule of k-medians: the per-dimension median of all members (the mean of both middle values, for
+/// an even amount of members). This is more robust against outliers than the mean, and minimizes the sum of
+/// Manhattan distances of the members to their centroid.
+pub struct MedianUpdater;
+
+impl<T: Primitive> CentroidUpdater<T> for MedianUpdater {
+    fn update(&self, members: &[&[T]], centroid: &mut [T]) {
+        let mut values = vec![T::zero(); members.len()];
+        let mid = members.len() / 2;
+        centroid.iter_mut().enumerate().for_each(|(d, c)| {
+            values.iter_mut().zip(members.iter()).for_each(|(v, m)| *v = m[d]);
+            let (lower, &mut upper, _) = values.select_nth_unstable_by(mid, |a, b| a.partial_cmp(b).unwrap());
+            *c = if members.len().is_multiple_of(2) {
+                let lower = lower.iter().cloned().fold(T::neg_infinity(), T::max);
+                (lower + upper) / T::from(2).unwrap()
+            } else {
+                upper
+            };
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_update() {
+        let mut centroid = vec![0.0f64; 2];
+        MedianUpdater.update(&[&[0.0, 1.0], &[2.0, 100.0], &[1.0, 2.0]], &mut centroid);
+        assert_eq!(centroid, vec![1.0, 2.0]);
+        MedianUpdater.update(&[&[0.0, 1.0], &[2.0, 100.0], &[1.0, 2.0], &[5.0, 3.0]], &mut centroid);
+        assert_eq!(centroid, vec![1.5, 2.5]);
+    }
+}
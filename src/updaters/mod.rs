@@ -0,0 +1,9 @@
+mod geometric_median;
+mod mean;
+mod median;
+mod normalized_mean;
+
+pub use geometric_median::GeometricMedianUpdater;
+pub use mean::MeanUpdater;
+pub use median::MedianUpdater;
+pub use normalized_mean::NormalizedMeanUpdater;
@@ -0,0 +1,30 @@
+use crate::{CentroidUpdater, Primitive};
+
+/// Centroid update rule of spherical k-means: the mean of all members, normalized to unit length.
+/// Meant to be used on (unit-length) samples, that are compared by their direction only (e.g. text embeddings).
+/// A mean of length zero is kept as it is.
+pub struct NormalizedMeanUpdater;
+
+impl<T: Primitive> CentroidUpdater<T> for NormalizedMeanUpdater {
+    fn update(&self, members: &[&[T]], centroid: &mut [T]) {
+        crate::MeanUpdater.update(members, centroid);
+        let norm = centroid.iter().map(|&c| c * c).sum::<T>().sqrt();
+        if norm > T::zero() {
+            centroid.iter_mut().for_each(|c| *c = *c / norm);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalized_mean_update() {
+        let mut centroid = vec![0.0f64; 2];
+        NormalizedMeanUpdater.update(&[&[0.0, 1.0], &[6.0, 7.0]], &mut centroid);
+        assert_eq!(centroid, vec![0.6, 0.8]);
+        NormalizedMeanUpdater.update(&[&[-1.0, 1.0], &[1.0, -1.0]], &mut centroid);
+        assert_eq!(centroid, vec![0.0, 0.0]);
+    }
+}
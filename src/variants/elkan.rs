@@ -0,0 +1,226 @@
+use super::Lloyd;
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{self, LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rayon::prelude::*;
+use std::mem::size_of_val;
+
+pub(crate) struct Elkan<T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    D: DistanceFunction<T, LANES>,
+{
+    _p: std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, const LANES: usize, D> Elkan<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    /// Metric distance between two vectors, on which all bounds are based. The square root of a metric is a metric as
+    /// well, so this works for metric distance functions, as well as for squared metrics (e.g. [`crate::EuclideanDistance`]).
+    #[inline(always)]
+    pub(crate) fn metric(data: &KMeans<T, LANES, D>, a: &[T], b: &[T]) -> T { data.distance_fn.distance(a, b).sqrt() }
+
+    /// Assign every sample to its nearest centroid, calculating the distances to all centroids, and use them as
+    /// initial lower bounds.
+    fn assign_full(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, lower_bounds: &mut [T]) {
+        let centroids = &state.centroids;
+        let samples = data
+            .p_samples
+            .bfr
+            .par_chunks_exact(data.p_samples.stride)
+            .zip(state.assignments.par_iter_mut())
+            .zip(state.centroid_distances.par_iter_mut())
+            .zip(lower_bounds.par_chunks_exact_mut(state.k));
+        simd::for_each_packet(samples, simd::PACKET_SIZE, |(((s, assignment), centroid_dist), lower)| {
+            let (mut best_idx, mut best_dist) = (0, T::infinity());
+            centroids
+                .chunks_exact_stride()
+                .zip(lower.iter_mut())
+                .enumerate()
+                .for_each(|(idx, (c, l))| {
+                    let dist = data.distance_fn.distance(s, c);
+                    *l = dist.sqrt();
+                    if dist < best_dist {
+                        (best_idx, best_dist) = (idx, dist);
+                    }
+                });
+            *assignment = best_idx;
+            *centroid_dist = best_dist;
+        });
+    }
+
+    /// Assign every sample to its nearest centroid, skipping all centroids that can not be nearer than the current one,
+    /// according to the lower bounds and the distances between the centroids.
+    fn assign_bounded(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, lower_bounds: &mut [T]) {
+        let k = state.k;
+        let (centroid_dists, nearest_other) = Self::half_centroid_distances(data, state);
+        let centroids = &state.centroids;
+
+        let samples = data
+            .p_samples
+            .bfr
+            .par_chunks_exact(data.p_samples.stride)
+            .zip(state.assignments.par_iter_mut())
+            .zip(state.centroid_distances.par_iter_mut())
+            .zip(lower_bounds.par_chunks_exact_mut(k));
+        simd::for_each_packet(samples, simd::PACKET_SIZE, |(((s, assignment), centroid_dist), lower)| {
+            let (mut best_idx, mut best_dist) = (*assignment, data.distance_fn.distance(s, centroids.nth_stride(*assignment)));
+            let mut upper = best_dist.sqrt();
+            lower[best_idx] = upper;
+            if upper < nearest_other[best_idx] {
+                *centroid_dist = best_dist;
+                return;
+            }
+            for (idx, c) in centroids.chunks_exact_stride().enumerate() {
+                if idx == best_idx || upper < lower[idx] || upper < centroid_dists[best_idx * k + idx] {
+                    continue;
+                }
+                let dist = data.distance_fn.distance(s, c);
+                lower[idx] = dist.sqrt();
+                // Ties are resolved towards the lower index, as done by the exhaustive assignment
+                if dist < best_dist || (dist == best_dist && idx < best_idx) {
+                    (best_idx, best_dist, upper) = (idx, dist, lower[idx]);
+                }
+            }
+            *assignment = best_idx;
+            *centroid_dist = best_dist;
+        });
+    }
+
+    /// Half the (metric) distances between all centroids [row-major], and half the distance of every centroid to its
+    /// nearest other centroid.
+    pub(crate) fn half_centroid_distances(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>) -> (Vec<T>, Vec<T>) {
+        let k = state.k;
+        let centroids = &state.centroids;
+        let half = T::from(0.5).unwrap();
+        let centroid_dists: Vec<T> = simd::map_packets((0..k * k).into_par_iter(), simd::PACKET_SIZE, |i| {
+            half * Self::metric(data, centroids.nth_stride(i / k), centroids.nth_stride(i % k))
+        });
+        let nearest_other: Vec<T> = centroid_dists
+            .chunks_exact(k)
+            .enumerate()
+            .map(|(c, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|&(o, _)| o != c)
+                    .map(|(_, &d)| d)
+                    .fold(T::infinity(), T::min)
+            })
+            .collect();
+        state
+            .memory_usage
+            .record_temporaries(size_of_val(centroid_dists.as_slice()) + size_of_val(nearest_other.as_slice()));
+        (centroid_dists, nearest_other)
+    }
+
+    /// (Metric) distance every centroid moved since **old_centroids**.
+    pub(crate) fn drifts(data: &KMeans<T, LANES, D>, state: &KMeansState<T>, old_centroids: &StrideBuffer<T>) -> Vec<T> {
+        simd::dispatch(|| {
+            old_centroids
+                .chunks_exact_stride()
+                .zip(state.centroids.chunks_exact_stride())
+                .map(|(o, c)| Self::metric(data, o, c))
+                .collect()
+        })
+    }
+
+    /// Decrease all lower bounds by the distance their centroid moved since **old_centroids**.
+    fn update_bounds(data: &KMeans<T, LANES, D>, state: &KMeansState<T>, old_centroids: &StrideBuffer<T>, lower_bounds: &mut [T]) {
+        let drifts = Self::drifts(data, state, old_centroids);
+        lower_bounds.par_chunks_exact_mut(state.k).for_each(|lower| {
+            lower
+                .iter_mut()
+                .zip(drifts.iter().cloned())
+                .for_each(|(l, drift)| *l = (*l - drift).max(T::zero()));
+        });
+    }
+
+    #[inline(always)]
+    pub fn calculate<F>(data: &KMeans<T, LANES, D>, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        assert!(k <= data.sample_cnt);
+
+        let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
+        state.distsum = T::infinity();
+
+        // Initialize clusters and notify subscriber
+        init(data, &mut state, config);
+        config.notify_init(data, &mut state);
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (data.sample_cnt * k) as u64,
+            sample_dims: data.sample_dims,
+        });
+
+        // Lower bounds of the (metric) distance from every sample to every centroid [row-major]
+        let mut lower_bounds = vec![T::zero(); data.sample_cnt * k];
+        let mut old_centroids = state.centroids.clone();
+        for iteration in 1..=max_iter {
+            if iteration == 1 {
+                Self::assign_full(data, &mut state, &mut lower_bounds);
+            } else {
+                Self::assign_bounded(data, &mut state, &mut lower_bounds);
+            }
+            state
+                .memory_usage
+                .record_temporaries(size_of_val(lower_bounds.as_slice()) + size_of_val(old_centroids.bfr.as_slice()));
+            old_centroids.bfr.copy_from_slice(&state.centroids.bfr);
+            let new_distsum = Lloyd::update_centroids(data, &mut state, config);
+            // An accepted split-merge move restarts the convergence, so it must not lead to an abort
+            let interval = config.split_merge_interval;
+            let moved = interval > 0 && iteration.is_multiple_of(interval) && crate::split_merge::refine(data, &mut state);
+            Self::update_bounds(data, &state, &old_centroids, &mut lower_bounds);
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(data, &mut state, iteration, new_distsum);
+            state.n_iterations = iteration;
+            if let Some(reason) = stop_criteria
+                .next(data, &state, new_distsum)
+                .filter(|reason| !moved || reason.is_forced())
+            {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = new_distsum;
+        }
+
+        data.update_centroid_distances(&mut state);
+        state.distsum = config.distsum(&state.centroid_distances, data.sample_weights.as_deref());
+        crate::postprocessing::apply(data, &mut state, config);
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn elkan_equals_lloyd() {
+        let mut rnd = rand::rngs::StdRng::seed_from_u64(42);
+        let (sample_cnt, sample_dims, k) = (2000, 5, 12);
+        let samples: Vec<f64> = (0..sample_cnt * sample_dims)
+            .map(|i| ((i / sample_dims) % k) as f64 * 3.0 + rnd.gen_range(-2.0..2.0))
+            .collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let init = || KMeans::init_precomputed(samples[..k * sample_dims].to_vec());
+        let conf = KMeansConfig::default();
+
+        let expected = kmean.kmeans_lloyd(k, 100, init(), &conf);
+        let res = kmean.kmeans_elkan(k, 100, init(), &conf);
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.centroids.to_vec(), expected.centroids.to_vec());
+        assert_eq!(res.centroid_frequency, expected.centroid_frequency);
+        assert_eq!(res.distsum, expected.distsum);
+    }
+}
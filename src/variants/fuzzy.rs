@@ -0,0 +1,223 @@
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rayon::prelude::*;
+use std::mem::{size_of, size_of_val};
+
+/// Result of a fuzzy c-means calculation (see [`KMeans::kmeans_fuzzy`]).
+///
+/// ## Fields
+/// - **state**: The fuzzy c-means result, where **assignments** / **centroid_distances** refer to each sample's nearest
+///   cluster (which is also the cluster of its highest membership)
+/// - **memberships**: Membership degree of every sample in every cluster [row-major] = [<sample0: k memberships>,...],
+///   each row sums up to `1`
+#[derive(Clone, Debug)]
+pub struct FuzzyKMeansState<T: Primitive> {
+    pub state: KMeansState<T>,
+    pub memberships: Vec<T>,
+}
+impl<T: Primitive> FuzzyKMeansState<T> {
+    /// Membership degrees of the sample with the given index in all **k** clusters.
+    pub fn memberships_of(&self, sample_id: usize) -> &[T] { &self.memberships[sample_id * self.state.k..(sample_id + 1) * self.state.k] }
+}
+
+pub(crate) struct Fuzzy<T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    D: DistanceFunction<T, LANES>,
+{
+    _p: std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, const LANES: usize, D> Fuzzy<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    /// Assign every sample to its nearest cluster, and calculate its membership degrees
+    /// `u_j = 1 / sum_l (d_j / d_l)^(1 / (m - 1))` in all clusters.
+    ///
+    /// ## Returns
+    /// The fuzzy c-means objective `sum_i sum_j u_ij^m * d_ij` of the current centroids.
+    fn update_memberships(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, memberships: &mut [T], m: T) -> T {
+        let exponent = T::one() / (m - T::one());
+        let centroids = &state.centroids;
+        data.p_samples
+            .bfr
+            .par_chunks_exact(data.p_samples.stride)
+            .zip(state.assignments.par_iter_mut())
+            .zip(state.centroid_distances.par_iter_mut())
+            .zip(memberships.par_chunks_exact_mut(state.k))
+            .enumerate()
+            .map(|(sample_id, (((s, assignment), centroid_dist), membership))| {
+                centroids
+                    .chunks_exact_stride()
+                    .zip(membership.iter_mut())
+                    .for_each(|(c, d)| *d = data.distance_fn.distance(s, c));
+                let (best_idx, best_dist) = membership
+                    .iter()
+                    .cloned()
+                    .enumerate()
+                    .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+                    .unwrap();
+                *assignment = best_idx;
+                *centroid_dist = best_dist;
+
+                // Samples on top of a centroid fully belong to it
+                if best_dist <= T::zero() {
+                    membership
+                        .iter_mut()
+                        .enumerate()
+                        .for_each(|(c, u)| *u = T::from((c == best_idx) as u8).unwrap());
+                    return T::zero();
+                }
+                // Work with the distances relative to the nearest one, which keeps all powers within [0, 1]
+                let mut objective = T::zero();
+                membership.iter_mut().for_each(|u| {
+                    let d = *u;
+                    *u = (best_dist / d).powf(exponent);
+                    objective += u.powf(m) * d;
+                });
+                let ratio_sum = membership.iter().cloned().sum::<T>();
+                membership.iter_mut().for_each(|u| *u = *u / ratio_sum);
+                data.sample_weight(sample_id) * objective / ratio_sum.powf(m)
+            })
+            .sum()
+    }
+
+    /// Move every centroid into the mean of all samples, weighted by their membership degrees `u^m`.
+    /// Centroids without any weight are kept where they are.
+    fn update_centroids(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, memberships: &[T], m: T) {
+        let (k, stride) = (state.k, state.centroids.stride);
+        state
+            .memory_usage
+            .record_temporaries(size_of_val(memberships) + rayon::current_num_threads() * (k * stride + k) * size_of::<T>());
+        let (sums, weights) = data
+            .p_samples
+            .bfr
+            .par_chunks_exact(data.p_samples.stride)
+            .zip(memberships.par_chunks_exact(k))
+            .enumerate()
+            .fold(
+                || (vec![T::zero(); k * stride], vec![T::zero(); k]),
+                |(mut sums, mut weights), (sample_id, (s, membership))| {
+                    let sample_weight = data.sample_weight(sample_id);
+                    membership.iter().enumerate().filter(|(_, &u)| u > T::zero()).for_each(|(c, &u)| {
+                        let q = sample_weight * u.powf(m);
+                        weights[c] += q;
+                        sums[c * stride..(c + 1) * stride]
+                            .chunks_exact_mut(LANES)
+                            .zip(s.chunks_exact(LANES).map(|v| Simd::from_slice(v)))
+                            .for_each(|(sum, s)| {
+                                let result = Simd::from_slice(sum) + s * Simd::splat(q);
+                                sum.copy_from_slice(result.as_array());
+                            });
+                    });
+                    (sums, weights)
+                },
+            )
+            .reduce(
+                || (vec![T::zero(); k * stride], vec![T::zero(); k]),
+                |(mut sums0, mut weights0), (sums1, weights1)| {
+                    sums0.iter_mut().zip(sums1).for_each(|(s0, s1)| *s0 += s1);
+                    weights0.iter_mut().zip(weights1).for_each(|(w0, w1)| *w0 += w1);
+                    (sums0, weights0)
+                },
+            );
+
+        state
+            .centroids
+            .chunks_exact_stride_mut()
+            .zip(sums.chunks_exact(stride))
+            .zip(weights.iter().cloned())
+            .filter(|(_, weight)| *weight > T::zero())
+            .for_each(|((c, sum), weight)| c.iter_mut().zip(sum.iter()).for_each(|(c, &sum)| *c = sum / weight));
+    }
+
+    #[inline(always)]
+    pub fn calculate<F>(
+        data: &KMeans<T, LANES, D>, k: usize, max_iter: usize, m: T, init: F, config: &KMeansConfig<'_, T>,
+    ) -> FuzzyKMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        assert!(k > 0 && k <= data.sample_cnt);
+        assert!(m > T::one());
+
+        let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
+        state.distsum = T::infinity();
+        let mut memberships = vec![T::zero(); data.sample_cnt * k];
+
+        // Initialize clusters and notify subscriber
+        init(data, &mut state, config);
+        config.notify_init(data, &mut state);
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (data.sample_cnt * k) as u64,
+            sample_dims: data.sample_dims,
+        });
+
+        for i in 1..=max_iter {
+            let new_objective = Self::update_memberships(data, &mut state, &mut memberships, m);
+            Self::update_centroids(data, &mut state, &memberships, m);
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(data, &mut state, i, new_objective);
+            state.n_iterations = i;
+            if let Some(reason) = stop_criteria.next(data, &state, new_objective) {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = new_objective;
+        }
+
+        // Memberships (and the partitioning derived from them) of the final centroids
+        Self::update_memberships(data, &mut state, &mut memberships, m);
+        data.update_cluster_frequencies(&state.assignments, &mut state.centroid_frequency);
+        state.distsum = config.distsum(&state.centroid_distances, data.sample_weights.as_deref());
+        data.update_per_cluster_distsum(&mut state);
+        FuzzyKMeansState { state, memberships }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn soft_memberships() {
+        // The sample at 6.0 lies exactly between both groups
+        let samples = vec![0.0f64, 1.0, 2.0, 6.0, 10.0, 11.0, 12.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_fuzzy(2, 100, 2.0, KMeans::init_precomputed(vec![1.0, 11.0]), &KMeansConfig::default());
+
+        assert_eq!(res.memberships.len(), samples.len() * 2);
+        assert!((0..samples.len()).all(|s| (res.memberships_of(s).iter().sum::<f64>() - 1.0).abs() < 1e-9));
+        assert!((res.memberships_of(3)[0] - 0.5).abs() < 1e-6);
+        assert!([0, 1, 2].iter().all(|&s| res.memberships_of(s)[0] > 0.9));
+        assert!([4, 5, 6].iter().all(|&s| res.memberships_of(s)[1] > 0.9));
+        let centroids = res.state.centroids.to_vec();
+        assert!((centroids[0] + centroids[1] - 12.0).abs() < 1e-6);
+        assert!(centroids[0] > 1.0 && centroids[0] < 2.0);
+        assert_eq!(&res.state.assignments[..3], &[0, 0, 0]);
+        assert_eq!(&res.state.assignments[4..], &[1, 1, 1]);
+
+        // The hard partitioning's error is reported, not the fuzzy objective
+        assert_eq!(res.state.distsum, res.state.centroid_distances.iter().sum::<f64>());
+    }
+
+    #[test]
+    fn samples_on_centroids() {
+        // Both initial centroids coincide with samples, which must not result in NaN memberships
+        let samples = vec![0.0f64, 0.0, 1.0, 9.0, 10.0, 10.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_fuzzy(2, 1, 1.5, KMeans::init_precomputed(vec![0.0, 10.0]), &KMeansConfig::default());
+        assert!(res.memberships.iter().all(|u| u.is_finite()));
+        assert_eq!(res.state.assignments, vec![0, 0, 0, 1, 1, 1]);
+        assert_eq!(res.state.centroid_frequency, vec![3, 3]);
+    }
+}
@@ -0,0 +1,188 @@
+use super::{Elkan, Lloyd};
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{self, LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rayon::prelude::*;
+use std::mem::size_of_val;
+
+pub(crate) struct Hamerly<T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    D: DistanceFunction<T, LANES>,
+{
+    _p: std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, const LANES: usize, D> Hamerly<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    /// Assign every sample to its nearest centroid. Samples whose current centroid is nearer than the lower bound of
+    /// the distance to all other centroids are skipped, all other samples are compared against every centroid.
+    fn update_cluster_assignments(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, lower_bounds: &mut [T], first: bool) {
+        let nearest_other = match first {
+            true => vec![T::zero(); state.k],
+            false => Elkan::half_centroid_distances(data, state).1,
+        };
+        let centroids = &state.centroids;
+
+        let samples = data
+            .p_samples
+            .bfr
+            .par_chunks_exact(data.p_samples.stride)
+            .zip(state.assignments.par_iter_mut())
+            .zip(state.centroid_distances.par_iter_mut())
+            .zip(lower_bounds.par_iter_mut());
+        simd::for_each_packet(samples, simd::PACKET_SIZE, |(((s, assignment), centroid_dist), lower)| {
+            if !first {
+                let dist = data.distance_fn.distance(s, centroids.nth_stride(*assignment));
+                if dist.sqrt() < nearest_other[*assignment].max(*lower) {
+                    *centroid_dist = dist;
+                    return;
+                }
+            }
+            let (mut best_idx, mut best_dist, mut second_dist) = (0, T::infinity(), T::infinity());
+            centroids.chunks_exact_stride().enumerate().for_each(|(idx, c)| {
+                let dist = data.distance_fn.distance(s, c);
+                if dist < best_dist {
+                    (best_idx, best_dist, second_dist) = (idx, dist, best_dist);
+                } else if dist < second_dist {
+                    second_dist = dist;
+                }
+            });
+            *assignment = best_idx;
+            *centroid_dist = best_dist;
+            *lower = second_dist.sqrt();
+        });
+    }
+
+    /// Decrease all lower bounds by the largest distance any other centroid moved since **old_centroids**. Samples
+    /// that were reassigned outside of the assignment step (e.g. to fill empty clusters) lose their lower bound.
+    fn update_bounds(
+        data: &KMeans<T, LANES, D>, state: &KMeansState<T>, old_centroids: &StrideBuffer<T>, old_assignments: &[usize],
+        lower_bounds: &mut [T],
+    ) {
+        let drifts = Elkan::drifts(data, state, old_centroids);
+        let (max_idx, max_drift) = drifts
+            .iter()
+            .cloned()
+            .enumerate()
+            .fold((0, T::zero()), |best, (idx, d)| if d > best.1 { (idx, d) } else { best });
+        let second_drift = drifts
+            .iter()
+            .cloned()
+            .enumerate()
+            .filter(|&(idx, _)| idx != max_idx)
+            .map(|(_, d)| d)
+            .fold(T::zero(), T::max);
+        lower_bounds
+            .par_iter_mut()
+            .zip(state.assignments.par_iter().cloned())
+            .zip(old_assignments.par_iter().cloned())
+            .for_each(|((l, assignment), old_assignment)| {
+                let drift = if assignment == max_idx { second_drift } else { max_drift };
+                *l = match assignment == old_assignment {
+                    true => (*l - drift).max(T::zero()),
+                    false => T::zero(),
+                };
+            });
+    }
+
+    #[inline(always)]
+    pub fn calculate<F>(data: &KMeans<T, LANES, D>, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        assert!(k <= data.sample_cnt);
+
+        let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
+        state.distsum = T::infinity();
+
+        // Initialize clusters and notify subscriber
+        init(data, &mut state, config);
+        config.notify_init(data, &mut state);
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (data.sample_cnt * k) as u64,
+            sample_dims: data.sample_dims,
+        });
+
+        // Lower bound of the (metric) distance from every sample to its second-nearest centroid
+        let mut lower_bounds = vec![T::zero(); data.sample_cnt];
+        let mut old_centroids = state.centroids.clone();
+        let mut old_assignments = vec![0; data.sample_cnt];
+        for iteration in 1..=max_iter {
+            Self::update_cluster_assignments(data, &mut state, &mut lower_bounds, iteration == 1);
+            state.memory_usage.record_temporaries(
+                size_of_val(lower_bounds.as_slice()) + size_of_val(old_centroids.bfr.as_slice()) + size_of_val(old_assignments.as_slice()),
+            );
+            old_centroids.bfr.copy_from_slice(&state.centroids.bfr);
+            old_assignments.copy_from_slice(&state.assignments);
+            let new_distsum = Lloyd::update_centroids(data, &mut state, config);
+            // An accepted split-merge move restarts the convergence, so it must not lead to an abort
+            let interval = config.split_merge_interval;
+            let moved = interval > 0 && iteration.is_multiple_of(interval) && crate::split_merge::refine(data, &mut state);
+            Self::update_bounds(data, &state, &old_centroids, &old_assignments, &mut lower_bounds);
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(data, &mut state, iteration, new_distsum);
+            state.n_iterations = iteration;
+            if let Some(reason) = stop_criteria
+                .next(data, &state, new_distsum)
+                .filter(|reason| !moved || reason.is_forced())
+            {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = new_distsum;
+        }
+
+        data.update_centroid_distances(&mut state);
+        state.distsum = config.distsum(&state.centroid_distances, data.sample_weights.as_deref());
+        crate::postprocessing::apply(data, &mut state, config);
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn hamerly_equals_lloyd() {
+        let mut rnd = rand::rngs::StdRng::seed_from_u64(42);
+        let (sample_cnt, sample_dims, k) = (2000, 5, 12);
+        let samples: Vec<f64> = (0..sample_cnt * sample_dims)
+            .map(|i| ((i / sample_dims) % k) as f64 * 3.0 + rnd.gen_range(-2.0..2.0))
+            .collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let init = || KMeans::init_precomputed(samples[..k * sample_dims].to_vec());
+        let conf = KMeansConfig::default();
+
+        let expected = kmean.kmeans_lloyd(k, 100, init(), &conf);
+        let res = kmean.kmeans_hamerly(k, 100, init(), &conf);
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.centroids.to_vec(), expected.centroids.to_vec());
+        assert_eq!(res.centroid_frequency, expected.centroid_frequency);
+        assert_eq!(res.distsum, expected.distsum);
+    }
+
+    #[test]
+    fn hamerly_refills_empty_clusters() {
+        // The third centroid starts far away from all samples, and is refilled during the first iteration
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::default();
+        let init = || KMeans::init_precomputed(vec![1.0, 11.0, 100.0]);
+        let expected = kmean.kmeans_lloyd(3, 100, init(), &conf);
+        let res = kmean.kmeans_hamerly(3, 100, init(), &conf);
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.centroids.to_vec(), expected.centroids.to_vec());
+    }
+}
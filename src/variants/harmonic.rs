@@ -0,0 +1,167 @@
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rayon::prelude::*;
+use std::mem::size_of;
+
+pub(crate) struct Harmonic<T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    D: DistanceFunction<T, LANES>,
+{
+    _p: std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, const LANES: usize, D> Harmonic<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    /// Assign every sample to its nearest centroid, and move every centroid into the mean of all samples, weighted by
+    /// their harmonic memberships `d_j^(-p-2) / (sum_l d_l^(-p))^2`.
+    ///
+    /// ## Returns
+    /// The k-harmonic-means objective `sum_i k / sum_j d_ij^(-p)` of the centroids before the update.
+    fn update(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, p: T) -> T {
+        let (k, stride) = (state.k, state.centroids.stride);
+        let squared = data.distance_fn.is_squared_euclidean();
+        let kt = T::from(k).unwrap();
+        let centroids = &state.centroids;
+        state
+            .memory_usage
+            .record_temporaries(rayon::current_num_threads() * (k * stride + 2 * k) * size_of::<T>());
+
+        let (sums, weights, objective) = data
+            .p_samples
+            .bfr
+            .par_chunks_exact(data.p_samples.stride)
+            .zip(state.assignments.par_iter_mut())
+            .zip(state.centroid_distances.par_iter_mut())
+            .enumerate()
+            .fold(
+                || (vec![T::zero(); k * stride], vec![T::zero(); k], T::zero()),
+                |(mut sums, mut weights, mut objective), (sample_id, ((s, assignment), centroid_dist))| {
+                    let mut distances: Vec<T> = centroids.chunks_exact_stride().map(|c| data.distance_fn.distance(s, c)).collect();
+                    let (best_idx, best_dist) = distances
+                        .iter()
+                        .cloned()
+                        .enumerate()
+                        .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+                        .unwrap();
+                    *assignment = best_idx;
+                    *centroid_dist = best_dist;
+
+                    // Work with the distances relative to the nearest one, which keeps all powers within [0, 1].
+                    // Samples on top of a centroid are moved an epsilon away from it.
+                    if squared {
+                        distances.iter_mut().for_each(|d| *d = d.sqrt());
+                    }
+                    let d_min = distances[best_idx].max(T::epsilon());
+                    let ratios: Vec<T> = distances.iter().map(|&d| d_min / d.max(d_min)).collect();
+                    let ratio_sum = ratios.iter().map(|r| r.powf(p)).sum::<T>();
+                    let sample_weight = data.sample_weight(sample_id);
+                    objective += sample_weight * kt * d_min.powf(p) / ratio_sum;
+
+                    let scale = sample_weight * d_min.powf(p - T::from(2).unwrap()) / (ratio_sum * ratio_sum);
+                    ratios.iter().enumerate().for_each(|(c, r)| {
+                        let q = scale * r.powf(p + T::from(2).unwrap());
+                        weights[c] += q;
+                        sums[c * stride..(c + 1) * stride]
+                            .iter_mut()
+                            .zip(s.iter())
+                            .for_each(|(sum, &v)| *sum += q * v);
+                    });
+                    (sums, weights, objective)
+                },
+            )
+            .reduce(
+                || (vec![T::zero(); k * stride], vec![T::zero(); k], T::zero()),
+                |(mut sums0, mut weights0, objective0), (sums1, weights1, objective1)| {
+                    sums0.iter_mut().zip(sums1).for_each(|(s0, s1)| *s0 += s1);
+                    weights0.iter_mut().zip(weights1).for_each(|(w0, w1)| *w0 += w1);
+                    (sums0, weights0, objective0 + objective1)
+                },
+            );
+
+        // Centroids without any weight (all samples are on top of other centroids) are kept where they are
+        state
+            .centroids
+            .chunks_exact_stride_mut()
+            .zip(sums.chunks_exact(stride))
+            .zip(weights.iter().cloned())
+            .filter(|(_, weight)| *weight > T::zero())
+            .for_each(|((c, sum), weight)| c.iter_mut().zip(sum.iter()).for_each(|(c, &sum)| *c = sum / weight));
+        objective
+    }
+
+    #[inline(always)]
+    pub fn calculate<F>(
+        data: &KMeans<T, LANES, D>, k: usize, max_iter: usize, p: T, init: F, config: &KMeansConfig<'_, T>,
+    ) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        assert!(k <= data.sample_cnt);
+        assert!(p >= T::from(2).unwrap());
+
+        let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
+        state.distsum = T::infinity();
+
+        // Initialize clusters and notify subscriber
+        init(data, &mut state, config);
+        config.notify_init(data, &mut state);
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (data.sample_cnt * k) as u64,
+            sample_dims: data.sample_dims,
+        });
+
+        for i in 1..=max_iter {
+            let new_objective = Self::update(data, &mut state, p);
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(data, &mut state, i, new_objective);
+            state.n_iterations = i;
+            if let Some(reason) = stop_criteria.next(data, &state, new_objective) {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = new_objective;
+        }
+
+        // The result is reported as a partitioning, like the results of all other variants
+        data.update_cluster_assignments(&mut state, None);
+        state.centroid_frequency.iter_mut().for_each(|f| *f = 0);
+        state.assignments.iter().for_each(|&c| state.centroid_frequency[c] += 1);
+        state.distsum = config.distsum(&state.centroid_distances, data.sample_weights.as_deref());
+        crate::postprocessing::apply(data, &mut state, config);
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn insensitive_to_initialization() {
+        // Two initial centroids share the first group, which traps Lloyd in a local minimum
+        let samples = vec![0.0f64, 1.0, 10.0, 11.0, 20.0, 21.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::default();
+        let lloyd = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![0.0, 1.0, 15.5]), &conf);
+        assert_eq!(lloyd.assignments, vec![0, 1, 2, 2, 2, 2]);
+
+        let res = kmean.kmeans_harmonic(3, 100, 2.0, KMeans::init_precomputed(vec![0.0, 1.0, 15.5]), &conf);
+        let (a, b, c) = (res.assignments[0], res.assignments[2], res.assignments[4]);
+        assert_eq!(res.assignments, vec![a, a, b, b, c, c]);
+        assert_eq!(res.centroid_frequency, vec![2, 2, 2]);
+        let centroids = res.centroids.to_vec();
+        assert!((centroids[a] - 0.5).abs() < 1e-2 && (centroids[b] - 10.5).abs() < 1e-2 && (centroids[c] - 20.5).abs() < 1e-2);
+        assert_eq!(res.distsum, res.centroid_distances.iter().sum::<f64>());
+    }
+}
@@ -0,0 +1,258 @@
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{self, LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rand::Rng;
+use rayon::prelude::*;
+use std::mem::{size_of, size_of_val};
+
+/// Share of the sampling probability that is distributed uniformly over all samples. This guarantees every sample
+/// a non-zero probability (and thus bounded weights), even if its loss is zero.
+const UNIFORM_SHARE: f64 = 0.5;
+
+pub(crate) struct ImportanceMinibatch<T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    D: DistanceFunction<T, LANES>,
+{
+    _p: std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, const LANES: usize, D> ImportanceMinibatch<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    /// Loss of the given sample, weighted by its sample weight.
+    fn loss(data: &KMeans<T, LANES, D>, state: &KMeansState<T>, sample_id: usize) -> f64 {
+        (state.centroid_distances[sample_id] * data.sample_weight(sample_id))
+            .to_f64()
+            .unwrap()
+    }
+
+    /// Sampling probability of the given sample: a mixture of the uniform distribution, and a distribution
+    /// proportional to the sample's (last known) loss.
+    fn sampling_probability(losses: &SumTree, sample_id: usize) -> f64 {
+        let n = losses.len() as f64;
+        if losses.total() > 0.0 {
+            UNIFORM_SHARE / n + (1.0 - UNIFORM_SHARE) * losses.get(sample_id) / losses.total()
+        } else {
+            1.0 / n
+        }
+    }
+
+    /// Draw a sample from the mixture distribution of [`Self::sampling_probability`].
+    fn draw(losses: &SumTree, rnd: &mut impl Rng) -> usize {
+        if losses.total() > 0.0 && rnd.gen::<f64>() >= UNIFORM_SHARE {
+            losses.find(rnd.gen::<f64>() * losses.total())
+        } else {
+            rnd.gen_range(0..losses.len())
+        }
+    }
+
+    fn update_cluster_assignments(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, batch: &[usize]) {
+        let centroids = &state.centroids;
+        let nearest: Vec<(usize, T)> = simd::map_packets(batch.par_iter(), simd::PACKET_SIZE, |&sample_id| {
+            let s = data.p_samples.nth_stride(sample_id);
+            centroids
+                .chunks_exact_stride()
+                .map(|c| data.distance_fn.distance(s, c))
+                .enumerate()
+                .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+                .unwrap()
+        });
+        batch.iter().zip(nearest).for_each(|(&sample_id, (assignment, dist))| {
+            state.assignments[sample_id] = assignment;
+            state.centroid_distances[sample_id] = dist;
+        });
+    }
+
+    /// Weighted variant of the mini-batch centroid update, where each sample contributes with its inverse-probability
+    /// weight `w / (n * p)` (with the sample's weight **w**, `1` without sample weights). This corrects the bias of the
+    /// non-uniform sampling, so every centroid still converges towards the (sample-weighted) mean of its cluster.
+    fn update_centroids(
+        data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, batch: &[usize], probabilities: &[f64], centroid_weights: &mut [f64],
+    ) {
+        let n = data.sample_cnt as f64;
+        batch.iter().cloned().zip(probabilities).for_each(|(sample_id, &probability)| {
+            let assignment = state.assignments[sample_id];
+            let weight = data.sample_weight(sample_id).to_f64().unwrap() / (n * probability);
+            centroid_weights[assignment] += weight;
+            let learn_rate = T::from(weight / centroid_weights[assignment]).unwrap();
+            let inv_learn_rate = T::one() - learn_rate;
+            state
+                .centroids
+                .nth_stride_mut(assignment)
+                .iter_mut()
+                .zip(data.p_samples.nth_stride(sample_id).iter().cloned())
+                .for_each(|(c, s)| {
+                    *c = inv_learn_rate * *c + learn_rate * s;
+                });
+        });
+    }
+
+    #[inline(always)]
+    pub fn calculate<F>(
+        data: &KMeans<T, LANES, D>, batch_size: usize, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+    ) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        assert!(k <= data.sample_cnt);
+        assert!(batch_size <= data.sample_cnt);
+
+        let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
+        state.distsum = T::infinity();
+
+        // Initialize clusters and notify subscriber
+        init(data, &mut state, config);
+        config.notify_init(data, &mut state);
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (batch_size * k) as u64,
+            sample_dims: data.sample_dims,
+        });
+
+        // Update cluster assignments for all samples, to get an initial loss for every sample
+        data.update_cluster_assignments(&mut state, None);
+        let mut losses = SumTree::new((0..data.sample_cnt).map(|sample_id| Self::loss(data, &state, sample_id)).collect());
+        let mut centroid_weights = vec![0.0; k];
+
+        for i in 1..=max_iter {
+            // Losses of samples outside of the batches are only updated when they are drawn again
+            let batch: Vec<usize> = {
+                let mut rnd = config.rnd.borrow_mut();
+                (0..batch_size).map(|_| Self::draw(&losses, &mut *rnd)).collect()
+            };
+            let probabilities: Vec<f64> = batch
+                .iter()
+                .map(|&sample_id| Self::sampling_probability(&losses, sample_id))
+                .collect();
+
+            // Tree of losses, the batch, its probabilities and its nearest centroids
+            state.memory_usage.record_temporaries(
+                size_of_val(losses.nodes.as_slice()) + batch.len() * (2 * size_of::<usize>() + size_of::<f64>() + size_of::<T>()),
+            );
+            Self::update_cluster_assignments(data, &mut state, &batch);
+            batch
+                .iter()
+                .for_each(|&sample_id| losses.set(sample_id, Self::loss(data, &state, sample_id)));
+            let new_distsum = config.distsum(&state.centroid_distances, data.sample_weights.as_deref());
+            Self::update_centroids(data, &mut state, &batch, &probabilities, &mut centroid_weights);
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(data, &mut state, i, new_distsum);
+            state.n_iterations = i;
+            if let Some(reason) = stop_criteria.next(data, &state, new_distsum) {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = new_distsum;
+        }
+
+        data.update_cluster_assignments(&mut state, None);
+        data.update_cluster_frequencies(&state.assignments, &mut state.centroid_frequency);
+        state.distsum = config.distsum(&state.centroid_distances, data.sample_weights.as_deref());
+        crate::postprocessing::apply(data, &mut state, config);
+        state
+    }
+}
+
+/// Binary tree over non-negative values, where every inner node holds the sum of its children. Updating a value and
+/// drawing an index proportional to the values both take `O(log n)`.
+struct SumTree {
+    /// Nodes of the complete tree in breadth-first order, with the root at `1` and the children of node `i` at `2i`
+    /// and `2i + 1`. The leaves start at `leaves`, padded with zeros to a power of two.
+    nodes: Vec<f64>,
+    leaves: usize,
+    len: usize,
+}
+impl SumTree {
+    fn new(values: Vec<f64>) -> Self {
+        let leaves = values.len().next_power_of_two();
+        let mut nodes = vec![0.0; 2 * leaves];
+        nodes[leaves..leaves + values.len()].copy_from_slice(&values);
+        (1..leaves).rev().for_each(|i| nodes[i] = nodes[2 * i] + nodes[2 * i + 1]);
+        Self {
+            nodes,
+            leaves,
+            len: values.len(),
+        }
+    }
+
+    fn len(&self) -> usize { self.len }
+
+    fn total(&self) -> f64 { self.nodes[1] }
+
+    fn get(&self, idx: usize) -> f64 { self.nodes[self.leaves + idx] }
+
+    fn set(&mut self, idx: usize, value: f64) {
+        let mut node = self.leaves + idx;
+        self.nodes[node] = value;
+        // Sums are recalculated from the children (instead of adding the difference), so no rounding errors accumulate
+        while node > 1 {
+            node /= 2;
+            self.nodes[node] = self.nodes[2 * node] + self.nodes[2 * node + 1];
+        }
+    }
+
+    /// Index of the value, within whose range of the cumulative sums **target** (in `[0, total)`) lies.
+    fn find(&self, mut target: f64) -> usize {
+        let mut node = 1;
+        while node < self.leaves {
+            let left = self.nodes[2 * node];
+            // Never descend into an empty subtree, which rounding errors of the target could cause
+            if target < left || self.nodes[2 * node + 1] <= 0.0 {
+                node *= 2;
+            } else {
+                target -= left;
+                node = 2 * node + 1;
+            }
+        }
+        node - self.leaves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SumTree;
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn sum_tree() {
+        let mut tree = SumTree::new(vec![1.0, 0.0, 2.0, 3.0, 0.5]);
+        assert_eq!((tree.len(), tree.total()), (5, 6.5));
+        let found: Vec<usize> = [0.0, 0.99, 1.0, 2.99, 3.0, 5.99, 6.0, 6.49].iter().map(|&t| tree.find(t)).collect();
+        assert_eq!(found, vec![0, 0, 2, 2, 3, 3, 4, 4]);
+
+        tree.set(1, 4.0);
+        tree.set(3, 0.0);
+        assert_eq!((tree.total(), tree.get(1), tree.get(3)), (7.5, 4.0, 0.0));
+        let found: Vec<usize> = [0.5, 1.0, 4.99, 5.0, 6.99, 7.0].iter().map(|&t| tree.find(t)).collect();
+        assert_eq!(found, vec![0, 1, 1, 2, 2, 4]);
+        // Targets beyond the total (from rounding) still find a non-empty value
+        assert_eq!(tree.find(7.5), 4);
+    }
+
+    #[test]
+    fn importance_minibatch_finds_rare_cluster() {
+        // Two large clusters, and one rare but far-away cluster
+        let mut rnd = rand::rngs::StdRng::seed_from_u64(1337);
+        let mut samples: Vec<f64> = (0..2000).map(|i| (i % 2) as f64 * 10.0 + rnd.gen_range(-1.0..1.0)).collect();
+        samples.extend((0..20).map(|_| 100.0 + rnd.gen_range(-1.0..1.0)));
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::build().random_generator(rnd).build();
+
+        let res = kmean.kmeans_minibatch_importance(100, 3, 200, KMeans::init_precomputed(vec![0.0, 10.0, 90.0]), &conf);
+        let mut centroids = res.centroids.to_vec();
+        centroids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!((centroids[0] - 0.0).abs() < 0.5);
+        assert!((centroids[1] - 10.0).abs() < 0.5);
+        assert!((centroids[2] - 100.0).abs() < 0.5);
+        assert_eq!(res.centroid_frequency.iter().sum::<usize>(), samples.len());
+    }
+}
@@ -0,0 +1,280 @@
+use super::Lloyd;
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use std::mem::{size_of, size_of_val};
+use std::ops::Range;
+
+/// Maximum amount of samples in a leaf of the kd-tree
+const LEAF_SIZE: usize = 16;
+/// Minimum amount of samples of a node, whose children are filtered in parallel
+const PARALLEL_SIZE: usize = 2048;
+
+/// Node of the kd-tree, covering a contiguous range of the tree's sample order.
+struct Node<T> {
+    /// Lower corner of the bounding box of the node's samples
+    lo: Vec<T>,
+    /// Upper corner of the bounding box of the node's samples
+    hi: Vec<T>,
+    range: Range<usize>,
+    children: Option<(usize, usize)>,
+}
+
+/// kd-tree over all samples, split at the median of the widest dimension of each node.
+struct KdTree<T> {
+    nodes: Vec<Node<T>>,
+    /// Sample ids, ordered such that every node covers a contiguous range
+    order: Vec<usize>,
+}
+
+pub(crate) struct KdTreeFiltering<T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    D: DistanceFunction<T, LANES>,
+{
+    _p: std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, const LANES: usize, D> KdTreeFiltering<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    fn build(data: &KMeans<T, LANES, D>) -> KdTree<T> {
+        let mut tree = KdTree {
+            nodes: Vec::new(),
+            order: (0..data.sample_cnt).collect(),
+        };
+        let mut order = std::mem::take(&mut tree.order);
+        Self::build_node(data, &mut order, 0, &mut tree.nodes);
+        tree.order = order;
+        tree
+    }
+
+    fn build_node(data: &KMeans<T, LANES, D>, order: &mut [usize], offset: usize, nodes: &mut Vec<Node<T>>) -> usize {
+        let dims = data.sample_dims;
+        let (mut lo, mut hi) = (vec![T::infinity(); dims], vec![T::neg_infinity(); dims]);
+        order.iter().for_each(|&sample_id| {
+            let s = &data.p_samples.nth_stride(sample_id)[..dims];
+            lo.iter_mut().zip(hi.iter_mut()).zip(s.iter()).for_each(|((lo, hi), &v)| {
+                *lo = lo.min(v);
+                *hi = hi.max(v);
+            });
+        });
+        let (split_dim, extent) = lo
+            .iter()
+            .zip(hi.iter())
+            .map(|(&lo, &hi)| hi - lo)
+            .enumerate()
+            .max_by(|(_, e0), (_, e1)| e0.partial_cmp(e1).unwrap())
+            .unwrap();
+
+        let node_idx = nodes.len();
+        nodes.push(Node {
+            lo,
+            hi,
+            range: offset..offset + order.len(),
+            children: None,
+        });
+        // Nodes of identical samples can not be split any further
+        if order.len() > LEAF_SIZE && extent > T::zero() {
+            let mid = order.len() / 2;
+            let value = |sample_id: usize| data.p_samples.nth_stride(sample_id)[split_dim];
+            order.select_nth_unstable_by(mid, |&a, &b| value(a).partial_cmp(&value(b)).unwrap());
+            let (left, right) = order.split_at_mut(mid);
+            let left = Self::build_node(data, left, offset, nodes);
+            let right = Self::build_node(data, right, offset + mid, nodes);
+            nodes[node_idx].children = Some((left, right));
+        }
+        node_idx
+    }
+
+    /// Whether **candidate** is farther than **best** from every point of the bounding box of **node**. The difference
+    /// of the squared distances is linear in the point, so it is enough to check the corner of the box that lies
+    /// furthest in the direction from **best** to **candidate**. The padding of **corner** has to be zero.
+    fn is_dominated(data: &KMeans<T, LANES, D>, node: &Node<T>, candidate: &[T], best: &[T], corner: &mut [T]) -> bool {
+        corner
+            .iter_mut()
+            .zip(node.lo.iter().zip(node.hi.iter()))
+            .zip(candidate.iter().zip(best.iter()))
+            .for_each(|((v, (&lo, &hi)), (&c, &b))| *v = if c > b { hi } else { lo });
+        data.distance_fn.distance(corner, candidate) > data.distance_fn.distance(corner, best)
+    }
+
+    /// Assign the samples of **node** to their nearest centroid among the **candidates**, writing the results into
+    /// **assignments** and **distances** (in the order of the tree). Candidates that are farther than the centroid
+    /// nearest to the node's midpoint from the whole node are pruned, once a single candidate is left, all samples of
+    /// the node are assigned to it.
+    #[allow(clippy::too_many_arguments)]
+    fn filter(
+        data: &KMeans<T, LANES, D>, tree: &KdTree<T>, centroids: &StrideBuffer<T>, node_idx: usize, candidates: &[usize],
+        assignments: &mut [usize], distances: &mut [T],
+    ) {
+        let node = &tree.nodes[node_idx];
+        let dims = data.sample_dims;
+        let mut point = vec![T::zero(); centroids.stride];
+        point[..dims]
+            .iter_mut()
+            .zip(node.lo.iter().zip(node.hi.iter()))
+            .for_each(|(v, (&lo, &hi))| *v = (lo + hi) / T::from(2).unwrap());
+        let best = candidates
+            .iter()
+            .cloned()
+            .map(|c| (c, data.distance_fn.distance(&point, centroids.nth_stride(c))))
+            .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+            .unwrap()
+            .0;
+        let remaining: Vec<usize> = candidates
+            .iter()
+            .cloned()
+            .filter(|&c| c == best || !Self::is_dominated(data, node, centroids.nth_stride(c), centroids.nth_stride(best), &mut point))
+            .collect();
+
+        let samples = &tree.order[node.range.clone()];
+        let candidates = remaining.as_slice();
+        match node.children {
+            Some((left, right)) if candidates.len() > 1 => {
+                let mid = tree.nodes[left].range.len();
+                let (left_assignments, right_assignments) = assignments.split_at_mut(mid);
+                let (left_distances, right_distances) = distances.split_at_mut(mid);
+                let mut filter_left = move || Self::filter(data, tree, centroids, left, candidates, left_assignments, left_distances);
+                let mut filter_right = move || Self::filter(data, tree, centroids, right, candidates, right_assignments, right_distances);
+                if samples.len() >= PARALLEL_SIZE {
+                    rayon::join(filter_left, filter_right);
+                } else {
+                    filter_left();
+                    filter_right();
+                }
+            },
+            _ => samples
+                .iter()
+                .zip(assignments.iter_mut().zip(distances.iter_mut()))
+                .for_each(|(&sample_id, (assignment, distance))| {
+                    let s = data.p_samples.nth_stride(sample_id);
+                    let (mut best_idx, mut best_dist) = (0, T::infinity());
+                    candidates.iter().cloned().for_each(|c| {
+                        let dist = data.distance_fn.distance(s, centroids.nth_stride(c));
+                        if dist < best_dist {
+                            (best_idx, best_dist) = (c, dist);
+                        }
+                    });
+                    *assignment = best_idx;
+                    *distance = best_dist;
+                }),
+        }
+    }
+
+    /// Assign every sample to its nearest centroid, by filtering the candidates along the kd-tree.
+    fn update_cluster_assignments(
+        data: &KMeans<T, LANES, D>, tree: &KdTree<T>, state: &mut KMeansState<T>, assignments: &mut [usize], distances: &mut [T],
+    ) {
+        let candidates: Vec<usize> = (0..state.k).collect();
+        Self::filter(data, tree, &state.centroids, 0, &candidates, assignments, distances);
+        tree.order
+            .iter()
+            .zip(assignments.iter().zip(distances.iter()))
+            .for_each(|(&sample_id, (&assignment, &distance))| {
+                state.assignments[sample_id] = assignment;
+                state.centroid_distances[sample_id] = distance;
+            });
+    }
+
+    #[inline(always)]
+    pub fn calculate<F>(data: &KMeans<T, LANES, D>, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        assert!(k <= data.sample_cnt);
+        assert!(
+            data.distance_fn.is_squared_euclidean(),
+            "the kd-tree filtering requires the squared euclidean distance"
+        );
+
+        let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
+        state.distsum = T::infinity();
+
+        // Initialize clusters and notify subscriber
+        init(data, &mut state, config);
+        config.notify_init(data, &mut state);
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (data.sample_cnt * k) as u64,
+            sample_dims: data.sample_dims,
+        });
+
+        // The tree is built once, the assignments and distances are calculated in the order of the tree
+        let tree = Self::build(data);
+        let mut tree_assignments = vec![0; data.sample_cnt];
+        let mut tree_distances = vec![T::zero(); data.sample_cnt];
+        let tree_size = tree.nodes.len() * (2 * data.sample_dims * size_of::<T>() + size_of::<Node<T>>())
+            + size_of_val(tree.order.as_slice())
+            + size_of_val(tree_assignments.as_slice())
+            + size_of_val(tree_distances.as_slice());
+        for iteration in 1..=max_iter {
+            Self::update_cluster_assignments(data, &tree, &mut state, &mut tree_assignments, &mut tree_distances);
+            state.memory_usage.record_temporaries(tree_size);
+            let new_distsum = Lloyd::update_centroids(data, &mut state, config);
+            // An accepted split-merge move restarts the convergence, so it must not lead to an abort
+            let interval = config.split_merge_interval;
+            let moved = interval > 0 && iteration.is_multiple_of(interval) && crate::split_merge::refine(data, &mut state);
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(data, &mut state, iteration, new_distsum);
+            state.n_iterations = iteration;
+            if let Some(reason) = stop_criteria
+                .next(data, &state, new_distsum)
+                .filter(|reason| !moved || reason.is_forced())
+            {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = new_distsum;
+        }
+
+        data.update_centroid_distances(&mut state);
+        state.distsum = config.distsum(&state.centroid_distances, data.sample_weights.as_deref());
+        crate::postprocessing::apply(data, &mut state, config);
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+    use rand::prelude::*;
+
+    #[test]
+    fn kdtree_equals_lloyd() {
+        let mut rnd = rand::rngs::StdRng::seed_from_u64(7);
+        let (sample_cnt, sample_dims, k) = (5000, 3, 12);
+        let samples: Vec<f64> = (0..sample_cnt * sample_dims)
+            .map(|i| ((i / sample_dims) % k) as f64 * 3.0 + rnd.gen_range(-2.0..2.0))
+            .collect();
+        let kmean: KMeans<f64, 4, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let init = || KMeans::init_precomputed(samples[..k * sample_dims].to_vec());
+        let conf = KMeansConfig::default();
+
+        let expected = kmean.kmeans_lloyd(k, 100, init(), &conf);
+        let res = kmean.kmeans_kdtree(k, 100, init(), &conf);
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.centroids.to_vec(), expected.centroids.to_vec());
+        assert_eq!(res.centroid_frequency, expected.centroid_frequency);
+        assert_eq!(res.distsum, expected.distsum);
+    }
+
+    #[test]
+    fn kdtree_duplicate_samples() {
+        // Many identical samples end up in a leaf that can not be split
+        let samples: Vec<f32> = (0..200).map(|i| if i < 150 { 1.0 } else { 5.0 + (i % 3) as f32 }).collect();
+        let kmean: KMeans<f32, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_kdtree(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &KMeansConfig::default());
+        assert_eq!(res.centroid_frequency, vec![150, 50]);
+        // The second cluster holds 17 samples of 5, 17 of 6 and 16 of 7
+        assert!((res.centroids[0][0] - 1.0).abs() < 1e-6);
+        assert!((res.centroids[1][0] - 5.98).abs() < 1e-5);
+    }
+}
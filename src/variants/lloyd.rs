@@ -1,7 +1,10 @@
-use crate::api::DistanceFunction;
+use super::KMeansRun;
+use crate::api::{CentroidUpdater, DistanceFunction};
 use crate::memory::*;
-use crate::{KMeans, KMeansConfig, KMeansState};
-use std::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::simd::{self, LaneCount, Simd, SupportedLaneCount};
+use crate::{scratch, KMeans, KMeansConfig, KMeansState};
+use rayon::prelude::*;
+use std::mem::{size_of, size_of_val};
 
 pub(crate) struct Lloyd<T, const LANES: usize, D>
 where
@@ -19,42 +22,73 @@ where
     Simd<T, LANES>: SupportedSimdArray<T, LANES>,
     D: DistanceFunction<T, LANES>,
 {
-    fn update_centroids(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>) -> T {
+    pub(crate) fn update_centroids(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, config: &KMeansConfig<'_, T>) -> T {
         // Sum all samples in a cluster together into new_centroids
         // Count non-empty clusters
         let mut used_centroids_cnt = 0;
-        let mut new_centroids = StrideBuffer::new::<LANES>(state.centroids.centroid_cnt, state.centroids.centroid_dim);
+        let mut new_centroids = scratch::stride_buffer::<T, LANES>(state.centroids.centroid_cnt, state.centroids.centroid_dim);
         let mut new_distsum = T::zero();
 
+        // Total weight of every cluster (only with sample weights, otherwise the centroid_frequency is used)
+        let weights = data.sample_weights.as_deref();
+        let mut cluster_weights = scratch::vec(if weights.is_some() { state.k } else { 0 }, T::zero());
         let (centroid_frequency, assignments, centroid_distances) =
             (&mut state.centroid_frequency, &state.assignments, &state.centroid_distances);
         rayon::scope(|s| {
             s.spawn(|_| {
                 used_centroids_cnt = data.update_cluster_frequencies(assignments, centroid_frequency);
+                if let Some(weights) = weights {
+                    assignments
+                        .iter()
+                        .zip(weights.iter())
+                        .for_each(|(&centroid_id, &w)| cluster_weights[centroid_id] += w);
+                }
             });
             s.spawn(|_| {
-                data.p_samples
-                    .chunks_exact_stride()
-                    .zip(assignments.iter().cloned())
-                    .for_each(|(s, centroid_id)| {
-                        new_centroids
-                            .nth_stride_mut(centroid_id)
-                            .chunks_exact_mut(LANES)
-                            .zip(s.chunks_exact(LANES).map(|i| Simd::from_slice(i)))
-                            .for_each(|(c, s)| {
-                                let c_simd = Simd::from_slice(c);
-                                let result = c_simd + s;
-                                c.copy_from_slice(result.as_array());
+                simd::dispatch(|| {
+                    if config.high_precision {
+                        Self::sum_centroids_f64(data, assignments, &mut new_centroids);
+                    } else if config.compensated_summation {
+                        Self::sum_centroids_compensated(data, assignments, &mut new_centroids);
+                    } else if let Some(weights) = weights {
+                        Self::sum_centroids_weighted(data, assignments, weights, &mut new_centroids);
+                    } else {
+                        data.p_samples
+                            .chunks_exact_stride()
+                            .zip(assignments.iter().cloned())
+                            .for_each(|(s, centroid_id)| {
+                                new_centroids
+                                    .nth_stride_mut(centroid_id)
+                                    .chunks_exact_mut(LANES)
+                                    .zip(s.chunks_exact(LANES).map(|i| Simd::from_slice(i)))
+                                    .for_each(|(c, s)| {
+                                        let c_simd = Simd::from_slice(c);
+                                        let result = c_simd + s;
+                                        c.copy_from_slice(result.as_array());
+                                    });
                             });
-                    });
+                    }
+                })
             });
             s.spawn(|_| {
-                new_distsum = centroid_distances.iter().cloned().sum();
+                new_distsum = config.iteration_distsum(centroid_distances, weights);
             });
         });
 
+        let sum_buffer = match (config.high_precision, config.compensated_summation) {
+            (true, _) => new_centroids.bfr.len() * size_of::<f64>(),
+            (false, true) => size_of_val(new_centroids.bfr.as_slice()),
+            (false, false) => 0,
+        };
+        state
+            .memory_usage
+            .record_temporaries(size_of_val(new_centroids.bfr.as_slice()) + sum_buffer + size_of_val(cluster_weights.as_slice()));
         if used_centroids_cnt != state.k {
-            let mut distance_sorted_samples: Vec<usize> = (0..data.sample_cnt).collect();
+            let mut distance_sorted_samples = scratch::vec(data.sample_cnt, 0);
+            distance_sorted_samples.iter_mut().enumerate().for_each(|(i, v)| *v = i);
+            state
+                .memory_usage
+                .record_temporaries(size_of_val(new_centroids.bfr.as_slice()) + size_of_val(distance_sorted_samples.as_slice()));
             distance_sorted_samples
                 .sort_unstable_by(|&i1, &i2| state.centroid_distances[i1].partial_cmp(&state.centroid_distances[i2]).unwrap());
 
@@ -75,7 +109,12 @@ where
                     // Re-Assign found sample to centroid without any samples
                     state.centroid_frequency[prev_centroid_id] -= 1;
                     state.centroid_frequency[i] += 1;
-                    new_distsum -= state.centroid_distances[sample_id];
+                    let weight = data.sample_weight(sample_id);
+                    if weights.is_some() {
+                        cluster_weights[prev_centroid_id] -= weight;
+                        cluster_weights[i] = weight;
+                    }
+                    new_distsum -= state.centroid_distances[sample_id] * weight;
                     // Centroid is moved into the chosen point -> the points centroid distance is 0
                     state.centroid_distances[sample_id] = T::zero();
                     // new_centroids is a sum of all points within a centroid here.
@@ -87,7 +126,7 @@ where
                         .take(data.p_samples.stride)
                         .zip(data.p_samples.bfr.iter().skip(sample_id * data.p_samples.stride).cloned())
                         .for_each(|(cv, sv)| {
-                            *cv -= sv;
+                            *cv -= sv * weight;
                         });
                     // Chosen sample is single point in cluster -> set cluster's sum to chosen point
                     new_centroids
@@ -97,20 +136,29 @@ where
                         .take(data.p_samples.stride)
                         .zip(data.p_samples.bfr.iter().skip(sample_id * data.p_samples.stride).cloned())
                         .for_each(|(cv, sv)| {
-                            *cv = sv;
+                            *cv = sv * weight;
                         });
                     state.assignments[sample_id] = i;
                 }
             }
         }
         // Calculate new centroids from updated cluster_assignments
+        if let Some(updater) = &config.centroid_updater {
+            Self::update_centroids_with(data, state, updater.as_ref());
+            return new_distsum;
+        }
         state
             .centroids
             .chunks_exact_stride_mut()
             .zip(new_centroids.chunks_exact_stride())
             .zip(state.centroid_frequency.iter().cloned())
-            .for_each(|((c, nc), cfreq)| {
-                let cfreq_factor_simd = Simd::splat(T::one() / T::from(cfreq).unwrap());
+            .enumerate()
+            .for_each(|(centroid_id, ((c, nc), cfreq))| {
+                let cluster_weight = match weights {
+                    Some(_) => cluster_weights[centroid_id],
+                    None => T::from(cfreq).unwrap(),
+                };
+                let cfreq_factor_simd = Simd::splat(T::one() / cluster_weight);
                 c.chunks_exact_mut(LANES)
                     .zip(nc.chunks_exact(LANES).map(|v| Simd::from_slice(v)))
                     .for_each(|(c, nc)| {
@@ -121,36 +169,126 @@ where
         new_distsum
     }
 
+    /// Sum all samples in a cluster together into **new_centroids**, each multiplied with its weight.
+    #[inline(always)]
+    fn sum_centroids_weighted(data: &KMeans<T, LANES, D>, assignments: &[usize], weights: &[T], new_centroids: &mut StrideBuffer<T>) {
+        data.p_samples
+            .chunks_exact_stride()
+            .zip(assignments.iter().cloned())
+            .zip(weights.iter().cloned())
+            .for_each(|((s, centroid_id), w)| {
+                let w = Simd::splat(w);
+                new_centroids
+                    .nth_stride_mut(centroid_id)
+                    .chunks_exact_mut(LANES)
+                    .zip(s.chunks_exact(LANES).map(|i| Simd::from_slice(i)))
+                    .for_each(|(c, s)| {
+                        let result = Simd::from_slice(c) + s * w;
+                        c.copy_from_slice(result.as_array());
+                    });
+            });
+    }
+
+    /// Sum all samples in a cluster together into **new_centroids** (weighted, if configured), accumulating in f64 precision.
+    #[inline(always)]
+    fn sum_centroids_f64(data: &KMeans<T, LANES, D>, assignments: &[usize], new_centroids: &mut StrideBuffer<T>) {
+        let stride = data.p_samples.stride;
+        let mut sums = scratch::vec(new_centroids.bfr.len(), 0.0f64);
+        data.p_samples
+            .chunks_exact_stride()
+            .zip(assignments.iter().cloned())
+            .enumerate()
+            .for_each(|(sample_id, (s, centroid_id))| {
+                let w = data.sample_weight(sample_id).to_f64().unwrap();
+                sums[centroid_id * stride..(centroid_id + 1) * stride]
+                    .iter_mut()
+                    .zip(s.iter())
+                    .for_each(|(c, v)| *c += v.to_f64().unwrap() * w);
+            });
+        new_centroids
+            .bfr
+            .iter_mut()
+            .zip(sums.iter())
+            .for_each(|(c, &sum)| *c = T::from(sum).unwrap());
+    }
+
+    /// Sum all samples in a cluster together into **new_centroids** (weighted, if configured), using compensated
+    /// (Kahan) summation.
+    #[inline(always)]
+    fn sum_centroids_compensated(data: &KMeans<T, LANES, D>, assignments: &[usize], new_centroids: &mut StrideBuffer<T>) {
+        let mut compensations = scratch::stride_buffer::<T, LANES>(new_centroids.centroid_cnt, new_centroids.centroid_dim);
+        data.p_samples
+            .chunks_exact_stride()
+            .zip(assignments.iter().cloned())
+            .enumerate()
+            .for_each(|(sample_id, (s, centroid_id))| {
+                let w = Simd::splat(data.sample_weight(sample_id));
+                new_centroids
+                    .nth_stride_mut(centroid_id)
+                    .chunks_exact_mut(LANES)
+                    .zip(compensations.nth_stride_mut(centroid_id).chunks_exact_mut(LANES))
+                    .zip(s.chunks_exact(LANES).map(|i| Simd::from_slice(i)))
+                    .for_each(|((c, comp), s)| {
+                        let (sum, compensation) = (Simd::from_slice(c), Simd::from_slice(comp));
+                        let y = s * w - compensation;
+                        let t = sum + y;
+                        comp.copy_from_slice(((t - sum) - y).as_array());
+                        c.copy_from_slice(t.as_array());
+                    });
+            });
+    }
+
+    /// Calculate new centroids from the cluster_assignments, using a custom centroid update rule
+    fn update_centroids_with(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, updater: &dyn CentroidUpdater<T>) {
+        let dims = data.sample_dims;
+        let mut members = vec![Vec::new(); state.k];
+        data.p_samples
+            .chunks_exact_stride()
+            .zip(state.assignments.iter().cloned())
+            .for_each(|(s, centroid_id)| members[centroid_id].push(&s[..dims]));
+        state
+            .memory_usage
+            .record_temporaries(size_of_val(members.as_slice()) + members.iter().map(|m| size_of_val(m.as_slice())).sum::<usize>());
+        state
+            .centroids
+            .bfr
+            .par_chunks_exact_mut(state.centroids.stride)
+            .zip(members.par_iter())
+            .for_each(|(c, m)| updater.update(m, &mut c[..dims]));
+    }
+
     #[inline(always)]
     pub fn calculate<F>(data: &KMeans<T, LANES, D>, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
     where
         for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
     {
-        assert!(k <= data.sample_cnt);
-
-        let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
-        state.distsum = T::infinity();
-
-        // Initialize clusters and notify subscriber
-        init(data, &mut state, config);
-        (config.init_done)(&state);
-        let mut abort_strategy = config.abort_strategy.create_logic();
+        Self::iterate(data, k, max_iter, init, config).finish()
+    }
 
-        for i in 1..=max_iter {
-            data.update_cluster_assignments(&mut state, None);
-            let new_distsum = Self::update_centroids(data, &mut state);
+    /// Lloyd calculation like [`Lloyd::calculate`], but without applying the post-processing steps (e.g. for the
+    /// intermediate results of optimizers, that run many Lloyd calculations).
+    pub(crate) fn calculate_unprocessed<F>(
+        data: &KMeans<T, LANES, D>, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+    ) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        Self::iterate(data, k, max_iter, init, config).finish_unprocessed()
+    }
 
-            // Notify subscriber about finished iteration
-            (config.iteration_done)(&state, i, new_distsum);
-            if !abort_strategy.next(new_distsum) {
+    fn iterate<'r, F>(
+        data: &'r KMeans<T, LANES, D>, k: usize, max_iter: usize, init: F, config: &'r KMeansConfig<'r, T>,
+    ) -> KMeansRun<'r, T, LANES, D>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        let mut run = KMeansRun::new(data, k, init, config);
+        for _ in 1..=max_iter {
+            if run.step().abort_requested {
                 break;
             }
-            state.distsum = new_distsum;
         }
-
-        data.update_centroid_distances(&mut state);
-        state.distsum = state.centroid_distances.iter().cloned().sum();
-        state
+        run
     }
 }
 
@@ -161,6 +299,80 @@ mod tests {
     use crate::EuclideanDistance;
     use rand::prelude::*;
 
+    #[test]
+    fn custom_centroid_updater() {
+        let samples = vec![0.0f64, 1.0, 2.0, 3.0, 100.0, 20.0, 21.0, 22.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::build().centroid_updater(crate::MedianUpdater).build();
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 20.0]), &conf);
+        assert_eq!(res.centroids.to_vec(), vec![1.5, 21.5]);
+        assert_eq!(res.assignments, vec![0, 0, 0, 0, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn estimated_inertia() {
+        let samples: Vec<f64> = (0..10000).map(|i| (i % 2) as f64 * 10.0 + (i % 11) as f64 * 0.1).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let iteration_distsums = std::sync::Mutex::new(Vec::new());
+        let conf = KMeansConfig::build()
+            .estimated_inertia(100)
+            .iteration_done(|_, _, distsum| iteration_distsums.lock().unwrap().push(distsum))
+            .build();
+        let res = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &conf);
+        let expected = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![0.0, 10.0]), &KMeansConfig::default());
+
+        // The result is exact, the intermediate distance sums are estimates
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.distsum, expected.distsum);
+        let estimate = *iteration_distsums.lock().unwrap().last().unwrap();
+        assert!((estimate - expected.distsum).abs() / expected.distsum < 0.1);
+    }
+
+    #[test]
+    fn high_precision_accumulation_f32() {
+        // Large sums of f32 values lose most of their precision, which shows in the centroid and the distsum
+        let (sample_cnt, sample_dims) = (1 << 18, 8);
+        let samples: Vec<f32> = (0..sample_cnt * sample_dims)
+            .map(|i| if (i / sample_dims) % 2 == 0 { 1000.0 } else { 1000.2 })
+            .collect();
+        let expected_mean = (1000.0f32 as f64 + 1000.2f32 as f64) / 2.0;
+        let expected_distsum: f64 = samples.iter().map(|&v| (v as f64 - expected_mean).powi(2)).sum();
+        let kmean: KMeans<f32, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let run = |enabled| {
+            let conf = KMeansConfig::build().high_precision_accumulation(enabled).build();
+            let res = kmean.kmeans_lloyd(1, 2, KMeans::init_precomputed(vec![1000.0; sample_dims]), &conf);
+            let mean_error = res
+                .centroids
+                .to_vec()
+                .into_iter()
+                .map(|c| (c as f64 - expected_mean).abs())
+                .fold(0.0, f64::max);
+            (mean_error, (res.distsum as f64 - expected_distsum).abs() / expected_distsum)
+        };
+
+        let (mean_error, distsum_error) = run(true);
+        assert!(mean_error < 1e-4);
+        assert!(distsum_error < 1e-3);
+        let (plain_mean_error, plain_distsum_error) = run(false);
+        assert!(plain_mean_error > mean_error);
+        assert!(plain_distsum_error > distsum_error);
+    }
+
+    #[test]
+    fn compensated_summation_f32() {
+        let (sample_cnt, sample_dims) = (1 << 18, 8);
+        let samples: Vec<f32> = (0..sample_cnt * sample_dims)
+            .map(|i| if (i / sample_dims) % 2 == 0 { 1000.0 } else { 1000.2 })
+            .collect();
+        let expected_mean = (1000.0f32 as f64 + 1000.2f32 as f64) / 2.0;
+        let expected_distsum: f64 = samples.iter().map(|&v| (v as f64 - expected_mean).powi(2)).sum();
+        let kmean: KMeans<f32, 8, _> = KMeans::new(&samples, sample_cnt, sample_dims, EuclideanDistance);
+        let conf = KMeansConfig::build().compensated_summation(true).build();
+        let res = kmean.kmeans_lloyd(1, 2, KMeans::init_precomputed(vec![1000.0; sample_dims]), &conf);
+        assert!(res.centroids.to_vec().into_iter().all(|c| (c as f64 - expected_mean).abs() < 1e-4));
+        assert!((res.distsum as f64 - expected_distsum).abs() / expected_distsum < 1e-3);
+    }
+
     #[test]
     fn iris_dataset_f64() {
         let samples = vec![
@@ -287,6 +499,27 @@ mod tests {
         assert_kmeans_result_eq(should, res);
     }
 
+    #[test]
+    fn resume() {
+        let mut rnd = StdRng::seed_from_u64(7);
+        let samples: Vec<f64> = (0..3000).map(|i| (i % 3) as f64 * 10.0 + rnd.gen_range(-3.0..3.0)).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let init = || KMeans::init_precomputed(vec![-5.0, -4.0, 30.0]);
+        let conf = KMeansConfig::default();
+
+        let full = kmean.kmeans_lloyd(3, 100, init(), &conf);
+        let partial = kmean.kmeans_lloyd(3, 2, init(), &conf);
+        assert!(full.n_iterations > 2);
+        let resumed = kmean.kmeans_lloyd_resume(partial, 100, &conf);
+        assert_eq!(resumed.centroids.to_vec(), full.centroids.to_vec());
+        assert_eq!(resumed.assignments, full.assignments);
+
+        // Warm start from a converged result
+        let warm = kmean.kmeans_lloyd_resume(full.clone(), 100, &conf);
+        assert_eq!(warm.centroids.to_vec(), full.centroids.to_vec());
+        assert!(warm.n_iterations <= 2);
+    }
+
     #[test]
     fn empty_cluster_handling() {
         let samples = vec![1.0, 0.0, 2.0, 0.0, 3.0, 0.0];
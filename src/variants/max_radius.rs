@@ -0,0 +1,151 @@
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::memory_usage::MemoryUsage;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::variants::Lloyd;
+use crate::{KMeans, KMeansConfig, KMeansState};
+
+pub(crate) struct MaxRadius<T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    D: DistanceFunction<T, LANES>,
+{
+    _p: std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, const LANES: usize, D> MaxRadius<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    /// Spawn new centroids on the samples outside of **max_radius** of their nearest centroid (leader clustering): The
+    /// uncovered samples are visited in order, and each one that is not within **max_radius** of a centroid spawned
+    /// before becomes a new centroid. Afterwards, all samples are reassigned.
+    ///
+    /// ## Returns
+    /// The amount of spawned centroids.
+    fn spawn_centroids(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, max_radius: T) -> usize {
+        let mut leaders: Vec<usize> = Vec::new();
+        state
+            .centroid_distances
+            .iter()
+            .enumerate()
+            .filter(|(_, &d)| d > max_radius)
+            .for_each(|(sample_id, _)| {
+                let s = data.p_samples.nth_stride(sample_id);
+                if leaders
+                    .iter()
+                    .all(|&l| data.distance_fn.distance(s, data.p_samples.nth_stride(l)) > max_radius)
+                {
+                    leaders.push(sample_id);
+                }
+            });
+        if leaders.is_empty() {
+            return 0;
+        }
+
+        let k = state.k + leaders.len();
+        let mut centroids = StrideBuffer::new::<LANES>(k, data.sample_dims);
+        centroids.bfr[..state.centroids.bfr.len()].copy_from_slice(&state.centroids.bfr);
+        leaders.iter().enumerate().for_each(|(idx, &l)| {
+            centroids
+                .nth_stride_mut(state.k + idx)
+                .copy_from_slice(data.p_samples.nth_stride(l))
+        });
+        state.centroids = centroids;
+        state.k = k;
+        state.centroid_frequency.resize(k, 0);
+        state.per_cluster_distsum.resize(k, T::zero());
+        state.memory_usage.state = MemoryUsage::new::<T, LANES>(data.sample_cnt, data.sample_dims, k).state;
+        data.update_cluster_assignments(state, None);
+        leaders.len()
+    }
+
+    fn iteration_cost(data: &KMeans<T, LANES, D>, k: usize) -> IterationCost {
+        IterationCost {
+            distance_evaluations: (data.sample_cnt * k) as u64,
+            sample_dims: data.sample_dims,
+        }
+    }
+
+    #[inline(always)]
+    pub fn calculate<F>(
+        data: &KMeans<T, LANES, D>, k: usize, max_iter: usize, max_radius: T, init: F, config: &KMeansConfig<'_, T>,
+    ) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        assert!(k > 0 && k <= data.sample_cnt);
+        assert!(max_radius >= T::zero());
+
+        let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
+        state.distsum = T::infinity();
+
+        // Initialize clusters and notify subscriber
+        init(data, &mut state, config);
+        config.notify_init(data, &mut state);
+        let mut stop_criteria = StopCriteria::new(config, &state, Self::iteration_cost(data, k));
+
+        for i in 1..=max_iter {
+            data.update_cluster_assignments(&mut state, None);
+            let spawned = Self::spawn_centroids(data, &mut state, max_radius);
+            let new_distsum = Lloyd::update_centroids(data, &mut state, config);
+            // Spawned centroids restart the convergence, so they must not lead to an abort
+            if spawned > 0 {
+                stop_criteria = StopCriteria::new(config, &state, Self::iteration_cost(data, state.k));
+            }
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(data, &mut state, i, new_distsum);
+            state.n_iterations = i;
+            if let Some(reason) = stop_criteria
+                .next(data, &state, new_distsum)
+                .filter(|reason| spawned == 0 || reason.is_forced())
+            {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = new_distsum;
+        }
+
+        // Moving the centroids into their means may have left samples outside the radius, which get centroids of their
+        // own (without moving any centroid again), so the result always satisfies the constraint
+        data.update_cluster_assignments(&mut state, None);
+        Self::spawn_centroids(data, &mut state, max_radius);
+        data.update_cluster_frequencies(&state.assignments, &mut state.centroid_frequency);
+        state.distsum = config.distsum(&state.centroid_distances, data.sample_weights.as_deref());
+        data.update_per_cluster_distsum(&mut state);
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn clusters_within_radius() {
+        let samples: Vec<f64> = (0..40).map(|i| (i % 20) as f64 + (i / 20) as f64 * 0.5).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::default();
+
+        // Squared distances of at most 4 (radius 2) need at least 4 clusters for the range of 19.5
+        let res = kmean.kmeans_max_radius(1, 100, 4.0, KMeans::init_precomputed(vec![10.0]), &conf);
+        assert!(res.k >= 4);
+        assert!(res.centroid_distances.iter().all(|&d| d <= 4.0));
+        assert_eq!(res.centroid_frequency.iter().sum::<usize>(), samples.len());
+        assert_eq!(res.centroids.centroid_cnt, res.k);
+        assert!((res.distsum - res.centroid_distances.iter().sum::<f64>()).abs() < 1e-9);
+
+        // Without uncovered samples, this is the plain Lloyd algorithm
+        let lloyd = kmean.kmeans_lloyd(2, 100, KMeans::init_precomputed(vec![5.0, 15.0]), &conf);
+        let res = kmean.kmeans_max_radius(2, 100, 1000.0, KMeans::init_precomputed(vec![5.0, 15.0]), &conf);
+        assert_eq!(res.k, 2);
+        assert_eq!(res.assignments, lloyd.assignments);
+        assert_eq!(res.centroids.to_vec(), lloyd.centroids.to_vec());
+    }
+}
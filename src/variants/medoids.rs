@@ -0,0 +1,273 @@
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState, StopReason};
+use rayon::prelude::*;
+use std::mem::{size_of, size_of_val};
+
+/// Result of a k-medoids calculation (see [`KMeans::kmedoids`]).
+///
+/// ## Fields
+/// - **state**: The k-medoids result, whose **centroids** are copies of the medoids
+/// - **medoids**: Index of the sample, that is the medoid (center) of each cluster
+#[derive(Clone, Debug)]
+pub struct KMedoidsState<T: Primitive> {
+    pub state: KMeansState<T>,
+    pub medoids: Vec<usize>,
+}
+
+pub(crate) struct KMedoids<T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    D: DistanceFunction<T, LANES>,
+{
+    _p: std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, const LANES: usize, D> KMedoids<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    /// Replace every initial centroid with its nearest sample, that was not chosen as medoid before.
+    fn nearest_samples(data: &KMeans<T, LANES, D>, centroids: &StrideBuffer<T>) -> Vec<usize> {
+        let mut medoids: Vec<usize> = Vec::with_capacity(centroids.centroid_cnt);
+        centroids.chunks_exact_stride().for_each(|c| {
+            let medoid = data
+                .p_samples
+                .bfr
+                .par_chunks_exact(data.p_samples.stride)
+                .enumerate()
+                .filter(|(sample_id, _)| !medoids.contains(sample_id))
+                .map(|(sample_id, s)| (sample_id, data.distance_fn.distance(s, c)))
+                .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+                .unwrap()
+                .0;
+            medoids.push(medoid);
+        });
+        medoids
+    }
+
+    /// Assign every sample to its nearest medoid, and store the distance to its second-nearest medoid.
+    fn update_nearest(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, second_distances: &mut [T]) {
+        let centroids = &state.centroids;
+        data.p_samples
+            .bfr
+            .par_chunks_exact(data.p_samples.stride)
+            .zip(state.assignments.par_iter_mut())
+            .zip(state.centroid_distances.par_iter_mut())
+            .zip(second_distances.par_iter_mut())
+            .for_each(|(((s, assignment), centroid_dist), second_dist)| {
+                let (mut best_idx, mut best_dist, mut second) = (0, T::infinity(), T::infinity());
+                centroids.chunks_exact_stride().enumerate().for_each(|(idx, c)| {
+                    let dist = data.distance_fn.distance(s, c);
+                    if dist < best_dist {
+                        second = best_dist;
+                        (best_idx, best_dist) = (idx, dist);
+                    } else if dist < second {
+                        second = dist;
+                    }
+                });
+                *assignment = best_idx;
+                *centroid_dist = best_dist;
+                *second_dist = second;
+            });
+    }
+
+    /// Increase of the total deviation, when removing each medoid (and moving its samples to their second-nearest
+    /// medoid).
+    fn removal_loss(data: &KMeans<T, LANES, D>, state: &KMeansState<T>, second_distances: &[T]) -> Vec<T> {
+        let mut loss = vec![T::zero(); state.k];
+        state
+            .assignments
+            .iter()
+            .zip(state.centroid_distances.iter().zip(second_distances.iter()))
+            .enumerate()
+            .for_each(|(sample_id, (&assignment, (&nearest, &second)))| {
+                loss[assignment] += data.sample_weight(sample_id) * (second - nearest);
+            });
+        loss
+    }
+
+    /// Find the best medoid to swap with the **candidate** sample (FasterPAM), evaluating all medoids at once.
+    ///
+    /// ## Returns
+    /// The medoid to replace, and the change of the total deviation the swap causes.
+    fn evaluate_swap(
+        data: &KMeans<T, LANES, D>, state: &KMeansState<T>, second_distances: &[T], removal_loss: &[T], candidate: usize,
+    ) -> (usize, T) {
+        let k = state.k;
+        let c = data.p_samples.nth_stride(candidate);
+        let (gain, mut deltas) = data
+            .p_samples
+            .bfr
+            .par_chunks_exact(data.p_samples.stride)
+            .zip(state.assignments.par_iter())
+            .zip(state.centroid_distances.par_iter().zip(second_distances.par_iter()))
+            .enumerate()
+            .fold(
+                || (T::zero(), vec![T::zero(); k]),
+                |(mut gain, mut deltas), (sample_id, ((s, &assignment), (&nearest, &second)))| {
+                    let dist = data.distance_fn.distance(s, c);
+                    let weight = data.sample_weight(sample_id);
+                    if k == 1 {
+                        // The only medoid is always the one that is replaced
+                        gain += weight * (dist - nearest);
+                    } else if dist < nearest {
+                        // The sample moves to the candidate, independent of the removed medoid
+                        gain += weight * (dist - nearest);
+                        deltas[assignment] += weight * (nearest - second);
+                    } else if dist < second {
+                        // The sample moves to the candidate instead of its second-nearest medoid, if its medoid is removed
+                        deltas[assignment] += weight * (dist - second);
+                    }
+                    (gain, deltas)
+                },
+            )
+            .reduce(
+                || (T::zero(), vec![T::zero(); k]),
+                |(gain0, mut deltas0), (gain1, deltas1)| {
+                    deltas0.iter_mut().zip(deltas1).for_each(|(d0, d1)| *d0 += d1);
+                    (gain0 + gain1, deltas0)
+                },
+            );
+        if k > 1 {
+            deltas.iter_mut().zip(removal_loss.iter()).for_each(|(d, &loss)| *d += loss);
+        }
+        let (medoid, delta) = deltas
+            .into_iter()
+            .enumerate()
+            .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+            .unwrap();
+        (medoid, delta + gain)
+    }
+
+    #[inline(always)]
+    pub fn calculate<F>(data: &KMeans<T, LANES, D>, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMedoidsState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        assert!(k > 0 && k <= data.sample_cnt);
+
+        let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
+        state.distsum = T::infinity();
+
+        // Initialize clusters, start with the samples nearest to the initial centroids, and notify subscriber
+        init(data, &mut state, config);
+        let mut medoids = Self::nearest_samples(data, &state.centroids);
+        medoids.iter().enumerate().for_each(|(idx, &medoid)| {
+            state
+                .centroids
+                .nth_stride_mut(idx)
+                .copy_from_slice(data.p_samples.nth_stride(medoid));
+        });
+        config.notify_init(data, &mut state);
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (data.sample_cnt * data.sample_cnt) as u64,
+            sample_dims: data.sample_dims,
+        });
+
+        let weights = data.sample_weights.as_deref();
+        let mut is_medoid = vec![false; data.sample_cnt];
+        medoids.iter().for_each(|&medoid| is_medoid[medoid] = true);
+        let mut second_distances = vec![T::infinity(); data.sample_cnt];
+        Self::update_nearest(data, &mut state, &mut second_distances);
+        let mut removal_loss = Self::removal_loss(data, &state, &second_distances);
+        let mut deviation = config.distsum(&state.centroid_distances, weights);
+        state.memory_usage.record_temporaries(
+            size_of_val(second_distances.as_slice())
+                + size_of_val(is_medoid.as_slice())
+                + (rayon::current_num_threads() + 1) * k * size_of::<T>(),
+        );
+
+        for i in 1..=max_iter {
+            // Eagerly perform every swap that improves the total deviation (beyond rounding errors)
+            let mut swapped = false;
+            for candidate in 0..data.sample_cnt {
+                if is_medoid[candidate] {
+                    continue;
+                }
+                let (medoid, change) = Self::evaluate_swap(data, &state, &second_distances, &removal_loss, candidate);
+                if change >= -T::epsilon() * deviation.abs() {
+                    continue;
+                }
+                is_medoid[medoids[medoid]] = false;
+                is_medoid[candidate] = true;
+                medoids[medoid] = candidate;
+                state
+                    .centroids
+                    .nth_stride_mut(medoid)
+                    .copy_from_slice(data.p_samples.nth_stride(candidate));
+                Self::update_nearest(data, &mut state, &mut second_distances);
+                removal_loss = Self::removal_loss(data, &state, &second_distances);
+                deviation = config.distsum(&state.centroid_distances, weights);
+                swapped = true;
+            }
+            data.update_cluster_frequencies(&state.assignments, &mut state.centroid_frequency);
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(data, &mut state, i, deviation);
+            state.n_iterations = i;
+            // Without any swap, no medoid moved, and the next pass would not find any swap either
+            let reason = match swapped {
+                true => stop_criteria.next(data, &state, deviation),
+                false => Some(StopReason::Tolerance),
+            };
+            if let Some(reason) = reason {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = deviation;
+        }
+
+        data.update_cluster_frequencies(&state.assignments, &mut state.centroid_frequency);
+        state.distsum = config.distsum(&state.centroid_distances, weights);
+        data.update_per_cluster_distsum(&mut state);
+        KMedoidsState { state, medoids }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig, ManhattanDistance, StopReason};
+
+    #[test]
+    fn medoids_are_samples() {
+        // The outlier pulls the mean of the second cluster, but not its medoid
+        let samples = vec![0.0f64, 1.0, 2.0, 3.0, 4.0, 20.0, 21.0, 22.0, 23.0, 40.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, ManhattanDistance);
+        let res = kmean.kmedoids(2, 100, KMeans::init_precomputed(vec![5.0, 30.0]), &KMeansConfig::default());
+        assert_eq!(res.medoids, vec![2, 7]);
+        assert_eq!(res.state.centroids.to_vec(), vec![2.0, 22.0]);
+        assert_eq!(res.state.assignments, vec![0, 0, 0, 0, 0, 1, 1, 1, 1, 1]);
+        assert_eq!(res.state.centroid_frequency, vec![5, 5]);
+        assert_eq!(res.state.distsum, 28.0);
+        assert_eq!(res.state.cluster_inertias(), &[6.0, 22.0]);
+        assert_eq!(res.state.stop_reason, StopReason::Tolerance);
+    }
+
+    #[test]
+    fn optimal_medoids() {
+        // Compare against an exhaustive search over all triples of medoids
+        let samples: Vec<f64> = (0..40).map(|i| ((i * 7) % 13) as f64 + (i % 3) as f64 * 20.0).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 20, 2, EuclideanDistance);
+        let res = kmean.kmedoids(3, 100, KMeans::init_precomputed(samples[..6].to_vec()), &KMeansConfig::default());
+
+        let dist = |a: usize, b: usize| (samples[2 * a] - samples[2 * b]).powi(2) + (samples[2 * a + 1] - samples[2 * b + 1]).powi(2);
+        let cost = |medoids: &[usize]| {
+            (0..20)
+                .map(|s| medoids.iter().map(|&m| dist(s, m)).fold(f64::INFINITY, f64::min))
+                .sum::<f64>()
+        };
+        let best = (0..20)
+            .flat_map(|a| (a + 1..20).flat_map(move |b| (b + 1..20).map(move |c| [a, b, c])))
+            .map(|medoids| cost(&medoids))
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!(res.state.distsum, best);
+        assert_eq!(cost(&res.medoids), best);
+    }
+}
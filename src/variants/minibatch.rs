@@ -1,10 +1,14 @@
+use super::Lloyd;
+use crate::abort_strategy::{IterationCost, StopCriteria, StopReason};
 use crate::api::DistanceFunction;
 use crate::memory::*;
+use crate::simd::{self, LaneCount, Simd, SupportedLaneCount};
 use crate::{KMeans, KMeansConfig, KMeansState};
+use rand::distributions::WeightedIndex;
 use rand::prelude::*;
 use rayon::prelude::*;
+use std::mem::{size_of, size_of_val};
 use std::ops::{DerefMut, Range};
-use std::simd::{LaneCount, Simd, SupportedLaneCount};
 
 struct BatchInfo {
     start_idx: usize,
@@ -19,6 +23,179 @@ impl BatchInfo {
     }
 }
 
+/// Accumulator for the deferred centroid updates of one batch (see [`crate::KMeansConfigBuilder::sparse_centroid_updates`]).
+/// Only the centroids touched by the batch get a slot, so its work and memory are independent of k (except for the
+/// slot lookup table, which is allocated once).
+struct SparseAccumulator<T: Primitive> {
+    /// For every centroid, its slot within the accumulator (usize::MAX if untouched by the current batch)
+    slots: Vec<usize>,
+    /// Centroid of every slot
+    touched: Vec<usize>,
+    /// Sum of all samples of every slot [row-major, with the stride of the samples]
+    sums: Vec<T>,
+    /// Amount (or total weight, with sample weights) of samples of every slot
+    counts: Vec<T>,
+    /// Share of the old centroid position that is retained after all updates of every slot: `Π (1 - rate)`
+    retained: Vec<T>,
+}
+impl<T: Primitive> SparseAccumulator<T> {
+    fn new(k: usize) -> Self {
+        Self {
+            slots: vec![usize::MAX; k],
+            touched: Vec::new(),
+            sums: Vec::new(),
+            counts: Vec::new(),
+            retained: Vec::new(),
+        }
+    }
+
+    fn memory(&self) -> usize {
+        size_of_val(self.slots.as_slice())
+            + self.touched.capacity() * size_of::<usize>()
+            + self.sums.capacity() * size_of::<T>()
+            + self.counts.capacity() * size_of::<T>()
+            + self.retained.capacity() * size_of::<T>()
+    }
+}
+
+/// Early stopping on an exponentially weighted moving average of the batch inertia (see
+/// [`crate::KMeansConfigBuilder::minibatch_max_no_improvement`]).
+pub(crate) struct EarlyStopping<T: Primitive> {
+    max_no_improvement: usize,
+    /// Weight of the newest batch within the moving average
+    alpha: T,
+    /// Moving average of the batch inertia (None before the first batch)
+    ewa_inertia: Option<T>,
+    /// Lowest moving average so far
+    ewa_inertia_min: T,
+    no_improvement: usize,
+}
+impl<T: Primitive> EarlyStopping<T> {
+    pub(crate) fn new(max_no_improvement: usize, batch_size: usize, sample_cnt: usize) -> Self {
+        Self {
+            max_no_improvement,
+            alpha: (T::from(2 * batch_size).unwrap() / T::from(sample_cnt + 1).unwrap()).min(T::one()),
+            ewa_inertia: None,
+            ewa_inertia_min: T::infinity(),
+            no_improvement: 0,
+        }
+    }
+
+    /// Add the inertia of the latest batch.
+    /// ## Returns
+    /// Whether the calculation should stop
+    pub(crate) fn next(&mut self, batch_inertia: T) -> bool {
+        let ewa_inertia = match self.ewa_inertia {
+            Some(ewa) => ewa * (T::one() - self.alpha) + batch_inertia * self.alpha,
+            None => batch_inertia,
+        };
+        self.ewa_inertia = Some(ewa_inertia);
+        if ewa_inertia < self.ewa_inertia_min {
+            self.ewa_inertia_min = ewa_inertia;
+            self.no_improvement = 0;
+        } else {
+            self.no_improvement += 1;
+        }
+        self.no_improvement >= self.max_no_improvement
+    }
+}
+
+/// Stratified drawing of mini-batches from the current clusters (see
+/// [`crate::KMeansConfigBuilder::minibatch_stratified_sampling`]). The drawn samples are swapped into a block at the
+/// start of the shuffled samples, which is then processed like any other batch.
+struct StratifiedBatches {
+    min_per_cluster: usize,
+    /// Shuffled positions of the current members of each cluster
+    members: Vec<Vec<usize>>,
+    /// Index of every shuffled position within the members of its cluster
+    slots: Vec<usize>,
+    /// Whether every shuffled position was drawn into the current batch
+    drawn: Vec<bool>,
+}
+impl StratifiedBatches {
+    fn new(min_per_cluster: usize, k: usize, assignments: &[usize]) -> Self {
+        let mut members = vec![Vec::new(); k];
+        let slots = assignments
+            .iter()
+            .enumerate()
+            .map(|(pos, &c)| {
+                members[c].push(pos);
+                members[c].len() - 1
+            })
+            .collect();
+        Self {
+            min_per_cluster,
+            members,
+            slots,
+            drawn: vec![false; assignments.len()],
+        }
+    }
+
+    fn memory(&self) -> usize { 2 * size_of_val(self.slots.as_slice()) + size_of_val(self.drawn.as_slice()) }
+
+    /// Draw the shuffled positions of the next batch: Every cluster contributes its proportional share of
+    /// **batch_size**, but at least **min_per_cluster** samples (or all of its members, if it has less).
+    fn draw<R: Rng + ?Sized>(&mut self, batch_size: usize, rnd: &mut R) -> Vec<usize> {
+        let sample_cnt = self.slots.len();
+        let mut drawn = Vec::with_capacity(batch_size);
+        for members in self.members.iter_mut() {
+            let cnt = (batch_size * members.len() / sample_cnt)
+                .max(self.min_per_cluster)
+                .min(members.len());
+            // Partial Fisher-Yates shuffle, that moves the drawn members to the front of the cluster's members
+            for i in 0..cnt {
+                let j = rnd.gen_range(i..members.len());
+                members.swap(i, j);
+                self.slots[members[i]] = i;
+                self.slots[members[j]] = j;
+                drawn.push(members[i]);
+            }
+        }
+        drawn
+    }
+
+    /// Bookkeeping after the samples at the shuffled positions **a** and **b** were swapped.
+    fn swap(&mut self, a: usize, b: usize, assignments: &[usize]) {
+        self.slots.swap(a, b);
+        self.members[assignments[a]][self.slots[a]] = a;
+        self.members[assignments[b]][self.slots[b]] = b;
+    }
+
+    /// Bookkeeping after the sample at the shuffled position **pos** moved from cluster **from** to cluster **to**.
+    fn reassign(&mut self, pos: usize, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+        let slot = self.slots[pos];
+        self.members[from].swap_remove(slot);
+        if let Some(&moved) = self.members[from].get(slot) {
+            self.slots[moved] = slot;
+        }
+        self.slots[pos] = self.members[to].len();
+        self.members[to].push(pos);
+    }
+}
+
+/// Sample weights of a mini-batch calculation (see [`KMeans::with_sample_weights`]).
+struct MinibatchWeights<T: Primitive> {
+    /// Weight of every shuffled sample
+    shuffled: Vec<T>,
+    /// Total weight of all samples every centroid has been updated with
+    centroid_weights: Vec<T>,
+}
+impl<T: Primitive> MinibatchWeights<T> {
+    /// Weight of the given shuffled sample, and the learning rate for updating the centroid **assignment** with it.
+    fn learn_rate(&mut self, sample_id: usize, assignment: usize, count: usize, iteration: usize, config: &KMeansConfig<'_, T>) -> (T, T) {
+        let weight = self.shuffled[sample_id];
+        self.centroid_weights[assignment] += weight;
+        let weight_sum = self.centroid_weights[assignment];
+        (
+            weight,
+            config.learning_schedule.weighted_learn_rate(count, weight, weight_sum, iteration),
+        )
+    }
+}
+
 pub(crate) struct Minibatch<T, const LANES: usize, D>
 where
     T: Primitive,
@@ -44,27 +221,28 @@ where
         // TODO: Switch to par_chunks_exact, when that is merged in rayon (https://github.com/rayon-rs/rayon/pull/629).
         // par_chunks() works, because sample-dimensions are manually padded, so that there is no remainder
 
-        // manually calculate work-packet size, because rayon does not do static scheduling (which is more apropriate here)
-        let work_packet_size = batch.batch_size / rayon::current_num_threads();
-        shuffled_samples[batch.gen_range(data.p_samples.stride)]
+        let work_packet_size = data.work_packet_size(batch.batch_size);
+        let samples = shuffled_samples[batch.gen_range(data.p_samples.stride)]
             .par_chunks_exact(data.p_samples.stride)
-            .with_min_len(work_packet_size)
             .zip(state.assignments[batch.gen_range(1)].par_iter_mut())
-            .zip(state.centroid_distances[batch.gen_range(1)].par_iter_mut())
-            .for_each(|((s, assignment), centroid_dist)| {
-                let (best_idx, best_dist) = centroids
-                    .chunks_exact_stride()
-                    .take(k)
-                    .map(|c| data.distance_fn.distance(s, c))
-                    .enumerate()
-                    .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
-                    .unwrap();
-                *assignment = best_idx;
-                *centroid_dist = best_dist;
-            });
+            .zip(state.centroid_distances[batch.gen_range(1)].par_iter_mut());
+        simd::for_each_packet(samples, work_packet_size, |((s, assignment), centroid_dist)| {
+            let (best_idx, best_dist) = centroids
+                .chunks_exact_stride()
+                .take(k)
+                .map(|c| data.distance_fn.distance(s, c))
+                .enumerate()
+                .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+                .unwrap();
+            *assignment = best_idx;
+            *centroid_dist = best_dist;
+        });
     }
 
-    fn update_centroids(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, batch: &BatchInfo, shuffled_samples: &[T]) {
+    fn update_centroids(
+        data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, batch: &BatchInfo, shuffled_samples: &[T], iteration: usize,
+        config: &KMeansConfig<'_, T>, mut weights: Option<&mut MinibatchWeights<T>>,
+    ) {
         let centroid_frequency = &mut state.centroid_frequency;
         let centroids = &mut state.centroids;
         let assignments = &state.assignments;
@@ -72,9 +250,14 @@ where
         shuffled_samples[batch.gen_range(data.p_samples.stride)]
             .chunks_exact(data.p_samples.stride)
             .zip(assignments[batch.gen_range(1)].iter().cloned())
-            .for_each(|(sample, assignment)| {
+            .enumerate()
+            .for_each(|(batch_idx, (sample, assignment))| {
                 centroid_frequency[assignment] += 1;
-                let learn_rate = T::one() / T::from(centroid_frequency[assignment]).unwrap();
+                let count = centroid_frequency[assignment];
+                let learn_rate = match weights.as_deref_mut() {
+                    Some(w) => w.learn_rate(batch.start_idx + batch_idx, assignment, count, iteration, config).1,
+                    None => config.learning_schedule.learn_rate(count, iteration),
+                };
                 let inv_learn_rate = T::one() - learn_rate;
                 centroids
                     .bfr
@@ -88,6 +271,150 @@ where
             });
     }
 
+    /// Variant of [`Self::update_centroids`], that accumulates the samples of each touched centroid first, and moves
+    /// every touched centroid only once per batch: Applying the rates `r_1..r_m` of all **m** updates of a centroid
+    /// at once, it is moved by `w = 1 - Π (1 - r_i)` towards the mean of its samples of this batch. For the default
+    /// [`crate::LearningSchedule::InverseCount`], this is the exact same result as the sequential updates.
+    #[allow(clippy::too_many_arguments)]
+    fn update_centroids_sparse(
+        data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, batch: &BatchInfo, shuffled_samples: &[T], iteration: usize,
+        config: &KMeansConfig<'_, T>, acc: &mut SparseAccumulator<T>, mut weights: Option<&mut MinibatchWeights<T>>,
+    ) {
+        let stride = data.p_samples.stride;
+        shuffled_samples[batch.gen_range(stride)]
+            .chunks_exact(stride)
+            .zip(state.assignments[batch.gen_range(1)].iter().cloned())
+            .enumerate()
+            .for_each(|(batch_idx, (sample, assignment))| {
+                if acc.slots[assignment] == usize::MAX {
+                    acc.slots[assignment] = acc.touched.len();
+                    acc.touched.push(assignment);
+                    acc.sums.resize(acc.sums.len() + stride, T::zero());
+                    acc.counts.push(T::zero());
+                    acc.retained.push(T::one());
+                }
+                let slot = acc.slots[assignment];
+                state.centroid_frequency[assignment] += 1;
+                let count = state.centroid_frequency[assignment];
+                let (weight, learn_rate) = match weights.as_deref_mut() {
+                    Some(w) => w.learn_rate(batch.start_idx + batch_idx, assignment, count, iteration, config),
+                    None => (T::one(), config.learning_schedule.learn_rate(count, iteration)),
+                };
+                acc.counts[slot] += weight;
+                acc.retained[slot] = acc.retained[slot] * (T::one() - learn_rate);
+                let weight = Simd::splat(weight);
+                acc.sums[slot * stride..(slot + 1) * stride]
+                    .chunks_exact_mut(LANES)
+                    .zip(sample.chunks_exact(LANES).map(|v| Simd::from_slice(v)))
+                    .for_each(|(c, s)| {
+                        let result = Simd::from_slice(c) + s * weight;
+                        c.copy_from_slice(result.as_array());
+                    });
+            });
+        state.memory_usage.record_temporaries(acc.memory());
+
+        acc.touched.iter().cloned().enumerate().for_each(|(slot, centroid_id)| {
+            let weight = T::one() - acc.retained[slot];
+            let (inv_weight, sample_weight) = (T::one() - weight, weight / acc.counts[slot]);
+            state
+                .centroids
+                .nth_stride_mut(centroid_id)
+                .iter_mut()
+                .zip(acc.sums[slot * stride..(slot + 1) * stride].iter().cloned())
+                .for_each(|(c, sum)| *c = inv_weight * *c + sample_weight * sum);
+            acc.slots[centroid_id] = usize::MAX;
+        });
+        acc.touched.clear();
+        acc.sums.clear();
+        acc.counts.clear();
+        acc.retained.clear();
+    }
+
+    /// Move all centroids with a frequency below the configured ratio of the highest frequency onto far-away samples
+    /// of the current batch (see [`crate::KMeansConfigBuilder::minibatch_reassignment_ratio`]).
+    /// ## Returns
+    /// Whether any centroid was moved.
+    fn reassign_low_count_centroids(
+        data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, batch: &BatchInfo, shuffled_samples: &[T], config: &KMeansConfig<'_, T>,
+        weights: Option<&mut MinibatchWeights<T>>,
+    ) -> bool {
+        let max_frequency = T::from(*state.centroid_frequency.iter().max().unwrap()).unwrap();
+        let threshold = config.reassignment_ratio * max_frequency;
+        let mut to_reassign: Vec<usize> = (0..state.k)
+            .filter(|&c| T::from(state.centroid_frequency[c]).unwrap() < threshold)
+            .collect();
+        to_reassign.truncate(batch.batch_size / 2);
+        if to_reassign.is_empty() {
+            return false;
+        }
+
+        // Draw distinct samples of the batch, with a probability proportional to their (weighted) distance to their centroid
+        let mut probabilities: Vec<f64> = state.centroid_distances[batch.gen_range(1)]
+            .iter()
+            .enumerate()
+            .map(|(batch_idx, d)| {
+                let weight = weights.as_ref().map_or(T::one(), |w| w.shuffled[batch.start_idx + batch_idx]);
+                (*d * weight).to_f64().unwrap()
+            })
+            .collect();
+        let mut rnd = config.rnd.borrow_mut();
+        let new_frequency = (0..state.k)
+            .filter(|c| !to_reassign.contains(c))
+            .map(|c| state.centroid_frequency[c])
+            .min()
+            .unwrap_or(0);
+        let mut weights = weights.map(|w| {
+            let new_weight = (0..state.k)
+                .filter(|c| !to_reassign.contains(c))
+                .map(|c| w.centroid_weights[c])
+                .fold(T::infinity(), T::min);
+            (w, if new_weight.is_finite() { new_weight } else { T::zero() })
+        });
+        let mut moved = false;
+        for centroid_id in to_reassign {
+            let Ok(distribution) = WeightedIndex::new(&probabilities) else {
+                break;
+            };
+            let sample_id = distribution.sample(rnd.deref_mut());
+            probabilities[sample_id] = 0.0;
+            let stride = data.p_samples.stride;
+            let sample = &shuffled_samples[(batch.start_idx + sample_id) * stride..(batch.start_idx + sample_id + 1) * stride];
+            state.centroids.nth_stride_mut(centroid_id).copy_from_slice(sample);
+            state.centroid_frequency[centroid_id] = new_frequency;
+            if let Some((w, new_weight)) = weights.as_mut() {
+                w.centroid_weights[centroid_id] = *new_weight;
+            }
+            moved = true;
+        }
+        moved
+    }
+
+    /// Swap the **drawn** samples of a stratified batch into the block at the start of the shuffled samples.
+    fn gather_stratified(
+        state: &mut KMeansState<T>, shuffled_samples: &mut StrideBuffer<T>, shuffle_idxs: &mut [usize],
+        mut weights: Option<&mut MinibatchWeights<T>>, strata: &mut StratifiedBatches, drawn: &[usize],
+    ) -> BatchInfo {
+        let batch_size = drawn.len();
+        let stride = shuffled_samples.stride;
+        drawn.iter().for_each(|&pos| strata.drawn[pos] = true);
+        // Every drawn sample outside of the block takes the place of an undrawn sample inside of it
+        let undrawn: Vec<usize> = (0..batch_size).filter(|&pos| !strata.drawn[pos]).collect();
+        let outside = drawn.iter().cloned().filter(|&pos| pos >= batch_size);
+        for (pos, other) in undrawn.into_iter().zip(outside) {
+            let (block, rest) = shuffled_samples.bfr.split_at_mut(other * stride);
+            block[pos * stride..(pos + 1) * stride].swap_with_slice(&mut rest[..stride]);
+            state.assignments.swap(pos, other);
+            state.centroid_distances.swap(pos, other);
+            shuffle_idxs.swap(pos, other);
+            if let Some(w) = weights.as_deref_mut() {
+                w.shuffled.swap(pos, other);
+            }
+            strata.swap(pos, other, &state.assignments);
+        }
+        drawn.iter().for_each(|&pos| strata.drawn[pos] = false);
+        BatchInfo { start_idx: 0, batch_size }
+    }
+
     fn shuffle_samples(data: &KMeans<T, LANES, D>, config: &KMeansConfig<'_, T>) -> (Vec<usize>, StrideBuffer<T>) {
         let mut idxs: Vec<usize> = (0..data.sample_cnt).collect();
         idxs.shuffle(config.rnd.borrow_mut().deref_mut());
@@ -111,6 +438,29 @@ where
     pub fn calculate<F>(
         data: &KMeans<T, LANES, D>, batch_size: usize, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>,
     ) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        let mut state = Self::train(data, batch_size, k, max_iter, init, config);
+        crate::postprocessing::apply(data, &mut state, config);
+        state
+    }
+
+    /// Mini-batch training, followed by up to **polish_iter** full Lloyd iterations, starting from the mini-batch result.
+    pub fn calculate_polished<F>(
+        data: &KMeans<T, LANES, D>, batch_size: usize, k: usize, max_iter: usize, polish_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+    ) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        let trained = Self::train(data, batch_size, k, max_iter, init, config);
+        Lloyd::calculate(data, k, polish_iter, move |_, state, _| *state = trained, config)
+    }
+
+    /// Mini-batch training, without applying the post-processing steps.
+    fn train<F>(
+        data: &KMeans<T, LANES, D>, batch_size: usize, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+    ) -> KMeansState<T>
     where
         for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
     {
@@ -118,15 +468,21 @@ where
         assert!(batch_size <= data.sample_cnt);
 
         // Copy and shuffle sample_data, then only take consecutive blocks (with batch_size) from there
-        let (shuffle_idxs, shuffled_samples) = Self::shuffle_samples(data, config);
+        let (mut shuffle_idxs, mut shuffled_samples) = Self::shuffle_samples(data, config);
 
         let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
         state.distsum = T::infinity();
+        state
+            .memory_usage
+            .record_temporaries(size_of_val(shuffle_idxs.as_slice()) + size_of_val(shuffled_samples.bfr.as_slice()));
 
         // Initialize clusters and notify subscriber
         init(data, &mut state, config);
-        (config.init_done)(&state);
-        let mut abort_strategy = config.abort_strategy.create_logic();
+        config.notify_init(data, &mut state);
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (batch_size * k) as u64,
+            sample_dims: data.sample_dims,
+        });
 
         // Update cluster assignments for all samples, to get rid of the INFINITES in centroid_distances
         Self::update_cluster_assignments(
@@ -140,20 +496,78 @@ where
             None,
         );
 
+        let mut sparse_acc = config.sparse_centroid_updates.then(|| SparseAccumulator::new(k));
+        let mut weights = data.sample_weights.as_ref().map(|w| MinibatchWeights {
+            shuffled: shuffle_idxs.iter().map(|&idx| w[idx]).collect(),
+            centroid_weights: vec![T::zero(); k],
+        });
+        if let Some(w) = &weights {
+            state
+                .memory_usage
+                .record_temporaries(size_of_val(w.shuffled.as_slice()) + size_of_val(w.centroid_weights.as_slice()));
+        }
+        let mut early_stopping =
+            (config.max_no_improvement > 0).then(|| EarlyStopping::new(config.max_no_improvement, batch_size, data.sample_cnt));
+        let mut strata = (config.stratified_min_per_cluster > 0)
+            .then(|| StratifiedBatches::new(config.stratified_min_per_cluster, k, &state.assignments));
+        if let Some(strata) = &strata {
+            state.memory_usage.record_temporaries(strata.memory());
+        }
         for i in 1..=max_iter {
-            // Only shuffle a beginning index for a consecutive block within the shuffled samples as batch
-            let batch = BatchInfo {
-                batch_size,
-                start_idx: config.rnd.borrow_mut().gen_range(0..data.sample_cnt - batch_size),
+            let batch = match strata.as_mut() {
+                Some(strata) => {
+                    let drawn = strata.draw(batch_size, config.rnd.borrow_mut().deref_mut());
+                    Self::gather_stratified(
+                        &mut state,
+                        &mut shuffled_samples,
+                        &mut shuffle_idxs,
+                        weights.as_mut(),
+                        strata,
+                        &drawn,
+                    )
+                },
+                // Only shuffle a beginning index for a consecutive block within the shuffled samples as batch
+                None => BatchInfo {
+                    batch_size,
+                    start_idx: config.rnd.borrow_mut().gen_range(0..data.sample_cnt - batch_size),
+                },
             };
 
+            let prev_assignments = strata.as_ref().map(|_| state.assignments[batch.gen_range(1)].to_vec());
             Self::update_cluster_assignments(data, &mut state, &batch, &shuffled_samples.bfr, None);
-            let new_distsum = state.centroid_distances.iter().cloned().sum();
-            Self::update_centroids(data, &mut state, &batch, &shuffled_samples.bfr);
+            if let (Some(strata), Some(prev_assignments)) = (strata.as_mut(), prev_assignments) {
+                prev_assignments.into_iter().enumerate().for_each(|(batch_idx, prev)| {
+                    let pos = batch.start_idx + batch_idx;
+                    strata.reassign(pos, prev, state.assignments[pos]);
+                });
+            }
+            let new_distsum = config.distsum(&state.centroid_distances, weights.as_ref().map(|w| w.shuffled.as_slice()));
+            let no_improvement = early_stopping.as_mut().is_some_and(|early_stopping| {
+                let batch_range = batch.start_idx..batch.start_idx + batch.batch_size;
+                let batch_weights = weights.as_ref().map(|w| &w.shuffled[batch_range.clone()]);
+                let batch_weight = batch_weights.map_or(T::from(batch.batch_size).unwrap(), |w| w.iter().cloned().sum());
+                early_stopping.next(config.distsum(&state.centroid_distances[batch_range], batch_weights) / batch_weight)
+            });
+            match &mut sparse_acc {
+                Some(acc) => {
+                    Self::update_centroids_sparse(data, &mut state, &batch, &shuffled_samples.bfr, i, config, acc, weights.as_mut())
+                },
+                None => Self::update_centroids(data, &mut state, &batch, &shuffled_samples.bfr, i, config, weights.as_mut()),
+            }
+            // Re-seeded centroids restart the convergence, so they must not lead to an abort
+            let reassigned = config.reassignment_ratio > T::zero()
+                && i.is_multiple_of(10 + state.centroid_frequency.iter().min().unwrap())
+                && Self::reassign_low_count_centroids(data, &mut state, &batch, &shuffled_samples.bfr, config, weights.as_mut());
 
             // Notify subscriber about finished iteration
-            (config.iteration_done)(&state, i, new_distsum);
-            if !abort_strategy.next(new_distsum) {
+            config.notify_iteration(data, &mut state, i, new_distsum);
+            state.n_iterations = i;
+            let reason = stop_criteria.next(data, &state, new_distsum);
+            if let Some(reason) = reason
+                .or(no_improvement.then_some(StopReason::NoBatchImprovement))
+                .filter(|reason| !reassigned || reason.is_forced())
+            {
+                state.stop_reason = reason;
                 break;
             }
             state.distsum = new_distsum;
@@ -172,7 +586,7 @@ where
                 non_empty_clusters -= data.update_cluster_frequencies(assignments, centroid_frequency);
             });
             s.spawn(|_| {
-                *distsum = centroid_distances.iter().cloned().sum();
+                *distsum = config.distsum(centroid_distances, data.sample_weights.as_deref());
             });
         });
         state
@@ -325,4 +739,147 @@ mod tests {
 
         assert_kmeans_result_eq(should, res);
     }
+
+    #[test]
+    fn polished_equals_lloyd_on_minibatch_result() {
+        let mut rnd = StdRng::seed_from_u64(1337);
+        let samples: Vec<f64> = (0..1000).map(|i| (i % 4) as f64 * 3.0 + rnd.gen_range(-2.0..2.0)).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = || KMeansConfig::build().random_generator(StdRng::seed_from_u64(42)).build();
+
+        let trained = kmean.kmeans_minibatch(10, 4, 20, KMeans::init_random_sample, &conf());
+        let expected = kmean.kmeans_lloyd(4, 100, KMeans::init_precomputed(trained.centroids.to_vec()), &conf());
+        let res = kmean.kmeans_minibatch_polished(10, 4, 20, 100, KMeans::init_random_sample, &conf());
+        assert_eq!(res.centroids.to_vec(), expected.centroids.to_vec());
+        assert_eq!(res.assignments, expected.assignments);
+        assert!(res.distsum <= trained.distsum);
+
+        // Without polish iterations, this is the plain mini-batch result
+        let res = kmean.kmeans_minibatch_polished(10, 4, 20, 0, KMeans::init_random_sample, &conf());
+        assert_eq!(res.centroids.to_vec(), trained.centroids.to_vec());
+        assert_eq!(res.distsum, trained.distsum);
+    }
+
+    #[test]
+    fn sparse_centroid_updates_equal_sequential_updates() {
+        let mut rnd = StdRng::seed_from_u64(1337);
+        let samples: Vec<f64> = (0..2000).map(|i| ((i / 2) % 4) as f64 * 3.0 + rnd.gen_range(-1.0..1.0)).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 1000, 2, EuclideanDistance);
+        let conf = |sparse| {
+            KMeansConfig::build()
+                .random_generator(StdRng::seed_from_u64(42))
+                .sparse_centroid_updates(sparse)
+                .build()
+        };
+
+        let expected = kmean.kmeans_minibatch(50, 4, 30, KMeans::init_random_sample, &conf(false));
+        let res = kmean.kmeans_minibatch(50, 4, 30, KMeans::init_random_sample, &conf(true));
+        assert!(res
+            .centroids
+            .to_vec()
+            .iter()
+            .zip(expected.centroids.to_vec())
+            .all(|(c, e)| (c - e).abs() < 1e-9));
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.centroid_frequency, expected.centroid_frequency);
+    }
+
+    #[test]
+    fn reassignment_of_low_count_centroids() {
+        let mut rnd = StdRng::seed_from_u64(1337);
+        let samples: Vec<f64> = (0..900).map(|i| (i % 3) as f64 * 10.0 + rnd.gen_range(-1.0..1.0)).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        // The third centroid starts far away from all samples, and never receives any of them
+        let init = || KMeans::init_precomputed(vec![0.0, 10.0, 1000.0]);
+
+        let abort_strategy = AbortStrategy::NoImprovementForXIterations {
+            x: 100,
+            threshold: 0.0,
+            abort_on_negative: false,
+        };
+        let conf = KMeansConfig::build()
+            .random_generator(StdRng::seed_from_u64(42))
+            .abort_strategy(abort_strategy.clone())
+            .build();
+        let res = kmean.kmeans_minibatch(30, 3, 100, init(), &conf);
+        assert_eq!(res.centroid_frequency[2], 0);
+
+        let conf = KMeansConfig::build()
+            .random_generator(StdRng::seed_from_u64(42))
+            .abort_strategy(abort_strategy)
+            .minibatch_reassignment_ratio(0.01)
+            .build();
+        let res = kmean.kmeans_minibatch(30, 3, 100, init(), &conf);
+        let mut centroids = res.centroids.to_vec();
+        centroids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        centroids
+            .iter()
+            .zip([0.0, 10.0, 20.0])
+            .for_each(|(c, expected)| assert!((c - expected).abs() < 1.0));
+        assert!(res.centroid_frequency.iter().all(|&f| f > 0));
+    }
+
+    #[test]
+    fn early_stopping_without_improvement() {
+        let mut rnd = StdRng::seed_from_u64(1337);
+        let samples: Vec<f64> = (0..1000).map(|i| (i % 4) as f64 * 10.0 + rnd.gen_range(-1.0..1.0)).collect();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let init = || KMeans::init_precomputed(vec![0.0, 10.0, 20.0, 30.0]);
+        // Never abort because of the distsum, only because of the batch inertia
+        let conf = |max_no_improvement| {
+            KMeansConfig::build()
+                .random_generator(StdRng::seed_from_u64(42))
+                .abort_strategy(AbortStrategy::NoImprovementForXIterations {
+                    x: usize::MAX,
+                    threshold: 0.0,
+                    abort_on_negative: false,
+                })
+                .minibatch_max_no_improvement(max_no_improvement)
+                .build()
+        };
+
+        let full = kmean.kmeans_minibatch(50, 4, 1000, init(), &conf(0));
+        assert_eq!((full.n_iterations, full.stop_reason), (1000, StopReason::MaxIterations));
+
+        let res = kmean.kmeans_minibatch(50, 4, 1000, init(), &conf(10));
+        assert_eq!(res.stop_reason, StopReason::NoBatchImprovement);
+        assert!(res.n_iterations >= 10 && res.n_iterations < 1000);
+        assert!((res.distsum - full.distsum).abs() / full.distsum < 0.05);
+    }
+
+    #[test]
+    fn stratified_batches() {
+        let mut rnd = StdRng::seed_from_u64(7);
+        let mut samples: Vec<f64> = (0..1000).map(|_| rnd.gen_range(-1.0..1.0)).collect();
+        samples.extend([99.0, 100.0, 100.5, 101.0]);
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        // The small cluster is updated with (at least) 2 samples in every batch, although its share is 0
+        let conf = KMeansConfig::build()
+            .random_generator(StdRng::seed_from_u64(42))
+            .minibatch_stratified_sampling(2)
+            .iteration_done(|state, iteration, _| assert_eq!(state.centroid_frequency[1], 2 * iteration))
+            .build();
+        let res = kmean.kmeans_minibatch(20, 2, 50, KMeans::init_precomputed(vec![0.0, 100.0]), &conf);
+        assert!(res.n_iterations > 0);
+        assert_eq!(res.centroid_frequency, vec![1000, 4]);
+        assert!((res.centroids[1][0] - 100.0).abs() < 1.0);
+
+        // Bookkeeping of the members, while samples are drawn, swapped and reassigned
+        let mut assignments: Vec<usize> = (0..100).map(|i| i % 3).collect();
+        let mut strata = StratifiedBatches::new(5, 3, &assignments);
+        let consistent = |strata: &StratifiedBatches, assignments: &[usize]| {
+            (0..assignments.len()).all(|pos| strata.members[assignments[pos]][strata.slots[pos]] == pos)
+                && strata.members.iter().map(|m| m.len()).sum::<usize>() == assignments.len()
+        };
+        let drawn = strata.draw(30, &mut rnd);
+        let drawn_per_cluster: Vec<usize> = (0..3).map(|c| drawn.iter().filter(|&&pos| assignments[pos] == c).count()).collect();
+        assert_eq!(drawn_per_cluster, vec![10, 9, 9]);
+        assert!(consistent(&strata, &assignments));
+        assignments.swap(3, 50);
+        strata.swap(3, 50, &assignments);
+        assert!(consistent(&strata, &assignments));
+        strata.reassign(7, assignments[7], 2);
+        assignments[7] = 2;
+        assert!(consistent(&strata, &assignments));
+    }
 }
@@ -1,5 +1,30 @@
+mod elkan;
+mod fuzzy;
+mod hamerly;
+mod harmonic;
+mod importance_minibatch;
+mod kdtree;
 mod lloyd;
+mod max_radius;
+mod medoids;
 mod minibatch;
+mod overlapping;
+mod run;
+mod spherical;
 
+pub(crate) use elkan::Elkan;
+pub(crate) use fuzzy::Fuzzy;
+pub use fuzzy::FuzzyKMeansState;
+pub(crate) use hamerly::Hamerly;
+pub(crate) use harmonic::Harmonic;
+pub(crate) use importance_minibatch::ImportanceMinibatch;
+pub(crate) use kdtree::KdTreeFiltering;
 pub(crate) use lloyd::Lloyd;
-pub(crate) use minibatch::Minibatch;
+pub(crate) use max_radius::MaxRadius;
+pub(crate) use medoids::KMedoids;
+pub use medoids::KMedoidsState;
+pub(crate) use minibatch::{EarlyStopping, Minibatch};
+pub(crate) use overlapping::Overlapping;
+pub use overlapping::OverlappingKMeansState;
+pub use run::{IterationSnapshot, IterationStats, KMeansRun, KMeansSnapshots};
+pub(crate) use spherical::Spherical;
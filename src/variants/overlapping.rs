@@ -0,0 +1,184 @@
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rayon::prelude::*;
+use std::mem::{size_of, size_of_val};
+
+/// Result of an overlapping k-means calculation (see [`KMeans::kmeans_overlapping`]).
+///
+/// ## Fields
+/// - **state**: The k-means result, where **assignments** / **centroid_distances** refer to each sample's nearest
+///   cluster, while **centroid_frequency** counts all members of each cluster (including overlapping ones)
+/// - **memberships**: For each sample, all clusters it belongs to (ascending, always containing its nearest cluster)
+#[derive(Clone, Debug)]
+pub struct OverlappingKMeansState<T: Primitive> {
+    pub state: KMeansState<T>,
+    pub memberships: Vec<Vec<usize>>,
+}
+
+pub(crate) struct Overlapping<T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    D: DistanceFunction<T, LANES>,
+{
+    _p: std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, const LANES: usize, D> Overlapping<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    /// Assign every sample to its nearest cluster, as well as to all clusters within **overlap** times that distance.
+    fn update_memberships(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, memberships: &mut [Vec<usize>], overlap: T) {
+        let centroids = &state.centroids;
+        data.p_samples
+            .bfr
+            .par_chunks_exact(data.p_samples.stride)
+            .zip(state.assignments.par_iter_mut())
+            .zip(state.centroid_distances.par_iter_mut())
+            .zip(memberships.par_iter_mut())
+            .for_each(|(((s, assignment), centroid_dist), membership)| {
+                let distances: Vec<T> = centroids.chunks_exact_stride().map(|c| data.distance_fn.distance(s, c)).collect();
+                let (best_idx, best_dist) = distances
+                    .iter()
+                    .cloned()
+                    .enumerate()
+                    .min_by(|(_, d0), (_, d1)| d0.partial_cmp(d1).unwrap())
+                    .unwrap();
+                *assignment = best_idx;
+                *centroid_dist = best_dist;
+                membership.clear();
+                membership.extend((0..distances.len()).filter(|&c| c == best_idx || distances[c] <= best_dist * overlap));
+            });
+    }
+
+    /// Move every centroid into the (weighted) mean of all its members (including overlapping ones).
+    /// Centroids without any members are kept where they are.
+    fn update_centroids(data: &KMeans<T, LANES, D>, state: &mut KMeansState<T>, memberships: &[Vec<usize>]) {
+        let mut new_centroids = StrideBuffer::new::<LANES>(state.k, data.sample_dims);
+        let mut cluster_weights = vec![T::zero(); state.k];
+        state.memory_usage.record_temporaries(
+            size_of_val(new_centroids.bfr.as_slice())
+                + size_of_val(cluster_weights.as_slice())
+                + size_of_val(memberships)
+                + memberships.iter().map(|m| m.capacity() * size_of::<usize>()).sum::<usize>(),
+        );
+        state.centroid_frequency.iter_mut().for_each(|f| *f = 0);
+        data.p_samples
+            .chunks_exact_stride()
+            .zip(memberships.iter())
+            .enumerate()
+            .for_each(|(sample_id, (s, membership))| {
+                let weight = data.sample_weight(sample_id);
+                membership.iter().cloned().for_each(|c| {
+                    state.centroid_frequency[c] += 1;
+                    cluster_weights[c] += weight;
+                    new_centroids
+                        .nth_stride_mut(c)
+                        .chunks_exact_mut(LANES)
+                        .zip(s.chunks_exact(LANES).map(|v| Simd::from_slice(v)))
+                        .for_each(|(c, s)| {
+                            let result = Simd::from_slice(c) + s * Simd::splat(weight);
+                            c.copy_from_slice(result.as_array());
+                        });
+                });
+            });
+        state
+            .centroids
+            .chunks_exact_stride_mut()
+            .zip(new_centroids.chunks_exact_stride())
+            .zip(cluster_weights.iter().cloned())
+            .filter(|(_, cweight)| *cweight > T::zero())
+            .for_each(|((c, nc), cweight)| {
+                let cfreq_factor = T::one() / cweight;
+                c.iter_mut().zip(nc.iter().cloned()).for_each(|(c, nc)| *c = nc * cfreq_factor);
+            });
+    }
+
+    #[inline(always)]
+    pub fn calculate<F>(
+        data: &KMeans<T, LANES, D>, k: usize, max_iter: usize, overlap: T, init: F, config: &KMeansConfig<'_, T>,
+    ) -> OverlappingKMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        assert!(k <= data.sample_cnt);
+        assert!(overlap >= T::one());
+
+        let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
+        state.distsum = T::infinity();
+        let mut memberships = vec![Vec::new(); data.sample_cnt];
+
+        // Initialize clusters and notify subscriber
+        init(data, &mut state, config);
+        config.notify_init(data, &mut state);
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (data.sample_cnt * k) as u64,
+            sample_dims: data.sample_dims,
+        });
+
+        for i in 1..=max_iter {
+            Self::update_memberships(data, &mut state, &mut memberships, overlap);
+            let new_distsum = config.distsum(&state.centroid_distances, data.sample_weights.as_deref());
+            Self::update_centroids(data, &mut state, &memberships);
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(data, &mut state, i, new_distsum);
+            state.n_iterations = i;
+            if let Some(reason) = stop_criteria.next(data, &state, new_distsum) {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = new_distsum;
+        }
+
+        Self::update_memberships(data, &mut state, &mut memberships, overlap);
+        state.centroid_frequency.iter_mut().for_each(|f| *f = 0);
+        memberships.iter().flatten().for_each(|&c| state.centroid_frequency[c] += 1);
+        state.distsum = config.distsum(&state.centroid_distances, data.sample_weights.as_deref());
+        data.update_per_cluster_distsum(&mut state);
+        OverlappingKMeansState { state, memberships }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn overlapping_memberships() {
+        // The sample at 5.0 lies exactly between both groups
+        let samples = vec![0.0f64, 1.0, 2.0, 5.0, 8.0, 9.0, 10.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::default();
+        let res = kmean.kmeans_overlapping(2, 100, 1.5, KMeans::init_precomputed(vec![1.0, 9.0]), &conf);
+
+        assert_eq!(res.memberships, vec![
+            vec![0],
+            vec![0],
+            vec![0],
+            vec![0, 1],
+            vec![1],
+            vec![1],
+            vec![1]
+        ]);
+        assert_eq!(res.state.centroids.to_vec(), vec![2.0, 8.0]);
+        assert_eq!(res.state.centroid_frequency, vec![4, 4]);
+
+        // Without overlap (and without ties), this is a partitioning
+        let samples = vec![0.0f64, 1.0, 2.0, 4.0, 8.0, 9.0, 10.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let res = kmean.kmeans_overlapping(2, 100, 1.0, KMeans::init_precomputed(vec![1.0, 9.0]), &conf);
+        assert!(res
+            .memberships
+            .iter()
+            .zip(res.state.assignments.iter())
+            .all(|(m, &a)| m == &vec![a]));
+    }
+}
@@ -0,0 +1,274 @@
+use super::Lloyd;
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState, StopReason};
+
+/// Statistics of one iteration of a step-wise k-means calculation, as returned by [`KMeansRun::step`].
+///
+/// ## Fields
+/// - **iteration**: Number of the iteration (starting at `1`)
+/// - **distsum**: Total sum of distances of all samples to their centroid, before the centroids were moved
+/// - **improvement**: Decrease of **distsum**, compared to the previous iteration (infinity for the first iteration)
+/// - **abort_requested**: Whether the configured [`crate::AbortStrategy`] (or the convergence tolerance) would have
+///   stopped the calculation after this iteration. The run can still be continued by calling [`KMeansRun::step`] again.
+#[derive(Clone, Debug)]
+pub struct IterationStats<T: Primitive> {
+    pub iteration: usize,
+    pub distsum: T,
+    pub improvement: T,
+    pub abort_requested: bool,
+}
+
+/// Step-wise k-means calculation (Lloyd), as created by [`KMeans::start_lloyd`].
+///
+/// Instead of being limited to the callbacks of [`KMeansConfig`], this allows to interleave own logic (e.g. custom
+/// stopping criteria, edits of the centroids or logging) between the iterations of the calculation.
+pub struct KMeansRun<'r, T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    kmean: &'r KMeans<T, LANES, D>,
+    config: &'r KMeansConfig<'r, T>,
+    state: KMeansState<T>,
+    stop_criteria: StopCriteria<T>,
+    iteration: usize,
+}
+impl<'r, T, const LANES: usize, D> KMeansRun<'r, T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    pub(crate) fn new<F>(kmean: &'r KMeans<T, LANES, D>, k: usize, init: F, config: &'r KMeansConfig<'r, T>) -> Self
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        assert!(k <= kmean.sample_cnt);
+
+        let mut state = KMeansState::new::<LANES>(kmean.sample_cnt, kmean.sample_dims, k);
+        state.distsum = T::infinity();
+
+        // Initialize clusters and notify subscriber
+        init(kmean, &mut state, config);
+        config.notify_init(kmean, &mut state);
+        let stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (kmean.sample_cnt * k) as u64,
+            sample_dims: kmean.sample_dims,
+        });
+        Self {
+            kmean,
+            config,
+            state,
+            stop_criteria,
+            iteration: 0,
+        }
+    }
+
+    /// Do one iteration of the calculation: assign all samples to their nearest centroid, and move every centroid
+    /// into the mean of its samples afterwards.
+    ///
+    /// ## Returns
+    /// Statistics of the finished iteration, see [`IterationStats`].
+    pub fn step(&mut self) -> IterationStats<T> {
+        self.iteration += 1;
+        self.kmean.update_cluster_assignments(&mut self.state, None);
+        let new_distsum = Lloyd::update_centroids(self.kmean, &mut self.state, self.config);
+        // An accepted split-merge move restarts the convergence, so it must not lead to an abort
+        let interval = self.config.split_merge_interval;
+        let moved = interval > 0 && self.iteration.is_multiple_of(interval) && crate::split_merge::refine(self.kmean, &mut self.state);
+
+        // Notify subscriber about finished iteration
+        self.config
+            .notify_iteration(self.kmean, &mut self.state, self.iteration, new_distsum);
+        let stop_reason = self
+            .stop_criteria
+            .next(self.kmean, &self.state, new_distsum)
+            .filter(|reason| !moved || reason.is_forced());
+        self.state.n_iterations = self.iteration;
+        self.state.stop_reason = stop_reason.unwrap_or(StopReason::MaxIterations);
+        let improvement = self.state.distsum - new_distsum;
+        self.state.distsum = new_distsum;
+        IterationStats {
+            iteration: self.iteration,
+            distsum: new_distsum,
+            improvement,
+            abort_requested: stop_reason.is_some(),
+        }
+    }
+
+    /// Amount of iterations that were done so far.
+    pub fn iterations(&self) -> usize { self.iteration }
+
+    /// Current state of the calculation.
+    pub fn state(&self) -> &KMeansState<T> { &self.state }
+
+    /// Mutable access to the current state of the calculation, e.g. to edit the centroids before the next iteration.
+    /// Note that the next call to [`KMeansRun::step`] reassigns all samples, so only the **centroids** carry over.
+    pub fn state_mut(&mut self) -> &mut KMeansState<T> { &mut self.state }
+
+    /// Turn this run into an iterator, yielding a snapshot of the state after each iteration (e.g. for the animation of
+    /// the convergence, or the debugging of pathological runs).
+    ///
+    /// ## Arguments
+    /// - **max_iter**: Maximum amount of iterations to yield
+    /// - **with_assignments**: Whether the snapshots should also contain the assignments of all samples
+    ///
+    /// The iterator ends after **max_iter** iterations, or directly after the iteration in which the configured
+    /// [`crate::AbortStrategy`] requested an abort. Afterwards, [`KMeansSnapshots::finish`] returns the final result.
+    pub fn into_snapshots(self, max_iter: usize, with_assignments: bool) -> KMeansSnapshots<'r, T, LANES, D> {
+        KMeansSnapshots {
+            run: self,
+            max_iter,
+            with_assignments,
+            done: false,
+        }
+    }
+
+    /// Finish the calculation, and return its final result. This updates the distances of all samples to their
+    /// centroid, and applies the post-processing steps enabled in the configuration.
+    pub fn finish(self) -> KMeansState<T> {
+        let (kmean, config) = (self.kmean, self.config);
+        let mut state = self.finish_unprocessed();
+        crate::postprocessing::apply(kmean, &mut state, config);
+        state
+    }
+
+    /// Finish the calculation like [`KMeansRun::finish`], but without applying the post-processing steps.
+    pub(crate) fn finish_unprocessed(mut self) -> KMeansState<T> {
+        self.kmean.update_centroid_distances(&mut self.state);
+        self.state.distsum = self
+            .config
+            .distsum(&self.state.centroid_distances, self.kmean.sample_weights.as_deref());
+        self.state
+    }
+}
+
+/// Snapshot of the state of a k-means calculation after one iteration, as yielded by [`KMeansSnapshots`].
+///
+/// ## Fields
+/// - **stats**: Statistics of the iteration, see [`IterationStats`]
+/// - **centroids**: Centroids after the iteration [row-major] = [<centroid0>,<centroid1>,<centroid2>,...]
+/// - **assignments**: If requested: Assignments of all samples, that were used to calculate the **centroids**
+#[derive(Clone, Debug)]
+pub struct IterationSnapshot<T: Primitive> {
+    pub stats: IterationStats<T>,
+    pub centroids: Vec<T>,
+    pub assignments: Option<Vec<usize>>,
+}
+
+/// Iterator over the intermediate states of a k-means calculation, as created by [`KMeansRun::into_snapshots`].
+pub struct KMeansSnapshots<'r, T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    run: KMeansRun<'r, T, LANES, D>,
+    max_iter: usize,
+    with_assignments: bool,
+    done: bool,
+}
+impl<T, const LANES: usize, D> KMeansSnapshots<'_, T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    /// Finish the calculation, and return its final result (see [`KMeansRun::finish`]).
+    pub fn finish(self) -> KMeansState<T> { self.run.finish() }
+}
+impl<T, const LANES: usize, D> Iterator for KMeansSnapshots<'_, T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    type Item = IterationSnapshot<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.run.iterations() >= self.max_iter {
+            return None;
+        }
+        let stats = self.run.step();
+        self.done = stats.abort_requested;
+        Some(IterationSnapshot {
+            stats,
+            centroids: self.run.state.centroids.to_vec(),
+            assignments: self.with_assignments.then(|| self.run.state.assignments.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn step_wise_equals_lloyd() {
+        let samples = vec![0.0f64, 1.0, 2.0, 3.0, 10.0, 11.0, 12.0, 13.0, 20.0, 25.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::default();
+        let init = || KMeans::init_precomputed(vec![0.0, 1.0, 2.0]);
+        let expected = kmean.kmeans_lloyd(3, 100, init(), &conf);
+
+        let mut run = kmean.start_lloyd(3, init(), &conf);
+        let first = run.step();
+        assert_eq!(first.iteration, 1);
+        assert!(first.improvement.is_infinite());
+        while !run.step().abort_requested {}
+        let iterations = run.iterations();
+        let res = run.finish();
+        assert!(iterations > 1);
+        assert_eq!(res.centroids.to_vec(), expected.centroids.to_vec());
+        assert_eq!(res.assignments, expected.assignments);
+        assert_eq!(res.distsum, expected.distsum);
+    }
+
+    #[test]
+    fn step_wise_centroid_edit() {
+        let samples = vec![0.0f64, 1.0, 2.0, 10.0, 11.0, 12.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::default();
+        let mut run = kmean.start_lloyd(2, KMeans::init_precomputed(vec![0.0, 1.0]), &conf);
+        run.step();
+        // Move the second centroid into the second group, before the next iteration
+        run.state_mut().centroids.nth_stride_mut(1)[0] = 11.0;
+        run.step();
+        let res = run.finish();
+        assert_eq!(res.centroids.to_vec(), vec![1.0, 11.0]);
+    }
+
+    #[test]
+    fn snapshots() {
+        let samples = vec![0.0f64, 1.0, 2.0, 3.0, 10.0, 11.0, 12.0, 13.0, 20.0, 25.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len(), 1, EuclideanDistance);
+        let conf = KMeansConfig::default();
+        let expected = kmean.kmeans_lloyd(3, 100, KMeans::init_precomputed(vec![0.0, 1.0, 2.0]), &conf);
+
+        let mut snapshots = kmean
+            .start_lloyd(3, KMeans::init_precomputed(vec![0.0, 1.0, 2.0]), &conf)
+            .into_snapshots(100, true);
+        let all: Vec<_> = snapshots.by_ref().collect();
+        assert!(all.len() > 1);
+        assert!(all.last().unwrap().stats.abort_requested);
+        assert!(all
+            .iter()
+            .all(|s| s.centroids.len() == 3 && s.assignments.as_ref().unwrap().len() == samples.len()));
+        assert_eq!(all.last().unwrap().centroids, expected.centroids.to_vec());
+        assert_eq!(snapshots.finish().assignments, expected.assignments);
+
+        let limited = kmean
+            .start_lloyd(3, KMeans::init_precomputed(vec![0.0, 1.0, 2.0]), &conf)
+            .into_snapshots(1, false);
+        assert_eq!(limited.map(|s| s.assignments).collect::<Vec<_>>(), vec![None]);
+    }
+}
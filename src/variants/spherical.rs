@@ -0,0 +1,103 @@
+use super::Lloyd;
+use crate::abort_strategy::{IterationCost, StopCriteria};
+use crate::api::DistanceFunction;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rayon::prelude::*;
+
+pub(crate) struct Spherical<T, const LANES: usize, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    D: DistanceFunction<T, LANES>,
+{
+    _p: std::marker::PhantomData<(T, D)>,
+}
+
+impl<T, const LANES: usize, D> Spherical<T, LANES, D>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    /// Scale all centroids to unit length. Centroids of length zero are kept as they are.
+    fn normalize_centroids(state: &mut KMeansState<T>) {
+        state.centroids.bfr.par_chunks_exact_mut(state.centroids.stride).for_each(|c| {
+            let norm = c.iter().map(|&v| v * v).sum::<T>().sqrt();
+            if norm > T::zero() {
+                c.iter_mut().for_each(|v| *v = *v / norm);
+            }
+        });
+    }
+
+    #[inline(always)]
+    pub fn calculate<F>(data: &KMeans<T, LANES, D>, k: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>) -> KMeansState<T>
+    where
+        for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+    {
+        assert!(k <= data.sample_cnt);
+
+        let mut state = KMeansState::new::<LANES>(data.sample_cnt, data.sample_dims, k);
+        state.distsum = T::infinity();
+
+        // Initialize clusters and notify subscriber
+        init(data, &mut state, config);
+        Self::normalize_centroids(&mut state);
+        config.notify_init(data, &mut state);
+        let mut stop_criteria = StopCriteria::new(config, &state, IterationCost {
+            distance_evaluations: (data.sample_cnt * k) as u64,
+            sample_dims: data.sample_dims,
+        });
+
+        for i in 1..=max_iter {
+            data.update_cluster_assignments(&mut state, None);
+            let new_distsum = Lloyd::update_centroids(data, &mut state, config);
+            Self::normalize_centroids(&mut state);
+
+            // Notify subscriber about finished iteration
+            config.notify_iteration(data, &mut state, i, new_distsum);
+            state.n_iterations = i;
+            if let Some(reason) = stop_criteria.next(data, &state, new_distsum) {
+                state.stop_reason = reason;
+                break;
+            }
+            state.distsum = new_distsum;
+        }
+
+        data.update_centroid_distances(&mut state);
+        state.distsum = config.distsum(&state.centroid_distances, data.sample_weights.as_deref());
+        crate::postprocessing::apply(data, &mut state, config);
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CosineDistance, KMeans, KMeansConfig};
+
+    #[test]
+    fn unit_length_centroids() {
+        // Two groups of directions, with samples of very different lengths
+        let samples = vec![1.0f64, 0.1, 10.0, 0.0, 5.0, -0.5, 0.1, 1.0, 0.0, 10.0, -0.5, 5.0];
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, samples.len() / 2, 2, CosineDistance);
+        let res = kmean.kmeans_spherical(
+            2,
+            100,
+            KMeans::init_precomputed(vec![1.0, -1.0, -1.0, 1.0]),
+            &KMeansConfig::default(),
+        );
+        assert_eq!(res.assignments, vec![0, 0, 0, 1, 1, 1]);
+
+        // The centroids point into the direction of the mean of their samples
+        let centroids = res.centroids.to_vec();
+        for c in centroids.chunks_exact(2) {
+            assert!((c[0] * c[0] + c[1] * c[1] - 1.0).abs() < 1e-12);
+        }
+        let mean = [16.0f64 / 3.0, -0.4 / 3.0];
+        let norm = (mean[0] * mean[0] + mean[1] * mean[1]).sqrt();
+        assert!((centroids[0] - mean[0] / norm).abs() < 1e-12 && (centroids[1] - mean[1] / norm).abs() < 1e-12);
+        assert!((centroids[2] - mean[1] / norm).abs() < 1e-12 && (centroids[3] - mean[0] / norm).abs() < 1e-12);
+    }
+}
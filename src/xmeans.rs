@@ -0,0 +1,158 @@
+use crate::api::DistanceFunction;
+use crate::incremental::split_cluster;
+use crate::memory::*;
+use crate::simd::{LaneCount, Simd, SupportedLaneCount};
+use crate::variants::Lloyd;
+use crate::{KMeans, KMeansConfig, KMeansState};
+use rayon::prelude::*;
+
+/// Bayesian information criterion of a model of spherical gaussians (with one shared variance), as used by X-means.
+///
+/// ## Arguments
+/// - **counts**: (Weighted) amount of samples of every cluster of the model
+/// - **sse**: (Weighted) sum of squared distances of the samples to their centroids
+/// - **dims**: Amount of dimensions of the samples
+///
+/// ## Returns
+/// The BIC (higher is better), or **None** if the variance cannot be estimated (samples without any spread, or not
+/// more samples than clusters).
+fn bic<T: Primitive>(counts: &[T], sse: T, dims: usize) -> Option<T> {
+    let (r, k, m) = (
+        counts.iter().cloned().sum::<T>(),
+        T::from(counts.len()).unwrap(),
+        T::from(dims).unwrap(),
+    );
+    if r <= k || sse <= T::zero() {
+        return None;
+    }
+    let two = T::from(2).unwrap();
+    let variance = sse / ((r - k) * m);
+    let log_likelihood = counts.iter().filter(|&&c| c > T::zero()).map(|&c| c * (c / r).ln()).sum::<T>()
+        - r * m / two * (two * T::from(std::f64::consts::PI).unwrap() * variance).ln()
+        - m * (r - k) / two;
+    // Mixing probabilities, centroids and the variance
+    let params = (k - T::one()) + k * m + T::one();
+    Some(log_likelihood - params / two * r.ln())
+}
+
+/// Split the given cluster into two with a local 2-means, if this improves the BIC of the cluster's samples.
+///
+/// ## Returns
+/// The improvement of the BIC and the two (padded) centroids of the halves, if the split is an improvement.
+fn evaluate_split<T, const LANES: usize, D>(
+    kmean: &KMeans<T, LANES, D>, state: &KMeansState<T>, cluster: usize, members: &[usize],
+) -> Option<(T, StrideBuffer<T>)>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+{
+    let parent_cnt = members.iter().map(|&m| kmean.sample_weight(m)).sum::<T>();
+    let parent_sse = members
+        .iter()
+        .map(|&m| kmean.sample_weight(m) * state.centroid_distances[m])
+        .sum::<T>();
+    // Clusters without any spread are never split
+    let parent = bic(&[parent_cnt], parent_sse, kmean.sample_dims)?;
+
+    let split = split_cluster(kmean, state.centroids.nth_stride(cluster), members);
+    let (mut counts, mut sse) = ([T::zero(); 2], T::zero());
+    members.iter().for_each(|&m| {
+        let s = kmean.p_samples.nth_stride(m);
+        let distances = [
+            kmean.distance_fn.distance(s, split.nth_stride(0)),
+            kmean.distance_fn.distance(s, split.nth_stride(1)),
+        ];
+        let side = (distances[1] < distances[0]) as usize;
+        counts[side] += kmean.sample_weight(m);
+        sse += kmean.sample_weight(m) * distances[side];
+    });
+    let improvement = match bic(&counts, sse, kmean.sample_dims) {
+        Some(children) => children - parent,
+        // Both halves consist of equal samples, which fits them perfectly
+        None if sse <= T::zero() && counts.iter().all(|&c| c > T::zero()) => T::infinity(),
+        None => return None,
+    };
+    (improvement > T::zero()).then_some((improvement, split))
+}
+
+#[inline(always)]
+pub fn calculate<T, const LANES: usize, D, F>(
+    kmean: &KMeans<T, LANES, D>, k_min: usize, k_max: usize, max_iter: usize, init: F, config: &KMeansConfig<'_, T>,
+) -> KMeansState<T>
+where
+    T: Primitive,
+    LaneCount<LANES>: SupportedLaneCount,
+    Simd<T, LANES>: SupportedSimdArray<T, LANES>,
+    D: DistanceFunction<T, LANES>,
+    for<'c> F: FnOnce(&KMeans<T, LANES, D>, &mut KMeansState<T>, &KMeansConfig<'c, T>),
+{
+    assert!(k_min > 0 && k_min <= k_max && k_max <= kmean.sample_cnt);
+    assert!(kmean.distance_fn.is_squared_euclidean());
+
+    let mut state = Lloyd::calculate(kmean, k_min, max_iter, init, config);
+    while state.k < k_max {
+        let mut members = vec![Vec::new(); state.k];
+        state.assignments.iter().enumerate().for_each(|(idx, &c)| members[c].push(idx));
+        let mut splits: Vec<(usize, T, StrideBuffer<T>)> = (0..state.k)
+            .into_par_iter()
+            .filter(|&c| members[c].len() > 2)
+            .filter_map(|c| evaluate_split(kmean, &state, c, &members[c]).map(|(improvement, split)| (c, improvement, split)))
+            .collect();
+        if splits.is_empty() {
+            break;
+        }
+
+        // Split the clusters with the highest improvements, within the limit of k_max clusters
+        splits.sort_by(|(_, i0, _), (_, i1, _)| i1.partial_cmp(i0).unwrap());
+        splits.truncate(k_max - state.k);
+        let dims = kmean.sample_dims;
+        let mut centroids = state.centroids.to_vec();
+        splits.iter().for_each(|(c, _, split)| {
+            centroids[c * dims..(c + 1) * dims].copy_from_slice(&split.nth_stride(0)[..dims]);
+            centroids.extend_from_slice(&split.nth_stride(1)[..dims]);
+        });
+        state = Lloyd::calculate(kmean, state.k + splits.len(), max_iter, KMeans::init_precomputed(centroids), config);
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EuclideanDistance, KMeans, KMeansConfig};
+
+    /// Three square blobs of 36 samples each, around (0, 0), (10, 0) and (0, 10).
+    fn blobs() -> Vec<f64> {
+        [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]
+            .iter()
+            .flat_map(|&(x, y)| (0..36).flat_map(move |i| [x + (i % 6) as f64 * 0.4 - 1.0, y + (i / 6) as f64 * 0.4 - 1.0]))
+            .collect()
+    }
+
+    #[test]
+    fn xmeans_selects_k() {
+        let samples = blobs();
+        let kmean: KMeans<f64, 8, _> = KMeans::new(&samples, 108, 2, EuclideanDistance);
+        let res = kmean.kmeans_xmeans(1, 10, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+        assert_eq!(res.k, 3);
+        assert_eq!(res.centroid_frequency, vec![36, 36, 36]);
+        assert!((0..3).all(|b| res.assignments[b * 36..(b + 1) * 36].iter().all(|&a| a == res.assignments[b * 36])));
+
+        // The amount of clusters is limited by k_max
+        let res = kmean.kmeans_xmeans(1, 2, 100, KMeans::init_kmeanplusplus, &KMeansConfig::default());
+        assert_eq!(res.k, 2);
+    }
+
+    #[test]
+    fn bic_prefers_separated_clusters() {
+        let single = super::bic(&[100.0f64], 100.0 * 50.0, 2).unwrap();
+        let split = super::bic(&[50.0f64, 50.0], 100.0 * 1.0, 2).unwrap();
+        assert!(split > single);
+
+        // Halving a uniform blob does not reduce its variance enough
+        let halved = super::bic(&[50.0f64, 50.0], 100.0 * 0.625 * 0.12, 2).unwrap();
+        assert!(halved < super::bic(&[100.0f64], 100.0 * 0.12, 2).unwrap());
+        assert_eq!(super::bic(&[3.0f64], 0.0, 2), None);
+    }
+}